@@ -0,0 +1,921 @@
+// Copyright 2018 Peter Williams <peter@newton.cx> and collaborators
+// Licensed under the MIT License
+
+/*! A client for the `slurmrestd` REST API.
+
+The `RestClient` type talks to Slurm's REST daemon over HTTP, authenticating
+with a JWT such as one generated by `scontrol token`. This crate does not use
+the Slurm C libraries at all, so it can be used on machines without a Slurm
+installation, or with a local installation that does not match the version
+running on the cluster.
+
+The results are returned as plain data. Job states, step IDs, and the like are
+left as the names that Slurm reports, since decoding them into richer types
+requires knowledge of the Slurm version in use. The `rest` feature of the
+`slurm` crate builds on this crate to return that crate's types instead.
+
+```no_run
+fn print_my_jobs() -> Result<(), slurm_rest::Error> {
+    let client = slurm_rest::RestClient::from_env()?;
+    let mut query = slurm_rest::JobsQuery::default();
+    query.users.push("alice".to_owned());
+
+    for job in client.get_jobs(&query)? {
+        println!("{} {} {}", job.job_id, job.job_name, job.state);
+    }
+
+    Ok(())
+}
+```
+
+*/
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// The version of the REST API that is used by default.
+///
+/// This corresponds to Slurm 23.11. Use `RestClient::api_version` to select
+/// a different version.
+pub const DEFAULT_API_VERSION: &str = "v0.0.40";
+
+/// The error type of this crate.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// The HTTP request could not be made.
+    #[error("slurmrestd request failed: {0}")]
+    Request(String),
+
+    /// The server returned an HTTP error without any further explanation.
+    #[error("slurmrestd returned HTTP status {0}")]
+    Status(u16),
+
+    /// The server reported one or more errors.
+    #[error("slurmrestd reported: {}", .0.join("; "))]
+    Server(Vec<String>),
+
+    /// An I/O error, such as a failure to read the response body.
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+
+    /// Some other error, such as a response missing a required field.
+    #[error("{0}")]
+    Other(String),
+}
+
+impl From<ureq::Error> for Error {
+    fn from(e: ureq::Error) -> Self {
+        Error::Request(e.to_string())
+    }
+}
+
+/// A job time limit.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum TimeLimit {
+    /// The job may run for the specified number of minutes.
+    Minutes(u32),
+
+    /// The job may run indefinitely.
+    Unlimited,
+}
+
+/// Information about a job, as reported by the controller.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct JobInfo {
+    pub account: Option<String>,
+    pub admin_comment: Option<String>,
+    pub array_job_id: Option<u32>,
+    pub array_task_id: Option<u32>,
+    pub batch_host: Option<String>,
+    pub comment: Option<String>,
+    pub dependency: Option<String>,
+    /// The raw `wait(2)`-style exit status; not meaningful until the job
+    /// has finished.
+    pub exit_code: Option<u32>,
+    pub fed_origin: Option<String>,
+    pub fed_siblings_active: Vec<String>,
+    pub fed_siblings_viable: Vec<String>,
+    pub gres_detail: Vec<String>,
+    pub is_batch: bool,
+    pub job_id: u32,
+    pub licenses: Option<String>,
+    pub mcs_label: Option<String>,
+    /// The memory requested per CPU, in mebibytes.
+    pub memory_per_cpu: Option<u64>,
+    /// The memory requested per node, in mebibytes.
+    pub memory_per_node: Option<u64>,
+    pub name: String,
+    pub nodes: Option<String>,
+    pub num_cpus: u32,
+    pub partition: String,
+    pub priority: u32,
+    pub qos: Option<String>,
+    pub requeue: bool,
+    pub restart_cnt: u16,
+    pub start_time: Option<DateTime<Utc>>,
+    /// Slurm's name for the job's base state, such as `PENDING`.
+    pub state: String,
+    /// Slurm's name for the reason that the job is pending, such as
+    /// `Resources`.
+    pub state_reason: Option<String>,
+    pub stderr_path: Option<String>,
+    pub stdin_path: Option<String>,
+    pub stdout_path: Option<String>,
+    pub time_limit: Option<TimeLimit>,
+    pub user_id: Option<u32>,
+    pub user_name: Option<String>,
+    pub work_dir: Option<String>,
+}
+
+/// Accounting information about a job.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JobRecord {
+    pub account: Option<String>,
+    pub alloc_nodes: u32,
+    pub array_job_id: Option<u32>,
+    pub cluster: Option<String>,
+    /// The highest raw `wait(2)`-style exit status of the job's steps.
+    pub derived_exit_code: u32,
+    pub elapsed: Duration,
+    pub eligible_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    /// The raw `wait(2)`-style exit status, if the job has ended.
+    pub exit_code: Option<u32>,
+    pub job_id: u32,
+    pub job_name: String,
+    pub node_list: Option<String>,
+    pub partition: Option<String>,
+    pub priority: u32,
+    pub req_cpus: u32,
+    /// The memory requested per CPU, in mebibytes.
+    pub req_memory_per_cpu: Option<u64>,
+    /// The memory requested per node, in mebibytes.
+    pub req_memory_per_node: Option<u64>,
+    pub start_time: Option<DateTime<Utc>>,
+    /// Slurm's name for the job's base state, such as `COMPLETED`.
+    pub state: String,
+    pub steps: Vec<StepRecord>,
+    pub submit_time: Option<DateTime<Utc>>,
+    pub sys_cpu: Duration,
+    pub time_limit: Option<TimeLimit>,
+    pub tot_cpu: Duration,
+    /// Allocated TRES amounts, keyed by TRES ID.
+    pub tres_alloc: BTreeMap<u32, u64>,
+    /// Requested TRES amounts, keyed by TRES ID.
+    pub tres_req: BTreeMap<u32, u64>,
+    pub user: Option<String>,
+    pub user_cpu: Duration,
+    pub wckey: Option<String>,
+    pub work_dir: Option<String>,
+}
+
+/// Accounting information about a job step.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StepRecord {
+    pub elapsed: Duration,
+    pub end_time: Option<DateTime<Utc>>,
+    /// The raw `wait(2)`-style exit status, if the step has ended.
+    pub exit_code: Option<u32>,
+    pub nnodes: u32,
+    pub node_list: Option<String>,
+    pub ntasks: u32,
+    pub start_time: Option<DateTime<Utc>>,
+    /// Slurm's name for the step's base state, such as `COMPLETED`.
+    pub state: String,
+    /// The step ID without the job ID, such as `0` or `batch`.
+    pub step_id: String,
+    pub step_name: String,
+    pub sys_cpu: Duration,
+    pub tot_cpu: Duration,
+    /// Allocated TRES amounts, keyed by TRES ID.
+    pub tres_alloc: BTreeMap<u32, u64>,
+    pub user_cpu: Duration,
+}
+
+/// Criteria for an accounting query. Empty fields match everything.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct JobsQuery {
+    pub users: Vec<String>,
+    pub accounts: Vec<String>,
+    pub jobs: Vec<u32>,
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+}
+
+/// A batch job to submit.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct JobSubmission {
+    /// The wrapper script, which should start with a shebang (`#!`) line.
+    pub script: String,
+    /// The job's environment, as `NAME=value` strings.
+    pub environment: Vec<String>,
+    pub name: Option<String>,
+    pub comment: Option<String>,
+    pub partition: Option<String>,
+    pub work_dir: Option<String>,
+    pub num_tasks: Option<u32>,
+    pub time_limit: Option<TimeLimit>,
+    /// The memory to request per CPU, in mebibytes.
+    pub memory_per_cpu: Option<u64>,
+    /// The memory to request per node, in mebibytes.
+    pub memory_per_node: Option<u64>,
+    pub stdin_path: Option<String>,
+    pub stdout_path: Option<String>,
+    pub stderr_path: Option<String>,
+}
+
+impl JobSubmission {
+    /// Encode the job description in the form expected by the
+    /// `job/submit` endpoint.
+    fn to_json(&self) -> Value {
+        let mut desc = Map::new();
+        desc.insert("script".to_owned(), self.script.clone().into());
+        desc.insert("environment".to_owned(), self.environment.clone().into());
+
+        for &(key, value) in &[
+            ("name", &self.name),
+            ("comment", &self.comment),
+            ("partition", &self.partition),
+            ("current_working_directory", &self.work_dir),
+            ("standard_input", &self.stdin_path),
+            ("standard_output", &self.stdout_path),
+            ("standard_error", &self.stderr_path),
+        ] {
+            if let Some(ref value) = *value {
+                desc.insert(key.to_owned(), value.clone().into());
+            }
+        }
+
+        if let Some(n) = self.num_tasks {
+            desc.insert("tasks".to_owned(), n.into());
+        }
+
+        match self.time_limit {
+            Some(TimeLimit::Minutes(m)) => {
+                desc.insert("time_limit".to_owned(), number_object(m as u64, false));
+            }
+            Some(TimeLimit::Unlimited) => {
+                desc.insert("time_limit".to_owned(), number_object(0, true));
+            }
+            None => {}
+        }
+
+        if let Some(mib) = self.memory_per_node {
+            desc.insert("memory_per_node".to_owned(), number_object(mib, false));
+        }
+
+        if let Some(mib) = self.memory_per_cpu {
+            desc.insert("memory_per_cpu".to_owned(), number_object(mib, false));
+        }
+
+        let mut payload = Map::new();
+        payload.insert("job".to_owned(), Value::Object(desc));
+        Value::Object(payload)
+    }
+}
+
+/// A connection to a `slurmrestd` server.
+#[derive(Clone)]
+pub struct RestClient {
+    agent: ureq::Agent,
+    base_url: String,
+    api_version: String,
+    user_name: String,
+    token: String,
+}
+
+impl RestClient {
+    /// Create a new client for the server at `base_url`, such as
+    /// `http://slurm-head:6820`, authenticating as `user_name` with the JWT
+    /// `token`.
+    #[must_use]
+    pub fn new<U, N, T>(base_url: U, user_name: N, token: T) -> RestClient
+    where
+        U: Into<String>,
+        N: Into<String>,
+        T: Into<String>,
+    {
+        let mut base_url = base_url.into();
+
+        while base_url.ends_with('/') {
+            base_url.pop();
+        }
+
+        RestClient {
+            agent: ureq::Agent::new(),
+            base_url,
+            api_version: DEFAULT_API_VERSION.to_owned(),
+            user_name: user_name.into(),
+            token: token.into(),
+        }
+    }
+
+    /// Create a new client configured from environment variables.
+    ///
+    /// The server URL is taken from `SLURMRESTD_URL`, the token from
+    /// `SLURM_JWT` (as set by `scontrol token`), and the user name from
+    /// `USER`.
+    pub fn from_env() -> Result<RestClient, Error> {
+        let var = |name: &str| {
+            std::env::var(name)
+                .map_err(|_| Error::Other(format!("environment variable {} must be set", name)))
+        };
+
+        Ok(RestClient::new(
+            var("SLURMRESTD_URL")?,
+            var("USER")?,
+            var("SLURM_JWT")?,
+        ))
+    }
+
+    /// Set the version of the REST API to use, such as `v0.0.39`.
+    pub fn api_version<S: Into<String>>(&mut self, version: S) -> &mut Self {
+        self.api_version = version.into();
+        self
+    }
+
+    fn url(&self, plugin: &str, path: &str) -> String {
+        format!("{}/{}/{}/{}", self.base_url, plugin, self.api_version, path)
+    }
+
+    fn request(&self, method: &str, url: &str) -> ureq::Request {
+        self.agent
+            .request(method, url)
+            .set("X-SLURM-USER-NAME", &self.user_name)
+            .set("X-SLURM-USER-TOKEN", &self.token)
+    }
+
+    /// Perform a request and decode its JSON response, turning errors
+    /// reported by the server into `Error`s.
+    fn execute(&self, result: Result<ureq::Response, ureq::Error>) -> Result<Value, Error> {
+        let body: Value = match result {
+            Ok(resp) => resp.into_json()?,
+            Err(ureq::Error::Status(code, resp)) => {
+                if let Ok(body) = resp.into_json::<Value>() {
+                    check_errors(&body)?;
+                }
+
+                return Err(Error::Status(code));
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        check_errors(&body)?;
+        Ok(body)
+    }
+
+    /// Get information about a single job from the controller.
+    pub fn get_job_info(&self, jid: u32) -> Result<JobInfo, Error> {
+        let url = self.url("slurm", &format!("job/{}", jid));
+        let body = self.execute(self.request("GET", &url).call())?;
+
+        let job = body["jobs"]
+            .as_array()
+            .and_then(|jobs| jobs.first())
+            .ok_or_else(|| {
+                Error::Other(format!(
+                    "slurmrestd returned no information for job {}",
+                    jid
+                ))
+            })?;
+
+        job_info(job)
+    }
+
+    /// Query the accounting database for jobs.
+    pub fn get_jobs(&self, query: &JobsQuery) -> Result<Vec<JobRecord>, Error> {
+        let url = self.url("slurmdb", "jobs");
+        let mut req = self.request("GET", &url);
+
+        if !query.users.is_empty() {
+            req = req.query("users", &query.users.join(","));
+        }
+
+        if !query.accounts.is_empty() {
+            req = req.query("account", &query.accounts.join(","));
+        }
+
+        if !query.jobs.is_empty() {
+            let ids: Vec<String> = query.jobs.iter().map(|j| j.to_string()).collect();
+            req = req.query("step", &ids.join(","));
+        }
+
+        if let Some(t) = query.start {
+            req = req.query("start_time", &t.timestamp().to_string());
+        }
+
+        if let Some(t) = query.end {
+            req = req.query("end_time", &t.timestamp().to_string());
+        }
+
+        let body = self.execute(req.call())?;
+
+        match body["jobs"].as_array() {
+            Some(jobs) => jobs.iter().map(job_record).collect(),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Submit a batch job, returning its ID.
+    pub fn submit_batch(&self, job: &JobSubmission) -> Result<u32, Error> {
+        let url = self.url("slurm", "job/submit");
+        let body = self.execute(self.request("POST", &url).send_json(job.to_json()))?;
+
+        number(&body["job_id"]).map(|j| j as u32).ok_or_else(|| {
+            Error::Other("slurmrestd did not report the ID of the new job".to_owned())
+        })
+    }
+}
+
+/// Turn the `errors` array included in slurmrestd responses into an `Error`.
+fn check_errors(body: &Value) -> Result<(), Error> {
+    let errors = match body["errors"].as_array() {
+        Some(e) if !e.is_empty() => e,
+        _ => return Ok(()),
+    };
+
+    Err(Error::Server(
+        errors
+            .iter()
+            .map(|e| {
+                string(&e["description"])
+                    .or_else(|| string(&e["error"]))
+                    .unwrap_or_else(|| format!("error {}", e["error_number"]))
+            })
+            .collect(),
+    ))
+}
+
+/// Encode a number in the `{set, infinite, number}` form used by newer
+/// versions of the API.
+fn number_object(value: u64, infinite: bool) -> Value {
+    let mut obj = Map::new();
+    obj.insert("set".to_owned(), true.into());
+    obj.insert("infinite".to_owned(), infinite.into());
+    obj.insert("number".to_owned(), value.into());
+    Value::Object(obj)
+}
+
+/// Decode a number that may be given either directly or in the `{set,
+/// infinite, number}` form. Unset and infinite values yield None.
+fn number(v: &Value) -> Option<u64> {
+    if let Some(n) = v.as_u64() {
+        return Some(n);
+    }
+
+    if v["set"].as_bool() == Some(false) || v["infinite"].as_bool() == Some(true) {
+        return None;
+    }
+
+    v["number"].as_u64()
+}
+
+/// Decode a string, treating empty strings as missing.
+fn string(v: &Value) -> Option<String> {
+    v.as_str().filter(|s| !s.is_empty()).map(|s| s.to_owned())
+}
+
+/// Decode a comma-separated list of names.
+fn string_list(v: &Value) -> Vec<String> {
+    match v.as_str() {
+        Some(s) => s
+            .split(',')
+            .filter(|n| !n.is_empty())
+            .map(|n| n.to_owned())
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Decode a Unix timestamp, treating zero as missing.
+fn timestamp(v: &Value) -> Option<DateTime<Utc>> {
+    number(v)
+        .filter(|t| *t != 0)
+        .and_then(|t| Utc.timestamp_opt(t as i64, 0).single())
+}
+
+/// Decode a number of seconds.
+fn seconds(v: &Value) -> Duration {
+    Duration::seconds(number(v).unwrap_or(0) as i64)
+}
+
+/// Decode a CPU time given as `{seconds, microseconds}`.
+fn cpu_time(v: &Value) -> Duration {
+    Duration::seconds(number(&v["seconds"]).unwrap_or(0) as i64)
+        + Duration::microseconds(number(&v["microseconds"]).unwrap_or(0) as i64)
+}
+
+/// Decode a time limit in minutes.
+fn time_limit(v: &Value) -> Option<TimeLimit> {
+    if v["infinite"].as_bool() == Some(true) {
+        Some(TimeLimit::Unlimited)
+    } else {
+        number(v).map(|m| TimeLimit::Minutes(m as u32))
+    }
+}
+
+/// Decode a memory amount, treating zero as missing.
+fn memory(v: &Value) -> Option<u64> {
+    number(v).filter(|m| *m != 0)
+}
+
+/// Decode an exit code into Slurm's raw `wait(2)`-style form.
+///
+/// Older API versions give the raw value directly; newer ones give an object
+/// with separate return-code and signal fields.
+fn exit_code(v: &Value) -> Option<u32> {
+    if let Some(n) = v.as_u64() {
+        return Some(n as u32);
+    }
+
+    if v.is_null() {
+        return None;
+    }
+
+    let signal = number(&v["signal"]["id"])
+        .or_else(|| number(&v["signal"]["signal_id"]))
+        .unwrap_or(0) as u32;
+
+    if signal != 0 {
+        Some(signal & 0x7f)
+    } else {
+        number(&v["return_code"]).map(|rc| (rc as u32 & 0xff) << 8)
+    }
+}
+
+/// Decode a job or step state, which is a plain string in older API
+/// versions and a list of a base state followed by flags in newer ones.
+fn state(v: &Value) -> Result<String, Error> {
+    v.as_str()
+        .or_else(|| v["current"].as_str())
+        .or_else(|| {
+            v.as_array()
+                .and_then(|a| a.first())
+                .and_then(|s| s.as_str())
+        })
+        .or_else(|| {
+            v["current"]
+                .as_array()
+                .and_then(|a| a.first())
+                .and_then(|s| s.as_str())
+        })
+        .map(|s| s.to_owned())
+        .ok_or_else(|| Error::Other("slurmrestd did not report a job state".to_owned()))
+}
+
+/// Decode a TRES list of the form `[{type, name, id, count}, ...]`.
+fn tres(v: &Value) -> BTreeMap<u32, u64> {
+    let mut amounts = BTreeMap::new();
+
+    if let Some(items) = v.as_array() {
+        for item in items {
+            if let (Some(id), Some(count)) = (number(&item["id"]), number(&item["count"])) {
+                amounts.insert(id as u32, count);
+            }
+        }
+    }
+
+    amounts
+}
+
+fn job_info(job: &Value) -> Result<JobInfo, Error> {
+    Ok(JobInfo {
+        account: string(&job["account"]),
+        admin_comment: string(&job["admin_comment"]),
+        array_job_id: number(&job["array_job_id"])
+            .filter(|j| *j != 0)
+            .map(|j| j as u32),
+        array_task_id: number(&job["array_task_id"]).map(|t| t as u32),
+        batch_host: string(&job["batch_host"]),
+        comment: string(&job["comment"]),
+        dependency: string(&job["dependency"]),
+        exit_code: exit_code(&job["exit_code"]),
+        fed_origin: string(&job["federation_origin"]),
+        fed_siblings_active: string_list(&job["federation_siblings_active"]),
+        fed_siblings_viable: string_list(&job["federation_siblings_viable"]),
+        gres_detail: job["gres_detail"]
+            .as_array()
+            .map(|a| a.iter().filter_map(string).collect())
+            .unwrap_or_default(),
+        is_batch: job["batch_flag"].as_bool().unwrap_or(false),
+        job_id: number(&job["job_id"])
+            .ok_or_else(|| Error::Other("slurmrestd returned a job without an ID".to_owned()))?
+            as u32,
+        licenses: string(&job["licenses"]),
+        mcs_label: string(&job["mcs_label"]),
+        memory_per_cpu: memory(&job["memory_per_cpu"]),
+        memory_per_node: memory(&job["memory_per_node"]),
+        name: string(&job["name"]).unwrap_or_default(),
+        nodes: string(&job["nodes"]),
+        num_cpus: number(&job["cpus"]).unwrap_or(0) as u32,
+        partition: string(&job["partition"]).unwrap_or_default(),
+        priority: number(&job["priority"]).unwrap_or(0) as u32,
+        qos: string(&job["qos"]),
+        requeue: job["requeue"].as_bool().unwrap_or(false),
+        restart_cnt: number(&job["restart_cnt"]).unwrap_or(0) as u16,
+        start_time: timestamp(&job["start_time"]),
+        state: state(&job["job_state"])?,
+        state_reason: string(&job["state_reason"]),
+        stderr_path: string(&job["standard_error"]),
+        stdin_path: string(&job["standard_input"]),
+        stdout_path: string(&job["standard_output"]),
+        time_limit: time_limit(&job["time_limit"]),
+        user_id: number(&job["user_id"]).map(|u| u as u32),
+        user_name: string(&job["user_name"]),
+        work_dir: string(&job["current_working_directory"]),
+    })
+}
+
+fn step_record(step: &Value) -> Result<StepRecord, Error> {
+    let id = &step["step"]["id"];
+    let step_id = match id.as_str() {
+        Some(text) => text.rsplit('.').next().unwrap_or(text).to_owned(),
+        None => match id["step_id"].as_str() {
+            Some(text) => text.to_owned(),
+            None => number(&id["step_id"]).unwrap_or(0).to_string(),
+        },
+    };
+    let time = &step["time"];
+    let end_time = timestamp(&time["end"]);
+
+    Ok(StepRecord {
+        elapsed: seconds(&time["elapsed"]),
+        end_time,
+        exit_code: end_time.and(exit_code(&step["exit_code"])),
+        nnodes: number(&step["nodes"]["count"]).unwrap_or(0) as u32,
+        node_list: string(&step["nodes"]["range"]),
+        ntasks: number(&step["tasks"]["count"]).unwrap_or(0) as u32,
+        start_time: timestamp(&time["start"]),
+        state: state(&step["state"])?,
+        step_id,
+        step_name: string(&step["step"]["name"]).unwrap_or_default(),
+        sys_cpu: cpu_time(&time["system"]),
+        tot_cpu: cpu_time(&time["total"]),
+        tres_alloc: tres(&step["tres"]["allocated"]),
+        user_cpu: cpu_time(&time["user"]),
+    })
+}
+
+fn job_record(job: &Value) -> Result<JobRecord, Error> {
+    let time = &job["time"];
+    let end_time = timestamp(&time["end"]);
+    let steps = match job["steps"].as_array() {
+        Some(steps) => steps.iter().map(step_record).collect::<Result<_, _>>()?,
+        None => Vec::new(),
+    };
+
+    Ok(JobRecord {
+        account: string(&job["account"]),
+        alloc_nodes: number(&job["allocation_nodes"]).unwrap_or(0) as u32,
+        array_job_id: number(&job["array"]["job_id"])
+            .filter(|j| *j != 0)
+            .map(|j| j as u32),
+        cluster: string(&job["cluster"]),
+        derived_exit_code: exit_code(&job["derived_exit_code"]).unwrap_or(0),
+        elapsed: seconds(&time["elapsed"]),
+        eligible_time: timestamp(&time["eligible"]),
+        end_time,
+        exit_code: end_time.and(exit_code(&job["exit_code"])),
+        job_id: number(&job["job_id"])
+            .ok_or_else(|| Error::Other("slurmrestd returned a job without an ID".to_owned()))?
+            as u32,
+        job_name: string(&job["name"]).unwrap_or_default(),
+        node_list: string(&job["nodes"]).filter(|n| n != "None assigned"),
+        partition: string(&job["partition"]),
+        priority: number(&job["priority"]).unwrap_or(0) as u32,
+        req_cpus: number(&job["required"]["CPUs"]).unwrap_or(0) as u32,
+        req_memory_per_cpu: memory(&job["required"]["memory_per_cpu"]),
+        req_memory_per_node: memory(&job["required"]["memory_per_node"]),
+        start_time: timestamp(&time["start"]),
+        state: state(&job["state"])?,
+        steps,
+        submit_time: timestamp(&time["submission"]),
+        sys_cpu: cpu_time(&time["system"]),
+        time_limit: time_limit(&time["limit"]),
+        tot_cpu: cpu_time(&time["total"]),
+        tres_alloc: tres(&job["tres"]["allocated"]),
+        tres_req: tres(&job["tres"]["requested"]),
+        user: string(&job["user"]),
+        user_cpu: cpu_time(&time["user"]),
+        wckey: string(&job["wckey"]["wckey"]).or_else(|| string(&job["wckey"])),
+        work_dir: string(&job["working_directory"]),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn number_forms() {
+        assert_eq!(number(&json!(17)), Some(17));
+        assert_eq!(
+            number(&json!({"set": true, "infinite": false, "number": 17})),
+            Some(17)
+        );
+        assert_eq!(number(&json!({"number": 17})), Some(17));
+        assert_eq!(
+            number(&json!({"set": false, "infinite": false, "number": 0})),
+            None
+        );
+        assert_eq!(
+            number(&json!({"set": true, "infinite": true, "number": 0})),
+            None
+        );
+        assert_eq!(number(&json!(null)), None);
+        assert_eq!(number(&json!("17")), None);
+        assert_eq!(number(&number_object(42, false)), Some(42));
+    }
+
+    #[test]
+    fn exit_code_forms() {
+        assert_eq!(exit_code(&json!(256)), Some(256));
+        assert_eq!(exit_code(&json!(null)), None);
+        assert_eq!(
+            exit_code(
+                &json!({"status": ["EXITED"], "return_code": {"set": true, "infinite": false, "number": 3}})
+            ),
+            Some(3 << 8)
+        );
+        assert_eq!(
+            exit_code(
+                &json!({"status": ["SIGNALED"], "return_code": 0, "signal": {"id": 9, "name": "KILL"}})
+            ),
+            Some(9)
+        );
+        assert_eq!(
+            exit_code(&json!({"return_code": 0, "signal": {"signal_id": 15}})),
+            Some(15)
+        );
+        assert_eq!(exit_code(&json!({"return_code": 0})), Some(0));
+    }
+
+    #[test]
+    fn state_forms() {
+        assert_eq!(state(&json!("COMPLETED")).unwrap(), "COMPLETED");
+        assert_eq!(
+            state(&json!(["RUNNING", "CONFIGURING"])).unwrap(),
+            "RUNNING"
+        );
+        assert_eq!(
+            state(&json!({"current": "FAILED", "reason": "None"})).unwrap(),
+            "FAILED"
+        );
+        assert_eq!(
+            state(&json!({"current": ["PENDING"], "reason": "Priority"})).unwrap(),
+            "PENDING"
+        );
+        assert!(state(&json!(null)).is_err());
+        assert!(state(&json!([])).is_err());
+    }
+
+    #[test]
+    fn step_record_decoding() {
+        let step = step_record(&json!({
+            "step": {"id": "1234.batch", "name": "batch"},
+            "time": {
+                "elapsed": 60,
+                "start": 1700000000,
+                "end": 1700000060,
+                "system": {"seconds": 1, "microseconds": 500000},
+                "user": {"seconds": 10, "microseconds": 0},
+                "total": {"seconds": 11, "microseconds": 500000}
+            },
+            "exit_code": {"status": ["EXITED"], "return_code": 1},
+            "nodes": {"count": 1, "range": "node01"},
+            "tasks": {"count": 4},
+            "state": "FAILED",
+            "tres": {"allocated": [{"type": "cpu", "id": 1, "count": 4}]}
+        }))
+        .unwrap();
+
+        assert_eq!(step.step_id, "batch");
+        assert_eq!(step.step_name, "batch");
+        assert_eq!(step.state, "FAILED");
+        assert_eq!(step.exit_code, Some(1 << 8));
+        assert_eq!(step.elapsed, Duration::seconds(60));
+        assert_eq!(step.sys_cpu, Duration::milliseconds(1500));
+        assert_eq!(step.tot_cpu, Duration::milliseconds(11500));
+        assert_eq!(step.nnodes, 1);
+        assert_eq!(step.ntasks, 4);
+        assert_eq!(step.node_list.as_deref(), Some("node01"));
+        assert_eq!(step.tres_alloc.get(&1), Some(&4));
+        assert_eq!(step.start_time, Utc.timestamp_opt(1700000000, 0).single());
+
+        // Older API versions give the step ID separately, possibly as a
+        // number.
+        let old = step_record(&json!({
+            "step": {"id": {"job_id": 1234, "step_id": 2}},
+            "time": {"end": 0},
+            "exit_code": 0,
+            "state": "RUNNING"
+        }))
+        .unwrap();
+
+        assert_eq!(old.step_id, "2");
+        assert_eq!(old.exit_code, None);
+        assert_eq!(old.end_time, None);
+        assert!(step_record(&json!({"step": {"id": "1.0"}})).is_err());
+    }
+
+    #[test]
+    fn job_record_decoding() {
+        let job = job_record(&json!({
+            "job_id": 1234,
+            "name": "sweep",
+            "account": "astro",
+            "cluster": "main",
+            "partition": "",
+            "nodes": "None assigned",
+            "user": "alice",
+            "array": {"job_id": 0},
+            "allocation_nodes": 0,
+            "priority": {"set": true, "infinite": false, "number": 100},
+            "required": {
+                "CPUs": 2,
+                "memory_per_cpu": {"set": true, "infinite": false, "number": 0},
+                "memory_per_node": {"set": true, "infinite": false, "number": 4096}
+            },
+            "time": {
+                "elapsed": 0,
+                "eligible": 1700000000,
+                "end": 0,
+                "start": 0,
+                "submission": 1700000000,
+                "limit": {"set": true, "infinite": true, "number": 0}
+            },
+            "exit_code": {"return_code": 0},
+            "derived_exit_code": {"return_code": 2},
+            "state": {"current": ["PENDING"], "reason": "Priority"},
+            "tres": {
+                "allocated": [],
+                "requested": [{"type": "cpu", "id": 1, "count": 2}, {"type": "mem", "id": 2, "count": 4096}]
+            },
+            "wckey": {"wckey": "", "flags": []},
+            "working_directory": "/home/alice",
+            "steps": []
+        }))
+        .unwrap();
+
+        assert_eq!(job.job_id, 1234);
+        assert_eq!(job.job_name, "sweep");
+        assert_eq!(job.state, "PENDING");
+        assert_eq!(job.partition, None);
+        assert_eq!(job.node_list, None);
+        assert_eq!(job.array_job_id, None);
+        assert_eq!(job.priority, 100);
+        assert_eq!(job.req_cpus, 2);
+        assert_eq!(job.req_memory_per_cpu, None);
+        assert_eq!(job.req_memory_per_node, Some(4096));
+        assert_eq!(job.time_limit, Some(TimeLimit::Unlimited));
+        assert_eq!(job.end_time, None);
+        assert_eq!(job.exit_code, None);
+        assert_eq!(job.derived_exit_code, 2 << 8);
+        assert_eq!(job.tres_req.get(&2), Some(&4096));
+        assert!(job.tres_alloc.is_empty());
+        assert_eq!(job.wckey, None);
+        assert_eq!(job.work_dir.as_deref(), Some("/home/alice"));
+        assert!(job.steps.is_empty());
+
+        let finished = job_record(&json!({
+            "job_id": 99,
+            "array": {"job_id": 98},
+            "state": "COMPLETED",
+            "time": {"start": 1700000000, "end": 1700000600, "limit": 30},
+            "exit_code": 0,
+            "steps": [{"step": {"id": "99.0"}, "state": "COMPLETED"}]
+        }))
+        .unwrap();
+
+        assert_eq!(finished.array_job_id, Some(98));
+        assert_eq!(finished.exit_code, Some(0));
+        assert_eq!(finished.time_limit, Some(TimeLimit::Minutes(30)));
+        assert_eq!(finished.steps.len(), 1);
+        assert_eq!(finished.steps[0].step_id, "0");
+
+        assert!(job_record(&json!({"state": "COMPLETED"})).is_err());
+    }
+
+    #[test]
+    fn job_submission_encoding() {
+        let job = JobSubmission {
+            script: "#!/bin/sh\ntrue\n".to_owned(),
+            name: Some("test".to_owned()),
+            time_limit: Some(TimeLimit::Minutes(10)),
+            memory_per_cpu: Some(2000),
+            ..Default::default()
+        };
+        let payload = job.to_json();
+        let desc = &payload["job"];
+
+        assert_eq!(desc["name"], "test");
+        assert_eq!(desc["environment"], json!([]));
+        assert_eq!(number(&desc["time_limit"]), Some(10));
+        assert_eq!(number(&desc["memory_per_cpu"]), Some(2000));
+        assert!(desc.get("memory_per_node").is_none());
+        assert!(desc.get("partition").is_none());
+    }
+}
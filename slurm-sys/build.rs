@@ -14,7 +14,7 @@
 //! be a disaster if we actually wanted to run the resulting code, but we
 //! don't.
 //!
-//! In this case we download the pre-generated file using `wget` since it is
+//! In this case we download the pre-generated file using `curl` since it is
 //! available on docs.rs and we avoid having to link this file with all sorts
 //! of network libraries. We could store it in Git, but the file is big and I
 //! want to avoid the possibility of confusion.
@@ -23,12 +23,16 @@
 //! built on docs.rs in order to activate the hack! That's done by (ab)using
 //! the `rustc_args` and `rustdoc_args` properties of the
 //! `package.metadata.docs.rs` section of Cargo.toml.
-
-extern crate bindgen;
-extern crate pkg_config;
+//!
+//! Outside of docs.rs, the same approach of skipping bindgen can be requested
+//! with the `SLURM_BINDINGS_FILE` environment variable; see README.md. Unlike
+//! on docs.rs, the libraries are still linked in that case. We don't ship
+//! bindings for particular Slurm releases: the bindings depend on the exact
+//! headers and target architecture, so users generate their own with
+//! `SLURM_BINDINGS_SAVE` on a machine that has clang and the headers.
 
 use std::env;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::prelude::*;
 use std::io::BufReader;
 use std::path::PathBuf;
@@ -38,12 +42,57 @@ const PREBUILT_BINDINGS_URL: &str =
     "https://gist.github.com/pkgw/40e36f9dc0d771323205fc0617ac7141/\
      raw/6405dba98cd0eec7fab483b3d090b919e1383094/bindings.rs";
 
+/// The system libraries that a static libslurm needs by default.
+const DEFAULT_STATIC_SYSTEM_LIBS: &str = "pthread dl m resolv";
+
 fn main() {
     let mut do_the_bindgen = true;
     let mut builder = bindgen::Builder::default().header("src/wrapper.h");
     let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
     let bindings_path = out_dir.join("bindings.rs");
 
+    // Controller-only installs may lack libslurmdb. We still generate
+    // bindings for its functions (they come from the same headers), but we
+    // only link with it if the `slurmdb` feature is enabled.
+    let link_slurmdb = env::var_os("CARGO_FEATURE_SLURMDB").is_some();
+
+    // Static linking can be requested with either the `static` feature or
+    // the `SLURM_STATIC` environment variable.
+    let link_static = env::var_os("CARGO_FEATURE_STATIC").is_some()
+        || target_env("SLURM_STATIC")
+            .map(|v| v == "1")
+            .unwrap_or(false);
+    let link_kind = if link_static { "static" } else { "dylib" };
+
+    // When cross-compiling, clang must parse the headers for the target
+    // rather than the host, and all of the files should come from the
+    // target's sysroot if one is given.
+    let target = env::var("TARGET").unwrap();
+    let cross = target != env::var("HOST").unwrap();
+    let sysroot = target_env("SLURM_SYSROOT");
+
+    if cross {
+        builder = builder.clang_arg(format!("--target={}", target));
+    }
+
+    if let Some(ref sysroot) = sysroot {
+        builder = builder.clang_arg(format!("--sysroot={}", sysroot));
+    }
+
+    // Bindings may also be provided ahead of time, so that machines without
+    // clang or the Slurm headers can build this crate. We still need to link
+    // with the libraries, so this doesn't affect the code below.
+    if let Some(path) = target_env("SLURM_BINDINGS_FILE").map(PathBuf::from) {
+        do_the_bindgen = false;
+        fs::copy(&path, &bindings_path).unwrap_or_else(|e| {
+            panic!(
+                "couldn't copy pre-generated bindings file {}: {}",
+                path.display(),
+                e
+            )
+        });
+    }
+
     if cfg!(slurmrs_on_docs_rs) {
         // Activate the hack!
         do_the_bindgen = false;
@@ -54,40 +103,88 @@ fn main() {
             .arg(PREBUILT_BINDINGS_URL)
             .status()
             .expect("failed to execute process");
-    } else if let Ok(libdir) = env::var("SLURM_LIBDIR") {
+    } else if let Some(libdir) = target_env("SLURM_LIBDIR") {
         // Some Slurm installs don't have a pkg-config file.
-        println!("cargo:rustc-link-search=native={}", libdir);
-        println!("cargo:rustc-link-lib=dylib=slurm");
-        println!("cargo:rustc-link-lib=dylib=slurmdb");
+        link_from_dir(&libdir, link_kind, link_slurmdb);
 
-        if let Ok(incdir) = env::var("SLURM_INCDIR") {
+        if let Some(incdir) = target_env("SLURM_INCDIR") {
             builder = builder.clang_arg(format!("-I{}", incdir));
         }
     } else {
-        let slurm = pkg_config::Config::new()
+        if let Some(ref sysroot) = sysroot {
+            configure_pkg_config(sysroot, cross);
+        }
+
+        match pkg_config::Config::new()
             .atleast_version("15.0")
+            .statik(link_static)
             .probe("slurm")
-            .unwrap();
+        {
+            Ok(slurm) => {
+                if link_slurmdb {
+                    println!("cargo:rustc-link-lib={}=slurmdb", link_kind);
+                }
+
+                for ref path in &slurm.include_paths {
+                    builder = builder.clang_arg(format!("-I{}", path.display()));
+                }
+            }
+
+            Err(e) => {
+                // Sysroots assembled by copying files from the target
+                // machine often lack working pkg-config files, so look in
+                // the standard places.
+                let libdir = sysroot.as_ref().and_then(|s| find_sysroot_libdir(s));
+
+                match libdir {
+                    Some(libdir) => {
+                        link_from_dir(&libdir.display().to_string(), link_kind, link_slurmdb);
+                        let incdir = PathBuf::from(sysroot.as_ref().unwrap()).join("usr/include");
+                        builder = builder.clang_arg(format!("-I{}", incdir.display()));
+                    }
 
-        println!("cargo:rustc-link-lib=dylib=slurmdb");
+                    None => panic!("could not find Slurm with pkg-config: {}", e),
+                }
+            }
+        }
+    }
 
-        for ref path in &slurm.include_paths {
-            builder = builder.clang_arg(format!("-I{}", path.display()));
+    if link_static && !cfg!(slurmrs_on_docs_rs) {
+        // The static libraries don't carry their dependencies with them, so
+        // we have to name them ourselves. The default list suffices for
+        // typical builds, but it can be overridden.
+        let system_libs = target_env("SLURM_STATIC_LIBS")
+            .unwrap_or_else(|| DEFAULT_STATIC_SYSTEM_LIBS.to_owned());
+
+        for lib in system_libs.split(|c: char| c == ',' || c.is_whitespace()) {
+            if !lib.is_empty() {
+                println!("cargo:rustc-link-lib=dylib={}", lib);
+            }
         }
     }
 
     if do_the_bindgen {
         let bindings = builder
+            .whitelist_type("assoc_shares_object_t")
             .whitelist_type("job_.*")
+            .whitelist_type("node_.*")
+            .whitelist_type("priority_factors_.*")
+            .whitelist_type("shares_.*")
             .whitelist_type("slurm_.*")
             .whitelist_type("slurmdb_.*")
             .whitelist_function("slurm_.*")
             .whitelist_function("slurmdb_.*")
             .whitelist_var("ESCRIPT.*")
             .whitelist_var("ESLURM.*")
+            .whitelist_var("JOB_.*")
+            .whitelist_var("JOBCOND_FLAG_.*")
+            .whitelist_var("KILL_.*")
+            .whitelist_var("NODE_STATE_.*")
+            .whitelist_var("PREEMPT_MODE_.*")
             .whitelist_var("SLURM.*")
             .whitelist_var("SLURMDB.*")
             .whitelist_var("SLURMRS.*")
+            .whitelist_var("SHOW_.*")
             .rustfmt_bindings(true)
             .generate()
             .expect("Unable to generate bindings");
@@ -95,6 +192,12 @@ fn main() {
         bindings
             .write_to_file(&bindings_path)
             .expect("Couldn't write bindings!");
+
+        // This makes it easy to create the files for SLURM_BINDINGS_FILE.
+        if let Some(path) = target_env("SLURM_BINDINGS_SAVE") {
+            fs::copy(&bindings_path, &path)
+                .unwrap_or_else(|e| panic!("couldn't save bindings to {}: {}", path, e));
+        }
     }
 
     // Now, we (grossly) parse the bindings file and emit a second file. This
@@ -104,56 +207,120 @@ fn main() {
     // the C code supports. This all is the least-bad approach I can devise
     // that deals with the fact that the C API is not super stable.
 
-    let bindings_file = File::open(&bindings_path).expect(&format!(
-        "couldn't open bindgen output file {}",
-        bindings_path.display()
-    ));
+    let bindings_file = File::open(&bindings_path).unwrap_or_else(|e| {
+        panic!(
+            "couldn't open bindgen output file {}: {}",
+            bindings_path.display(),
+            e
+        )
+    });
     let bindings_buf = BufReader::new(bindings_file);
 
-    let features_path = out_dir.join("features.rs");
-    let mut features_file = File::create(&features_path).expect(&format!(
-        "couldn't create features output file {}",
-        features_path.display()
-    ));
+    let mut features = FeaturesFile::create(out_dir.join("features.rs"));
 
-    writeln!(features_file, "pub const C_API_FEATURES: &[&str] = &[").expect(&format!(
-        "couldn't write to features output file {}",
-        features_path.display()
-    ));
+    features.line("pub const C_API_FEATURES: &[&str] = &[");
 
     #[derive(Clone, Copy, Debug, Eq, PartialEq)]
     enum State {
         Scanning,
+        CheckingJobCondT,
+        CheckingJobRecT,
         CheckingSelectedStepT,
+        CheckingStepRecT,
         CheckingSubmitResponseMsg,
+        CheckingQosAdd,
+        CheckingKillJob2,
+        CheckingLoadPriorityFactors,
+        CheckingPriorityFactorsObject,
     }
 
     let mut state = State::Scanning;
     let mut n_lines = 0;
+    let mut error_names = Vec::new();
 
     for maybe_line in bindings_buf.lines() {
-        let line = maybe_line.expect(&format!(
-            "couldn't read bindgen output file {}",
-            bindings_path.display()
-        ));
+        let line = maybe_line.unwrap_or_else(|e| {
+            panic!(
+                "couldn't read bindgen output file {}: {}",
+                bindings_path.display(),
+                e
+            )
+        });
         n_lines += 1;
 
         match state {
             State::Scanning => {
-                if line.starts_with("pub struct slurmdb_selected_step_t {") {
+                if let Some(name) = error_constant_name(&line) {
+                    error_names.push(name.to_owned());
+                }
+
+                if line.starts_with("pub struct slurmdb_job_cond_t {") {
+                    state = State::CheckingJobCondT;
+                } else if line.starts_with("pub struct slurmdb_job_rec_t {") {
+                    state = State::CheckingJobRecT;
+                } else if line.starts_with("pub struct slurmdb_selected_step_t {") {
                     state = State::CheckingSelectedStepT;
+                } else if line.starts_with("pub struct slurmdb_step_rec_t {") {
+                    state = State::CheckingStepRecT;
+                } else if line.starts_with("pub struct priority_factors_object {") {
+                    state = State::CheckingPriorityFactorsObject;
                 } else if line.starts_with("pub struct submit_response_msg {") {
                     state = State::CheckingSubmitResponseMsg;
+                } else if line.trim_start().starts_with("pub fn slurmdb_qos_add(") {
+                    // Function declarations are indented inside `extern`
+                    // blocks, and may or may not be split across lines.
+                    if line.contains(";") {
+                        if line.contains("uid: u32") {
+                            features.feature("qos_add_uid");
+                        }
+                    } else {
+                        state = State::CheckingQosAdd;
+                    }
+                } else if line.trim_start().starts_with("pub fn slurm_kill_job2(") {
+                    // Slurm 20.11 added a federation `sibling` argument.
+                    if line.contains(";") {
+                        if line.contains("sibling:") {
+                            features.feature("kill_job2_sibling");
+                        }
+                    } else {
+                        state = State::CheckingKillJob2;
+                    }
+                } else if line
+                    .trim_start()
+                    .starts_with("pub fn slurm_load_priority_factors(")
+                {
+                    // Slurm 23.02 dropped the request argument; filtering is
+                    // left to the client.
+                    if line.contains(";") {
+                        if line.contains("priority_factors_request_msg_t") {
+                            features.feature("load_priority_factors_request");
+                        }
+                    } else {
+                        state = State::CheckingLoadPriorityFactors;
+                    }
                 } else if line.starts_with("pub const job_states_JOB_DEADLINE") {
-                    writeln!(features_file, "\"job_state_deadline\",").expect(&format!(
-                        "couldn't write to features output file {}",
-                        features_path.display()
-                    ));
+                    features.feature("job_state_deadline");
                 } else if line.starts_with("pub const job_states_JOB_OOM") {
-                    writeln!(features_file, "\"job_state_oom\",").expect(&format!(
-                        "couldn't write to features output file {}",
-                        features_path.display()
-                    ));
+                    features.feature("job_state_oom");
+                }
+            }
+
+            State::CheckingJobCondT => {
+                if line == "}" {
+                    state = State::Scanning;
+                } else if line.contains("pub flags: u32") {
+                    // Slurm 20.02 replaced `duplicates`,
+                    // `without_usage_truncation`, and friends with flag bits.
+                    features.feature("job_cond_t_flags");
+                }
+            }
+
+            State::CheckingJobRecT => {
+                if line == "}" {
+                    state = State::Scanning;
+                } else if line.contains("pub script: *mut") {
+                    // Slurm 21.08 can store batch scripts in the database.
+                    features.feature("job_rec_t_script");
                 }
             }
 
@@ -161,12 +328,61 @@ fn main() {
                 if line == "}" {
                     state = State::Scanning;
                 } else if line.contains("pack_job_offset") {
-                    writeln!(features_file, "\"selected_step_t_pack_job_offset\",").expect(
-                        &format!(
-                            "couldn't write to features output file {}",
-                            features_path.display()
-                        ),
-                    );
+                    features.feature("selected_step_t_pack_job_offset");
+                } else if line.contains("het_job_offset") {
+                    features.feature("selected_step_t_het_job_offset");
+                } else if line.contains("step_id: slurm_step_id_t") {
+                    // Slurm 20.11 replaced the flat `jobid` and `stepid`
+                    // fields with a `slurm_step_id_t` structure.
+                    features.feature("selected_step_t_step_id");
+                }
+            }
+
+            State::CheckingStepRecT => {
+                if line == "}" {
+                    state = State::Scanning;
+                } else if line.contains("step_id: slurm_step_id_t") {
+                    features.feature("step_rec_t_step_id");
+                }
+            }
+
+            State::CheckingQosAdd => {
+                if line.contains("uid: u32") {
+                    features.feature("qos_add_uid");
+                }
+
+                if line.contains(";") {
+                    state = State::Scanning;
+                }
+            }
+
+            State::CheckingKillJob2 => {
+                if line.contains("sibling:") {
+                    features.feature("kill_job2_sibling");
+                }
+
+                if line.contains(";") {
+                    state = State::Scanning;
+                }
+            }
+
+            State::CheckingLoadPriorityFactors => {
+                if line.contains("priority_factors_request_msg_t") {
+                    features.feature("load_priority_factors_request");
+                }
+
+                if line.contains(";") {
+                    state = State::Scanning;
+                }
+            }
+
+            State::CheckingPriorityFactorsObject => {
+                if line == "}" {
+                    state = State::Scanning;
+                } else if line.contains("prio_factors:") {
+                    // Slurm 23.02 moved the factors into a separate
+                    // `priority_factors_t` structure.
+                    features.feature("priority_factors_t");
                 }
             }
 
@@ -174,10 +390,7 @@ fn main() {
                 if line == "}" {
                     state = State::Scanning;
                 } else if line.contains("job_submit_user_msg") {
-                    writeln!(features_file, "\"submit_response_user_message\",").expect(&format!(
-                        "couldn't write to features output file {}",
-                        features_path.display()
-                    ));
+                    features.feature("submit_response_user_message");
                 }
             }
         }
@@ -189,8 +402,148 @@ fn main() {
         "to build this crate you must install a functional \"rustfmt\" (see README.md)"
     );
 
-    writeln!(features_file, "];").expect(&format!(
-        "couldn't write to features output file {}",
-        features_path.display()
-    ));
+    features.line("];");
+
+    // We also record the names of all of the error codes defined by the C
+    // API, so that the main crate can enumerate them.
+
+    features.line("pub const C_API_ERRORS: &[&str] = &[");
+
+    for name in &error_names {
+        features.feature(name);
+    }
+
+    features.line("];");
+}
+
+/// The file in which we record what we learn about the C API, for use by the
+/// `slurm` crate's build script.
+struct FeaturesFile {
+    file: File,
+    path: PathBuf,
+}
+
+impl FeaturesFile {
+    fn create(path: PathBuf) -> FeaturesFile {
+        let file = File::create(&path).unwrap_or_else(|e| {
+            panic!(
+                "couldn't create features output file {}: {}",
+                path.display(),
+                e
+            )
+        });
+
+        FeaturesFile { file, path }
+    }
+
+    /// Write a line of Rust code to the file.
+    fn line(&mut self, text: &str) {
+        writeln!(self.file, "{}", text).unwrap_or_else(|e| {
+            panic!(
+                "couldn't write to features output file {}: {}",
+                self.path.display(),
+                e
+            )
+        });
+    }
+
+    /// Write an item in one of the string-list constants.
+    fn feature(&mut self, name: &str) {
+        self.line(&format!("\"{}\",", name));
+    }
+}
+
+/// Get the value of an environment variable that can be customized for the
+/// target platform.
+///
+/// As with the `cc` and `pkg-config` crates, the variable `NAME_<target>` is
+/// preferred, where `<target>` is the target triple either as-is or with
+/// dashes replaced by underscores. Plain `NAME` is the fallback.
+fn target_env(name: &str) -> Option<String> {
+    let target = env::var("TARGET").unwrap();
+
+    env::var(format!("{}_{}", name, target))
+        .or_else(|_| env::var(format!("{}_{}", name, target.replace("-", "_"))))
+        .or_else(|_| env::var(name))
+        .ok()
+}
+
+/// Emit the directives to link with the Slurm libraries in a directory.
+fn link_from_dir(libdir: &str, link_kind: &str, link_slurmdb: bool) {
+    println!("cargo:rustc-link-search=native={}", libdir);
+    println!("cargo:rustc-link-lib={}=slurm", link_kind);
+
+    if link_slurmdb {
+        println!("cargo:rustc-link-lib={}=slurmdb", link_kind);
+    }
+}
+
+/// Point pkg-config at the files in a sysroot.
+///
+/// Settings made by the user take precedence. The pkg-config crate reads
+/// these variables from the environment of the build script, so we set them
+/// there.
+fn configure_pkg_config(sysroot: &str, cross: bool) {
+    if target_env("PKG_CONFIG_SYSROOT_DIR").is_none() {
+        env::set_var("PKG_CONFIG_SYSROOT_DIR", sysroot);
+    }
+
+    if target_env("PKG_CONFIG_LIBDIR").is_none() && target_env("PKG_CONFIG_PATH").is_none() {
+        let dirs: Vec<String> = [
+            "usr/lib64/pkgconfig",
+            "usr/lib/pkgconfig",
+            "usr/share/pkgconfig",
+        ]
+        .iter()
+        .map(|d| PathBuf::from(sysroot).join(d).display().to_string())
+        .collect();
+        env::set_var("PKG_CONFIG_LIBDIR", dirs.join(":"));
+    }
+
+    // By default the pkg-config crate refuses to work when cross-compiling,
+    // since it would find the host's libraries. With a sysroot, it won't.
+    if cross && env::var_os("PKG_CONFIG_ALLOW_CROSS").is_none() {
+        env::set_var("PKG_CONFIG_ALLOW_CROSS", "1");
+    }
+}
+
+/// Look for the Slurm libraries in the standard library directories of a
+/// sysroot.
+fn find_sysroot_libdir(sysroot: &str) -> Option<PathBuf> {
+    let target = env::var("TARGET").unwrap();
+    let mut multiarch = target.splitn(2, '-').next().unwrap().to_owned();
+    multiarch.push_str("-linux-gnu");
+
+    let candidates = vec![
+        "usr/lib64".to_owned(),
+        "usr/lib".to_owned(),
+        format!("usr/lib/{}", multiarch),
+    ];
+
+    candidates
+        .iter()
+        .map(|d| PathBuf::from(sysroot).join(d))
+        .find(|d| d.join("libslurm.so").exists() || d.join("libslurm.a").exists())
+}
+
+/// If this line of the bindings defines one of Slurm's error codes, return
+/// the name of the code.
+///
+/// Besides the `ESLURM*` codes, we pick out the communication errors, which
+/// don't follow that naming convention.
+fn error_constant_name(line: &str) -> Option<&str> {
+    if !line.starts_with("pub const ") {
+        return None;
+    }
+
+    let name = line["pub const ".len()..].split(':').next().unwrap_or("");
+
+    if name.starts_with("ESLURM")
+        || name.contains("_COMMUNICATIONS_")
+        || name.starts_with("SLURM_PROTOCOL_SOCKET_")
+    {
+        Some(name)
+    } else {
+        None
+    }
 }
@@ -9,14 +9,75 @@
 
 enum {
     SLURMRS_NO_VAL = NO_VAL,
+    SLURMRS_INFINITE = INFINITE,
 };
 
 #ifdef NO_VAL64
 enum {
     SLURMRS_NO_VAL64 = NO_VAL64,
+    SLURMRS_INFINITE64 = INFINITE64,
 };
 #endif
 
+/* Job flag bits. Since Slurm 20.11 these are defined with the function-like
+ * SLURM_BIT() macro, which bindgen can't evaluate. Flags that a given
+ * version of Slurm lacks are defined as zero, so that they never match. */
+
+enum {
+#ifdef GRES_ENFORCE_BIND
+    SLURMRS_GRES_ENFORCE_BIND = GRES_ENFORCE_BIND,
+#else
+    SLURMRS_GRES_ENFORCE_BIND = 0,
+#endif
+#ifdef KILL_INV_DEP
+    SLURMRS_KILL_INV_DEP = KILL_INV_DEP,
+#else
+    SLURMRS_KILL_INV_DEP = 0,
+#endif
+#ifdef NO_KILL_INV_DEP
+    SLURMRS_NO_KILL_INV_DEP = NO_KILL_INV_DEP,
+#else
+    SLURMRS_NO_KILL_INV_DEP = 0,
+#endif
+#ifdef NODE_REBOOT
+    SLURMRS_NODE_REBOOT = NODE_REBOOT,
+#else
+    SLURMRS_NODE_REBOOT = 0,
+#endif
+#ifdef SPREAD_JOB
+    SLURMRS_SPREAD_JOB = SPREAD_JOB,
+#else
+    SLURMRS_SPREAD_JOB = 0,
+#endif
+#ifdef USE_MIN_NODES
+    SLURMRS_USE_MIN_NODES = USE_MIN_NODES,
+#else
+    SLURMRS_USE_MIN_NODES = 0,
+#endif
+};
+
+/* Accounting query flags. Slurm 20.02 replaced several fields of
+ * `slurmdb_job_cond_t` with these bits, and 20.11 switched them to
+ * SLURM_BIT() as above. Flags that a given version lacks are zero. */
+
+enum {
+#ifdef JOBCOND_FLAG_DUP
+    SLURMRS_JOBCOND_FLAG_DUP = JOBCOND_FLAG_DUP,
+#else
+    SLURMRS_JOBCOND_FLAG_DUP = 0,
+#endif
+#ifdef JOBCOND_FLAG_NO_TRUNC
+    SLURMRS_JOBCOND_FLAG_NO_TRUNC = JOBCOND_FLAG_NO_TRUNC,
+#else
+    SLURMRS_JOBCOND_FLAG_NO_TRUNC = 0,
+#endif
+#ifdef JOBCOND_FLAG_SCRIPT
+    SLURMRS_JOBCOND_FLAG_SCRIPT = JOBCOND_FLAG_SCRIPT,
+#else
+    SLURMRS_JOBCOND_FLAG_SCRIPT = 0,
+#endif
+};
+
 /* The official API doesn't expose the memory management functions,
  * but we need them: see discussion in the Rust docs. */
 
@@ -17,10 +17,244 @@
 //! a new version adds a new major feature and certain upstream programs need
 //! to know that it is available. We don't have that situation yet, though.)
 
-extern crate slurm_sys;
+//!
+//! We also use the list of error codes defined by the C API to generate the
+//! complete enumeration of the `SlurmError` type, sorting them into broad
+//! categories with an explicit table.
+
+use std::env;
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::PathBuf;
+
+/// All of the C API features that `slurm-sys` might detect.
+///
+/// These are declared to the compiler so that it can check our `#[cfg]`
+/// directives for typos.
+const KNOWN_C_API_FEATURES: &[&str] = &[
+    "job_cond_t_flags",
+    "job_rec_t_script",
+    "job_state_deadline",
+    "job_state_oom",
+    "kill_job2_sibling",
+    "load_priority_factors_request",
+    "priority_factors_t",
+    "qos_add_uid",
+    "selected_step_t_het_job_offset",
+    "selected_step_t_pack_job_offset",
+    "selected_step_t_step_id",
+    "step_rec_t_step_id",
+    "submit_response_user_message",
+];
 
 fn main() {
+    println!("cargo:rustc-check-cfg=cfg(slurmrs_on_docs_rs)");
+
+    for feat in KNOWN_C_API_FEATURES {
+        println!("cargo:rustc-check-cfg=cfg(slurm_api_{})", feat);
+    }
+
     for feat in slurm_sys::C_API_FEATURES {
         println!("cargo:rustc-cfg=slurm_api_{}", feat);
     }
+
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    let errors_path = out_dir.join("errors.rs");
+    let mut errors_file = File::create(&errors_path).unwrap_or_else(|e| {
+        panic!(
+            "couldn't create errors output file {}: {}",
+            errors_path.display(),
+            e
+        )
+    });
+
+    writeln!(errors_file, "declare_slurm_errors!(").unwrap_or_else(|e| {
+        panic!(
+            "couldn't write to errors output file {}: {}",
+            errors_path.display(),
+            e
+        )
+    });
+
+    let mut seen = std::collections::HashSet::new();
+    let mut first = true;
+
+    for name in slurm_sys::C_API_ERRORS {
+        let rust_name = rust_error_name(name);
+
+        if !seen.insert(rust_name.clone()) {
+            continue;
+        }
+
+        if !first {
+            writeln!(errors_file, ",").unwrap_or_else(|e| {
+                panic!(
+                    "couldn't write to errors output file {}: {}",
+                    errors_path.display(),
+                    e
+                )
+            });
+        }
+
+        first = false;
+
+        write!(
+            errors_file,
+            "    <{}, slurm_sys::{}, \"The Slurm error `{}`.\", {};>",
+            rust_name,
+            name,
+            name,
+            error_category(name)
+        )
+        .unwrap_or_else(|e| {
+            panic!(
+                "couldn't write to errors output file {}: {}",
+                errors_path.display(),
+                e
+            )
+        });
+    }
+
+    writeln!(errors_file, "\n);").unwrap_or_else(|e| {
+        panic!(
+            "couldn't write to errors output file {}: {}",
+            errors_path.display(),
+            e
+        )
+    });
+}
+
+/// Convert a C error-code name into a CamelCase Rust variant name.
+///
+/// The common `ESLURM_` and `SLURM_` prefixes are dropped, but other
+/// prefixes (such as `ESLURMD_`) are retained to keep the names distinct.
+fn rust_error_name(name: &str) -> String {
+    let stem = if name.starts_with("ESLURM_") {
+        &name["ESLURM_".len()..]
+    } else if name.starts_with("ESLURMD_") {
+        &name["E".len()..]
+    } else if name.starts_with("SLURM_") {
+        &name["SLURM_".len()..]
+    } else {
+        name
+    };
+
+    stem.split('_')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            let head = chars.next().unwrap().to_ascii_uppercase();
+            let tail: String = chars.map(|c| c.to_ascii_lowercase()).collect();
+            format!("{}{}", head, tail)
+        })
+        .collect()
+}
+
+/// The broad categories of the C error codes that have one, as pairs of a
+/// `SlurmErrorCategory` variant name and the codes in it.
+///
+/// Codes that are not listed here, including any added by future versions of
+/// Slurm, fall into the `Other` category. Codes that a given version of Slurm
+/// lacks are simply never looked up.
+const ERROR_CATEGORIES: &[(&str, &[&str])] = &[
+    (
+        "Communication",
+        &[
+            "SLURMCTLD_COMMUNICATIONS_BACKOFF",
+            "SLURMCTLD_COMMUNICATIONS_CONNECTION_ERROR",
+            "SLURMCTLD_COMMUNICATIONS_RECEIVE_ERROR",
+            "SLURMCTLD_COMMUNICATIONS_SEND_ERROR",
+            "SLURMCTLD_COMMUNICATIONS_SHUTDOWN_ERROR",
+            "SLURM_COMMUNICATIONS_CONNECTION_ERROR",
+            "SLURM_COMMUNICATIONS_MISSING_SOCKET_ERROR",
+            "SLURM_COMMUNICATIONS_RECEIVE_ERROR",
+            "SLURM_COMMUNICATIONS_SEND_ERROR",
+            "SLURM_COMMUNICATIONS_SHUTDOWN_ERROR",
+            "SLURM_PROTOCOL_INSANE_MSG_LENGTH",
+            "SLURM_PROTOCOL_IO_STREAM_VERSION_ERROR",
+            "SLURM_PROTOCOL_SOCKET_IMPL_NEGATIVE_RECV_LENGTH",
+            "SLURM_PROTOCOL_SOCKET_IMPL_NOT_ALL_DATA_SENT",
+            "SLURM_PROTOCOL_SOCKET_IMPL_TIMEOUT",
+            "SLURM_PROTOCOL_SOCKET_IMPL_ZERO_RECV_LENGTH",
+            "SLURM_PROTOCOL_SOCKET_ZERO_BYTES_SENT",
+            "SLURM_PROTOCOL_VERSION_ERROR",
+            "ESLURM_PROTOCOL_INCOMPLETE_PACKET",
+        ],
+    ),
+    (
+        "Auth",
+        &[
+            "SLURM_PROTOCOL_AUTHENTICATION_ERROR",
+            "ESLURM_ACCESS_DENIED",
+            "ESLURM_AUTH_BADARG",
+            "ESLURM_AUTH_CRED_INVALID",
+            "ESLURM_AUTH_EXPIRED",
+            "ESLURM_AUTH_FOPEN_ERROR",
+            "ESLURM_AUTH_NET_ERROR",
+            "ESLURM_AUTH_NOBODY",
+            "ESLURM_AUTH_SKIP",
+            "ESLURM_AUTH_SOCKET_INVALID_PEER",
+            "ESLURM_AUTH_UNABLE_TO_SIGN",
+            "ESLURM_AUTH_UNPACK",
+            "ESLURM_USER_ID_MISSING",
+            "ESLURM_USER_ID_UNKNOWN",
+            "ESLURMD_CREDENTIAL_EXPIRED",
+            "ESLURMD_CREDENTIAL_REVOKED",
+            "ESLURMD_CREDENTIAL_REPLAYED",
+            "ESLURMD_INVALID_JOB_CREDENTIAL",
+        ],
+    ),
+    (
+        "Limits",
+        &[
+            "ESLURM_ACCOUNTING_POLICY",
+            "ESLURM_JOB_SCRIPT_TOO_LARGE",
+            "ESLURM_NODES_BUSY",
+            "ESLURM_PORTS_BUSY",
+            "ESLURM_QOS_THRES",
+            "ESLURM_REQUESTED_PART_CONFIG_UNAVAILABLE",
+            "ESLURM_TOO_MANY_REQUESTED_CPUS",
+            "ESLURM_TOO_MANY_REQUESTED_NODES",
+        ],
+    ),
+    (
+        "InvalidArgument",
+        &[
+            "ESLURM_BAD_DIST",
+            "ESLURM_BAD_NAME",
+            "ESLURM_BAD_TASK_COUNT",
+            "ESLURM_DEPENDENCY",
+            "ESLURM_DUPLICATE_JOB_ID",
+            "ESLURM_ENVIRONMENT_MISSING",
+            "ESLURM_INVALID_ACCOUNT",
+            "ESLURM_INVALID_ARRAY",
+            "ESLURM_INVALID_BURST_BUFFER_REQUEST",
+            "ESLURM_INVALID_CPU_COUNT",
+            "ESLURM_INVALID_DEPENDENCY",
+            "ESLURM_INVALID_FEATURE",
+            "ESLURM_INVALID_GRES",
+            "ESLURM_INVALID_JOB_ID",
+            "ESLURM_INVALID_LICENSES",
+            "ESLURM_INVALID_NODE_COUNT",
+            "ESLURM_INVALID_NODE_NAME",
+            "ESLURM_INVALID_PARTITION_NAME",
+            "ESLURM_INVALID_QOS",
+            "ESLURM_INVALID_TIME_LIMIT",
+            "ESLURM_INVALID_TIME_VALUE",
+            "ESLURM_INVALID_TRES",
+            "ESLURM_INVALID_WCKEY",
+            "ESLURM_MISSING_TIME_LIMIT",
+            "ESLURM_PATHNAME_TOO_LONG",
+            "ESLURM_REQUESTED_NODE_CONFIG_UNAVAILABLE",
+        ],
+    ),
+];
+
+/// Look up the broad category of a C error code.
+fn error_category(name: &str) -> &'static str {
+    ERROR_CATEGORIES
+        .iter()
+        .find(|(_, codes)| codes.contains(&name))
+        .map(|(category, _)| *category)
+        .unwrap_or("Other")
 }
@@ -4,15 +4,9 @@
 /*! Demonstration of querying the Slurmdb job accounting database.
  */
 
-extern crate chrono;
-#[macro_use]
-extern crate clap;
-extern crate failure;
-extern crate slurm;
-
+use anyhow::Error;
 use chrono::Utc;
-use clap::{App, Arg};
-use failure::Error;
+use clap::{crate_version, App, Arg};
 use slurm::JobStepRecordSharedFields;
 use std::process;
 
@@ -46,11 +40,7 @@ fn main() {
 fn inner(jobid: &str) -> Result<i32, Error> {
     let jobid = jobid.parse::<slurm::JobId>()?;
 
-    let mut filter = slurm::JobFiltersOwned::default();
-    filter
-        .step_list_mut()
-        .append(slurm::JobStepFilterOwned::new(jobid));
-
+    let filter = slurm::JobFiltersOwned::for_job(jobid)?;
     let db = slurm::DatabaseConnectionOwned::new()?;
     let jobs = db.get_jobs(&filter)?;
     let now = Utc::now();
@@ -83,8 +73,11 @@ fn inner(jobid: &str) -> Result<i32, Error> {
             println!("  still waiting to start");
         }
 
-        if let Some(t_st) = job.start_time() {
-            let t_limit = t_st + chrono::Duration::minutes(job.time_limit() as i64);
+        if let (Some(t_st), Some(limit)) = (
+            job.start_time(),
+            job.time_limit().and_then(|l| l.as_duration()),
+        ) {
+            let t_limit = t_st + limit;
             let remaining = t_limit.signed_duration_since(now).num_minutes();
             if remaining > 0 {
                 println!("  time left until job hits time limit: {} min", remaining);
@@ -4,16 +4,9 @@
 /*! Make a list of recent jobs belonging to this user.
  */
 
-extern crate chrono;
-#[macro_use]
-extern crate clap;
-extern crate failure;
-extern crate itertools;
-extern crate slurm;
-
+use anyhow::Error;
 use chrono::{Duration, Utc};
-use clap::App;
-use failure::Error;
+use clap::{crate_version, App};
 use itertools::Itertools;
 use slurm::JobStepRecordSharedFields;
 use std::collections::HashMap;
@@ -42,9 +35,7 @@ fn inner() -> Result<i32, Error> {
     let now = Utc::now();
     let min_start = now - Duration::days(7);
 
-    let mut filter = slurm::JobFiltersOwned::default();
-    filter.userid_list_mut().append("555409");
-    filter.usage_start(min_start);
+    let filter = slurm::JobFiltersOwned::for_user_since(555409, min_start)?;
 
     let db = slurm::DatabaseConnectionOwned::new()?;
     let jobs = db.get_jobs(&filter)?;
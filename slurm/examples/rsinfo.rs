@@ -4,13 +4,8 @@
 /*! Print out information about a job.
  */
 
-#[macro_use]
-extern crate clap;
-extern crate failure;
-extern crate slurm;
-
-use clap::{App, Arg};
-use failure::Error;
+use anyhow::Error;
+use clap::{crate_version, App, Arg};
 use std::process;
 
 fn main() {
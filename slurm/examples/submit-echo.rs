@@ -4,14 +4,8 @@
 /*! Submit a hello-world echo job
  */
 
-#[macro_use]
-extern crate clap;
-#[macro_use]
-extern crate failure;
-extern crate slurm;
-
-use clap::App;
-use failure::Error;
+use anyhow::{format_err, Error};
+use clap::{crate_version, App};
 use std::env;
 use std::process;
 
@@ -45,21 +39,21 @@ fn inner() -> Result<i32, Error> {
             .to_owned()
     };
 
-    let mut desc = slurm::JobDescriptorOwned::new();
+    let mut desc = slurm::JobDescriptorOwned::new()?;
 
-    desc.set_name("helloworld")
-        .set_argv(&["helloworld"])
-        .inherit_environment()
-        .set_stderr_path(&log)
-        .set_stdin_path("/dev/null")
-        .set_stdout_path(&log)
+    desc.set_name("helloworld")?
+        .set_argv(&["helloworld"])?
+        .inherit_environment()?
+        .set_stderr_path(&log)?
+        .set_stdin_path("/dev/null")?
+        .set_stdout_path(&log)?
         .set_work_dir_cwd()?
         .set_script(
             "#! /bin/bash
 set -e -x
 echo hello world \"$@\"
 ",
-        )
+        )?
         .set_gid_current() // JobDescriptor args must come after due to the return type
         .set_num_tasks(1)
         .set_time_limit(5)
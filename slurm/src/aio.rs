@@ -0,0 +1,100 @@
+// Copyright 2018 Peter Williams <peter@newton.cx> and collaborators
+// Licensed under the MIT License
+
+/*! Asynchronous versions of the blocking Slurm calls.
+
+This module is only available if the `async` feature is enabled. Each
+function runs the corresponding blocking call on Tokio's blocking-thread
+pool, so these functions must be called from within a Tokio runtime.
+
+The wrapper types of this crate are not `Send`, so they cannot be returned
+from the thread pool. The functions here therefore return plain-Rust
+snapshots of their results. For the same reason, inputs such as query
+filters and job descriptors are passed as closures that construct them on the
+thread that uses them.
+
+```no_run
+async fn print_state(jid: slurm::JobId) -> Result<(), slurm::Error> {
+    let info = slurm::aio::get_job_info(jid).await?;
+    println!("{}: {}", info.job_id, info.state_reason);
+    Ok(())
+}
+```
+
+*/
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::task::JoinHandle;
+
+#[cfg(feature = "slurmdb")]
+use crate::{DatabaseConnectionOwned, JobFiltersOwned, JobRecordSnapshot};
+use crate::{Error, JobDescriptorOwned, JobId, JobIdentifier, JobInfoSnapshot};
+
+/// A future resolving to the result of a blocking call that has been moved
+/// to Tokio's blocking-thread pool.
+#[derive(Debug)]
+pub struct Blocking<T>(JoinHandle<Result<T, Error>>);
+
+impl<T> Future for Blocking<T> {
+    type Output = Result<T, Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        Pin::new(&mut self.0).poll(cx).map(|r| match r {
+            Ok(inner) => inner,
+            Err(e) => Err(format_err!("blocking Slurm call did not complete: {}", e)),
+        })
+    }
+}
+
+fn spawn<T, F>(func: F) -> Blocking<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, Error> + Send + 'static,
+{
+    Blocking(tokio::task::spawn_blocking(func))
+}
+
+/// Get information about a single job.
+///
+/// This is the asynchronous version of `slurm::get_job_info`.
+pub fn get_job_info<J: Into<JobIdentifier>>(jid: J) -> Blocking<JobInfoSnapshot> {
+    let jid = jid.into();
+    spawn(move || Ok(crate::get_job_info(jid)?.to_snapshot()))
+}
+
+/// Query the accounting database for jobs.
+///
+/// A new database connection is opened for the query, using the default
+/// options. The filters are constructed by calling `make_filters` on the
+/// thread that performs the query.
+///
+/// This function is only available if the `slurmdb` feature is enabled.
+#[cfg(feature = "slurmdb")]
+pub fn get_jobs<F>(make_filters: F) -> Blocking<Vec<JobRecordSnapshot>>
+where
+    F: FnOnce() -> Result<JobFiltersOwned, Error> + Send + 'static,
+{
+    spawn(move || {
+        let filters = make_filters()?;
+        let db = DatabaseConnectionOwned::new()?;
+        let jobs = db.get_jobs(&filters)?;
+        Ok(jobs.iter().map(|job| job.to_snapshot()).collect())
+    })
+}
+
+/// Submit a batch job, returning its ID.
+///
+/// The job descriptor is constructed by calling `make_desc` on the thread
+/// that performs the submission.
+pub fn submit_batch<F>(make_desc: F) -> Blocking<JobId>
+where
+    F: FnOnce() -> Result<JobDescriptorOwned, Error> + Send + 'static,
+{
+    spawn(move || {
+        let desc = make_desc()?;
+        let msg = desc.submit_batch()?;
+        Ok(msg.job_id())
+    })
+}
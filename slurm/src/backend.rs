@@ -0,0 +1,241 @@
+// Copyright 2018 Peter Williams <peter@newton.cx> and collaborators
+// Licensed under the MIT License
+
+/*! Abstractions over the ways of talking to Slurm.
+
+The `Scheduler` trait covers queries and submissions handled by the
+controller, `slurmctld`, and the `Accounting` trait covers queries handled by
+the accounting database, `slurmdbd`. Applications that are written against
+these traits, rather than against the C-API wrapper types, can switch between
+the native backend in this module, the REST backend in the `rest` module,
+and the test double in the `mock` module without other changes.
+
+The traits work in terms of the plain-Rust types of this crate, such as
+`JobQuery` and `JobRecordSnapshot`, since only the native backend can
+produce the types that wrap Slurm's own data structures. Helpers that work
+with any backend, such as `get_jobs_sharded`, live here too.
+
+```no_run
+use slurm::backend::{Accounting, NativeBackend};
+
+fn count_jobs<A: Accounting>(backend: &A, user: &str) -> Result<usize, slurm::Error> {
+    let mut query = slurm::JobQuery::new();
+    query.user(user);
+    Ok(backend.get_jobs(&query)?.len())
+}
+
+fn main() {
+    let n = count_jobs(&NativeBackend::new(), "alice").unwrap();
+    println!("{} jobs", n);
+}
+```
+
+*/
+
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use crate::{
+    get_job_info, BatchJobSpec, Error, JobId, JobInfoSnapshot, JobQuery, JobRecordSnapshot,
+};
+#[cfg(feature = "slurmdb")]
+use crate::{DatabaseConnectionOptions, DatabaseConnectionOwned};
+
+/// Access to the Slurm controller.
+pub trait Scheduler {
+    /// Get information about a single job.
+    fn get_job_info(&self, jid: JobId) -> Result<JobInfoSnapshot, Error>;
+
+    /// Submit a batch job, returning its ID.
+    fn submit_batch(&self, job: &BatchJobSpec) -> Result<JobId, Error>;
+}
+
+/// Access to the Slurm accounting database.
+pub trait Accounting {
+    /// Query the database for jobs.
+    fn get_jobs(&self, query: &JobQuery) -> Result<Vec<JobRecordSnapshot>, Error>;
+}
+
+/// The backend that uses the Slurm C library.
+///
+/// This is the default way of talking to Slurm, and it is what the free
+/// functions and wrapper types of this crate use directly. A new database
+/// connection is opened for each accounting query. The `Accounting` trait is
+/// only implemented if the `slurmdb` feature is enabled.
+#[derive(Clone, Debug, Default)]
+pub struct NativeBackend {
+    #[cfg(feature = "slurmdb")]
+    db_options: DatabaseConnectionOptions,
+}
+
+impl NativeBackend {
+    /// Create a new backend that connects to the database with the default
+    /// options.
+    #[must_use]
+    pub fn new() -> NativeBackend {
+        NativeBackend::default()
+    }
+
+    /// Create a new backend that connects to the database with customized
+    /// options.
+    #[cfg(feature = "slurmdb")]
+    #[must_use]
+    pub fn with_options(db_options: DatabaseConnectionOptions) -> NativeBackend {
+        NativeBackend { db_options }
+    }
+}
+
+impl Scheduler for NativeBackend {
+    fn get_job_info(&self, jid: JobId) -> Result<JobInfoSnapshot, Error> {
+        Ok(get_job_info(jid)?.to_snapshot())
+    }
+
+    fn submit_batch(&self, job: &BatchJobSpec) -> Result<JobId, Error> {
+        let desc = job.to_descriptor()?;
+        Ok(desc.submit_batch()?.job_id())
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl Accounting for NativeBackend {
+    fn get_jobs(&self, query: &JobQuery) -> Result<Vec<JobRecordSnapshot>, Error> {
+        let filters = query.to_filters()?;
+        let db = DatabaseConnectionOwned::new_with(&self.db_options)?;
+        let jobs = db.get_jobs(&filters)?;
+        Ok(jobs.iter().map(|job| job.to_snapshot()).collect())
+    }
+}
+
+/// Query for jobs over a long time range by splitting it into shorter chunks.
+///
+/// Queries that span many months can time out on busy databases. This
+/// function splits the window from `start` to `end` into chunks of length
+/// `chunk`, runs `query` on each one, and merges the results. Any usage
+/// window already set in `query` is ignored. If `threads` is greater than
+/// one, up to that many chunks are queried at once; with the native backend,
+/// each query uses its own database connection.
+///
+/// Jobs that were active across a chunk boundary are returned by more than
+/// one chunk; the duplicates are removed. The chunk queries are made without
+/// usage truncation, so that each copy reports the job's true start and end
+/// times. The results are ordered by chunk,
+/// and within each chunk in the order returned by the backend.
+pub fn get_jobs_sharded<A: Accounting + Sync>(
+    backend: &A,
+    query: &JobQuery,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    chunk: Duration,
+    threads: usize,
+) -> Result<Vec<JobRecordSnapshot>, Error> {
+    if chunk <= Duration::zero() {
+        return Err(format_err!("the query chunk length must be positive"));
+    }
+
+    let mut windows = Vec::new();
+    let mut chunk_start = start;
+
+    while chunk_start < end {
+        let chunk_end = std::cmp::min(chunk_start + chunk, end);
+        windows.push((chunk_start, chunk_end));
+        chunk_start = chunk_end;
+    }
+
+    let run = |&(chunk_start, chunk_end): &(DateTime<Utc>, DateTime<Utc>)| {
+        let mut q = query.clone();
+        q.usage_start(chunk_start)
+            .usage_end(chunk_end)
+            .without_usage_truncation(true);
+        backend.get_jobs(&q)
+    };
+
+    let results: Vec<Result<Vec<JobRecordSnapshot>, Error>> = if threads <= 1 {
+        windows.iter().map(run).collect()
+    } else {
+        let next = AtomicUsize::new(0);
+        let slots = Mutex::new((0..windows.len()).map(|_| None).collect::<Vec<_>>());
+
+        std::thread::scope(|scope| {
+            for _ in 0..std::cmp::min(threads, windows.len()) {
+                scope.spawn(|| loop {
+                    let i = next.fetch_add(1, Ordering::SeqCst);
+
+                    if i >= windows.len() {
+                        break;
+                    }
+
+                    let result = run(&windows[i]);
+                    slots.lock().unwrap()[i] = Some(result);
+                });
+            }
+        });
+
+        slots
+            .into_inner()
+            .unwrap()
+            .into_iter()
+            .map(|r| r.unwrap_or_else(|| Err(format_err!("query worker thread failed"))))
+            .collect()
+    };
+
+    let mut seen = HashSet::new();
+    let mut jobs = Vec::new();
+
+    for result in results {
+        for job in result? {
+            if seen.insert((job.cluster.clone(), job.job_id, job.submit_time)) {
+                jobs.push(job);
+            }
+        }
+    }
+
+    Ok(jobs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockCluster;
+    use crate::JobState;
+
+    #[test]
+    fn sharded_query_reports_untruncated_times() {
+        let cluster = MockCluster::new();
+        let jid = cluster
+            .submit_batch(&BatchJobSpec::new("#! /bin/sh\ntrue\n"))
+            .unwrap();
+        cluster.set_state(jid, JobState::Running).unwrap();
+        let started = cluster.get_job_info(jid).unwrap().start_time;
+
+        // The job is still running, so it spans the boundary between the two
+        // chunks.
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        let mid = Utc::now();
+        let start = mid - Duration::hours(1);
+        let end = mid + Duration::hours(1);
+
+        for threads in &[1, 2] {
+            let jobs = get_jobs_sharded(
+                &cluster,
+                &JobQuery::new(),
+                start,
+                end,
+                Duration::hours(1),
+                *threads,
+            )
+            .unwrap();
+            assert_eq!(jobs.len(), 1);
+            assert_eq!(jobs[0].job_id, jid);
+            assert_eq!(jobs[0].start_time, started);
+            assert_eq!(jobs[0].end_time, None);
+        }
+
+        // Whereas a plain query over the first chunk clips the job's times.
+        let mut query = JobQuery::new();
+        query.usage_start(start).usage_end(mid);
+        let jobs = cluster.get_jobs(&query).unwrap();
+        assert_eq!(jobs[0].end_time, Some(mid));
+    }
+}
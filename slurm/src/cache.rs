@@ -0,0 +1,145 @@
+// Copyright 2018 Peter Williams <peter@newton.cx> and collaborators
+// Licensed under the MIT License
+
+/*! Caching of queries to the Slurm controller.
+
+Monitoring tools that poll the controller in a loop can put a lot of load on
+`slurmctld` on large clusters. The `CachedClient` type remembers the results
+of the job and node queries for a configurable time-to-live (TTL), and serves
+repeated queries from memory until the TTL expires. When the TTL does expire,
+the client asks the controller whether anything has changed since the cached
+result was generated, and only downloads new data if so.
+
+```no_run
+use std::time::Duration;
+
+fn main() {
+    let mut client = slurm::cache::CachedClient::new(Duration::from_secs(30));
+
+    loop {
+        let n = client.jobs().unwrap().len();
+        println!("{} jobs", n);
+        std::thread::sleep(Duration::from_secs(5));
+    }
+}
+```
+
+*/
+
+use std::time::{Duration, Instant};
+
+use crate::{
+    get_all_jobs, get_all_jobs_since, get_nodes, get_nodes_since, Error, JobInfoMessage,
+    JobInfoMessageOwned, LoadResult, NodeInfoMessage, NodeInfoMessageOwned,
+};
+
+/// A cached query result and the time at which it was obtained.
+#[derive(Debug)]
+struct Entry<T> {
+    fetched: Instant,
+    value: T,
+}
+
+/// A client that caches the results of queries to the controller.
+#[derive(Debug)]
+pub struct CachedClient {
+    ttl: Duration,
+    jobs: Option<Entry<JobInfoMessageOwned>>,
+    nodes: Option<Entry<NodeInfoMessageOwned>>,
+}
+
+impl CachedClient {
+    /// Create a new client whose cached results expire after `ttl`.
+    #[must_use]
+    pub fn new(ttl: Duration) -> CachedClient {
+        CachedClient {
+            ttl,
+            jobs: None,
+            nodes: None,
+        }
+    }
+
+    /// Get the time-to-live of cached results.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Change the time-to-live of cached results.
+    ///
+    /// This affects results that have already been cached as well as future
+    /// ones.
+    pub fn set_ttl(&mut self, ttl: Duration) -> &mut Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Forget all cached results, so that the next queries go to the
+    /// controller.
+    pub fn invalidate(&mut self) {
+        self.jobs = None;
+        self.nodes = None;
+    }
+
+    fn is_fresh<T>(&self, entry: &Option<Entry<T>>) -> bool {
+        match *entry {
+            Some(ref e) => e.fetched.elapsed() < self.ttl,
+            None => false,
+        }
+    }
+
+    /// Get information about all jobs, as with `slurm::get_all_jobs`.
+    pub fn jobs(&mut self) -> Result<&JobInfoMessage, Error> {
+        if !self.is_fresh(&self.jobs) {
+            let now = Instant::now();
+
+            let update = match self.jobs {
+                Some(ref e) => get_all_jobs_since(e.value.last_update())?,
+                None => LoadResult::Updated(get_all_jobs()?),
+            };
+
+            match update {
+                LoadResult::Updated(value) => {
+                    self.jobs = Some(Entry {
+                        fetched: now,
+                        value,
+                    });
+                }
+                LoadResult::NotChanged => {
+                    if let Some(ref mut e) = self.jobs {
+                        e.fetched = now;
+                    }
+                }
+            }
+        }
+
+        Ok(&self.jobs.as_ref().unwrap().value)
+    }
+
+    /// Get information about all nodes, as with `slurm::get_nodes`.
+    pub fn nodes(&mut self) -> Result<&NodeInfoMessage, Error> {
+        if !self.is_fresh(&self.nodes) {
+            let now = Instant::now();
+
+            let update = match self.nodes {
+                Some(ref e) => get_nodes_since(e.value.last_update())?,
+                None => LoadResult::Updated(get_nodes()?),
+            };
+
+            match update {
+                LoadResult::Updated(value) => {
+                    self.nodes = Some(Entry {
+                        fetched: now,
+                        value,
+                    });
+                }
+                LoadResult::NotChanged => {
+                    if let Some(ref mut e) = self.nodes {
+                        e.fetched = now;
+                    }
+                }
+            }
+        }
+
+        Ok(&self.nodes.as_ref().unwrap().value)
+    }
+}
@@ -0,0 +1,207 @@
+// Copyright 2018 Peter Williams <peter@newton.cx> and collaborators
+// Licensed under the MIT License
+
+/*! Exporting accounting query results as Arrow record batches and Parquet
+files.
+
+This module is only available if the `arrow` feature is enabled. Each job
+becomes one row of a table with the schema returned by `job_schema`. Times
+are expressed as UTC timestamps with a resolution of one second, and
+durations as numbers of seconds, so that the tables can be loaded directly
+into tools such as pandas.
+
+```no_run
+fn archive_account(name: &str) -> Result<(), slurm::Error> {
+    let filter = slurm::JobFiltersOwned::for_account(name)?;
+    let db = slurm::DatabaseConnectionOwned::new()?;
+    let jobs = db.get_jobs(&filter)?;
+    let file = std::fs::File::create("jobs.parquet")?;
+    slurm::export::arrow::write_parquet(file, &jobs)?;
+    Ok(())
+}
+```
+
+*/
+
+use arrow::array::{
+    ArrayRef, Float64Builder, Int32Builder, Int64Builder, StringBuilder, TimestampSecondBuilder,
+    UInt32Builder, UInt64Builder,
+};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::ArrowWriter;
+use parquet::errors::ParquetError;
+use std::io::Write;
+use std::sync::Arc;
+
+use crate::{Error, JobRecord, JobRecordSnapshot, SlurmList, TimeLimit};
+
+/// The number of jobs converted into each record batch when writing Parquet
+/// files.
+const BATCH_SIZE: usize = 8192;
+
+impl From<ArrowError> for Error {
+    fn from(e: ArrowError) -> Self {
+        format_err!("Arrow error: {}", e)
+    }
+}
+
+impl From<ParquetError> for Error {
+    fn from(e: ParquetError) -> Self {
+        format_err!("Parquet error: {}", e)
+    }
+}
+
+fn timestamp_type() -> DataType {
+    DataType::Timestamp(TimeUnit::Second, Some("UTC".into()))
+}
+
+/// Get the schema of the tables of jobs produced by this module.
+pub fn job_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("job_id", DataType::UInt32, false),
+        Field::new("array_job_id", DataType::UInt32, true),
+        Field::new("job_name", DataType::Utf8, false),
+        Field::new("user", DataType::Utf8, true),
+        Field::new("uid", DataType::UInt32, false),
+        Field::new("account", DataType::Utf8, true),
+        Field::new("cluster", DataType::Utf8, true),
+        Field::new("partition", DataType::Utf8, true),
+        Field::new("wckey", DataType::Utf8, true),
+        Field::new("qos_id", DataType::UInt32, false),
+        Field::new("state", DataType::Utf8, false),
+        Field::new("exit_code", DataType::Int32, true),
+        Field::new("submit_time", timestamp_type(), false),
+        Field::new("eligible_time", timestamp_type(), true),
+        Field::new("start_time", timestamp_type(), true),
+        Field::new("end_time", timestamp_type(), true),
+        Field::new("elapsed_secs", DataType::Int64, false),
+        Field::new("time_limit_mins", DataType::UInt32, true),
+        Field::new("alloc_nodes", DataType::UInt32, false),
+        Field::new("alloc_cpus", DataType::UInt64, true),
+        Field::new("req_cpus", DataType::UInt32, false),
+        Field::new("req_mem_mib", DataType::UInt64, true),
+        Field::new("max_rss_kib", DataType::UInt64, true),
+        Field::new("tot_cpu_secs", DataType::Float64, false),
+        Field::new("node_list", DataType::Utf8, true),
+    ]))
+}
+
+/// Convert a set of job snapshots into a record batch with the schema
+/// returned by `job_schema`.
+///
+/// Jobs with unlimited time limits have a null `time_limit_mins`.
+pub fn jobs_to_record_batch(jobs: &[JobRecordSnapshot]) -> Result<RecordBatch, Error> {
+    let n = jobs.len();
+    let mut job_id = UInt32Builder::with_capacity(n);
+    let mut array_job_id = UInt32Builder::with_capacity(n);
+    let mut job_name = StringBuilder::new();
+    let mut user = StringBuilder::new();
+    let mut uid = UInt32Builder::with_capacity(n);
+    let mut account = StringBuilder::new();
+    let mut cluster = StringBuilder::new();
+    let mut partition = StringBuilder::new();
+    let mut wckey = StringBuilder::new();
+    let mut qos_id = UInt32Builder::with_capacity(n);
+    let mut state = StringBuilder::new();
+    let mut exit_code = Int32Builder::with_capacity(n);
+    let mut submit_time = TimestampSecondBuilder::with_capacity(n).with_timezone("UTC");
+    let mut eligible_time = TimestampSecondBuilder::with_capacity(n).with_timezone("UTC");
+    let mut start_time = TimestampSecondBuilder::with_capacity(n).with_timezone("UTC");
+    let mut end_time = TimestampSecondBuilder::with_capacity(n).with_timezone("UTC");
+    let mut elapsed_secs = Int64Builder::with_capacity(n);
+    let mut time_limit_mins = UInt32Builder::with_capacity(n);
+    let mut alloc_nodes = UInt32Builder::with_capacity(n);
+    let mut alloc_cpus = UInt64Builder::with_capacity(n);
+    let mut req_cpus = UInt32Builder::with_capacity(n);
+    let mut req_mem_mib = UInt64Builder::with_capacity(n);
+    let mut max_rss_kib = UInt64Builder::with_capacity(n);
+    let mut tot_cpu_secs = Float64Builder::with_capacity(n);
+    let mut node_list = StringBuilder::new();
+
+    for job in jobs {
+        job_id.append_value(job.job_id);
+        array_job_id.append_option(job.array_job_id);
+        job_name.append_value(&job.job_name);
+        user.append_option(job.user.as_ref());
+        uid.append_value(job.uid);
+        account.append_option(job.account.as_ref());
+        cluster.append_option(job.cluster.as_ref());
+        partition.append_option(job.partition.as_ref());
+        wckey.append_option(job.wckey.as_ref());
+        qos_id.append_value(job.qos_id);
+        state.append_value(job.state.slurm_name());
+        exit_code.append_option(job.exit_code);
+        submit_time.append_value(job.submit_time.timestamp());
+        eligible_time.append_option(job.eligible_time.map(|t| t.timestamp()));
+        start_time.append_option(job.start_time.map(|t| t.timestamp()));
+        end_time.append_option(job.end_time.map(|t| t.timestamp()));
+        elapsed_secs.append_value(job.elapsed.num_seconds());
+        time_limit_mins.append_option(match job.time_limit {
+            Some(TimeLimit::Minutes(m)) => Some(m),
+            _ => None,
+        });
+        alloc_nodes.append_value(job.alloc_nodes);
+        alloc_cpus.append_option(job.tres_alloc.cpus());
+        req_cpus.append_value(job.req_cpus);
+        req_mem_mib.append_option(job.req_mem.map(|m| m.mib()));
+        max_rss_kib.append_option(job.max_rss);
+        tot_cpu_secs.append_value(job.tot_cpu.num_milliseconds() as f64 * 1e-3);
+        node_list.append_option(job.node_list.as_ref());
+    }
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(job_id.finish()),
+        Arc::new(array_job_id.finish()),
+        Arc::new(job_name.finish()),
+        Arc::new(user.finish()),
+        Arc::new(uid.finish()),
+        Arc::new(account.finish()),
+        Arc::new(cluster.finish()),
+        Arc::new(partition.finish()),
+        Arc::new(wckey.finish()),
+        Arc::new(qos_id.finish()),
+        Arc::new(state.finish()),
+        Arc::new(exit_code.finish()),
+        Arc::new(submit_time.finish()),
+        Arc::new(eligible_time.finish()),
+        Arc::new(start_time.finish()),
+        Arc::new(end_time.finish()),
+        Arc::new(elapsed_secs.finish()),
+        Arc::new(time_limit_mins.finish()),
+        Arc::new(alloc_nodes.finish()),
+        Arc::new(alloc_cpus.finish()),
+        Arc::new(req_cpus.finish()),
+        Arc::new(req_mem_mib.finish()),
+        Arc::new(max_rss_kib.finish()),
+        Arc::new(tot_cpu_secs.finish()),
+        Arc::new(node_list.finish()),
+    ];
+
+    Ok(RecordBatch::try_new(job_schema(), columns)?)
+}
+
+/// Write the records in a job query result to a Parquet file.
+///
+/// The records are converted in batches, so that the whole query result does
+/// not need to be duplicated in memory.
+pub fn write_parquet<W: Write + Send>(dest: W, jobs: &SlurmList<JobRecord>) -> Result<W, Error> {
+    let mut writer = ArrowWriter::try_new(dest, job_schema(), None)?;
+    let mut buf = Vec::with_capacity(BATCH_SIZE);
+
+    for job in jobs {
+        buf.push(job.to_snapshot());
+
+        if buf.len() == BATCH_SIZE {
+            writer.write(&jobs_to_record_batch(&buf)?)?;
+            buf.clear();
+        }
+    }
+
+    if !buf.is_empty() {
+        writer.write(&jobs_to_record_batch(&buf)?)?;
+    }
+
+    Ok(writer.into_inner()?)
+}
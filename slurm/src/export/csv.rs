@@ -0,0 +1,361 @@
+// Copyright 2018 Peter Williams <peter@newton.cx> and collaborators
+// Licensed under the MIT License
+
+/*! Exporting accounting query results as CSV.
+
+The columns are named and formatted after the corresponding fields of the
+`sacct` command, so that the output can stand in for `sacct --parsable2`
+output in spreadsheet-based reporting. For example:
+
+```no_run
+use slurm::export::csv::{self, Column};
+
+fn dump_account(name: &str) -> Result<(), slurm::Error> {
+    let filter = slurm::JobFiltersOwned::for_account(name)?;
+    let db = slurm::DatabaseConnectionOwned::new()?;
+    let jobs = db.get_jobs(&filter)?;
+    let columns = [Column::JobId, Column::User, Column::State, Column::Elapsed];
+    csv::write_jobs(std::io::stdout(), &jobs, &columns)?;
+    Ok(())
+}
+```
+
+*/
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Error as FmtError, Formatter};
+use std::io::Write;
+use std::str::FromStr;
+
+use crate::{
+    format_duration, format_time, Error, JobRecord, JobStepRecordSharedFields, SlurmList,
+    StepRecord, TresAmounts, TresRecord, TresSpec,
+};
+
+macro_rules! make_columns {
+    ($(<$rustname:ident, $sacctname:expr, $doc:expr;>),*) => {
+        /// A column that can appear in the CSV output.
+        ///
+        /// Each column corresponds to a field of `sacct`.
+        #[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+        #[non_exhaustive]
+        pub enum Column {
+            $(
+                #[doc=$doc] $rustname,
+            )*
+        }
+
+        impl Column {
+            /// Get the name of this column as used by `sacct`, such as
+            /// `JobID`. This is used in the header line of the output.
+            pub fn sacct_name(&self) -> &'static str {
+                match *self {
+                    $(
+                        Column::$rustname => $sacctname,
+                    )*
+                }
+            }
+
+            /// Get all of the available columns.
+            pub fn all() -> &'static [Column] {
+                &[$(Column::$rustname),*]
+            }
+        }
+    }
+}
+
+make_columns! {
+    <Account, "Account", "The account charged for the job.";>,
+    <AllocCpus, "AllocCPUS", "The number of CPUs allocated.";>,
+    <AllocTres, "AllocTRES", "The trackable resources allocated.";>,
+    <Cluster, "Cluster", "The cluster that ran the job.";>,
+    <DerivedExitCode, "DerivedExitCode", "The highest exit code of any of the job's steps.";>,
+    <Elapsed, "Elapsed", "The wallclock time used.";>,
+    <Eligible, "Eligible", "When the job became eligible to run.";>,
+    <End, "End", "When the job or step ended.";>,
+    <ExitCode, "ExitCode", "The exit code and terminating signal.";>,
+    <JobId, "JobID", "The job ID, with a step suffix for steps.";>,
+    <JobName, "JobName", "The name of the job or step.";>,
+    <MaxRss, "MaxRSS", "The maximum resident set size of any task.";>,
+    <MaxVmSize, "MaxVMSize", "The maximum virtual memory size of any task.";>,
+    <NNodes, "NNodes", "The number of nodes allocated.";>,
+    <NodeList, "NodeList", "The nodes that ran the job or step.";>,
+    <Partition, "Partition", "The partition that ran the job.";>,
+    <Priority, "Priority", "The job's scheduling priority.";>,
+    <QosRaw, "QOSRAW", "The numeric ID of the job's QOS.";>,
+    <ReqCpus, "ReqCPUS", "The number of CPUs requested.";>,
+    <ReqMem, "ReqMem", "The amount of memory requested.";>,
+    <ReqTres, "ReqTRES", "The trackable resources requested.";>,
+    <Start, "Start", "When the job or step started.";>,
+    <State, "State", "The state of the job or step.";>,
+    <Submit, "Submit", "When the job was submitted.";>,
+    <SystemCpu, "SystemCPU", "The system CPU time used.";>,
+    <Timelimit, "Timelimit", "The job's time limit.";>,
+    <TotalCpu, "TotalCPU", "The total CPU time used.";>,
+    <Uid, "UID", "The numeric ID of the user that ran the job.";>,
+    <User, "User", "The name of the user that ran the job.";>,
+    <UserCpu, "UserCPU", "The user CPU time used.";>,
+    <WcKey, "WCKey", "The job's workload characterization key.";>,
+    <WorkDir, "WorkDir", "The job's working directory.";>
+}
+
+/// The columns that `sacct` prints by default.
+pub const DEFAULT_COLUMNS: &[Column] = &[
+    Column::JobId,
+    Column::JobName,
+    Column::Partition,
+    Column::Account,
+    Column::AllocCpus,
+    Column::State,
+    Column::ExitCode,
+];
+
+impl Display for Column {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+        write!(f, "{}", self.sacct_name())
+    }
+}
+
+impl FromStr for Column {
+    type Err = Error;
+
+    /// Parse a column from its `sacct` name. As with `sacct`, the
+    /// comparison is case-insensitive.
+    fn from_str(text: &str) -> Result<Self, Error> {
+        Column::all()
+            .iter()
+            .find(|c| c.sacct_name().eq_ignore_ascii_case(text))
+            .cloned()
+            .ok_or_else(|| parse_err!("unrecognized sacct field name \"{}\"", text))
+    }
+}
+
+/// Parse a comma-separated list of `sacct` field names, as would be given
+/// to `sacct --format`.
+pub fn parse_columns(text: &str) -> Result<Vec<Column>, Error> {
+    text.split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse())
+        .collect()
+}
+
+/// A writer that emits accounting records as rows of CSV.
+#[derive(Debug)]
+pub struct CsvWriter<W: Write> {
+    dest: W,
+    columns: Vec<Column>,
+    include_steps: bool,
+    tres_names: BTreeMap<u32, String>,
+}
+
+impl<W: Write> CsvWriter<W> {
+    /// Create a new writer that emits the specified columns.
+    ///
+    /// By default, one row is written per job, and TRES are identified by
+    /// their numeric IDs.
+    pub fn new(dest: W, columns: &[Column]) -> Self {
+        CsvWriter {
+            dest: dest,
+            columns: columns.to_vec(),
+            include_steps: false,
+            tres_names: BTreeMap::new(),
+        }
+    }
+
+    /// Specify whether a row should be written for each job step, following
+    /// the row of its job.
+    pub fn include_steps(&mut self, value: bool) -> &mut Self {
+        self.include_steps = value;
+        self
+    }
+
+    /// Identify TRES by name, rather than numeric ID, using the definitions
+    /// returned by `DatabaseConnection::get_tres`.
+    pub fn tres_names(&mut self, tres: &SlurmList<TresRecord>) -> &mut Self {
+        self.tres_names = tres.iter().map(|t| (t.id(), t.full_name())).collect();
+        self
+    }
+
+    /// Write the header line naming the columns.
+    pub fn write_header(&mut self) -> Result<(), Error> {
+        let cells: Vec<String> = self
+            .columns
+            .iter()
+            .map(|c| c.sacct_name().to_owned())
+            .collect();
+        self.write_row(&cells)
+    }
+
+    /// Write the row for one job, followed by the rows of its steps if
+    /// requested.
+    pub fn write_job(&mut self, job: &JobRecord) -> Result<(), Error> {
+        let cells: Vec<String> = self
+            .columns
+            .iter()
+            .map(|c| self.job_value(*c, job))
+            .collect();
+        self.write_row(&cells)?;
+
+        if self.include_steps {
+            for step in job.steps() {
+                let cells: Vec<String> = self
+                    .columns
+                    .iter()
+                    .map(|c| self.step_value(*c, job, &step))
+                    .collect();
+                self.write_row(&cells)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write the rows for every job in a list, such as the result of
+    /// `DatabaseConnection::get_jobs`.
+    pub fn write_jobs(&mut self, jobs: &SlurmList<JobRecord>) -> Result<(), Error> {
+        for job in jobs {
+            self.write_job(&job)?;
+        }
+
+        Ok(())
+    }
+
+    /// Unwrap this writer, returning the underlying destination.
+    pub fn into_inner(self) -> W {
+        self.dest
+    }
+
+    fn write_row(&mut self, cells: &[String]) -> Result<(), Error> {
+        for (i, cell) in cells.iter().enumerate() {
+            if i > 0 {
+                self.dest.write_all(b",")?;
+            }
+
+            if cell.contains(|c: char| c == ',' || c == '"' || c == '\n' || c == '\r') {
+                write!(self.dest, "\"{}\"", cell.replace('"', "\"\""))?;
+            } else {
+                self.dest.write_all(cell.as_bytes())?;
+            }
+        }
+
+        self.dest.write_all(b"\n")?;
+        Ok(())
+    }
+
+    fn job_value(&self, column: Column, job: &JobRecord) -> String {
+        match column {
+            Column::JobId => job.job_id().to_string(),
+            Column::JobName => job.job_name().into_owned(),
+            Column::DerivedExitCode => job.derived_exit_status().to_string(),
+            Column::Eligible => format_time(job.eligible_time()),
+            Column::NNodes => job.alloc_nodes().to_string(),
+            Column::Priority => job.priority().to_string(),
+            Column::QosRaw => job.qos_id().to_string(),
+            Column::ReqCpus => job.req_cpus().to_string(),
+            Column::ReqMem => display_or_empty(job.req_mem()),
+            Column::ReqTres => self.format_tres(&job.tres_req()),
+            Column::Submit => format_time(Some(job.submit_time())),
+            Column::Timelimit => display_or_empty(job.time_limit()),
+            c => self.shared_value(c, job, job),
+        }
+    }
+
+    fn step_value(&self, column: Column, job: &JobRecord, step: &StepRecord) -> String {
+        match column {
+            Column::JobId => format!("{}.{}", job.job_id(), step.step_id()),
+            Column::JobName => step.step_name().into_owned(),
+            Column::NNodes => step.nnodes().to_string(),
+            Column::DerivedExitCode
+            | Column::Eligible
+            | Column::Priority
+            | Column::QosRaw
+            | Column::ReqCpus
+            | Column::ReqMem
+            | Column::ReqTres
+            | Column::Submit
+            | Column::Timelimit => String::new(),
+            c => self.shared_value(c, job, step),
+        }
+    }
+
+    /// Get the value of a column that is computed the same way for jobs and
+    /// steps. Steps inherit the job-level descriptive fields, as in `sacct`.
+    fn shared_value(
+        &self,
+        column: Column,
+        job: &JobRecord,
+        rec: &dyn JobStepRecordSharedFields,
+    ) -> String {
+        match column {
+            Column::Account => cow_or_empty(job.account()),
+            Column::AllocCpus => display_or_empty(rec.tres_alloc().cpus()),
+            Column::AllocTres => self.format_tres(&rec.tres_alloc()),
+            Column::Cluster => cow_or_empty(job.cluster()),
+            Column::Elapsed => format_duration(rec.elapsed()),
+            Column::End => format_time(rec.end_time()),
+            Column::ExitCode => display_or_empty(rec.exit_status()),
+            Column::MaxRss => rec
+                .stats()
+                .rss_max()
+                .map(|kib| format!("{}K", kib))
+                .unwrap_or_default(),
+            Column::MaxVmSize => rec
+                .max_vm_size()
+                .map(|kib| format!("{}K", kib))
+                .unwrap_or_default(),
+            Column::NodeList => cow_or_empty(rec.node_list()),
+            Column::Partition => cow_or_empty(job.partition()),
+            Column::Start => format_time(rec.start_time()),
+            Column::State => rec.state().slurm_name(),
+            Column::SystemCpu => format_duration(rec.sys_cpu()),
+            Column::TotalCpu => format_duration(rec.tot_cpu()),
+            Column::Uid => job.uid().to_string(),
+            Column::User => cow_or_empty(job.user()),
+            Column::UserCpu => format_duration(rec.user_cpu()),
+            Column::WcKey => cow_or_empty(job.wckey()),
+            Column::WorkDir => cow_or_empty(job.work_dir()),
+            _ => String::new(),
+        }
+    }
+
+    fn format_tres(&self, amounts: &TresAmounts) -> String {
+        let spec = TresSpec(
+            amounts
+                .iter()
+                .map(|(id, amount)| {
+                    let name = self
+                        .tres_names
+                        .get(&id)
+                        .cloned()
+                        .unwrap_or_else(|| id.to_string());
+                    (name, amount)
+                })
+                .collect(),
+        );
+        spec.to_string()
+    }
+}
+
+/// Write the records in a job query result as CSV, with a header line and
+/// one row per job.
+///
+/// Use `CsvWriter` directly for more control over the output.
+pub fn write_jobs<W: Write>(
+    dest: W,
+    jobs: &SlurmList<JobRecord>,
+    columns: &[Column],
+) -> Result<W, Error> {
+    let mut writer = CsvWriter::new(dest, columns);
+    writer.write_header()?;
+    writer.write_jobs(jobs)?;
+    Ok(writer.into_inner())
+}
+
+fn cow_or_empty<S: AsRef<str>>(value: Option<S>) -> String {
+    value.map(|s| s.as_ref().to_owned()).unwrap_or_default()
+}
+
+fn display_or_empty<T: Display>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
@@ -0,0 +1,10 @@
+// Copyright 2018 Peter Williams <peter@newton.cx> and collaborators
+// Licensed under the MIT License
+
+/*! Exporting accounting data to other formats.
+
+*/
+
+#[cfg(feature = "arrow")]
+pub mod arrow;
+pub mod csv;
@@ -14,10 +14,7 @@ yet been implemented.
 # Example: querying a running job
 
 ```no_run
-extern crate failure;
-extern crate slurm;
-
-fn print_random_job_information(jobid: slurm::JobId) -> Result<(), failure::Error> {
+fn print_random_job_information(jobid: slurm::JobId) -> Result<(), slurm::Error> {
     let info = slurm::get_job_info(jobid)?;
     println!("Job ID: {}", info.job_id()); // same as what we put in
     println!("Job's partition: {}", info.partition());
@@ -31,14 +28,8 @@ To gather information about jobs that have completed, you must connect to the
 Slurm accounting database and query it.
 
 ```no_run
-extern crate chrono;
-extern crate failure;
-extern crate slurm;
-
-fn print_other_job_information(jobid: slurm::JobId) -> Result<(), failure::Error> {
-    let mut filter = slurm::JobFiltersOwned::default();
-    filter.step_list_mut().append(slurm::JobStepFilterOwned::new(jobid));
-
+fn print_other_job_information(jobid: slurm::JobId) -> Result<(), slurm::Error> {
+    let filter = slurm::JobFiltersOwned::for_job(jobid)?;
     let db = slurm::DatabaseConnectionOwned::new()?;
     let jobs = db.get_jobs(&filter)?;
     let now = chrono::Utc::now();
@@ -63,30 +54,29 @@ fn print_other_job_information(jobid: slurm::JobId) -> Result<(), failure::Error
 # Submitting a “Hello World” job
 
 ```no_run
-extern crate failure;
-extern crate slurm;
-
-fn submit_hello_world() -> Result<slurm::JobId, failure::Error> {
+fn submit_hello_world() -> Result<slurm::JobId, slurm::Error> {
     let cwd = std::env::current_dir()?;
 
     let log = {
         let mut p = cwd.clone();
         p.push("%j.log");
-        p.to_str().ok_or(failure::err_msg("cannot stringify log path"))?.to_owned()
+        p.to_str()
+            .ok_or(slurm::Error::Other("cannot stringify log path".to_owned()))?
+            .to_owned()
     };
 
-    let mut desc = slurm::JobDescriptorOwned::new();
+    let mut desc = slurm::JobDescriptorOwned::new()?;
 
-    desc.set_name("helloworld")
-        .set_argv(&["helloworld"])
-        .inherit_environment()
-        .set_stderr_path(&log)
-        .set_stdin_path("/dev/null")
-        .set_stdout_path(&log)
+    desc.set_name("helloworld")?
+        .set_argv(&["helloworld"])?
+        .inherit_environment()?
+        .set_stderr_path(&log)?
+        .set_stdin_path("/dev/null")?
+        .set_stdout_path(&log)?
         .set_work_dir_cwd()?
         .set_script("#! /bin/bash \
                      set -e -x \
-                     echo hello world \"$@\"")
+                     echo hello world \"$@\"")?
         .set_gid_current() // JobDescriptor args must come after due to the return type
         .set_num_tasks(1)
         .set_time_limit(5)
@@ -109,40 +99,260 @@ their data when they go out of scope. Borrowed structures need not be
 immutable, but it is not possible to modify them in ways that require freeing
 or allocating memory associated with their sub-structures.
 
-*/
+Operations that allocate memory through Slurm's allocator, such as the
+constructors of the owned types and the setters that take strings, return a
+`Result` and report allocation failure as `ENOMEM` instead of aborting. Note
+that the Slurm library may still abort if its own internal allocations fail.
+
+# A note on threads
+
+Slurm reports most errors by setting the C library's `errno`, which is
+thread-local, and this crate reads it immediately after each failing call.
+Some Slurm functions (notably those of the accounting database API) instead
+return their error codes directly, and these are used in preference when
+available. Errors are therefore attributed correctly even when several
+threads are making Slurm calls at once.
+
+The free functions of this crate, such as `get_job_info`, may be called
+concurrently from multiple threads. The wrapper types, however, are neither
+`Send` nor `Sync`, because the underlying Slurm structures are not safe to
+share. In particular, each thread that needs to talk to the accounting
+database should open its own `DatabaseConnection`.
 
-extern crate chrono;
-#[macro_use]
-extern crate failure;
-#[macro_use]
-extern crate failure_derive;
-extern crate libc;
-extern crate slurm_sys;
+*/
 
+#[cfg(feature = "slurmdb")]
+use chrono::Local;
 use chrono::{DateTime, Duration, TimeZone, Utc};
-use failure::Error;
 use std::borrow::Cow;
-use std::default::Default;
-use std::ffi::CStr;
-use std::fmt::{Display, Error as FmtError, Formatter};
+use std::collections::BTreeMap;
+use std::ffi::{CStr, CString};
+use std::fmt::{Debug, Display, Error as FmtError, Formatter};
+use std::iter::Sum;
 use std::marker::PhantomData;
-use std::ops::{Deref, DerefMut};
+use std::ops::{Add, AddAssign, BitOr, BitOrAssign, Deref, DerefMut};
 use std::os::raw::{c_char, c_int, c_void};
+use std::str::FromStr;
+
+#[cfg(feature = "serde")]
+use serde::{Serialize, Serializer};
+
+/// The error type of this crate.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// An error reported by the Slurm library.
+    #[error("{0}")]
+    Slurm(#[from] SlurmError),
+
+    /// An I/O error, such as a failure to determine the current directory.
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+
+    /// Some text could not be parsed, such as a malformed TRES specification.
+    #[error("{0}")]
+    Parse(String),
+
+    /// Some other error, such as an unexpected value returned by Slurm.
+    #[error("{0}")]
+    Other(String),
+}
+
+/// Create an `Error::Parse` with a formatted message.
+macro_rules! parse_err {
+    ($($arg:tt)*) => {
+        Error::Parse(format!($($arg)*))
+    };
+}
+
+/// Create an `Error::Other` with a formatted message.
+macro_rules! format_err {
+    ($($arg:tt)*) => {
+        Error::Other(format!($($arg)*))
+    };
+}
+
+#[cfg(feature = "async")]
+pub mod aio;
+pub mod backend;
+pub mod cache;
+#[cfg(feature = "slurmdb")]
+pub mod export;
+pub mod mock;
+#[cfg(feature = "rest")]
+pub mod rest;
+pub mod watch;
+
+/// Optional features of the Slurm C API.
+///
+/// The Slurm C API changes from release to release, and this crate is
+/// compiled to match the version of the library that it is built against.
+/// This structure records which of the optional features were found; each
+/// field is true if the corresponding feature is available. Get it with
+/// `capabilities()`, or use the `CAPABILITIES` constant in constant
+/// expressions.
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Capabilities {
+    /// Whether the `JobState::Deadline` state exists.
+    pub job_state_deadline: bool,
+
+    /// Whether the `JobState::OutOfMemory` state exists.
+    pub job_state_oom: bool,
+
+    /// Whether QOS creation passes the UID of the caller.
+    pub qos_add_uid: bool,
+
+    /// Whether job step filters can select heterogeneous job components.
+    pub het_job_filters: bool,
+
+    /// Whether the batch scripts of jobs can be retrieved from the
+    /// accounting database, as in Slurm 21.08 and later.
+    pub job_scripts: bool,
+
+    /// Whether step IDs are stored in `slurm_step_id_t` structures, as in
+    /// Slurm 20.11 and later.
+    pub step_id_struct: bool,
+
+    /// Whether the response to a job submission can include a message for
+    /// the user.
+    pub submit_response_user_message: bool,
+}
+
+/// The optional features of the Slurm C API available to this build.
+pub const CAPABILITIES: Capabilities = Capabilities {
+    job_state_deadline: cfg!(slurm_api_job_state_deadline),
+    job_state_oom: cfg!(slurm_api_job_state_oom),
+    qos_add_uid: cfg!(slurm_api_qos_add_uid),
+    het_job_filters: cfg!(any(
+        slurm_api_selected_step_t_pack_job_offset,
+        slurm_api_selected_step_t_het_job_offset
+    )),
+    job_scripts: cfg!(slurm_api_job_rec_t_script),
+    step_id_struct: cfg!(slurm_api_selected_step_t_step_id),
+    submit_response_user_message: cfg!(slurm_api_submit_response_user_message),
+};
+
+/// Get the optional features of the Slurm C API available to this build.
+///
+/// The raw names of the detected features are available as
+/// `slurm_sys::C_API_FEATURES`.
+pub fn capabilities() -> Capabilities {
+    CAPABILITIES
+}
 
 /// A job identifier number; this will always be `u32`.
 pub type JobId = u32;
 
-/// A job-step identifier number; this will always be `u32`.
-pub type StepId = u32;
+/// A job-step identifier.
+///
+/// Besides ordinary numbered steps, Slurm uses special sentinel step numbers
+/// to identify the "pseudo-steps" that run a job's batch script and that
+/// contain processes adopted from outside of Slurm. This type makes those
+/// explicit. It is formatted and parsed the same way as in `sacct` output:
+/// `batch`, `extern`, or the step number.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[non_exhaustive]
+pub enum StepId {
+    /// An ordinary step with the specified number.
+    Numbered(u32),
+
+    /// A step that has not yet been assigned a number.
+    Pending,
+
+    /// The pseudo-step running the job's batch script.
+    Batch,
+
+    /// The pseudo-step containing processes adopted from outside Slurm.
+    Extern,
+}
+
+impl From<u32> for StepId {
+    /// Decode a raw Slurm step number, which may be a sentinel value.
+    fn from(raw: u32) -> StepId {
+        match raw {
+            slurm_sys::SLURM_PENDING_STEP => StepId::Pending,
+            slurm_sys::SLURM_BATCH_SCRIPT => StepId::Batch,
+            slurm_sys::SLURM_EXTERN_CONT => StepId::Extern,
+            n => StepId::Numbered(n),
+        }
+    }
+}
+
+impl StepId {
+    /// Encode this value as a raw Slurm step number.
+    pub fn to_slurm(&self) -> u32 {
+        match *self {
+            StepId::Numbered(n) => n,
+            StepId::Pending => slurm_sys::SLURM_PENDING_STEP,
+            StepId::Batch => slurm_sys::SLURM_BATCH_SCRIPT,
+            StepId::Extern => slurm_sys::SLURM_EXTERN_CONT,
+        }
+    }
+}
+
+impl FromStr for StepId {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<StepId, Error> {
+        match text.trim() {
+            "batch" => Ok(StepId::Batch),
+            "extern" => Ok(StepId::Extern),
+            "pending" => Ok(StepId::Pending),
+            other => other
+                .parse()
+                .map(StepId::Numbered)
+                .map_err(|_| parse_err!("malformed step ID {:?}", text)),
+        }
+    }
+}
+
+impl Display for StepId {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+        match *self {
+            StepId::Numbered(n) => write!(f, "{}", n),
+            StepId::Pending => write!(f, "pending"),
+            StepId::Batch => write!(f, "batch"),
+            StepId::Extern => write!(f, "extern"),
+        }
+    }
+}
+
+/// Broad categories of Slurm errors.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum SlurmErrorCategory {
+    /// Errors relating to authentication and authorization.
+    Auth,
+
+    /// Errors arising from exceeding a limit on resources or jobs.
+    Limits,
+
+    /// Errors in communicating with Slurm daemons.
+    Communication,
+
+    /// Errors arising from an invalid request.
+    InvalidArgument,
+
+    /// Other errors.
+    Other,
+}
 
 /// A quick macro framework to map low-level slurm API errors to a Rust interface.
+///
+/// It is invoked from a file generated by our build script, which enumerates
+/// all of the error codes defined by the C API.
 macro_rules! declare_slurm_errors {
-    ($(<$rustname:ident, $sysname:path, $doc:expr;>),*) => {
-        /// Specifically-enumerated errors that we can get from the Slurm API.
+    ($(<$rustname:ident, $sysname:path, $doc:expr, $category:ident;>),*) => {
+        /// Errors that we can get from the Slurm API.
         ///
-        /// This is not exhaustive; the only specifically implemented options are ones
-        /// that are expected to be of interest to general developers.
-        #[derive(Copy, Clone, Debug, Eq, Fail, Hash, PartialEq)]
+        /// The variants are generated from the error codes defined by the
+        /// version of Slurm that this crate was built against, so the
+        /// precise set may vary. Error codes not known at build time, as
+        /// well as system errors, are represented with the `Other` variant.
+        #[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+        #[non_exhaustive]
         pub enum SlurmError {
             $(
                 #[doc=$doc] $rustname,
@@ -170,16 +380,146 @@ macro_rules! declare_slurm_errors {
                     &SlurmError::Other(errno) => errno,
                 }
             }
+
+            /// Get the broad category of this error.
+            pub fn category(&self) -> SlurmErrorCategory {
+                match self {
+                    $(
+                        &SlurmError::$rustname => SlurmErrorCategory::$category,
+                    )*
+                    &SlurmError::Other(_) => SlurmErrorCategory::Other,
+                }
+            }
         }
     }
 }
 
-declare_slurm_errors!(
-    <InvalidJobId, slurm_sys::ESLURM_INVALID_JOB_ID,
-     "The job ID did not correspond to a valid job.";>,
-    <InvalidPartitionName, slurm_sys::ESLURM_INVALID_PARTITION_NAME,
-     "The specified partition name was not recognized.";>
-);
+include!(concat!(env!("OUT_DIR"), "/errors.rs"));
+
+impl SlurmError {
+    /// Return true if this is an authentication or authorization error.
+    pub fn is_auth(&self) -> bool {
+        self.category() == SlurmErrorCategory::Auth
+    }
+
+    /// Return true if this error arose from exceeding a limit.
+    pub fn is_limit(&self) -> bool {
+        self.category() == SlurmErrorCategory::Limits
+    }
+
+    /// Return true if this is an error in communicating with a Slurm daemon.
+    pub fn is_communication(&self) -> bool {
+        self.category() == SlurmErrorCategory::Communication
+    }
+
+    /// Return true if this error arose from an invalid request.
+    pub fn is_invalid_argument(&self) -> bool {
+        self.category() == SlurmErrorCategory::InvalidArgument
+    }
+
+    /// Return true if this error is likely to be temporary, so that the
+    /// operation that caused it may succeed if retried.
+    ///
+    /// This is the case for a specific set of errors: failed connections and
+    /// socket timeouts, such as occur while the controller is failing over to
+    /// its backup; a controller or database that is temporarily unavailable;
+    /// and a controller too busy to create a new job record. Other
+    /// communication errors, such as protocol version mismatches, are not
+    /// transient.
+    pub fn is_transient(&self) -> bool {
+        match self.to_slurm() {
+            e if e == slurm_sys::SLURM_COMMUNICATIONS_CONNECTION_ERROR as c_int => true,
+            e if e == slurm_sys::SLURMCTLD_COMMUNICATIONS_CONNECTION_ERROR as c_int => true,
+            e if e == slurm_sys::SLURM_PROTOCOL_SOCKET_IMPL_TIMEOUT as c_int => true,
+            e if e == slurm_sys::ESLURM_ERROR_ON_DESC_TO_RECORD_COPY as c_int => true,
+            e if e == slurm_sys::ESLURM_IN_STANDBY_MODE as c_int => true,
+            e if e == slurm_sys::ESLURM_DB_CONNECTION as c_int => true,
+            libc::EAGAIN
+            | libc::EINTR
+            | libc::ECONNREFUSED
+            | libc::ECONNRESET
+            | libc::ETIMEDOUT => true,
+            _ => false,
+        }
+    }
+}
+
+/// A trait for errors that may be classified as transient.
+///
+/// This is used by `retry_with_backoff` to decide whether to retry an
+/// operation.
+pub trait MaybeTransient {
+    /// Return true if the operation that caused this error may succeed if
+    /// retried.
+    fn is_transient(&self) -> bool;
+}
+
+impl MaybeTransient for SlurmError {
+    fn is_transient(&self) -> bool {
+        SlurmError::is_transient(self)
+    }
+}
+
+impl MaybeTransient for Error {
+    fn is_transient(&self) -> bool {
+        match self {
+            &Error::Slurm(ref e) => e.is_transient(),
+            &Error::Io(ref e) => match e.kind() {
+                std::io::ErrorKind::Interrupted
+                | std::io::ErrorKind::TimedOut
+                | std::io::ErrorKind::ConnectionRefused
+                | std::io::ErrorKind::ConnectionReset => true,
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+}
+
+/// The number of attempts made by `retry_with_backoff`.
+const RETRY_ATTEMPTS: u32 = 6;
+
+/// The delay before the first retry made by `retry_with_backoff`, in
+/// milliseconds. It is doubled after each attempt.
+const RETRY_INITIAL_DELAY_MS: u64 = 250;
+
+/// Run an operation, retrying it with exponential backoff if it fails with a
+/// transient error.
+///
+/// The operation is attempted up to six times, with delays starting at a
+/// quarter of a second and doubling after each attempt, for a total of
+/// about eight seconds of waiting. This is long enough to ride out a typical
+/// failover of the Slurm controller. Errors that are not transient (see
+/// `SlurmError::is_transient`) are returned immediately, as is the error
+/// from the final attempt.
+///
+/// For instance:
+///
+/// ```no_run
+/// # fn main() -> Result<(), slurm::Error> {
+/// let info = slurm::retry_with_backoff(|| slurm::get_job_info(12345))?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn retry_with_backoff<T, E, F>(mut op: F) -> Result<T, E>
+where
+    E: MaybeTransient,
+    F: FnMut() -> Result<T, E>,
+{
+    let mut delay = RETRY_INITIAL_DELAY_MS;
+    let mut attempt = 1;
+
+    loop {
+        match op() {
+            Err(ref e) if attempt < RETRY_ATTEMPTS && e.is_transient() => {}
+            result => return result,
+        }
+
+        std::thread::sleep(std::time::Duration::from_millis(delay));
+        delay *= 2;
+        attempt += 1;
+    }
+}
 
 impl Display for SlurmError {
     fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
@@ -189,20 +529,32 @@ impl Display for SlurmError {
     }
 }
 
-/// Most Slurm API calls return an zero on success. The library API docs state
-/// that the return code on error is -1, and this macro encapsulates the task
-/// of obtaining an errno and converting it to a result. However, in at least
-/// one case the return code is an errno, which would be a nicer pattern from
-/// a thread-safety standpoint.
+impl std::error::Error for SlurmError {}
+
+/// Convert the return code of a Slurm API call into a result.
+///
+/// Most Slurm API calls return zero on success. The library API docs state
+/// that the return code on error is -1 (`SLURM_ERROR`), with the specific
+/// error stored in `errno`. However, many calls, especially in the accounting
+/// database API, return the specific error code directly. We use the
+/// returned code when it is informative, and otherwise read `errno`
+/// immediately, before any other call can clobber it.
+fn slurm_rc_to_result(rc: c_int) -> Result<(), SlurmError> {
+    match rc {
+        0 => Ok(()),
+        -1 => Err(SlurmError::from_slurm(unsafe {
+            slurm_sys::slurm_get_errno()
+        })),
+        errno => Err(SlurmError::from_slurm(errno)),
+    }
+}
+
+/// Try a Slurm call that returns an error code, using `slurm_rc_to_result`
+/// to generate an error if it fails.
 macro_rules! stry {
-    ($op:expr) => {{
-        if $op != 0 {
-            let e = unsafe { slurm_sys::slurm_get_errno() };
-            Err(SlurmError::from_slurm(e))
-        } else {
-            Ok(())
-        }?
-    }};
+    ($op:expr) => {
+        slurm_rc_to_result($op as c_int)?
+    };
 }
 
 /// This is like `stry!` but also wraps the Slurm call in an `unsafe{}` block,
@@ -228,38 +580,42 @@ macro_rules! pstry {
 }
 
 /// Allocate memory using Slurm's allocator.
-fn slurm_alloc_array<T>(count: usize) -> *mut T {
+///
+/// The memory is zeroed. Allocation failures are reported as `ENOMEM` rather
+/// than aborting the process. Requests for zero items yield a null pointer.
+fn slurm_alloc_array<T>(count: usize) -> Result<*mut T, SlurmError> {
     const TEXT: &[u8] = b"slurm-rs\0";
-    let ptr = unsafe {
-        slurm_sys::slurm_try_xmalloc(
-            std::mem::size_of::<T>() * count,
-            TEXT.as_ptr() as _,
-            1,
-            TEXT.as_ptr() as _,
-        )
+
+    let size = match std::mem::size_of::<T>().checked_mul(count) {
+        Some(0) => return Ok(0 as _),
+        Some(s) => s,
+        None => return Err(SlurmError::Other(libc::ENOMEM)),
     };
 
+    let ptr =
+        unsafe { slurm_sys::slurm_try_xmalloc(size, TEXT.as_ptr() as _, 1, TEXT.as_ptr() as _) };
+
     if ptr.is_null() {
-        panic!("Slurm memory allocation failed");
+        Err(SlurmError::Other(libc::ENOMEM))
+    } else {
+        Ok(ptr as _)
     }
-
-    ptr as _
 }
 
 /// Allocate a structure using Slurm's allocator.
-fn slurm_alloc<T>() -> *mut T {
+fn slurm_alloc<T>() -> Result<*mut T, SlurmError> {
     slurm_alloc_array(1)
 }
 
 /// Allocate a C-style string using Slurm's allocator, encoding it as UTF-8.
-fn slurm_alloc_utf8_string<S: AsRef<str>>(s: S) -> *mut c_char {
+fn slurm_alloc_utf8_string<S: AsRef<str>>(s: S) -> Result<*mut c_char, SlurmError> {
     let bytes = s.as_ref().as_bytes();
     let n = bytes.len() + 1;
-    let ptr = slurm_alloc_array(n);
+    let ptr = slurm_alloc_array(n)?;
     let dest = unsafe { std::slice::from_raw_parts_mut(ptr, n) };
     dest[..n - 1].copy_from_slice(bytes);
     dest[n - 1] = b'\0';
-    ptr as _
+    Ok(ptr as _)
 }
 
 /// Allocate an array of C-style strings using Slurm's allocator.
@@ -269,16 +625,21 @@ fn slurm_alloc_utf8_string<S: AsRef<str>>(s: S) -> *mut c_char {
 /// if the argument is an iterator of indeterminate size.
 fn slurm_alloc_utf8_string_array<I: IntoIterator<Item = S>, S: AsRef<str>>(
     strings: I,
-) -> (*mut *mut c_char, usize) {
+) -> Result<(*mut *mut c_char, usize), SlurmError> {
     let buf: Vec<_> = strings.into_iter().collect();
-    let ptr = slurm_alloc_array(buf.len());
-    let sl = unsafe { std::slice::from_raw_parts_mut(ptr, buf.len()) };
+    let mut ptr = slurm_alloc_array(buf.len())?;
 
     for (i, s) in buf.iter().enumerate() {
-        sl[i] = slurm_alloc_utf8_string(s.as_ref()) as _;
+        match slurm_alloc_utf8_string(s.as_ref()) {
+            Ok(p) => unsafe { *ptr.add(i) = p },
+            Err(e) => {
+                slurm_free_string_array(&mut ptr, i);
+                return Err(e);
+            }
+        }
     }
 
-    (ptr, buf.len())
+    Ok((ptr, buf.len()))
 }
 
 /// Free a structure using Slurm's allocator.
@@ -291,6 +652,20 @@ fn slurm_free<T>(thing: &mut *mut T) {
     unsafe { slurm_sys::slurm_xfree(p, TEXT.as_ptr() as _, 1, TEXT.as_ptr() as _) };
 }
 
+/// Replace a C-style string field with a new value allocated using Slurm's
+/// allocator, freeing the previous value (if any).
+///
+/// If the allocation fails, the field is left unchanged.
+fn slurm_replace_utf8_string<S: AsRef<str>>(
+    field: &mut *mut c_char,
+    value: S,
+) -> Result<(), SlurmError> {
+    let new = slurm_alloc_utf8_string(value)?;
+    slurm_free(field);
+    *field = new;
+    Ok(())
+}
+
 /// Free an array of strings allocated through Slurm's allocator.
 ///
 /// A mutable reference to the pointer is required; after freeing, the pointer
@@ -309,6 +684,175 @@ fn slurm_free_string_array(ptr_ref: &mut *mut *mut c_char, count: usize) {
     slurm_free(ptr_ref);
 }
 
+/// Get the batch script of a job from the controller.
+///
+/// This is the equivalent of `scontrol write batch_script`. The controller
+/// only has the scripts of jobs that are still in its queue; see
+/// `JobRecord::script` for finished jobs.
+pub fn get_batch_script<J: Into<JobIdentifier>>(jid: J) -> Result<String, Error> {
+    let jid = jid.into().resolve()?;
+    let mut buf: *mut c_char = 0 as _;
+    let mut size: libc::size_t = 0;
+
+    // The C API only knows how to write the script to a stream.
+    let fp = unsafe { libc::open_memstream(&mut buf, &mut size) };
+    if fp.is_null() {
+        return Err(std::io::Error::last_os_error().into());
+    }
+
+    let rc = unsafe { slurm_sys::slurm_job_batch_script(fp as _, jid) };
+    let errno = unsafe { slurm_sys::slurm_get_errno() };
+    unsafe { libc::fclose(fp) };
+
+    let text = unsafe { optional_cstr(buf) }
+        .map(|s| s.into_owned())
+        .unwrap_or_default();
+    unsafe { libc::free(buf as _) };
+
+    if rc != 0 {
+        return Err(SlurmError::from_slurm(errno).into());
+    }
+
+    Ok(text)
+}
+
+/// Convert a C string that may be null into a Rust string.
+///
+/// Many Slurm structures leave string fields null when they have no value.
+/// The lifetime of the result is unbounded, so callers must make sure to tie
+/// it to the lifetime of the structure containing the pointer.
+unsafe fn optional_cstr<'a>(ptr: *const c_char) -> Option<Cow<'a, str>> {
+    if ptr.is_null() {
+        None
+    } else {
+        Some(CStr::from_ptr(ptr).to_string_lossy())
+    }
+}
+
+/// Convert a Unix timestamp from Slurm into a `DateTime`.
+///
+/// Timestamps that `chrono` cannot represent, which Slurm should never
+/// produce, are mapped to the Unix epoch rather than causing a panic.
+fn unix_time(t: i64) -> DateTime<Utc> {
+    Utc.timestamp_opt(t, 0)
+        .single()
+        .unwrap_or_else(|| Utc.timestamp_opt(0, 0).unwrap())
+}
+
+/// Format a timestamp the way `sacct` and `scontrol` do, in local time.
+#[cfg(feature = "slurmdb")]
+fn format_time(t: Option<DateTime<Utc>>) -> String {
+    match t {
+        Some(t) => t
+            .with_timezone(&Local)
+            .format("%Y-%m-%dT%H:%M:%S")
+            .to_string(),
+        None => "Unknown".to_owned(),
+    }
+}
+
+/// Format a duration in the `[days-]hours:minutes:seconds` style.
+#[cfg(feature = "slurmdb")]
+fn format_duration(d: Duration) -> String {
+    let secs = d.num_seconds();
+    let days = secs / 86400;
+    let hours = (secs % 86400) / 3600;
+    let minutes = (secs % 3600) / 60;
+    let secs = secs % 60;
+
+    if days > 0 {
+        format!("{}-{:02}:{:02}:{:02}", days, hours, minutes, secs)
+    } else {
+        format!("{:02}:{:02}:{:02}", hours, minutes, secs)
+    }
+}
+
+/// Convert a 32-bit limit value into an Option.
+///
+/// Slurm uses the special values `NO_VAL` and `INFINITE` to indicate limits
+/// that are not set.
+#[cfg(feature = "slurmdb")]
+fn optional_limit(value: u32) -> Option<u32> {
+    match value {
+        slurm_sys::SLURMRS_NO_VAL | slurm_sys::SLURMRS_INFINITE => None,
+        v => Some(v),
+    }
+}
+
+/// The verbosity of Slurm's log messages.
+///
+/// These are the levels used by the `SlurmctldDebug` configuration setting
+/// and `scontrol setdebug`, from least to most verbose.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[non_exhaustive]
+pub enum LogLevel {
+    Quiet = 0,
+    Fatal = 1,
+    Error = 2,
+    Info = 3,
+    Verbose = 4,
+    Debug = 5,
+    Debug2 = 6,
+    Debug3 = 7,
+    Debug4 = 8,
+    Debug5 = 9,
+}
+
+impl LogLevel {
+    /// Get the name that Slurm uses for this level, such as `debug2`.
+    pub fn slurm_name(&self) -> &'static str {
+        match *self {
+            LogLevel::Quiet => "quiet",
+            LogLevel::Fatal => "fatal",
+            LogLevel::Error => "error",
+            LogLevel::Info => "info",
+            LogLevel::Verbose => "verbose",
+            LogLevel::Debug => "debug",
+            LogLevel::Debug2 => "debug2",
+            LogLevel::Debug3 => "debug3",
+            LogLevel::Debug4 => "debug4",
+            LogLevel::Debug5 => "debug5",
+        }
+    }
+
+    fn to_slurm(self) -> c_int {
+        self as c_int
+    }
+}
+
+impl FromStr for LogLevel {
+    type Err = Error;
+
+    /// Parse a level from its Slurm name, as accepted by `scontrol setdebug`.
+    fn from_str(s: &str) -> Result<Self, Error> {
+        Ok(match s.to_lowercase().as_ref() {
+            "quiet" => LogLevel::Quiet,
+            "fatal" => LogLevel::Fatal,
+            "error" => LogLevel::Error,
+            "info" => LogLevel::Info,
+            "verbose" => LogLevel::Verbose,
+            "debug" => LogLevel::Debug,
+            "debug2" => LogLevel::Debug2,
+            "debug3" => LogLevel::Debug3,
+            "debug4" => LogLevel::Debug4,
+            "debug5" => LogLevel::Debug5,
+            _ => return Err(parse_err!("unrecognized Slurm log level {:?}", s)),
+        })
+    }
+}
+
+/// Set the logging level of the Slurm controller, as with `scontrol
+/// setdebug`.
+///
+/// This affects `slurmctld` itself, not the current process, and requires
+/// administrative privileges. The change lasts until the controller is
+/// restarted or reconfigured.
+pub fn set_controller_log_level(level: LogLevel) -> Result<(), Error> {
+    ustry!(slurm_sys::slurm_set_debug_level(level.to_slurm() as u32));
+    Ok(())
+}
+
 /// A helper trait that lets us generically iterate over lists. It must be
 /// public so that we can expose `Iterator` for `SlurmListIteratorOwned`.
 pub trait UnownedFromSlurmPointer {
@@ -316,6 +860,19 @@ pub trait UnownedFromSlurmPointer {
     fn unowned_from_slurm_pointer(ptr: *mut c_void) -> Self;
 }
 
+/// A helper trait for list items that have owned versions. This lets us
+/// consume owned lists, handing ownership of each item to the caller.
+pub trait OwnedFromSlurmPointer: UnownedFromSlurmPointer {
+    /// The owned version of this type.
+    type Owned;
+
+    /// Take ownership of an item that has been detached from a Slurm list.
+    ///
+    /// This function is unsafe because the returned value will free the
+    /// pointer when dropped.
+    unsafe fn owned_from_slurm_pointer(ptr: *mut c_void) -> Self::Owned;
+}
+
 /// Sub-helpers for the "job state enum" macros.
 ///
 /// Some states are not available in older versions of Slurm. We jump through
@@ -345,7 +902,7 @@ macro_rules! jse_all {
         slurm_sys::$sysname
     };
     (FROMVAL, $rustname:ident) => {
-        Ok(JobState::$rustname)
+        JobState::$rustname
     };
 }
 
@@ -362,7 +919,7 @@ macro_rules! jse_deadline {
         0xFF00
     };
     (FROMVAL, $rustname:ident) => {
-        Err(format_err!("illegal job state code 0xFF00"))
+        JobState::Other(0xFF00)
     };
 }
 
@@ -379,13 +936,19 @@ macro_rules! jse_oom {
         0xFF01
     };
     (FROMVAL, $rustname:ident) => {
-        Err(format_err!("illegal job state code 0xFF01"))
+        JobState::Other(0xFF01)
     };
 }
 
 /// Helper for interfacing between the C `job_state` enum and our own type.
+///
+/// The first group of items are "base" states, which map onto values of the
+/// `job_states` enum. The second group are states derived from flag bits that
+/// Slurm sets on top of the base state; these take precedence, as in Slurm's
+/// own `job_state_string()`, and are checked in the order listed.
 macro_rules! make_job_state_enum {
-    ($(<$rustname:ident, $shortcode:ident, $filter:ident, $sysname:ident, $doc:expr;>),*) => {
+    ($(<$rustname:ident, $shortcode:ident, $name:expr, $filter:ident, $sysname:ident, $doc:expr;>),*;
+     $(<$flagrustname:ident, $flagshortcode:ident, $flagname:expr, $flagsysname:ident, $flagdoc:expr;>),*) => {
         /// States that a job or job step can be in.
         ///
         /// The `Deadline` and `OutOfMemory` states are not available in all
@@ -393,29 +956,55 @@ macro_rules! make_job_state_enum {
         /// when built against such a version of Slurm will yield a nonsense
         /// value that will probably cause bad things to happen. (TODO:
         /// research the precise versions of Slurm in which these were added.)
+        ///
+        /// New states may be added in the future, so matches on this type
+        /// must include a wildcard arm.
         #[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+        #[cfg_attr(feature = "serde", derive(Serialize))]
+        #[non_exhaustive]
         pub enum JobState {
             $(
                 #[doc=$doc] $rustname,
             )*
+            $(
+                #[doc=$flagdoc] $flagrustname,
+            )*
+            /// A state code that this crate does not recognize.
+            Other(u32),
         }
 
         impl JobState {
-            fn from_slurm(s: slurm_sys::job_states) -> Result<JobState, Error> {
+            fn from_slurm(s: slurm_sys::job_states) -> JobState {
                 match s {
                     $(
                         $filter!(SYSVAL, $sysname) => $filter!(FROMVAL, $rustname),
                     )*
-                    other => Err(format_err!("unrecognized job state code {}", other)),
+                    other => JobState::Other(other),
                 }
             }
 
+            /// Decode a full Slurm job state value, which may include flag
+            /// bits in addition to the base state.
+            fn from_slurm_full(s: u32) -> JobState {
+                $(
+                    if s & slurm_sys::$flagsysname != 0 {
+                        return JobState::$flagrustname;
+                    }
+                )*
+
+                JobState::from_slurm(s & slurm_sys::JOB_STATE_BASE)
+            }
+
             #[allow(unused)]
             fn to_slurm(&self) -> slurm_sys::job_states {
                 match self {
                     $(
                         &JobState::$rustname => $filter!(SYSVAL, $sysname),
                     )*
+                    $(
+                        &JobState::$flagrustname => slurm_sys::$flagsysname,
+                    )*
+                    &JobState::Other(raw) => raw,
                 }
             }
 
@@ -424,323 +1013,1356 @@ macro_rules! make_job_state_enum {
                     $(
                         &JobState::$rustname => stringify!($shortcode),
                     )*
+                    $(
+                        &JobState::$flagrustname => stringify!($flagshortcode),
+                    )*
+                    &JobState::Other(_) => "?",
                 }
             }
         }
+
+        impl FromStr for JobState {
+            type Err = Error;
+
+            /// Parse either Slurm's name for a state, such as `COMPLETED`,
+            /// or its short code, such as `CD`. Case is ignored.
+            ///
+            /// This does not call into the Slurm library, so it can be used
+            /// to decode states reported by other means, such as the REST
+            /// API.
+            fn from_str(s: &str) -> Result<JobState, Error> {
+                $(
+                    if s.eq_ignore_ascii_case($name) || s.eq_ignore_ascii_case(stringify!($shortcode)) {
+                        return Ok(JobState::$rustname);
+                    }
+                )*
+                $(
+                    if s.eq_ignore_ascii_case($flagname) || s.eq_ignore_ascii_case(stringify!($flagshortcode)) {
+                        return Ok(JobState::$flagrustname);
+                    }
+                )*
+
+                Err(parse_err!("unrecognized job state {:?}", s))
+            }
+        }
     }
 }
 
 make_job_state_enum! {
-    <Pending, PD, jse_all, job_states_JOB_PENDING, "The job has not yet started running.";>,
-    <Running, R, jse_all, job_states_JOB_RUNNING, "The job is running.";>,
-    <Suspended, S, jse_all, job_states_JOB_SUSPENDED, "The job has been suspended.";>,
-    <Complete, CG, jse_all, job_states_JOB_COMPLETE, "The job finished successfully.";>,
-    <Cancelled, CA, jse_all, job_states_JOB_CANCELLED, "The job was cancelled.";>,
-    <Failed, F, jse_all, job_states_JOB_FAILED, "The job finished unsuccessfully.";>,
-    <Timeout, TO, jse_all, job_states_JOB_TIMEOUT, "The job was killed because it exceeded its time allocation.";>,
-    <NodeFail, NF, jse_all, job_states_JOB_NODE_FAIL, "The node running the job failed.";>,
-    <Preempted, PR, jse_all, job_states_JOB_PREEMPTED, "The job was killed by preemption.";>,
-    <BootFail, BF, jse_all, job_states_JOB_BOOT_FAIL, "The job failed because Slurm failed to launch it.";>,
-    <Deadline, DL, jse_deadline, job_states_JOB_DEADLINE, "The job failed to start in time.";>,
-    <OutOfMemory, OM, jse_oom, job_states_JOB_OOM, "The job was killed because it exceeded its memory allocation.";>
+    <Pending, PD, "PENDING", jse_all, job_states_JOB_PENDING, "The job has not yet started running.";>,
+    <Running, R, "RUNNING", jse_all, job_states_JOB_RUNNING, "The job is running.";>,
+    <Suspended, S, "SUSPENDED", jse_all, job_states_JOB_SUSPENDED, "The job has been suspended.";>,
+    <Complete, CD, "COMPLETED", jse_all, job_states_JOB_COMPLETE, "The job finished successfully.";>,
+    <Cancelled, CA, "CANCELLED", jse_all, job_states_JOB_CANCELLED, "The job was cancelled.";>,
+    <Failed, F, "FAILED", jse_all, job_states_JOB_FAILED, "The job finished unsuccessfully.";>,
+    <Timeout, TO, "TIMEOUT", jse_all, job_states_JOB_TIMEOUT, "The job was killed because it exceeded its time allocation.";>,
+    <NodeFail, NF, "NODE_FAIL", jse_all, job_states_JOB_NODE_FAIL, "The node running the job failed.";>,
+    <Preempted, PR, "PREEMPTED", jse_all, job_states_JOB_PREEMPTED, "The job was killed by preemption.";>,
+    <BootFail, BF, "BOOT_FAIL", jse_all, job_states_JOB_BOOT_FAIL, "The job failed because Slurm failed to launch it.";>,
+    <Deadline, DL, "DEADLINE", jse_deadline, job_states_JOB_DEADLINE, "The job failed to start in time.";>,
+    <OutOfMemory, OM, "OUT_OF_MEMORY", jse_oom, job_states_JOB_OOM, "The job was killed because it exceeded its memory allocation.";>;
+    <Completing, CG, "COMPLETING", JOB_COMPLETING, "The job has ended and its processes are being cleaned up.";>,
+    <Configuring, CF, "CONFIGURING", JOB_CONFIGURING, "The job has been allocated nodes that are still being set up.";>,
+    <Resizing, RS, "RESIZING", JOB_RESIZING, "The size of the job is being changed.";>,
+    <Requeued, RQ, "REQUEUED", JOB_REQUEUE, "The job is being requeued.";>,
+    <RequeueFed, RF, "REQUEUE_FED", JOB_REQUEUE_FED, "The job is being requeued by a federated cluster.";>,
+    <RequeueHold, RH, "REQUEUE_HOLD", JOB_REQUEUE_HOLD, "The job is being requeued and held.";>,
+    <SpecialExit, SE, "SPECIAL_EXIT", JOB_SPECIAL_EXIT, "The job is being requeued and held because of a special exit code.";>,
+    <Stopped, ST, "STOPPED", JOB_STOPPED, "The job has been stopped with SIGSTOP.";>,
+    <Revoked, RV, "REVOKED", JOB_REVOKED, "The job was revoked because a sibling job in a federation started.";>
 }
 
-/// Helper for creating public structs that directly wrap Slurm API
-/// structures. Because we must use Slurm's internal allocator, these all wrap
-/// native pointers. It's a bit annoying but as far as I can tell it's what we
-/// have to do. All of these types are "borrowed" items; they should not
-/// implement Drop methods.
-macro_rules! make_slurm_wrap_struct {
-    ($rust_name:ident, $slurm_name:path, $doc:expr) => {
-        #[doc = $doc]
-        #[derive(Debug)]
-        pub struct $rust_name(*mut $slurm_name);
+impl JobState {
+    /// Get Slurm's name for this state, such as `COMPLETED`.
+    pub fn slurm_name(&self) -> String {
+        unsafe { CStr::from_ptr(slurm_sys::slurm_job_state_string(self.to_slurm() as _)) }
+            .to_string_lossy()
+            .into_owned()
+    }
 
-        impl $rust_name {
-            /// Access the underlying slurm_sys struct immutably.
-            #[allow(unused)]
-            #[inline(always)]
-            fn sys_data(&self) -> &$slurm_name {
-                unsafe { &(*self.0) }
-            }
+    /// Return true if the job has finished and will not run again without
+    /// outside intervention.
+    pub fn is_terminal(&self) -> bool {
+        match *self {
+            JobState::Complete
+            | JobState::Cancelled
+            | JobState::Failed
+            | JobState::Timeout
+            | JobState::NodeFail
+            | JobState::Preempted
+            | JobState::BootFail
+            | JobState::Deadline
+            | JobState::OutOfMemory
+            | JobState::Revoked => true,
+            _ => false,
+        }
+    }
 
-            /// Access the underlying slurm_sys struct mutably.
-            #[allow(unused)]
-            #[inline(always)]
-            fn sys_data_mut(&mut self) -> &mut $slurm_name {
-                unsafe { &mut (*self.0) }
-            }
+    /// Return true if the job is pending, running, or in a transitional
+    /// state; that is, if it is not terminal.
+    pub fn is_active(&self) -> bool {
+        !self.is_terminal()
+    }
+}
 
-            /// Transmute a reference to a pointer to the underlying datatype
-            /// into a reference to this wrapper struct. This leverages the
-            /// fact that the wrapper type is a unit struct that is basically
-            /// just a pointer itself. This function allows us to return
-            /// references to fields of various `slurm_sys` structs as if
-            /// they were our Rust wrapper types.
-            #[allow(unused)]
-            #[inline(always)]
-            unsafe fn transmute_ptr<'a>(ptr: &'a *mut $slurm_name) -> &'a Self {
-                std::mem::transmute(ptr)
-            }
+/// Flag bits that Slurm may set on top of a job's base state.
+///
+/// The most significant flag is also reflected in the `JobState` returned by
+/// `state()`, but several may be set at once.
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct JobStateFlags(u32);
+
+impl JobStateFlags {
+    fn from_slurm(s: u32) -> JobStateFlags {
+        JobStateFlags(s & !slurm_sys::JOB_STATE_BASE)
+    }
 
-            /// Like `transmute_ptr`, but mutable.
-            #[allow(unused)]
-            #[inline(always)]
-            unsafe fn transmute_ptr_mut<'a>(ptr: &'a mut *mut $slurm_name) -> &'a mut Self {
-                std::mem::transmute(ptr)
-            }
-        }
+    /// Get the raw flag bits.
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
 
-        impl UnownedFromSlurmPointer for $rust_name {
-            #[inline(always)]
-            fn unowned_from_slurm_pointer(ptr: *mut c_void) -> Self {
-                $rust_name(ptr as _)
-            }
-        }
-    };
-}
+    /// Return true if the job's processes are being cleaned up.
+    pub fn completing(&self) -> bool {
+        self.0 & slurm_sys::JOB_COMPLETING != 0
+    }
 
-/// Helper for creating "owned" versions of unowned structs. This is super
-/// tedious but I think it's what we need to do to correctly interface with
-/// Slurm's allocator.
-macro_rules! make_owned_version {
-    (@customdrop $unowned_type:ident, $owned_name:ident, $doc:expr) => {
-        #[doc=$doc]
-        #[derive(Debug)]
-        pub struct $owned_name($unowned_type);
+    /// Return true if the job's nodes are still being set up.
+    pub fn configuring(&self) -> bool {
+        self.0 & slurm_sys::JOB_CONFIGURING != 0
+    }
 
-        impl Deref for $owned_name {
-            type Target = $unowned_type;
+    /// Return true if the job is being requeued, in any fashion.
+    pub fn requeued(&self) -> bool {
+        self.0
+            & (slurm_sys::JOB_REQUEUE
+                | slurm_sys::JOB_REQUEUE_FED
+                | slurm_sys::JOB_REQUEUE_HOLD
+                | slurm_sys::JOB_SPECIAL_EXIT)
+            != 0
+    }
 
-            fn deref(&self) -> &$unowned_type {
-                &self.0
-            }
-        }
+    /// Return true if the job is being requeued and will be held once it is
+    /// pending again.
+    pub fn requeue_hold(&self) -> bool {
+        self.0 & slurm_sys::JOB_REQUEUE_HOLD != 0
+    }
 
-        impl DerefMut for $owned_name {
-            fn deref_mut(&mut self) -> &mut $unowned_type {
-                &mut self.0
-            }
-        }
+    /// Return true if the job is being requeued and held because it exited
+    /// with one of the configured special exit codes.
+    pub fn special_exit(&self) -> bool {
+        self.0 & slurm_sys::JOB_SPECIAL_EXIT != 0
+    }
 
-        impl $owned_name {
-            /// This function is unsafe because it may not be valid for the
-            /// returned value to be filled with zeros. (Slurm is generally
-            /// pretty good about all-zeros being OK, though.)
-            #[allow(unused)]
-            unsafe fn alloc_zeroed() -> Self {
-                $owned_name($unowned_type(slurm_alloc()))
-            }
+    /// Return true if the size of the job is being changed.
+    pub fn resizing(&self) -> bool {
+        self.0 & slurm_sys::JOB_RESIZING != 0
+    }
 
-            /// This function is unsafe because it can potentially leak memory
-            /// if not used correctly.
-            #[allow(unused)]
-            unsafe fn give_up_ownership(mut self) -> $unowned_type {
-                let ptr = (self.0).0;
-                (self.0).0 = 0 as _; // ensures that slurm_free() doesn't free the memory
-                $unowned_type(ptr)
-            }
+    /// Return true if the job was revoked by a federated sibling.
+    pub fn revoked(&self) -> bool {
+        self.0 & slurm_sys::JOB_REVOKED != 0
+    }
 
-            /// This function is unsafe because we commit ourselves to freeing
-            /// the passed-in pointer, which could potentially be bad if we
-            /// don't in fact own it.
-            #[allow(unused)]
-            unsafe fn assume_ownership(ptr: *mut c_void) -> Self {
-                $owned_name($unowned_type(ptr as _))
-            }
-        }
-    };
+    /// Return true if the job has been stopped with SIGSTOP.
+    pub fn stopped(&self) -> bool {
+        self.0 & slurm_sys::JOB_STOPPED != 0
+    }
+}
 
-    ($unowned_type:ident, $owned_name:ident, $doc:expr) => {
-        make_owned_version!(@customdrop $unowned_type, $owned_name, $doc);
+/// Render a raw job state code the way Slurm's tools do, such as
+/// `RUNNING` or `COMPLETING`.
+///
+/// The code may include flag bits, as in the `job_state` fields of Slurm's
+/// structures. For decoded states, see `JobState::slurm_name`.
+pub fn job_state_string(raw: u32) -> Cow<'static, str> {
+    unsafe { optional_cstr(slurm_sys::slurm_job_state_string(raw)) }
+        .unwrap_or(Cow::Borrowed("UNKNOWN"))
+}
 
-        impl Drop for $owned_name {
-            fn drop(&mut self) {
-                slurm_free(&mut (self.0).0);
-            }
-        }
-    };
+/// Render a raw job state-reason code the way Slurm's tools do, such as
+/// `Resources` or `AssocGrpCpuLimit`.
+pub fn job_reason_string(raw: u32) -> Cow<'static, str> {
+    unsafe { optional_cstr(slurm_sys::slurm_job_reason_string(raw as _)) }
+        .unwrap_or(Cow::Borrowed("UNKNOWN"))
 }
 
-// The slurm list type gets custom implementations because we give it a type
-// parameter to allow typed access.
+/// Render a raw node state code the way Slurm's tools do, including flags,
+/// such as `IDLE+DRAIN`.
+pub fn node_state_string(raw: u32) -> Cow<'static, str> {
+    unsafe { optional_cstr(slurm_sys::slurm_node_state_string(raw)) }
+        .unwrap_or(Cow::Borrowed("UNKNOWN"))
+}
 
-/// A list of some kind of object known to Slurm.
+/// Flag bits recording options with which a job was submitted.
 ///
-/// These lists show up in a variety of places in the Slurm API. As with the
-/// other core structures exposed by this crate, this type represents a
-/// *borrowed* reference to a list.
-#[derive(Debug)]
-pub struct SlurmList<T>(slurm_sys::List, PhantomData<T>);
+/// These correspond to the `bitflags` field of Slurm's job structures. Only
+/// the flags of interest to users are given accessors; the rest are
+/// available through `bits`. Accessors for flags that the Slurm version in
+/// use does not define always return false.
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct JobFlags(u64);
+
+impl JobFlags {
+    fn from_slurm<T: Into<u64>>(raw: T) -> JobFlags {
+        JobFlags(raw.into())
+    }
 
-impl<T> SlurmList<T> {
-    unsafe fn transmute_ptr<'a>(ptr: &'a slurm_sys::List) -> &'a Self {
-        std::mem::transmute(ptr)
+    /// Get the raw flag bits.
+    pub fn bits(&self) -> u64 {
+        self.0
     }
 
-    unsafe fn transmute_ptr_mut<'a>(ptr: &'a mut slurm_sys::List) -> &'a mut Self {
-        std::mem::transmute(ptr)
+    fn has(&self, flag: u32) -> bool {
+        self.0 & flag as u64 != 0
+    }
+
+    /// Return true if the job's tasks must be bound to the CPUs closest to
+    /// their generic resources, as with `--gres-flags=enforce-binding`.
+    pub fn gres_enforce_bind(&self) -> bool {
+        self.has(slurm_sys::SLURMRS_GRES_ENFORCE_BIND)
+    }
+
+    /// Return true if the job is to be cancelled if its dependencies can
+    /// never be satisfied, as with `--kill-on-invalid-dep=yes`.
+    pub fn kill_invalid_dependency(&self) -> bool {
+        self.has(slurm_sys::SLURMRS_KILL_INV_DEP)
+    }
+
+    /// Return true if the job is to be left pending if its dependencies can
+    /// never be satisfied, as with `--kill-on-invalid-dep=no`.
+    pub fn no_kill_invalid_dependency(&self) -> bool {
+        self.has(slurm_sys::SLURMRS_NO_KILL_INV_DEP)
+    }
+
+    /// Return true if the job's nodes are to be rebooted before it starts,
+    /// as with `--reboot`.
+    pub fn node_reboot(&self) -> bool {
+        self.has(slurm_sys::SLURMRS_NODE_REBOOT)
+    }
+
+    /// Return true if the job is to be spread over as many nodes as
+    /// possible, as with `--spread-job`.
+    pub fn spread_job(&self) -> bool {
+        self.has(slurm_sys::SLURMRS_SPREAD_JOB)
+    }
+
+    /// Return true if the job is to be given the minimum number of nodes in
+    /// its requested range, as with `--use-min-nodes`.
+    pub fn use_min_nodes(&self) -> bool {
+        self.has(slurm_sys::SLURMRS_USE_MIN_NODES)
     }
 }
 
-impl<T: UnownedFromSlurmPointer> SlurmList<T> {
-    pub fn iter<'a>(&'a self) -> SlurmListIteratorOwned<'a, T> {
-        let ptr = unsafe { slurm_sys::slurm_list_iterator_create(self.0) };
+/// The flag bit that Slurm sets in memory requests that are made on a
+/// per-CPU, rather than per-node, basis.
+const MEM_PER_CPU: u64 = 0x8000_0000_0000_0000;
 
-        if ptr.is_null() {
-            panic!("failed to create list iterator");
+/// An amount of memory requested by or allocated to a job.
+///
+/// Slurm measures memory in mebibytes, and it encodes whether a request is
+/// made per-node or per-CPU using a flag bit. This type makes that
+/// distinction explicit. It can be parsed from and formatted to the notation
+/// used by Slurm's tools: a number with an optional `K`, `M`, `G`, or `T`
+/// unit suffix (defaulting to `M`), and an optional trailing `n` or `c` to
+/// indicate a per-node or per-CPU amount (defaulting to per-node). For
+/// instance, `16G`, `4000Mc`, and `512`.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum MemSize {
+    /// An amount of memory per node, in mebibytes.
+    PerNode(u64),
+
+    /// An amount of memory per allocated CPU, in mebibytes.
+    PerCpu(u64),
+}
+
+impl MemSize {
+    /// Decode a raw Slurm memory value, which may have the per-CPU flag bit
+    /// set.
+    ///
+    /// Returns None for the special value `NO_VAL64`, which indicates that
+    /// no amount was specified.
+    fn from_slurm(raw: u64) -> Option<MemSize> {
+        if raw == slurm_sys::SLURMRS_NO_VAL64 {
+            None
+        } else if raw & MEM_PER_CPU != 0 {
+            Some(MemSize::PerCpu(raw & !MEM_PER_CPU))
+        } else {
+            Some(MemSize::PerNode(raw))
+        }
+    }
+
+    /// Encode this value in Slurm's raw representation.
+    fn to_slurm(&self) -> u64 {
+        match *self {
+            MemSize::PerNode(mib) => mib,
+            MemSize::PerCpu(mib) => mib | MEM_PER_CPU,
+        }
+    }
+
+    /// Get the amount of memory, in mebibytes.
+    pub fn mib(&self) -> u64 {
+        match *self {
+            MemSize::PerNode(mib) | MemSize::PerCpu(mib) => mib,
         }
+    }
 
-        SlurmListIteratorOwned(ptr as _, PhantomData)
+    /// Return true if this amount of memory is per-CPU, rather than
+    /// per-node.
+    pub fn is_per_cpu(&self) -> bool {
+        match *self {
+            MemSize::PerNode(_) => false,
+            MemSize::PerCpu(_) => true,
+        }
     }
 }
 
-/// An owned version of `SlurmList`.
-#[derive(Debug)]
-pub struct SlurmListOwned<T>(SlurmList<T>);
+impl FromStr for MemSize {
+    type Err = Error;
 
-impl<T> Deref for SlurmListOwned<T> {
-    type Target = SlurmList<T>;
+    fn from_str(text: &str) -> Result<MemSize, Error> {
+        let text = text.trim();
 
-    fn deref(&self) -> &SlurmList<T> {
-        &self.0
+        let (text, per_cpu) = if text.ends_with('c') {
+            (&text[..text.len() - 1], true)
+        } else if text.ends_with('n') {
+            (&text[..text.len() - 1], false)
+        } else {
+            (text, false)
+        };
+
+        let (digits, unit) = match text.char_indices().last() {
+            Some((i, c)) if c.is_ascii_alphabetic() => (&text[..i], c.to_ascii_uppercase()),
+            _ => (text, 'M'),
+        };
+
+        let number: u64 = digits
+            .parse()
+            .map_err(|_| parse_err!("malformed memory size {:?}", text))?;
+
+        let mib = match unit {
+            'K' => Some((number + 1023) / 1024),
+            'M' => Some(number),
+            'G' => number.checked_mul(1 << 10),
+            'T' => number.checked_mul(1 << 20),
+            other => return Err(parse_err!("unrecognized memory unit {:?}", other)),
+        };
+
+        let mib = match mib {
+            Some(m) if m & MEM_PER_CPU == 0 => m,
+            _ => return Err(parse_err!("memory size {:?} is too large", text)),
+        };
+
+        Ok(if per_cpu {
+            MemSize::PerCpu(mib)
+        } else {
+            MemSize::PerNode(mib)
+        })
     }
 }
 
-impl<T> DerefMut for SlurmListOwned<T> {
-    fn deref_mut(&mut self) -> &mut SlurmList<T> {
-        &mut self.0
+impl Display for MemSize {
+    /// Format the amount using the largest unit that represents it exactly,
+    /// followed by `n` or `c`, as done by `sacct`.
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+        let mib = self.mib();
+        let suffix = if self.is_per_cpu() { 'c' } else { 'n' };
+
+        if mib != 0 && mib % (1 << 20) == 0 {
+            write!(f, "{}T{}", mib >> 20, suffix)
+        } else if mib != 0 && mib % (1 << 10) == 0 {
+            write!(f, "{}G{}", mib >> 10, suffix)
+        } else {
+            write!(f, "{}M{}", mib, suffix)
+        }
     }
 }
 
-impl<T> SlurmListOwned<T> {
-    #[allow(unused)]
-    unsafe fn give_up_ownership(mut self) -> SlurmList<T> {
-        let ptr = (self.0).0;
-        (self.0).0 = 0 as _; // ensures that slurm_free() doesn't free the memory
-        SlurmList(ptr, PhantomData)
+/// A time limit for a job.
+///
+/// Slurm measures time limits in minutes, with a special value indicating no
+/// limit. This type can be parsed from the notations accepted by Slurm's
+/// tools: `minutes`, `minutes:seconds`, `hours:minutes:seconds`, `days-hours`,
+/// `days-hours:minutes`, `days-hours:minutes:seconds`, and `UNLIMITED`. As in
+/// Slurm, seconds are rounded up to the next minute. It is formatted in the
+/// `[days-]hours:minutes:seconds` style.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum TimeLimit {
+    /// A limit of the specified number of minutes.
+    Minutes(u32),
+
+    /// No limit.
+    Unlimited,
+}
+
+impl TimeLimit {
+    /// Decode a raw Slurm time limit.
+    ///
+    /// Returns None for the special value `NO_VAL`, which indicates that no
+    /// limit was specified.
+    fn from_slurm(raw: u32) -> Option<TimeLimit> {
+        match raw {
+            slurm_sys::SLURMRS_NO_VAL => None,
+            slurm_sys::SLURMRS_INFINITE => Some(TimeLimit::Unlimited),
+            minutes => Some(TimeLimit::Minutes(minutes)),
+        }
     }
 
-    #[allow(unused)]
-    unsafe fn assume_ownership(ptr: *mut c_void) -> Self {
-        SlurmListOwned(SlurmList(ptr as _, PhantomData))
+    /// Encode this value in Slurm's raw representation.
+    fn to_slurm(&self) -> u32 {
+        match *self {
+            TimeLimit::Minutes(minutes) => minutes,
+            TimeLimit::Unlimited => slurm_sys::SLURMRS_INFINITE,
+        }
+    }
+
+    /// Get this limit as a Duration, or None if it is unlimited.
+    pub fn as_duration(&self) -> Option<Duration> {
+        match *self {
+            TimeLimit::Minutes(minutes) => Some(Duration::minutes(minutes as i64)),
+            TimeLimit::Unlimited => None,
+        }
     }
 }
 
-impl<T> Drop for SlurmListOwned<T> {
-    fn drop(&mut self) {
-        unsafe { slurm_sys::slurm_list_destroy((self.0).0) };
+impl From<u32> for TimeLimit {
+    /// Interpret a number as a limit in minutes.
+    fn from(minutes: u32) -> TimeLimit {
+        TimeLimit::Minutes(minutes)
     }
 }
 
-/// Customized support for lists of strings.
-impl SlurmList<*mut c_char> {
-    pub fn iter<'a>(&'a self) -> SlurmStringListIteratorOwned<'a> {
-        let ptr = unsafe { slurm_sys::slurm_list_iterator_create(self.0) };
+impl FromStr for TimeLimit {
+    type Err = Error;
 
-        if ptr.is_null() {
-            panic!("failed to create list iterator");
+    fn from_str(text: &str) -> Result<TimeLimit, Error> {
+        let text = text.trim();
+
+        match text.to_ascii_uppercase().as_ref() {
+            "UNLIMITED" | "INFINITE" | "-1" => return Ok(TimeLimit::Unlimited),
+            _ => {}
         }
 
-        SlurmStringListIteratorOwned(ptr as _, PhantomData)
-    }
+        let malformed = || parse_err!("malformed time limit {:?}", text);
 
-    pub fn append<S: AsRef<str>>(&mut self, s: S) {
-        let ptr = slurm_alloc_utf8_string(s);
+        let parse = |piece: &str| -> Result<u64, Error> { piece.parse().map_err(|_| malformed()) };
 
-        if self.0.is_null() {
-            self.0 = unsafe { slurm_sys::slurm_list_create(Some(slurm_sys::slurmrs_free)) };
+        let (days, rest) = match text.find('-') {
+            Some(i) => (parse(&text[..i])?, &text[i + 1..]),
+            None => (0, text),
+        };
+
+        let pieces = rest.split(':').map(&parse).collect::<Result<Vec<_>, _>>()?;
+
+        // Without a day count, a lone number is minutes and a pair is
+        // minutes:seconds; with one, they are hours and hours:minutes.
+        let (hours, minutes, seconds) = match (text.contains('-'), pieces.as_slice()) {
+            (false, &[m]) => (0, m, 0),
+            (false, &[m, s]) => (0, m, s),
+            (true, &[h]) => (h, 0, 0),
+            (true, &[h, m]) => (h, m, 0),
+            (_, &[h, m, s]) => (h, m, s),
+            _ => return Err(malformed()),
+        };
+
+        let total = ((days * 24 + hours) * 60 + minutes) * 60 + seconds;
+        let total_minutes = (total + 59) / 60;
+
+        if total_minutes >= slurm_sys::SLURMRS_NO_VAL as u64 {
+            return Err(parse_err!("time limit {:?} is too large", text));
         }
 
-        unsafe {
-            slurm_sys::slurm_list_append(self.0, ptr as _);
+        Ok(TimeLimit::Minutes(total_minutes as u32))
+    }
+}
+
+impl Display for TimeLimit {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+        match *self {
+            TimeLimit::Unlimited => write!(f, "UNLIMITED"),
+            TimeLimit::Minutes(minutes) => {
+                let days = minutes / 1440;
+                let hours = (minutes % 1440) / 60;
+                let minutes = minutes % 60;
+
+                if days > 0 {
+                    write!(f, "{}-{:02}:{:02}:00", days, hours, minutes)
+                } else {
+                    write!(f, "{:02}:{:02}:00", hours, minutes)
+                }
+            }
         }
     }
 }
 
-// Likewise for iterating through lists, except the iterators are always owned
-#[derive(Debug)]
-pub struct SlurmListIteratorOwned<'a, T: 'a + UnownedFromSlurmPointer>(
-    *mut slurm_sys::listIterator,
-    PhantomData<&'a T>,
-);
+/// An identifier for a job that may be a component of a larger one.
+///
+/// Slurm identifies the tasks of job arrays with the notation `12345_7`, and
+/// the components of heterogeneous ("pack") jobs with the notation `12345+1`.
+/// This type parses and formats both, as well as plain job IDs. Each task or
+/// component also has a plain job ID of its own; use `resolve` to obtain it.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[non_exhaustive]
+pub enum JobIdentifier {
+    /// A plain job ID.
+    Plain(JobId),
+
+    /// A task within a job array: the array's job ID and the task ID.
+    ArrayTask(JobId, u32),
+
+    /// A component of a heterogeneous job: the leader's job ID and the
+    /// component offset.
+    PackComponent(JobId, u32),
+}
 
-impl<'a, T: 'a + UnownedFromSlurmPointer> Drop for SlurmListIteratorOwned<'a, T> {
-    fn drop(&mut self) {
-        unsafe { slurm_sys::slurm_list_iterator_destroy(self.0) };
+impl JobIdentifier {
+    /// Get the job ID at the base of this identifier.
+    ///
+    /// For array tasks and heterogeneous job components, this is the ID of
+    /// the array or leader job.
+    pub fn base_id(&self) -> JobId {
+        match *self {
+            JobIdentifier::Plain(jid)
+            | JobIdentifier::ArrayTask(jid, _)
+            | JobIdentifier::PackComponent(jid, _) => jid,
+        }
     }
-}
 
-impl<'a, T: 'a + UnownedFromSlurmPointer> Iterator for SlurmListIteratorOwned<'a, T> {
-    type Item = T;
+    /// Resolve this identifier into the plain job ID of the job that it
+    /// identifies, by asking the Slurm controller.
+    ///
+    /// For plain job IDs, no communication with the controller is needed.
+    pub fn resolve(&self) -> Result<JobId, Error> {
+        if let JobIdentifier::Plain(jid) = *self {
+            return Ok(jid);
+        }
 
-    fn next(&mut self) -> Option<T> {
-        let ptr = unsafe { slurm_sys::slurm_list_next(self.0) };
+        let mut text = slurm_alloc_utf8_string(self.to_string())?;
+        let jid = unsafe { slurm_sys::slurm_xlate_job_id(text) };
+        slurm_free(&mut text);
 
-        if ptr.is_null() {
-            None
+        if jid == 0 {
+            Err(format_err!("no such job {}", self))
         } else {
-            Some(T::unowned_from_slurm_pointer(ptr))
+            Ok(jid)
         }
     }
 }
 
-/// A helper for iterating through lists of strings.
-#[derive(Debug)]
-pub struct SlurmStringListIteratorOwned<'a>(*mut slurm_sys::listIterator, PhantomData<&'a str>);
+impl From<JobId> for JobIdentifier {
+    fn from(jid: JobId) -> JobIdentifier {
+        JobIdentifier::Plain(jid)
+    }
+}
 
-impl<'a> Drop for SlurmStringListIteratorOwned<'a> {
-    fn drop(&mut self) {
-        unsafe { slurm_sys::slurm_list_iterator_destroy(self.0) };
+impl FromStr for JobIdentifier {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<JobIdentifier, Error> {
+        let text = text.trim();
+        let malformed = || parse_err!("malformed job identifier {:?}", text);
+
+        if let Some(i) = text.find('_') {
+            let jid = text[..i].parse().map_err(|_| malformed())?;
+            let task = text[i + 1..].parse().map_err(|_| malformed())?;
+            Ok(JobIdentifier::ArrayTask(jid, task))
+        } else if let Some(i) = text.find('+') {
+            let jid = text[..i].parse().map_err(|_| malformed())?;
+            let offset = text[i + 1..].parse().map_err(|_| malformed())?;
+            Ok(JobIdentifier::PackComponent(jid, offset))
+        } else {
+            Ok(JobIdentifier::Plain(text.parse().map_err(|_| malformed())?))
+        }
     }
 }
 
-impl<'a> Iterator for SlurmStringListIteratorOwned<'a> {
-    type Item = Cow<'a, str>;
+impl Display for JobIdentifier {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+        match *self {
+            JobIdentifier::Plain(jid) => write!(f, "{}", jid),
+            JobIdentifier::ArrayTask(jid, task) => write!(f, "{}_{}", jid, task),
+            JobIdentifier::PackComponent(jid, offset) => write!(f, "{}+{}", jid, offset),
+        }
+    }
+}
 
-    fn next(&mut self) -> Option<Cow<'a, str>> {
-        let ptr = unsafe { slurm_sys::slurm_list_next(self.0) };
+/// Helper for creating public structs that directly wrap Slurm API
+/// structures. Because we must use Slurm's internal allocator, these all wrap
+/// native pointers. It's a bit annoying but as far as I can tell it's what we
+/// have to do. All of these types are "borrowed" items; they should not
+/// implement Drop methods.
+macro_rules! make_slurm_wrap_struct {
+    ($rust_name:ident, $slurm_name:path, $doc:expr) => {
+        #[doc = $doc]
+        pub struct $rust_name(*mut $slurm_name);
 
-        if ptr.is_null() {
-            None
-        } else {
-            Some(unsafe { CStr::from_ptr(ptr as _) }.to_string_lossy())
+        impl $rust_name {
+            /// Access the underlying slurm_sys struct immutably.
+            #[allow(unused)]
+            #[inline(always)]
+            fn sys_data(&self) -> &$slurm_name {
+                unsafe { &(*self.0) }
+            }
+
+            /// Access the underlying slurm_sys struct mutably.
+            #[allow(unused)]
+            #[inline(always)]
+            fn sys_data_mut(&mut self) -> &mut $slurm_name {
+                unsafe { &mut (*self.0) }
+            }
+
+            /// Transmute a reference to a pointer to the underlying datatype
+            /// into a reference to this wrapper struct. This leverages the
+            /// fact that the wrapper type is a unit struct that is basically
+            /// just a pointer itself. This function allows us to return
+            /// references to fields of various `slurm_sys` structs as if
+            /// they were our Rust wrapper types.
+            #[allow(unused)]
+            #[inline(always)]
+            unsafe fn transmute_ptr<'a>(ptr: &'a *mut $slurm_name) -> &'a Self {
+                std::mem::transmute(ptr)
+            }
+
+            /// Like `transmute_ptr`, but mutable.
+            #[allow(unused)]
+            #[inline(always)]
+            unsafe fn transmute_ptr_mut<'a>(ptr: &'a mut *mut $slurm_name) -> &'a mut Self {
+                std::mem::transmute(ptr)
+            }
         }
-    }
+
+        impl UnownedFromSlurmPointer for $rust_name {
+            #[inline(always)]
+            fn unowned_from_slurm_pointer(ptr: *mut c_void) -> Self {
+                $rust_name(ptr as _)
+            }
+        }
+    };
 }
 
-// Now we can finally start wrapping types that we care about.
+/// Implement `Debug` for a wrapper struct.
+///
+/// A derived implementation would only print the raw pointer, which is not
+/// very informative. Instead, we print the values returned by the named
+/// accessors, which should be cheap and should not fail. Wrappers without
+/// useful accessors, such as query filters, print their pointer.
+macro_rules! impl_wrapper_debug {
+    ($rust_name:ident) => {
+        impl Debug for $rust_name {
+            fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+                f.debug_tuple(stringify!($rust_name)).field(&self.0).finish()
+            }
+        }
+    };
 
-make_slurm_wrap_struct!(
-    JobInfo,
-    slurm_sys::job_info,
-    "\
-Information about a running job.
+    ($rust_name:ident; $($field:ident),+) => {
+        impl Debug for $rust_name {
+            fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+                if self.0.is_null() {
+                    return write!(f, "{}(null)", stringify!($rust_name));
+                }
 
-The following items in the Slurm API are *not* exposed in these Rust bindings:
+                f.debug_struct(stringify!($rust_name))
+                    $(.field(stringify!($field), &self.$field()))+
+                    .finish()
+            }
+        }
+    };
+}
 
-```ignore
-pub struct job_info {
-    pub account: *mut c_char,
-    pub admin_comment: *mut c_char,
-    pub alloc_node: *mut c_char,
-    pub alloc_sid: u32,
-    pub array_bitmap: *mut c_void,
-    pub array_job_id: u32,
-    pub array_task_id: u32,
-    pub array_max_tasks: u32,
-    pub array_task_str: *mut c_char,
-    pub assoc_id: u32,
-    pub batch_flag: u16,
-    pub batch_host: *mut c_char,
-    pub bitflags: u32,
-    pub boards_per_node: u16,
-    pub burst_buffer: *mut c_char,
+/// Helper for creating "owned" versions of unowned structs. This is super
+/// tedious but I think it's what we need to do to correctly interface with
+/// Slurm's allocator.
+macro_rules! make_owned_version {
+    (@customdrop $unowned_type:ident, $owned_name:ident, $doc:expr) => {
+        #[doc=$doc]
+        #[derive(Debug)]
+        #[must_use]
+        pub struct $owned_name($unowned_type);
+
+        impl Deref for $owned_name {
+            type Target = $unowned_type;
+
+            fn deref(&self) -> &$unowned_type {
+                &self.0
+            }
+        }
+
+        impl DerefMut for $owned_name {
+            fn deref_mut(&mut self) -> &mut $unowned_type {
+                &mut self.0
+            }
+        }
+
+        impl $owned_name {
+            /// This function is unsafe because it may not be valid for the
+            /// returned value to be filled with zeros. (Slurm is generally
+            /// pretty good about all-zeros being OK, though.)
+            #[allow(unused)]
+            unsafe fn alloc_zeroed() -> Result<Self, SlurmError> {
+                Ok($owned_name($unowned_type(slurm_alloc()?)))
+            }
+
+            /// This function is unsafe because it can potentially leak memory
+            /// if not used correctly.
+            #[allow(unused)]
+            unsafe fn give_up_ownership(mut self) -> $unowned_type {
+                let ptr = (self.0).0;
+                (self.0).0 = 0 as _; // ensures that slurm_free() doesn't free the memory
+                $unowned_type(ptr)
+            }
+
+            /// This function is unsafe because we commit ourselves to freeing
+            /// the passed-in pointer, which could potentially be bad if we
+            /// don't in fact own it.
+            #[allow(unused)]
+            unsafe fn assume_ownership(ptr: *mut c_void) -> Self {
+                $owned_name($unowned_type(ptr as _))
+            }
+        }
+    };
+
+    ($unowned_type:ident, $owned_name:ident, $doc:expr) => {
+        make_owned_version!(@customdrop $unowned_type, $owned_name, $doc);
+
+        impl Drop for $owned_name {
+            fn drop(&mut self) {
+                slurm_free(&mut (self.0).0);
+            }
+        }
+    };
+}
+
+// The slurm list type gets custom implementations because we give it a type
+// parameter to allow typed access.
+
+/// A list of some kind of object known to Slurm.
+///
+/// These lists show up in a variety of places in the Slurm API. As with the
+/// other core structures exposed by this crate, this type represents a
+/// *borrowed* reference to a list.
+#[derive(Debug)]
+pub struct SlurmList<T>(slurm_sys::List, PhantomData<T>);
+
+impl<T> SlurmList<T> {
+    /// Get the number of items in this list.
+    pub fn len(&self) -> usize {
+        if self.0.is_null() {
+            0
+        } else {
+            unsafe { slurm_sys::slurm_list_count(self.0) as usize }
+        }
+    }
+
+    /// Test whether this list contains no items.
+    ///
+    /// Lists are often represented by null pointers until something is added
+    /// to them, so this is true in that case too.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_null() || unsafe { slurm_sys::slurm_list_is_empty(self.0) } != 0
+    }
+
+    unsafe fn transmute_ptr<'a>(ptr: &'a slurm_sys::List) -> &'a Self {
+        std::mem::transmute(ptr)
+    }
+
+    /// Create a Slurm iterator over the list.
+    ///
+    /// A null list has no items, and gets a null iterator, which our
+    /// iterator types treat as exhausted.
+    fn iterator_ptr(&self) -> *mut slurm_sys::listIterator {
+        if self.0.is_null() {
+            return 0 as _;
+        }
+
+        let ptr = unsafe { slurm_sys::slurm_list_iterator_create(self.0) };
+
+        if ptr.is_null() {
+            panic!("failed to create list iterator");
+        }
+
+        ptr as _
+    }
+
+    unsafe fn transmute_ptr_mut<'a>(ptr: &'a mut slurm_sys::List) -> &'a mut Self {
+        std::mem::transmute(ptr)
+    }
+
+    /// Find the first item in the list for which the predicate, applied to
+    /// the raw item pointer, returns true.
+    fn find_raw<F: FnMut(*mut c_void) -> bool>(&self, mut pred: F) -> Option<*mut c_void> {
+        if self.0.is_null() {
+            return None;
+        }
+
+        // We iterate here rather than using `slurm_list_find_first`, since
+        // a panicking predicate must not unwind through C code.
+        let iter = unsafe { slurm_sys::slurm_list_iterator_create(self.0) };
+
+        if iter.is_null() {
+            panic!("failed to create list iterator");
+        }
+
+        let found = loop {
+            let ptr = unsafe { slurm_sys::slurm_list_next(iter) };
+
+            if ptr.is_null() {
+                break None;
+            }
+
+            if pred(ptr) {
+                break Some(ptr);
+            }
+        };
+
+        unsafe { slurm_sys::slurm_list_iterator_destroy(iter) };
+        found
+    }
+
+    /// Remove all items from the list for which the predicate, applied to
+    /// the raw item pointer, returns true. The items are freed by the list's
+    /// destructor function. Returns the number of items removed.
+    fn remove_raw<F: FnMut(*mut c_void) -> bool>(&mut self, mut pred: F) -> usize {
+        if self.0.is_null() {
+            return 0;
+        }
+
+        let iter = unsafe { slurm_sys::slurm_list_iterator_create(self.0) };
+
+        if iter.is_null() {
+            panic!("failed to create list iterator");
+        }
+
+        let mut n_removed = 0;
+
+        loop {
+            let ptr = unsafe { slurm_sys::slurm_list_next(iter) };
+
+            if ptr.is_null() {
+                break;
+            }
+
+            if pred(ptr) {
+                unsafe { slurm_sys::slurm_list_delete_item(iter) };
+                n_removed += 1;
+            }
+        }
+
+        unsafe { slurm_sys::slurm_list_iterator_destroy(iter) };
+        n_removed
+    }
+}
+
+impl<T: UnownedFromSlurmPointer> SlurmList<T> {
+    /// Find the first item in the list that matches a predicate.
+    pub fn find<'a, F: FnMut(&T) -> bool>(&'a self, mut pred: F) -> Option<SlurmListItem<'a, T>> {
+        self.find_raw(|ptr| pred(&T::unowned_from_slurm_pointer(ptr)))
+            .map(|ptr| SlurmListItem(T::unowned_from_slurm_pointer(ptr), PhantomData))
+    }
+
+    /// Remove all items from the list that match a predicate, returning the
+    /// number of items removed.
+    pub fn remove_if<F: FnMut(&T) -> bool>(&mut self, mut pred: F) -> usize {
+        self.remove_raw(|ptr| pred(&T::unowned_from_slurm_pointer(ptr)))
+    }
+
+    /// Retain only the items in the list that match a predicate.
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut pred: F) {
+        self.remove_if(|item| !pred(item));
+    }
+}
+
+impl<T: UnownedFromSlurmPointer> SlurmList<T> {
+    pub fn iter<'a>(&'a self) -> SlurmListIteratorOwned<'a, T> {
+        SlurmListIteratorOwned(self.iterator_ptr(), self.len(), PhantomData)
+    }
+}
+
+/// An owned version of `SlurmList`.
+#[derive(Debug)]
+#[must_use]
+pub struct SlurmListOwned<T>(SlurmList<T>);
+
+impl<T> Deref for SlurmListOwned<T> {
+    type Target = SlurmList<T>;
+
+    fn deref(&self) -> &SlurmList<T> {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for SlurmListOwned<T> {
+    fn deref_mut(&mut self) -> &mut SlurmList<T> {
+        &mut self.0
+    }
+}
+
+impl<T> SlurmListOwned<T> {
+    #[allow(unused)]
+    unsafe fn give_up_ownership(mut self) -> SlurmList<T> {
+        let ptr = (self.0).0;
+        (self.0).0 = 0 as _; // ensures that slurm_free() doesn't free the memory
+        SlurmList(ptr, PhantomData)
+    }
+
+    #[allow(unused)]
+    unsafe fn assume_ownership(ptr: *mut c_void) -> Self {
+        SlurmListOwned(SlurmList(ptr as _, PhantomData))
+    }
+}
+
+impl<T> Drop for SlurmListOwned<T> {
+    fn drop(&mut self) {
+        // Lists are created lazily, so the pointer may be null.
+        if !(self.0).0.is_null() {
+            unsafe { slurm_sys::slurm_list_destroy((self.0).0) };
+        }
+    }
+}
+
+/// Customized support for lists of strings.
+impl SlurmList<*mut c_char> {
+    /// Find the first string in the list that matches a predicate.
+    pub fn find<'a, F: FnMut(&str) -> bool>(&'a self, mut pred: F) -> Option<Cow<'a, str>> {
+        self.find_raw(|ptr| pred(&unsafe { CStr::from_ptr(ptr as _) }.to_string_lossy()))
+            .map(|ptr| unsafe { CStr::from_ptr(ptr as _) }.to_string_lossy())
+    }
+
+    /// Remove all strings from the list that match a predicate, returning
+    /// the number of items removed.
+    pub fn remove_if<F: FnMut(&str) -> bool>(&mut self, mut pred: F) -> usize {
+        self.remove_raw(|ptr| pred(&unsafe { CStr::from_ptr(ptr as _) }.to_string_lossy()))
+    }
+
+    /// Retain only the strings in the list that match a predicate.
+    pub fn retain<F: FnMut(&str) -> bool>(&mut self, mut pred: F) {
+        self.remove_if(|item| !pred(item));
+    }
+
+    pub fn iter<'a>(&'a self) -> SlurmStringListIteratorOwned<'a> {
+        SlurmStringListIteratorOwned(self.iterator_ptr(), self.len(), PhantomData)
+    }
+
+    /// Append a string to the list.
+    ///
+    /// This fails only if memory for the new string cannot be allocated.
+    pub fn append<S: AsRef<str>>(&mut self, s: S) -> Result<(), SlurmError> {
+        let ptr = slurm_alloc_utf8_string(s)?;
+
+        if self.0.is_null() {
+            self.0 = unsafe { slurm_sys::slurm_list_create(Some(slurm_sys::slurmrs_free)) };
+        }
+
+        unsafe {
+            slurm_sys::slurm_list_append(self.0, ptr as _);
+        }
+
+        Ok(())
+    }
+}
+
+// The standard collection traits have no way to report errors, so these
+// panic if a string cannot be allocated. Use `append` to handle that case.
+
+impl<'a> Extend<&'a str> for SlurmList<*mut c_char> {
+    fn extend<I: IntoIterator<Item = &'a str>>(&mut self, iter: I) {
+        for item in iter {
+            self.append(item).expect("Slurm memory allocation failed");
+        }
+    }
+}
+
+impl Extend<String> for SlurmList<*mut c_char> {
+    fn extend<I: IntoIterator<Item = String>>(&mut self, iter: I) {
+        for item in iter {
+            self.append(item).expect("Slurm memory allocation failed");
+        }
+    }
+}
+
+impl<'a> FromIterator<&'a str> for SlurmListOwned<*mut c_char> {
+    fn from_iter<I: IntoIterator<Item = &'a str>>(iter: I) -> Self {
+        let mut list = unsafe { SlurmListOwned::assume_ownership(0 as _) };
+        list.extend(iter);
+        list
+    }
+}
+
+impl FromIterator<String> for SlurmListOwned<*mut c_char> {
+    fn from_iter<I: IntoIterator<Item = String>>(iter: I) -> Self {
+        let mut list = unsafe { SlurmListOwned::assume_ownership(0 as _) };
+        list.extend(iter);
+        list
+    }
+}
+
+impl<'a> IntoIterator for &'a SlurmList<*mut c_char> {
+    type Item = Cow<'a, str>;
+    type IntoIter = SlurmStringListIteratorOwned<'a>;
+
+    fn into_iter(self) -> SlurmStringListIteratorOwned<'a> {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a SlurmListOwned<*mut c_char> {
+    type Item = Cow<'a, str>;
+    type IntoIter = SlurmStringListIteratorOwned<'a>;
+
+    fn into_iter(self) -> SlurmStringListIteratorOwned<'a> {
+        self.iter()
+    }
+}
+
+impl<'a, T: UnownedFromSlurmPointer> IntoIterator for &'a SlurmList<T> {
+    type Item = SlurmListItem<'a, T>;
+    type IntoIter = SlurmListIteratorOwned<'a, T>;
+
+    fn into_iter(self) -> SlurmListIteratorOwned<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T: UnownedFromSlurmPointer> IntoIterator for &'a SlurmListOwned<T> {
+    type Item = SlurmListItem<'a, T>;
+    type IntoIter = SlurmListIteratorOwned<'a, T>;
+
+    fn into_iter(self) -> SlurmListIteratorOwned<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T: OwnedFromSlurmPointer> IntoIterator for SlurmListOwned<T> {
+    type Item = T::Owned;
+    type IntoIter = SlurmListIntoIter<T>;
+
+    /// Consume the list, transferring ownership of its items to the caller.
+    fn into_iter(self) -> SlurmListIntoIter<T> {
+        SlurmListIntoIter(self)
+    }
+}
+
+/// Helper for implementing `OwnedFromSlurmPointer` for list items that have
+/// owned versions.
+#[cfg(feature = "slurmdb")]
+macro_rules! impl_owned_list_item {
+    ($unowned_type:ident, $owned_type:ident) => {
+        impl OwnedFromSlurmPointer for $unowned_type {
+            type Owned = $owned_type;
+
+            unsafe fn owned_from_slurm_pointer(ptr: *mut c_void) -> $owned_type {
+                $owned_type::assume_ownership(ptr)
+            }
+        }
+    };
+}
+
+/// Helper for implementing the conversion traits of the plain-Rust snapshot
+/// types. Each wrapper type must have a `to_snapshot` method. With the
+/// `serde` feature enabled, the wrapper types are serialized by way of their
+/// snapshots.
+macro_rules! impl_snapshot_traits {
+    ($unowned_type:ident, $snapshot_type:ident) => {
+        impl<'a> From<&'a $unowned_type> for $snapshot_type {
+            fn from(item: &'a $unowned_type) -> $snapshot_type {
+                item.to_snapshot()
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl Serialize for $unowned_type {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                self.to_snapshot().serialize(serializer)
+            }
+        }
+    };
+}
+
+/// Helper for implementing `Extend` and `FromIterator` for lists of items
+/// that have an `append` method taking an owned item.
+#[cfg(feature = "slurmdb")]
+macro_rules! impl_list_collection_traits {
+    ($unowned_type:ident, $owned_type:ident) => {
+        impl Extend<$owned_type> for SlurmList<$unowned_type> {
+            fn extend<I: IntoIterator<Item = $owned_type>>(&mut self, iter: I) {
+                for item in iter {
+                    self.append(item);
+                }
+            }
+        }
+
+        impl FromIterator<$owned_type> for SlurmListOwned<$unowned_type> {
+            fn from_iter<I: IntoIterator<Item = $owned_type>>(iter: I) -> Self {
+                let mut list = unsafe { SlurmListOwned::assume_ownership(0 as _) };
+                list.extend(iter);
+                list
+            }
+        }
+    };
+}
+
+/// An item borrowed from a `SlurmList`.
+///
+/// The unowned wrapper types are thin wrappers around pointers into the
+/// list's memory, so this type ties their lifetime to that of the list. It
+/// dereferences to the underlying wrapper type.
+#[derive(Debug)]
+pub struct SlurmListItem<'a, T: 'a>(T, PhantomData<&'a T>);
+
+impl<'a, T: 'a> Deref for SlurmListItem<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+// Likewise for iterating through lists, except the iterators are always
+// owned. We track the number of remaining items so that we can implement
+// `ExactSizeIterator`.
+#[derive(Debug)]
+pub struct SlurmListIteratorOwned<'a, T: 'a + UnownedFromSlurmPointer>(
+    *mut slurm_sys::listIterator,
+    usize,
+    PhantomData<&'a T>,
+);
+
+impl<'a, T: 'a + UnownedFromSlurmPointer> Drop for SlurmListIteratorOwned<'a, T> {
+    fn drop(&mut self) {
+        if !self.0.is_null() {
+            unsafe { slurm_sys::slurm_list_iterator_destroy(self.0) };
+        }
+    }
+}
+
+impl<'a, T: 'a + UnownedFromSlurmPointer> Iterator for SlurmListIteratorOwned<'a, T> {
+    type Item = SlurmListItem<'a, T>;
+
+    fn next(&mut self) -> Option<SlurmListItem<'a, T>> {
+        if self.0.is_null() {
+            return None;
+        }
+
+        let ptr = unsafe { slurm_sys::slurm_list_next(self.0) };
+
+        if ptr.is_null() {
+            None
+        } else {
+            self.1 = self.1.saturating_sub(1);
+            Some(SlurmListItem(
+                T::unowned_from_slurm_pointer(ptr),
+                PhantomData,
+            ))
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.1, Some(self.1))
+    }
+}
+
+impl<'a, T: 'a + UnownedFromSlurmPointer> ExactSizeIterator for SlurmListIteratorOwned<'a, T> {}
+
+/// A consuming iterator over an owned list.
+///
+/// Each item is detached from the list as it is yielded, so that it is freed
+/// when the caller drops it rather than when the list is destroyed. Any items
+/// that have not been yielded are freed along with the list when the
+/// iterator is dropped.
+#[derive(Debug)]
+pub struct SlurmListIntoIter<T: OwnedFromSlurmPointer>(SlurmListOwned<T>);
+
+impl<T: OwnedFromSlurmPointer> Iterator for SlurmListIntoIter<T> {
+    type Item = T::Owned;
+
+    fn next(&mut self) -> Option<T::Owned> {
+        let list = (self.0).0 .0;
+
+        if list.is_null() {
+            return None;
+        }
+
+        let ptr = unsafe { slurm_sys::slurm_list_pop(list) };
+
+        if ptr.is_null() {
+            None
+        } else {
+            Some(unsafe { T::owned_from_slurm_pointer(ptr) })
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.0.len();
+        (n, Some(n))
+    }
+}
+
+impl<T: OwnedFromSlurmPointer> ExactSizeIterator for SlurmListIntoIter<T> {}
+
+/// A helper for iterating through lists of strings.
+#[derive(Debug)]
+pub struct SlurmStringListIteratorOwned<'a>(
+    *mut slurm_sys::listIterator,
+    usize,
+    PhantomData<&'a str>,
+);
+
+impl<'a> Drop for SlurmStringListIteratorOwned<'a> {
+    fn drop(&mut self) {
+        if !self.0.is_null() {
+            unsafe { slurm_sys::slurm_list_iterator_destroy(self.0) };
+        }
+    }
+}
+
+impl<'a> Iterator for SlurmStringListIteratorOwned<'a> {
+    type Item = Cow<'a, str>;
+
+    fn next(&mut self) -> Option<Cow<'a, str>> {
+        if self.0.is_null() {
+            return None;
+        }
+
+        let ptr = unsafe { slurm_sys::slurm_list_next(self.0) };
+
+        if ptr.is_null() {
+            None
+        } else {
+            self.1 = self.1.saturating_sub(1);
+            Some(unsafe { CStr::from_ptr(ptr as _) }.to_string_lossy())
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.1, Some(self.1))
+    }
+}
+
+impl<'a> ExactSizeIterator for SlurmStringListIteratorOwned<'a> {}
+
+// Now we can finally start wrapping types that we care about.
+
+/// Helper for interfacing between the C `job_state_reason` enum and our own
+/// type.
+macro_rules! make_pending_reason_enum {
+    ($(<$rustname:ident, $sysname:ident, $doc:expr;>),*) => {
+        /// Reasons that a job may be waiting to run.
+        ///
+        /// Slurm defines many more reasons than are enumerated here, most of
+        /// them having to do with specific resource limits. These are
+        /// represented with the `Other` variant, which holds the raw Slurm
+        /// reason code. In all cases, the `Display` implementation gives
+        /// Slurm's own name for the reason, such as `AssocGrpCpuLimit`.
+        #[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+        #[cfg_attr(feature = "serde", derive(Serialize))]
+        #[non_exhaustive]
+        pub enum PendingReason {
+            $(
+                #[doc=$doc] $rustname,
+            )*
+            /// Some other reason, identified by its raw Slurm code.
+            Other(u32),
+        }
+
+        impl PendingReason {
+            fn from_slurm(code: u32) -> PendingReason {
+                match code {
+                    $(
+                        slurm_sys::$sysname => PendingReason::$rustname,
+                    )*
+                    other => PendingReason::Other(other),
+                }
+            }
+
+            fn to_slurm(&self) -> u32 {
+                match *self {
+                    $(
+                        PendingReason::$rustname => slurm_sys::$sysname,
+                    )*
+                    PendingReason::Other(code) => code,
+                }
+            }
+        }
+    }
+}
+
+make_pending_reason_enum! {
+    <NoReason, job_state_reason_WAIT_NO_REASON, "No particular reason is recorded.";>,
+    <Priority, job_state_reason_WAIT_PRIORITY, "Jobs with higher priority are queued ahead of this one.";>,
+    <Dependency, job_state_reason_WAIT_DEPENDENCY, "The job is waiting for a dependency to be satisfied.";>,
+    <Resources, job_state_reason_WAIT_RESOURCES, "The job is waiting for resources to become available.";>,
+    <PartitionNodeLimit, job_state_reason_WAIT_PART_NODE_LIMIT, "The job's node count exceeds its partition's limits.";>,
+    <PartitionTimeLimit, job_state_reason_WAIT_PART_TIME_LIMIT, "The job's time limit exceeds its partition's limit.";>,
+    <PartitionDown, job_state_reason_WAIT_PART_DOWN, "The job's partition is down.";>,
+    <PartitionInactive, job_state_reason_WAIT_PART_INACTIVE, "The job's partition is inactive.";>,
+    <Held, job_state_reason_WAIT_HELD, "The job is held by an administrator.";>,
+    <BeginTime, job_state_reason_WAIT_TIME, "The job's earliest start time has not yet arrived.";>,
+    <Licenses, job_state_reason_WAIT_LICENSES, "The job is waiting for licenses to become available.";>,
+    <AssocJobLimit, job_state_reason_WAIT_ASSOC_JOB_LIMIT, "The job's association has reached its job limit.";>,
+    <AssocResourceLimit, job_state_reason_WAIT_ASSOC_RESOURCE_LIMIT, "The job's association has reached a resource limit.";>,
+    <AssocTimeLimit, job_state_reason_WAIT_ASSOC_TIME_LIMIT, "The job's association has reached its time limit.";>,
+    <Reservation, job_state_reason_WAIT_RESERVATION, "The job is waiting for its reservation to become available.";>,
+    <NodeNotAvailable, job_state_reason_WAIT_NODE_NOT_AVAIL, "Nodes required by the job are not available.";>,
+    <HeldUser, job_state_reason_WAIT_HELD_USER, "The job is held by its owner.";>,
+    <DependencyNeverSatisfied, job_state_reason_WAIT_DEP_INVALID, "The job's dependency can never be satisfied.";>,
+    <QosJobLimit, job_state_reason_WAIT_QOS_JOB_LIMIT, "The job's QOS has reached its job limit.";>,
+    <QosResourceLimit, job_state_reason_WAIT_QOS_RESOURCE_LIMIT, "The job's QOS has reached a resource limit.";>,
+    <QosTimeLimit, job_state_reason_WAIT_QOS_TIME_LIMIT, "The job's QOS has reached its time limit.";>,
+    <AssocGrpJobs, job_state_reason_WAIT_ASSOC_GRP_JOB, "The job's association has reached its GrpJobs limit.";>,
+    <AssocMaxJobs, job_state_reason_WAIT_ASSOC_MAX_JOBS, "The job's association has reached its MaxJobs limit.";>,
+    <QosGrpJobs, job_state_reason_WAIT_QOS_GRP_JOB, "The job's QOS has reached its GrpJobs limit.";>,
+    <QosMaxJobsPerUser, job_state_reason_WAIT_QOS_MAX_JOB_PER_USER, "The job's QOS has reached its MaxJobsPerUser limit.";>
+}
+
+impl Display for PendingReason {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+        let ptr = unsafe { slurm_sys::slurm_job_reason_string(self.to_slurm()) };
+
+        match unsafe { optional_cstr(ptr) } {
+            Some(text) => write!(f, "{}", text),
+            None => write!(f, "(reason code {})", self.to_slurm()),
+        }
+    }
+}
+
+impl FromStr for PendingReason {
+    type Err = Error;
+
+    /// Parse Slurm's name for a reason, such as `Resources`.
+    fn from_str(s: &str) -> Result<PendingReason, Error> {
+        (0..slurm_sys::job_state_reason_REASON_END)
+            .map(PendingReason::from_slurm)
+            .find(|reason| reason.to_string() == s)
+            .ok_or_else(|| parse_err!("unrecognized pending reason {:?}", s))
+    }
+}
+
+make_slurm_wrap_struct!(
+    JobInfo,
+    slurm_sys::job_info,
+    "\
+Information about a running job.
+
+The following items in the Slurm API are *not* exposed in these Rust bindings:
+
+```ignore
+pub struct job_info {
+    pub alloc_node: *mut c_char,
+    pub alloc_sid: u32,
+    pub array_bitmap: *mut c_void,
+    pub array_max_tasks: u32,
+    pub array_task_str: *mut c_char,
+    pub assoc_id: u32,
+    pub boards_per_node: u16,
+    pub burst_buffer: *mut c_char,
     pub burst_buffer_state: *mut c_char,
     pub cluster: *mut c_char,
     pub cluster_features: *mut c_char,
     pub command: *mut c_char,
-    pub comment: *mut c_char,
     pub contiguous: u16,
     pub core_spec: u16,
     pub cores_per_socket: u16,
@@ -751,587 +2373,8259 @@ pub struct job_info {
     pub cpu_freq_gov: u32,
     pub deadline: time_t,
     pub delay_boot: u32,
-    pub dependency: *mut c_char,
     pub derived_ec: u32,
     pub eligible_time: time_t,
     pub end_time: time_t,
     pub exc_nodes: *mut c_char,
     pub exc_node_inx: *mut i32,
-    pub exit_code: u32,
     pub features: *mut c_char,
-    pub fed_origin_str: *mut c_char,
     pub fed_siblings_active: u64,
-    pub fed_siblings_active_str: *mut c_char,
     pub fed_siblings_viable: u64,
-    pub fed_siblings_viable_str: *mut c_char,
     pub gres: *mut c_char,
-    pub gres_detail_cnt: u32,
-    pub gres_detail_str: *mut *mut c_char,
     pub group_id: u32,
     pub job_resrcs: *mut job_resources_t,
-    pub job_state: u32,
     pub last_sched_eval: time_t,
-    pub licenses: *mut c_char,
     pub max_cpus: u32,
     pub max_nodes: u32,
-    pub mcs_label: *mut c_char,
-    pub name: *mut c_char,
     pub network: *mut c_char,
-    pub nodes: *mut c_char,
     pub nice: u32,
     pub node_inx: *mut i32,
     pub ntasks_per_core: u16,
     pub ntasks_per_node: u16,
     pub ntasks_per_socket: u16,
     pub ntasks_per_board: u16,
+    pub num_nodes: u32,
+    pub num_tasks: u32,
+    pub pack_job_id: u32,
+    pub pack_job_id_set: *mut c_char,
+    pub pack_job_offset: u32,
+    pub pn_min_cpus: u16,
+    pub pn_min_tmp_disk: u32,
+    pub power_flags: u8,
+    pub preempt_time: time_t,
+    pub pre_sus_time: time_t,
+    pub profile: u32,
+    pub reboot: u8,
+    pub req_nodes: *mut c_char,
+    pub req_node_inx: *mut i32,
+    pub req_switch: u32,
+    pub resize_time: time_t,
+    pub resv_name: *mut c_char,
+    pub sched_nodes: *mut c_char,
+    pub select_jobinfo: *mut dynamic_plugin_data_t,
+    pub shared: u16,
+    pub show_flags: u16,
+    pub sockets_per_board: u16,
+    pub sockets_per_node: u16,
+    pub start_protocol_ver: u16,
+    pub state_desc: *mut c_char,
+    pub submit_time: time_t,
+    pub suspend_time: time_t,
+    pub time_min: u32,
+    pub threads_per_core: u16,
+    pub tres_req_str: *mut c_char,
+    pub wait4switch: u32,
+    pub wckey: *mut c_char,
+}
+```
+
+"
+);
+
+impl_wrapper_debug!(JobInfo; job_id, partition, state_reason);
+
+impl JobInfo {
+    /// Get this job's ID.
+    pub fn job_id(&self) -> JobId {
+        self.sys_data().job_id
+    }
+
+    /// Get the minimum amount of memory required by this job, if specified.
+    pub fn min_memory(&self) -> Option<MemSize> {
+        MemSize::from_slurm(self.sys_data().pn_min_memory)
+    }
+
+    /// Get this job's name.
+    pub fn name(&self) -> Cow<str> {
+        unsafe { optional_cstr(self.sys_data().name) }.unwrap_or(Cow::Borrowed(""))
+    }
+
+    /// Get this job's time limit, or None if it has not been set. Pending
+    /// jobs without an explicit limit get their partition's default limit
+    /// once they start.
+    pub fn time_limit(&self) -> Option<TimeLimit> {
+        TimeLimit::from_slurm(self.sys_data().time_limit)
+    }
+
+    /// Get the time at which this job started, or None if it is not known.
+    ///
+    /// For pending jobs, this is the time at which the scheduler expects the
+    /// job to start, if it has made an estimate.
+    pub fn start_time(&self) -> Option<DateTime<Utc>> {
+        match self.sys_data().start_time as i64 {
+            0 => None,
+            t => Some(unix_time(t)),
+        }
+    }
+
+    /// Get the number of CPUs allocated to this job, or requested by it if
+    /// it is still pending.
+    pub fn num_cpus(&self) -> u32 {
+        self.sys_data().num_cpus
+    }
+
+    /// Get the numeric ID of the user that owns this job.
+    pub fn user_id(&self) -> u32 {
+        self.sys_data().user_id
+    }
+
+    /// Get this job's scheduling priority. Held jobs have a priority of
+    /// zero.
+    pub fn priority(&self) -> u32 {
+        self.sys_data().priority
+    }
+
+    /// Get the name of the user that owns this job, if the controller
+    /// provided it.
+    ///
+    /// The controller often leaves this unset; use `user_id` and the system
+    /// user database in that case.
+    pub fn user_name(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().user_name) }
+    }
+
+    /// Get the cluster partition on which this job resides.
+    pub fn partition(&self) -> Cow<str> {
+        unsafe { CStr::from_ptr(self.sys_data().partition) }.to_string_lossy()
+    }
+
+    /// Get the reason that this job is in its current state; most usefully,
+    /// why it is still pending.
+    pub fn state_reason(&self) -> PendingReason {
+        PendingReason::from_slurm(self.sys_data().state_reason as u32)
+    }
+
+    /// Get this job's current state.
+    pub fn state(&self) -> JobState {
+        JobState::from_slurm_full(self.sys_data().job_state)
+    }
+
+    /// Get the flag bits set on this job's state.
+    pub fn state_flags(&self) -> JobStateFlags {
+        JobStateFlags::from_slurm(self.sys_data().job_state)
+    }
+
+    /// Get the flags recording options with which this job was submitted.
+    pub fn bitflags(&self) -> JobFlags {
+        JobFlags::from_slurm(self.sys_data().bitflags)
+    }
+
+    /// Get the name of the node running this job's batch script, if it is
+    /// a batch job that has started.
+    pub fn batch_host(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().batch_host) }.filter(|s| !s.is_empty())
+    }
+
+    /// Return true if this is a batch job, as opposed to an allocation made
+    /// by `salloc` or `srun`.
+    pub fn is_batch(&self) -> bool {
+        self.sys_data().batch_flag != 0
+    }
+
+    /// Get the ID of the job array that this job belongs to, if any.
+    pub fn array_job_id(&self) -> Option<JobId> {
+        match self.sys_data().array_job_id {
+            0 => None,
+            other => Some(other),
+        }
+    }
+
+    /// Get the index of this job within its array.
+    ///
+    /// Returns None if this job is not part of an array, or if it is the
+    /// record of an array's tasks that have not yet started.
+    pub fn array_task_id(&self) -> Option<u32> {
+        match self.sys_data().array_task_id {
+            slurm_sys::SLURMRS_NO_VAL => None,
+            other => Some(other),
+        }
+    }
+
+    /// Get the path of this job's standard input stream, if set.
+    pub fn stdin_path(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().std_in) }.filter(|s| !s.is_empty())
+    }
+
+    /// Get the path of this job's standard output stream, if set.
+    ///
+    /// This is the pattern given at submission, so it may contain
+    /// replacement symbols such as `%j`. If it is unset, Slurm's default of
+    /// `slurm-%j.out` (or `slurm-%A_%a.out` for arrays) applies.
+    pub fn stdout_path(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().std_out) }.filter(|s| !s.is_empty())
+    }
+
+    /// Get the path of this job's standard error stream, if set.
+    ///
+    /// As with `stdout_path`, this may contain replacement symbols. If it is
+    /// unset, the standard error stream goes to the same file as standard
+    /// output.
+    pub fn stderr_path(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().std_err) }.filter(|s| !s.is_empty())
+    }
+
+    /// Get the working directory of this job.
+    pub fn work_dir(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().work_dir) }.filter(|s| !s.is_empty())
+    }
+
+    /// Get the trackable resources allocated to this job.
+    ///
+    /// This is empty if the job has not yet been allocated any resources.
+    pub fn tres_alloc(&self) -> TresAmounts {
+        unsafe { TresAmounts::from_slurm(self.sys_data().tres_alloc_str) }
+    }
+
+    /// Get the comment attached to this job by its submitter, if any.
+    pub fn comment(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().comment) }
+    }
+
+    /// Get the comment attached to this job by an administrator, if any.
+    pub fn admin_comment(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().admin_comment) }
+    }
+
+    /// Get the name of the federated cluster to which this job was
+    /// submitted, if the cluster is part of a federation.
+    pub fn fed_origin(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().fed_origin_str) }.filter(|s| !s.is_empty())
+    }
+
+    /// Get the names of the federated clusters that currently hold a
+    /// sibling of this job.
+    ///
+    /// This is empty if the cluster is not part of a federation.
+    pub fn fed_siblings_active(&self) -> Vec<String> {
+        split_name_list(self.sys_data().fed_siblings_active_str)
+    }
+
+    /// Get the names of the federated clusters on which this job may run.
+    ///
+    /// This is empty if the cluster is not part of a federation.
+    pub fn fed_siblings_viable(&self) -> Vec<String> {
+        split_name_list(self.sys_data().fed_siblings_viable_str)
+    }
+
+    /// Get the account charged for this job, if known.
+    pub fn account(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().account) }
+    }
+
+    /// Get the name of the QOS under which this job runs, if known.
+    pub fn qos(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().qos) }
+    }
+
+    /// Get this job's dependency specification, if it has one.
+    ///
+    /// Dependencies that have been satisfied are removed from the
+    /// specification by the controller. Use `JobDependencies` to parse it.
+    pub fn dependency(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().dependency) }.filter(|s| !s.is_empty())
+    }
+
+    /// Get the licenses requested by this job, if any, in the form
+    /// `name:count,...` used by the `--licenses` option of `sbatch`.
+    pub fn licenses(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().licenses) }.filter(|s| !s.is_empty())
+    }
+
+    /// Get this job's MCS (multi-category security) label, if any.
+    pub fn mcs_label(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().mcs_label) }.filter(|s| !s.is_empty())
+    }
+
+    /// Get the list of nodes allocated to this job, in Slurm's compressed
+    /// hostlist format, or None if no nodes have been allocated.
+    pub fn nodes(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().nodes) }.filter(|s| !s.is_empty())
+    }
+
+    /// Get the detailed descriptions of the generic resources allocated to
+    /// this job, one per allocated node.
+    ///
+    /// These are strings such as `gpu:tesla:2(IDX:0-1)`. They are in the
+    /// same order as the nodes of the job's hostlist (see `nodes`). Use
+    /// `gres_allocations` to parse them.
+    pub fn gres_detail(&self) -> Vec<Cow<str>> {
+        let data = self.sys_data();
+
+        if data.gres_detail_str.is_null() {
+            return Vec::new();
+        }
+
+        unsafe { std::slice::from_raw_parts(data.gres_detail_str, data.gres_detail_cnt as usize) }
+            .iter()
+            .map(|p| unsafe { optional_cstr(*p) }.unwrap_or(Cow::Borrowed("")))
+            .collect()
+    }
+
+    /// Get the generic resources allocated to this job on each of its nodes.
+    ///
+    /// The result maps node names to the resources allocated on them. It is
+    /// empty if the job has not been allocated any nodes.
+    pub fn gres_allocations(&self) -> Result<BTreeMap<String, Vec<GresAllocation>>, Error> {
+        let nodes = match self.nodes() {
+            Some(n) => expand_hostlist(n)?,
+            None => return Ok(BTreeMap::new()),
+        };
+
+        let mut result = BTreeMap::new();
+
+        for (node, detail) in nodes.into_iter().zip(self.gres_detail()) {
+            result.insert(node, GresAllocation::parse_list(&detail)?);
+        }
+
+        Ok(result)
+    }
+
+    /// Get the indices of the GPUs allocated to this job on each of its
+    /// nodes.
+    ///
+    /// These are the device numbers used by the node's GPU driver, so that
+    /// GPU monitoring data can be attributed to jobs. Nodes on which the job
+    /// has no GPUs are omitted.
+    pub fn gpu_indices(&self) -> Result<BTreeMap<String, Vec<u32>>, Error> {
+        Ok(self
+            .gres_allocations()?
+            .into_iter()
+            .filter_map(|(node, allocs)| {
+                let indices: Vec<u32> = allocs
+                    .into_iter()
+                    .filter(|a| a.name == "gpu")
+                    .flat_map(|a| a.indices)
+                    .collect();
+
+                if indices.is_empty() {
+                    None
+                } else {
+                    Some((node, indices))
+                }
+            })
+            .collect())
+    }
+
+    /// Get the number of times that this job has been restarted, e.g. by
+    /// being requeued.
+    pub fn restart_cnt(&self) -> u16 {
+        self.sys_data().restart_cnt
+    }
+
+    /// Return true if this job may be requeued after a node failure or by
+    /// an administrator, as with the `--requeue` option of `sbatch`.
+    pub fn requeue(&self) -> bool {
+        self.sys_data().requeue != 0
+    }
+
+    /// Get this job's decoded exit status, or None if it has not yet ended.
+    pub fn exit_status(&self) -> Option<ExitStatus> {
+        if self.state().is_terminal() {
+            Some(ExitStatus::from_slurm(self.sys_data().exit_code))
+        } else {
+            None
+        }
+    }
+
+    /// Format all of this job's information in the `Key=Value` style used
+    /// by `scontrol show job`.
+    ///
+    /// If `one_liner` is true, all of the fields are put on a single line.
+    pub fn sprint(&self, one_liner: bool) -> Result<String, SlurmError> {
+        let mut ptr = pstry!(slurm_sys::slurm_sprint_job_info(self.0, one_liner as c_int));
+        let text = unsafe { CStr::from_ptr(ptr) }
+            .to_string_lossy()
+            .into_owned();
+        slurm_free(&mut ptr);
+        Ok(text)
+    }
+}
+
+impl JobInfo {
+    /// Copy the data in this structure into a plain-Rust structure.
+    ///
+    /// The result does not refer to any Slurm memory, so it may outlive the
+    /// query result that contained this structure.
+    pub fn to_snapshot(&self) -> JobInfoSnapshot {
+        let owned = |s: Option<Cow<str>>| s.map(|s| s.into_owned());
+
+        JobInfoSnapshot {
+            account: owned(self.account()),
+            admin_comment: owned(self.admin_comment()),
+            array_job_id: self.array_job_id(),
+            array_task_id: self.array_task_id(),
+            batch_host: owned(self.batch_host()),
+            bitflags: self.bitflags(),
+            comment: owned(self.comment()),
+            dependency: owned(self.dependency()),
+            exit_status: self.exit_status(),
+            fed_origin: owned(self.fed_origin()),
+            fed_siblings_active: self.fed_siblings_active(),
+            fed_siblings_viable: self.fed_siblings_viable(),
+            gres_detail: self
+                .gres_detail()
+                .into_iter()
+                .map(|s| s.into_owned())
+                .collect(),
+            is_batch: self.is_batch(),
+            job_id: self.job_id(),
+            licenses: owned(self.licenses()),
+            mcs_label: owned(self.mcs_label()),
+            min_memory: self.min_memory(),
+            name: self.name().into_owned(),
+            nodes: owned(self.nodes()),
+            num_cpus: self.num_cpus(),
+            partition: self.partition().into_owned(),
+            priority: self.priority(),
+            qos: owned(self.qos()),
+            requeue: self.requeue(),
+            restart_cnt: self.restart_cnt(),
+            start_time: self.start_time(),
+            state: self.state(),
+            state_flags: self.state_flags(),
+            state_reason: self.state_reason(),
+            stderr_path: owned(self.stderr_path()),
+            stdin_path: owned(self.stdin_path()),
+            stdout_path: owned(self.stdout_path()),
+            time_limit: self.time_limit(),
+            tres_alloc: self.tres_alloc(),
+            user_id: self.user_id(),
+            user_name: owned(self.user_name()),
+            work_dir: owned(self.work_dir()),
+        }
+    }
+}
+
+/// A plain-Rust copy of the data in a `JobInfo`.
+///
+/// See the accessors of `JobInfo` for the meanings of the fields.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct JobInfoSnapshot {
+    pub account: Option<String>,
+    pub admin_comment: Option<String>,
+    pub array_job_id: Option<JobId>,
+    pub array_task_id: Option<u32>,
+    pub batch_host: Option<String>,
+    pub bitflags: JobFlags,
+    pub comment: Option<String>,
+    pub dependency: Option<String>,
+    pub exit_status: Option<ExitStatus>,
+    pub fed_origin: Option<String>,
+    pub fed_siblings_active: Vec<String>,
+    pub fed_siblings_viable: Vec<String>,
+    pub gres_detail: Vec<String>,
+    pub is_batch: bool,
+    pub job_id: JobId,
+    pub licenses: Option<String>,
+    pub mcs_label: Option<String>,
+    pub min_memory: Option<MemSize>,
+    pub name: String,
+    pub nodes: Option<String>,
     pub num_cpus: u32,
-    pub num_nodes: u32,
-    pub num_tasks: u32,
+    pub partition: String,
+    pub priority: u32,
+    pub qos: Option<String>,
+    pub requeue: bool,
+    pub restart_cnt: u16,
+    pub start_time: Option<DateTime<Utc>>,
+    pub state: JobState,
+    pub state_flags: JobStateFlags,
+    pub state_reason: PendingReason,
+    pub stderr_path: Option<String>,
+    pub stdin_path: Option<String>,
+    pub stdout_path: Option<String>,
+    pub time_limit: Option<TimeLimit>,
+    pub tres_alloc: TresAmounts,
+    pub user_id: u32,
+    pub user_name: Option<String>,
+    pub work_dir: Option<String>,
+}
+
+impl_snapshot_traits!(JobInfo, JobInfoSnapshot);
+
+impl Display for JobInfo {
+    /// Format the job exactly as `scontrol show job` does.
+    ///
+    /// If Slurm is unable to format the job, only its ID and state are
+    /// printed. Use `sprint` to detect that case.
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+        match self.sprint(false) {
+            Ok(text) => write!(f, "{}", text.trim_end()),
+            Err(_) => write!(
+                f,
+                "JobId={} JobState={}",
+                self.job_id(),
+                self.state().slurm_name()
+            ),
+        }
+    }
+}
+
+/// Split a comma-separated list of names, such as the federation fields of
+/// `JobInfo` or the partitions of `NodeInfo`.
+fn split_name_list(ptr: *const c_char) -> Vec<String> {
+    match unsafe { optional_cstr(ptr) } {
+        Some(text) => text
+            .split(',')
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_owned())
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+/// The kinds of dependency that a job can have on other jobs.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum DependencyKind {
+    /// Start after the other job has started, possibly with a delay.
+    After,
+
+    /// Start after the other job has ended, however it ended.
+    AfterAny,
+
+    /// Start after the other job has ended and its burst buffer has been
+    /// torn down.
+    AfterBurstBuffer,
+
+    /// Start each array task after the corresponding task of the other job
+    /// array has completed successfully.
+    AfterCorr,
+
+    /// Start after the other job has ended unsuccessfully.
+    AfterNotOk,
+
+    /// Start after the other job has completed successfully.
+    AfterOk,
+
+    /// Use this job to expand the resources of the other job.
+    Expand,
+
+    /// Start once no other job with the same name and user is running.
+    Singleton,
+}
+
+impl DependencyKind {
+    /// Get the name of this kind of dependency, as used by `sbatch
+    /// --dependency`.
+    pub fn slurm_name(&self) -> &'static str {
+        match *self {
+            DependencyKind::After => "after",
+            DependencyKind::AfterAny => "afterany",
+            DependencyKind::AfterBurstBuffer => "afterburstbuffer",
+            DependencyKind::AfterCorr => "aftercorr",
+            DependencyKind::AfterNotOk => "afternotok",
+            DependencyKind::AfterOk => "afterok",
+            DependencyKind::Expand => "expand",
+            DependencyKind::Singleton => "singleton",
+        }
+    }
+}
+
+impl FromStr for DependencyKind {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<DependencyKind, Error> {
+        match text {
+            "after" => Ok(DependencyKind::After),
+            "afterany" => Ok(DependencyKind::AfterAny),
+            "afterburstbuffer" => Ok(DependencyKind::AfterBurstBuffer),
+            "aftercorr" => Ok(DependencyKind::AfterCorr),
+            "afternotok" => Ok(DependencyKind::AfterNotOk),
+            "afterok" => Ok(DependencyKind::AfterOk),
+            "expand" => Ok(DependencyKind::Expand),
+            "singleton" => Ok(DependencyKind::Singleton),
+            _ => Err(parse_err!("unrecognized dependency type {:?}", text)),
+        }
+    }
+}
+
+/// One dependency of a job on another.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Dependency {
+    /// What the dependency requires of the other job.
+    pub kind: DependencyKind,
+
+    /// The job depended upon. This is None for `Singleton` dependencies. A
+    /// dependency on an entire job array is expressed with a plain job ID.
+    pub job: Option<JobIdentifier>,
+
+    /// The delay after the other job starts, in minutes, for `After`
+    /// dependencies that specify one.
+    pub delay_minutes: Option<u32>,
+
+    /// The status annotation added by the controller, such as `unfulfilled`
+    /// or `failed`, if present. Versions of Slurm before 19.05 do not add
+    /// these.
+    pub status: Option<String>,
+}
+
+/// A job's parsed dependency specification.
+///
+/// Slurm expresses these as strings like `afterok:123:124,singleton`. The
+/// items are separated by commas if all of them must be satisfied, or by
+/// question marks if any one of them suffices.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct JobDependencies {
+    /// Whether satisfying any one of the dependencies suffices.
+    pub require_any: bool,
+
+    /// The individual dependencies.
+    pub items: Vec<Dependency>,
+}
+
+impl FromStr for JobDependencies {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<JobDependencies, Error> {
+        let text = text.trim();
+        let require_any = text.contains('?');
+        let mut items = Vec::new();
+
+        for group in text.split(|c| c == ',' || c == '?') {
+            if group.is_empty() {
+                continue;
+            }
+
+            let mut pieces = group.split(':');
+            let (kind, status) = split_dependency_status(pieces.next().unwrap_or(""));
+            let kind: DependencyKind = kind.parse()?;
+
+            if kind == DependencyKind::Singleton {
+                items.push(Dependency {
+                    kind,
+                    job: None,
+                    delay_minutes: None,
+                    status,
+                });
+                continue;
+            }
+
+            for piece in pieces {
+                let (piece, status) = split_dependency_status(piece);
+
+                let (id, delay_minutes) = match piece.find('+') {
+                    Some(i) => {
+                        let delay = piece[i + 1..]
+                            .parse()
+                            .map_err(|_| parse_err!("malformed dependency delay in {:?}", text))?;
+                        (&piece[..i], Some(delay))
+                    }
+                    None => (piece, None),
+                };
+
+                // A dependency on all tasks of an array is written `123_*`.
+                let job = match id.strip_suffix("_*") {
+                    Some(array_id) => JobIdentifier::Plain(
+                        array_id
+                            .parse()
+                            .map_err(|_| parse_err!("malformed job ID in {:?}", text))?,
+                    ),
+                    None => id.parse()?,
+                };
+
+                items.push(Dependency {
+                    kind,
+                    job: Some(job),
+                    delay_minutes,
+                    status,
+                });
+            }
+        }
+
+        Ok(JobDependencies { require_any, items })
+    }
+}
+
+/// Split a trailing status annotation, like `(unfulfilled)`, from an item
+/// of a dependency specification.
+fn split_dependency_status(text: &str) -> (&str, Option<String>) {
+    match text.find('(') {
+        Some(i) if text.ends_with(')') => {
+            (&text[..i], Some(text[i + 1..text.len() - 1].to_owned()))
+        }
+        _ => (text, None),
+    }
+}
+
+/// A generic resource allocated to a job on one node.
+///
+/// This is parsed from one item of Slurm's detailed GRES descriptions (see
+/// `JobInfo::gres_detail`), which look like `gpu:tesla:2(IDX:0-1)`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct GresAllocation {
+    /// The name of the resource, such as `gpu`.
+    pub name: String,
+
+    /// The type of the resource, such as `tesla`, if one was specified.
+    pub kind: Option<String>,
+
+    /// The number of units allocated, if it was given.
+    pub count: Option<u64>,
+
+    /// The indices of the allocated devices on the node. This is empty if
+    /// the resource does not have device files or Slurm did not report
+    /// them.
+    pub indices: Vec<u32>,
+}
+
+impl GresAllocation {
+    /// Parse a comma-separated list of allocations, such as
+    /// `gpu:2(IDX:0-1),mps:100(0/100,0/100)`.
+    pub fn parse_list(text: &str) -> Result<Vec<GresAllocation>, Error> {
+        let mut items = Vec::new();
+        let mut depth = 0;
+        let mut start = 0;
+
+        for (i, c) in text.char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                ',' if depth == 0 => {
+                    items.push(&text[start..i]);
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+
+        items.push(&text[start..]);
+
+        items
+            .into_iter()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::parse)
+            .collect()
+    }
+}
+
+impl FromStr for GresAllocation {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<GresAllocation, Error> {
+        let text = text.trim();
+
+        let (spec, detail) = match text.find('(') {
+            Some(i) if text.ends_with(')') => (&text[..i], Some(&text[i + 1..text.len() - 1])),
+            Some(_) => return Err(parse_err!("malformed GRES allocation {:?}", text)),
+            None => (text, None),
+        };
+
+        let mut pieces: Vec<&str> = spec.split(':').collect();
+
+        let count = match pieces.last() {
+            Some(p) if pieces.len() > 1 && p.chars().all(|c| c.is_ascii_digit()) => {
+                let n = p
+                    .parse()
+                    .map_err(|_| parse_err!("malformed GRES count in {:?}", text))?;
+                pieces.pop();
+                Some(n)
+            }
+            _ => None,
+        };
+
+        let name = match pieces.first() {
+            Some(n) if !n.is_empty() => n.to_string(),
+            _ => return Err(parse_err!("missing GRES name in {:?}", text)),
+        };
+
+        let kind = if pieces.len() > 1 {
+            Some(pieces[1..].join(":"))
+        } else {
+            None
+        };
+
+        // Other details, such as the shares of MPS resources, may appear
+        // in the parentheses; we only understand the device indices.
+        let mut indices = Vec::new();
+
+        if let Some(list) = detail.and_then(|d| d.strip_prefix("IDX:")) {
+            if list != "N/A" {
+                for item in list.split(',') {
+                    let bad = || parse_err!("malformed GRES index list in {:?}", text);
+
+                    match item.find('-') {
+                        Some(i) => {
+                            let lo: u32 = item[..i].parse().map_err(|_| bad())?;
+                            let hi: u32 = item[i + 1..].parse().map_err(|_| bad())?;
+                            indices.extend(lo..=hi);
+                        }
+                        None => indices.push(item.parse().map_err(|_| bad())?),
+                    }
+                }
+            }
+        }
+
+        Ok(GresAllocation {
+            name,
+            kind,
+            count,
+            indices,
+        })
+    }
+}
+
+/// Options that control what information the controller returns.
+///
+/// Flags can be combined with the `|` operator. The default, `NONE`, is what
+/// Slurm's tools use unless asked otherwise.
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct ShowFlags(u16);
+
+impl ShowFlags {
+    /// No special options.
+    pub const NONE: ShowFlags = ShowFlags(0);
+
+    /// Include information about jobs and partitions that are hidden from
+    /// the caller, as with the `--all` option of `squeue`.
+    pub const ALL: ShowFlags = ShowFlags(slurm_sys::SHOW_ALL as u16);
+
+    /// Include detailed information, such as the resources allocated to a
+    /// job on each node.
+    pub const DETAIL: ShowFlags = ShowFlags(slurm_sys::SHOW_DETAIL as u16);
+
+    /// Only report information from the local cluster, even if it is part
+    /// of a federation.
+    pub const LOCAL: ShowFlags = ShowFlags(slurm_sys::SHOW_LOCAL as u16);
+
+    /// Report the sibling jobs of federated jobs individually.
+    pub const SIBLING: ShowFlags = ShowFlags(slurm_sys::SHOW_SIBLING as u16);
+
+    /// Report information from all clusters of the federation.
+    pub const FEDERATION: ShowFlags = ShowFlags(slurm_sys::SHOW_FEDERATION as u16);
+
+    /// Include nodes that are in the `FUTURE` state.
+    pub const FUTURE: ShowFlags = ShowFlags(slurm_sys::SHOW_FUTURE as u16);
+
+    /// Get the raw flag bits.
+    pub fn bits(&self) -> u16 {
+        self.0
+    }
+
+    /// Return true if all of the flags in `other` are set in this value.
+    pub fn contains(&self, other: ShowFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for ShowFlags {
+    type Output = ShowFlags;
+
+    fn bitor(self, other: ShowFlags) -> ShowFlags {
+        ShowFlags(self.0 | other.0)
+    }
+}
+
+impl BitOrAssign for ShowFlags {
+    fn bitor_assign(&mut self, other: ShowFlags) {
+        self.0 |= other.0;
+    }
+}
+
+/// Get information about a single job.
+///
+/// The job must still be running. If it existed but is no longer running,
+/// the result is an error (errno 2017, "invalid job id").
+///
+/// While the (successful) return value of this function is not a `JobInfo`
+/// struct, it is a type that derefs to `JobInfo`, and so can be used like
+/// one.
+///
+/// Array tasks and heterogeneous job components may be specified using the
+/// `JobIdentifier` type; plain job IDs may be passed directly.
+pub fn get_job_info<J: Into<JobIdentifier>>(jid: J) -> Result<SingleJobInfoMessageOwned, Error> {
+    get_job_info_with_flags(jid, ShowFlags::NONE)
+}
+
+/// Get information about a single job, with options controlling what is
+/// returned.
+///
+/// This is like `get_job_info`, but passes `flags` to the controller. For
+/// instance, `ShowFlags::DETAIL` is needed for the details of the resources
+/// allocated to the job to be filled in.
+pub fn get_job_info_with_flags<J: Into<JobIdentifier>>(
+    jid: J,
+    flags: ShowFlags,
+) -> Result<SingleJobInfoMessageOwned, Error> {
+    let jid = jid.into().resolve()?;
+    let mut msg: *mut slurm_sys::job_info_msg_t = 0 as _;
+
+    ustry!(slurm_sys::slurm_load_job(&mut msg, jid, flags.bits()));
+
+    let rc = unsafe { (*msg).record_count };
+    if rc != 1 {
+        return Err(format_err!(
+            "expected exactly one info record for job {}; got {} items",
+            jid,
+            rc
+        ));
+    }
+
+    Ok(unsafe { SingleJobInfoMessageOwned::assume_ownership(msg as _) })
+}
+
+make_slurm_wrap_struct!(
+    SingleJobInfoMessage,
+    slurm_sys::job_info_msg_t,
+    "Information about a single job.
+
+This type implements `Deref` to `JobInfo` and so can be essentially be
+treated as a `JobInfo`. Due to how the Slurm library manages memory, this
+separate type is necessary in some cases."
+);
+
+impl_wrapper_debug!(SingleJobInfoMessage; job_id, partition, state_reason);
+
+impl Deref for SingleJobInfoMessage {
+    type Target = JobInfo;
+
+    fn deref(&self) -> &JobInfo {
+        unsafe { JobInfo::transmute_ptr(&self.sys_data().job_array) }
+    }
+}
+
+impl DerefMut for SingleJobInfoMessage {
+    fn deref_mut(&mut self) -> &mut JobInfo {
+        unsafe { JobInfo::transmute_ptr_mut(&mut self.sys_data_mut().job_array) }
+    }
+}
+
+make_owned_version!(@customdrop SingleJobInfoMessage, SingleJobInfoMessageOwned,
+                    "An owned version of `SingleJobInfoMessage`.");
+
+impl Drop for SingleJobInfoMessageOwned {
+    fn drop(&mut self) {
+        unsafe { slurm_sys::slurm_free_job_info_msg((self.0).0) };
+    }
+}
+
+/// Wait for a job to finish, returning its final state and exit status.
+///
+/// The controller is polled every `poll_interval`. Once the job has left the
+/// controller's queue, its final state is obtained from the accounting
+/// database instead; if this crate is built without the `slurmdb` feature,
+/// that is an error. If the database has no record of the job either, the
+/// controller's "invalid job ID" error is returned. If the job has not
+/// finished after `timeout`, an error is returned.
+pub fn wait_for_job<J: Into<JobIdentifier>>(
+    jid: J,
+    poll_interval: std::time::Duration,
+    timeout: std::time::Duration,
+) -> Result<(JobState, Option<ExitStatus>), Error> {
+    let jid = jid.into();
+    let t0 = std::time::Instant::now();
+
+    loop {
+        match get_job_info(jid) {
+            Ok(info) => {
+                let state = info.state();
+
+                if state.is_terminal() {
+                    return Ok((state, info.exit_status()));
+                }
+            }
+
+            #[cfg(feature = "slurmdb")]
+            Err(Error::Slurm(e)) if e.to_slurm() == slurm_sys::ESLURM_INVALID_JOB_ID as c_int => {
+                let filters = JobFiltersOwned::for_job(jid)?;
+                let db = DatabaseConnectionOwned::new()?;
+                let jobs = db.get_jobs(&filters)?;
+
+                // A requeued job has one record per attempt; the last one is
+                // the current one. If there are none, the job ID is bogus
+                // and waiting longer won't help.
+                match jobs.iter().last() {
+                    Some(job) => {
+                        let state = job.state();
+
+                        if state.is_terminal() {
+                            return Ok((state, job.exit_status()));
+                        }
+                    }
+
+                    None => return Err(e.into()),
+                }
+            }
+
+            Err(e) => return Err(e),
+        }
+
+        if t0.elapsed() >= timeout {
+            return Err(format_err!("timed out waiting for job {} to finish", jid));
+        }
+
+        std::thread::sleep(poll_interval);
+    }
+}
+
+/// The result of a query that only returns data if it has changed since a
+/// specified time.
+#[derive(Debug)]
+pub enum LoadResult<T> {
+    /// The data have changed; here they are.
+    Updated(T),
+
+    /// The data have not changed.
+    NotChanged,
+}
+
+impl<T> LoadResult<T> {
+    /// Get the updated data, if there are any.
+    pub fn updated(self) -> Option<T> {
+        match self {
+            LoadResult::Updated(value) => Some(value),
+            LoadResult::NotChanged => None,
+        }
+    }
+}
+
+/// Convert the return code of one of the "load" calls, which accept an
+/// update time, into a result.
+///
+/// If the data have not changed since the update time, Slurm reports the
+/// error `SLURM_NO_CHANGE_IN_DATA`, which we translate.
+fn load_rc_to_result(rc: c_int) -> Result<bool, SlurmError> {
+    match slurm_rc_to_result(rc) {
+        Ok(()) => Ok(true),
+        Err(ref e) if e.to_slurm() == slurm_sys::SLURM_NO_CHANGE_IN_DATA as c_int => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Get information about all jobs known to the controller.
+pub fn get_all_jobs() -> Result<JobInfoMessageOwned, Error> {
+    match load_jobs(0)? {
+        LoadResult::Updated(msg) => Ok(msg),
+        LoadResult::NotChanged => Err(format_err!("controller reported no change in job data")),
+    }
+}
+
+/// Get information about all jobs known to the controller, if it has
+/// changed since the specified time.
+///
+/// The time should generally be the `last_update` of a previous query
+/// result. If nothing has changed, the controller replies without sending
+/// the job data, which greatly reduces the load caused by polling tools.
+pub fn get_all_jobs_since(last: DateTime<Utc>) -> Result<LoadResult<JobInfoMessageOwned>, Error> {
+    load_jobs(last.timestamp())
+}
+
+fn load_jobs(update_time: i64) -> Result<LoadResult<JobInfoMessageOwned>, Error> {
+    let mut msg: *mut slurm_sys::job_info_msg_t = 0 as _;
+
+    if !load_rc_to_result(
+        unsafe { slurm_sys::slurm_load_jobs(update_time as _, &mut msg, 0) } as c_int,
+    )? {
+        return Ok(LoadResult::NotChanged);
+    }
+
+    Ok(LoadResult::Updated(unsafe {
+        JobInfoMessageOwned::assume_ownership(msg as _)
+    }))
+}
+
+make_slurm_wrap_struct!(
+    JobInfoMessage,
+    slurm_sys::job_info_msg_t,
+    "Information about a set of jobs, as returned by the controller."
+);
+
+impl_wrapper_debug!(JobInfoMessage; len, last_update);
+
+impl JobInfoMessage {
+    /// Get the number of jobs in this message.
+    pub fn len(&self) -> usize {
+        self.sys_data().record_count as usize
+    }
+
+    /// Return true if this message contains no jobs.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the time at which the controller's job information was last
+    /// updated.
+    pub fn last_update(&self) -> DateTime<Utc> {
+        unix_time(self.sys_data().last_update as i64)
+    }
+
+    /// Iterate over the jobs in this message.
+    pub fn jobs<'a>(&'a self) -> impl Iterator<Item = SlurmListItem<'a, JobInfo>> + 'a {
+        let base = self.sys_data().job_array;
+        (0..self.len()).map(move |i| SlurmListItem(JobInfo(unsafe { base.add(i) }), PhantomData))
+    }
+}
+
+make_owned_version!(@customdrop JobInfoMessage, JobInfoMessageOwned,
+                    "An owned version of `JobInfoMessage`.");
+
+impl Drop for JobInfoMessageOwned {
+    fn drop(&mut self) {
+        unsafe { slurm_sys::slurm_free_job_info_msg((self.0).0) };
+    }
+}
+
+/// Get information about all jobs belonging to the specified user.
+///
+/// The user may be given by name or numeric ID. Only the user's own jobs are
+/// sent by the controller, so this is cheaper than `get_all_jobs` on busy
+/// clusters.
+pub fn get_user_jobs(user: &str) -> Result<JobInfoMessageOwned, Error> {
+    let uid = lookup_uid(user)?;
+    let mut msg: *mut slurm_sys::job_info_msg_t = 0 as _;
+    ustry!(slurm_sys::slurm_load_job_user(&mut msg, uid, 0));
+    Ok(unsafe { JobInfoMessageOwned::assume_ownership(msg as _) })
+}
+
+/// Find a user's jobs by name.
+///
+/// The `pattern` may contain the shell-style wildcards `*`, which matches
+/// any sequence of characters, and `?`, which matches any single character.
+/// A pattern without wildcards matches job names exactly.
+///
+/// ```no_run
+/// # fn main() -> Result<(), slurm::Error> {
+/// let found = slurm::find_jobs_by_name("alice", "analysis-*")?;
+///
+/// for job in found.jobs() {
+///     println!("{} {}", job.job_id(), job.name());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn find_jobs_by_name(user: &str, pattern: &str) -> Result<JobMatches, Error> {
+    let msg = get_user_jobs(user)?;
+    Ok(JobMatches::new(msg, |job| glob_match(pattern, &job.name())))
+}
+
+/// A subset of the jobs in a `JobInfoMessage`.
+///
+/// This holds on to the full message returned by the controller, so that
+/// the matching jobs can be accessed as `JobInfo` structures.
+#[derive(Debug)]
+pub struct JobMatches {
+    msg: JobInfoMessageOwned,
+    indices: Vec<usize>,
+}
+
+impl JobMatches {
+    fn new<F: FnMut(&JobInfo) -> bool>(msg: JobInfoMessageOwned, mut pred: F) -> JobMatches {
+        let indices = msg
+            .jobs()
+            .enumerate()
+            .filter(|(_, job)| pred(job))
+            .map(|(i, _)| i)
+            .collect();
+
+        JobMatches { msg, indices }
+    }
+
+    /// Get the number of matching jobs.
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// Return true if no jobs matched.
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// Get the full message from which the jobs were selected.
+    pub fn message(&self) -> &JobInfoMessage {
+        &self.msg
+    }
+
+    /// Iterate over the matching jobs.
+    pub fn jobs<'a>(&'a self) -> impl Iterator<Item = SlurmListItem<'a, JobInfo>> + 'a {
+        let base = self.msg.sys_data().job_array;
+        self.indices
+            .iter()
+            .map(move |&i| SlurmListItem(JobInfo(unsafe { base.add(i) }), PhantomData))
+    }
+}
+
+/// Options that control which parts of a job are signaled by `signal_job`.
+///
+/// Flags can be combined with the `|` operator.
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct KillFlags(u16);
+
+impl KillFlags {
+    /// Signal the steps of the job, but not its batch script, as `scancel`
+    /// does by default.
+    pub const NONE: KillFlags = KillFlags(0);
+
+    /// Signal only the batch script of the job, as with `scancel --batch`.
+    /// The signal is delivered to the batch shell, but not to its children.
+    pub const BATCH: KillFlags = KillFlags(slurm_sys::KILL_JOB_BATCH as u16);
+
+    /// Signal all of the tasks of a job array.
+    pub const ARRAY: KillFlags = KillFlags(slurm_sys::KILL_JOB_ARRAY as u16);
+
+    /// Signal the job's steps, but do not terminate the job itself.
+    pub const STEPS_ONLY: KillFlags = KillFlags(slurm_sys::KILL_STEPS_ONLY as u16);
+
+    /// Signal every part of the job, including the batch shell and its
+    /// children, as with `scancel --full`.
+    pub const FULL_JOB: KillFlags = KillFlags(slurm_sys::KILL_FULL_JOB as u16);
+
+    /// Get the raw flag bits.
+    pub fn bits(&self) -> u16 {
+        self.0
+    }
+
+    /// Return true if all of the flags in `other` are set in this value.
+    pub fn contains(&self, other: KillFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for KillFlags {
+    type Output = KillFlags;
+
+    fn bitor(self, other: KillFlags) -> KillFlags {
+        KillFlags(self.0 | other.0)
+    }
+}
+
+impl BitOrAssign for KillFlags {
+    fn bitor_assign(&mut self, other: KillFlags) {
+        self.0 |= other.0;
+    }
+}
+
+/// Change the maximum number of tasks of a job array that may run at once,
+/// as with `scontrol update ArrayTaskThrottle=`.
+///
+/// The change applies to tasks that have not yet started. A value of zero
+/// removes the limit.
+pub fn set_array_throttle(array_job_id: JobId, max_concurrent: u32) -> Result<(), Error> {
+    let mut desc = JobDescriptorOwned::for_update(array_job_id)?;
+    desc.set_array_throttle(max_concurrent)?;
+    desc.update_job()?;
+    Ok(())
+}
+
+/// How a job should be treated after `requeue_job` returns it to the queue.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum RequeueMode {
+    /// Make the job eligible to run again, as with `scontrol requeue`.
+    Requeue,
+
+    /// Hold the job once it is pending again, as with `scontrol
+    /// requeuehold`. It will not run until it is released.
+    Hold,
+
+    /// Hold the job and place it in the `SPECIAL_EXIT` state, as with
+    /// `scontrol requeuehold State=SpecialExit`. This is the state used for
+    /// jobs that exit with one of the configured special exit codes, and
+    /// marks the job as set aside for inspection.
+    SpecialExit,
+}
+
+impl RequeueMode {
+    fn to_slurm(self) -> u32 {
+        match self {
+            RequeueMode::Requeue => 0,
+            RequeueMode::Hold => slurm_sys::JOB_REQUEUE_HOLD,
+            RequeueMode::SpecialExit => slurm_sys::JOB_SPECIAL_EXIT,
+        }
+    }
+}
+
+/// Return a running or finished job to the queue.
+///
+/// The job must have been submitted as a batch job and be requeueable (see
+/// `JobInfo::requeue`). Its restart count is incremented.
+pub fn requeue_job<J: Into<JobIdentifier>>(jid: J, mode: RequeueMode) -> Result<(), Error> {
+    let jid = jid.into().resolve()?;
+    ustry!(slurm_sys::slurm_requeue(jid, mode.to_slurm()));
+    Ok(())
+}
+
+/// Mark a job's allocation as finished, with the specified return code.
+///
+/// This is what `salloc` does when its command exits: the job's resources
+/// are released without waiting for its time limit, and `return_code` is
+/// recorded as its exit code. It is meant for tools that manage interactive
+/// allocations; batch jobs complete on their own when their scripts exit.
+pub fn complete_job<J: Into<JobIdentifier>>(jid: J, return_code: u32) -> Result<(), Error> {
+    let jid = jid.into().resolve()?;
+    ustry!(slurm_sys::slurm_complete_job(jid, return_code));
+    Ok(())
+}
+
+/// Change the time limit of a job by a relative amount, as with `scontrol
+/// update TimeLimit=+...`.
+///
+/// The job's current limit is read from the controller, `extra` is added to
+/// it, and the result is applied; partial minutes are rounded up. `extra`
+/// may be negative to shorten the limit. Returns the new limit. Jobs
+/// without a limit are left alone, and `TimeLimit::Unlimited` is returned.
+/// It is an error to extend the limit of a job whose limit has not yet been
+/// set, since there is nothing to extend. Only administrators may raise
+/// time limits.
+pub fn extend_time_limit<J: Into<JobIdentifier>>(
+    jid: J,
+    extra: Duration,
+) -> Result<TimeLimit, Error> {
+    let jid = jid.into().resolve()?;
+
+    let current = match get_job_info(jid)?.time_limit() {
+        Some(TimeLimit::Minutes(m)) => m as i64,
+        Some(TimeLimit::Unlimited) => return Ok(TimeLimit::Unlimited),
+        None => return Err(format_err!("job {} does not have a time limit yet", jid)),
+    };
+
+    let secs = extra.num_seconds();
+    let delta = if secs >= 0 {
+        (secs + 59) / 60
+    } else {
+        secs / 60
+    };
+    let minutes = current + delta;
+
+    // The limit must stay distinguishable from Slurm's special values.
+    if minutes < 1 || minutes >= slurm_sys::SLURMRS_NO_VAL as i64 {
+        return Err(format_err!(
+            "cannot change time limit of job {} by {} minutes",
+            jid,
+            delta
+        ));
+    }
+
+    let limit = TimeLimit::Minutes(minutes as u32);
+    let mut desc = JobDescriptorOwned::for_update(jid)?;
+    desc.set_time_limit(limit);
+    desc.update_job()?;
+    Ok(limit)
+}
+
+/// Move a pending job to the top of its owner's queue, as with `scontrol
+/// top`.
+///
+/// The job is given the highest priority among the pending jobs of the same
+/// user, partition, account, and QOS, by exchanging priority with those
+/// jobs; this does not raise the user's priority overall. The controller may
+/// be configured to allow this only for administrators.
+pub fn top_job<J: Into<JobIdentifier>>(jid: J) -> Result<(), Error> {
+    let text = jid.into().to_string();
+    let c = CString::new(text).map_err(|_| format_err!("illegal job ID"))?;
+    ustry!(slurm_sys::slurm_top_job(c.as_ptr() as *mut c_char));
+    Ok(())
+}
+
+/// Prevent a pending job from starting, as with `scontrol hold`.
+///
+/// The job keeps its place in the queue but is given a priority of zero. If
+/// an ordinary user holds their own job, only they or an administrator may
+/// release it.
+pub fn hold_job<J: Into<JobIdentifier>>(jid: J) -> Result<(), Error> {
+    let mut desc = JobDescriptorOwned::for_update(jid)?;
+    desc.set_priority(0);
+    desc.update_job()?;
+    Ok(())
+}
+
+/// Release a held job, as with `scontrol release`.
+pub fn release_job<J: Into<JobIdentifier>>(jid: J) -> Result<(), Error> {
+    let mut desc = JobDescriptorOwned::for_update(jid)?;
+    desc.set_priority(slurm_sys::SLURMRS_INFINITE);
+    desc.update_job()?;
+    Ok(())
+}
+
+/// Cancel a job, as with `scancel`.
+///
+/// Array tasks and heterogeneous job components may be specified using the
+/// `JobIdentifier` type; plain job IDs may be passed directly.
+pub fn cancel_job<J: Into<JobIdentifier>>(jid: J) -> Result<(), Error> {
+    signal_job(jid, libc::SIGKILL, KillFlags::NONE)
+}
+
+/// Send a signal to a job, as with `scancel --signal`.
+///
+/// The `flags` control which parts of the job receive the signal. For
+/// instance, signals meant for the application should usually be sent
+/// without `KillFlags::BATCH`, so that they do not hit the batch shell,
+/// which does not forward them. To signal a single step, including the
+/// external step, use `signal_step`.
+pub fn signal_job<J: Into<JobIdentifier>>(
+    jid: J,
+    signal: c_int,
+    flags: KillFlags,
+) -> Result<(), Error> {
+    let jid = jid.into().resolve()?;
+    ustry!(slurm_sys::slurm_kill_job(jid, signal as u16, flags.bits()));
+    Ok(())
+}
+
+/// Send a signal to a single step of a job.
+///
+/// The step may be one of the pseudo-steps, such as `StepId::Extern`.
+pub fn signal_step<J: Into<JobIdentifier>>(
+    jid: J,
+    step: StepId,
+    signal: c_int,
+) -> Result<(), Error> {
+    let jid = jid.into().resolve()?;
+
+    #[cfg(slurm_api_selected_step_t_step_id)]
+    {
+        let mut step_id = slurm_sys::slurm_step_id_t {
+            job_id: jid,
+            step_het_comp: slurm_sys::SLURMRS_NO_VAL,
+            step_id: step.to_slurm(),
+        };
+        ustry!(slurm_sys::slurm_signal_job_step(
+            &mut step_id,
+            signal as u32
+        ));
+    }
+    #[cfg(not(slurm_api_selected_step_t_step_id))]
+    {
+        ustry!(slurm_sys::slurm_signal_job_step(
+            jid,
+            step.to_slurm(),
+            signal as _
+        ));
+    }
+
+    Ok(())
+}
+
+/// Criteria for selecting jobs to cancel with `cancel_matching`.
+///
+/// Jobs must match all of the criteria that are set. Empty lists match
+/// everything.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CancelFilter {
+    user: Option<String>,
+    name_glob: Option<String>,
+    states: Vec<JobState>,
+    partition: Option<String>,
+}
+
+impl CancelFilter {
+    /// Create a new filter that matches all jobs that have not yet ended.
+    #[must_use]
+    pub fn new() -> CancelFilter {
+        CancelFilter::default()
+    }
+
+    /// Only match jobs belonging to the specified user, given by name or
+    /// numeric ID.
+    pub fn user<S: Into<String>>(&mut self, user: S) -> &mut Self {
+        self.user = Some(user.into());
+        self
+    }
+
+    /// Only match jobs whose names match a shell-style pattern, as with
+    /// `find_jobs_by_name`.
+    pub fn name_glob<S: Into<String>>(&mut self, pattern: S) -> &mut Self {
+        self.name_glob = Some(pattern.into());
+        self
+    }
+
+    /// Match jobs in the specified state.
+    ///
+    /// This may be called more than once to match jobs in any of several
+    /// states.
+    pub fn state(&mut self, state: JobState) -> &mut Self {
+        self.states.push(state);
+        self
+    }
+
+    /// Only match jobs in the specified partition.
+    ///
+    /// Pending jobs that were submitted to several partitions match if any
+    /// one of them is this partition.
+    pub fn partition<S: Into<String>>(&mut self, partition: S) -> &mut Self {
+        self.partition = Some(partition.into());
+        self
+    }
+
+    fn matches(&self, job: &JobInfo) -> bool {
+        let state = job.state();
+
+        if state.is_terminal() {
+            return false;
+        }
+
+        if !self.states.is_empty() && !self.states.contains(&state) {
+            return false;
+        }
+
+        if let Some(ref pattern) = self.name_glob {
+            if !glob_match(pattern, &job.name()) {
+                return false;
+            }
+        }
+
+        if let Some(ref partition) = self.partition {
+            if !job.partition().split(',').any(|p| p == partition) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// Cancel all of the jobs that match a filter.
+///
+/// Returns the IDs of the jobs that were cancelled. If `dry_run` is true,
+/// nothing is cancelled, and the IDs of the jobs that would have been are
+/// returned. Jobs that end before they can be cancelled are skipped
+/// silently. If cancelling a job fails for any other reason, the error is
+/// returned immediately and the remaining jobs are left alone.
+///
+/// The pending tasks of a job array that have not yet been split off into
+/// their own records share a single record whose job ID is that of the
+/// array. When that record matches, only those pending tasks are cancelled,
+/// not the array's running tasks, and the array's job ID is reported.
+///
+/// ```no_run
+/// # fn main() -> Result<(), slurm::Error> {
+/// let mut filter = slurm::CancelFilter::new();
+/// filter.user("alice").name_glob("sweep-*").state(slurm::JobState::Pending);
+///
+/// for jid in slurm::cancel_matching(&filter, true)? {
+///     println!("would cancel {}", jid);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn cancel_matching(filter: &CancelFilter, dry_run: bool) -> Result<Vec<JobId>, Error> {
+    let msg = match filter.user {
+        Some(ref user) => get_user_jobs(user)?,
+        None => get_all_jobs()?,
+    };
+
+    let targets: Vec<(JobId, Option<String>)> = msg
+        .jobs()
+        .filter(|job| filter.matches(job))
+        .map(|job| (job.job_id(), pending_array_tasks(&job)))
+        .collect();
+
+    if dry_run {
+        return Ok(targets.into_iter().map(|(jid, _)| jid).collect());
+    }
+
+    let mut cancelled = Vec::new();
+
+    for (jid, tasks) in targets {
+        let result = match tasks {
+            Some(tasks) => kill_job_by_name(&tasks, libc::SIGKILL, KillFlags::NONE),
+            None => cancel_job(jid),
+        };
+
+        match result {
+            Ok(()) => cancelled.push(jid),
+            Err(Error::Slurm(ref e))
+                if e.to_slurm() == slurm_sys::ESLURM_ALREADY_DONE as c_int
+                    || e.to_slurm() == slurm_sys::ESLURM_INVALID_JOB_ID as c_int => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(cancelled)
+}
+
+/// If a job record holds the not-yet-split pending tasks of a job array, get
+/// a job ID string naming just those tasks, such as `1234_[5-99]`.
+///
+/// Signalling such a record by its numeric ID would hit the whole array.
+fn pending_array_tasks(job: &JobInfo) -> Option<String> {
+    if job.array_job_id() != Some(job.job_id()) || job.array_task_id().is_some() {
+        return None;
+    }
+
+    let tasks = unsafe { optional_cstr(job.sys_data().array_task_str) }?;
+    // Strip any throttle suffix, as in `5-99%4`.
+    let tasks = tasks.split('%').next().unwrap_or("");
+
+    if tasks.is_empty() {
+        return None;
+    }
+
+    Some(format!("{}_[{}]", job.job_id(), tasks))
+}
+
+/// Send a signal to the job named by a job ID string, such as `1234_[5-99]`.
+fn kill_job_by_name(name: &str, signal: c_int, flags: KillFlags) -> Result<(), Error> {
+    let name = CString::new(name).map_err(|_| format_err!("illegal job ID"))?;
+
+    #[cfg(slurm_api_kill_job2_sibling)]
+    ustry!(slurm_sys::slurm_kill_job2(
+        name.as_ptr(),
+        signal as u16,
+        flags.bits(),
+        std::ptr::null()
+    ));
+    #[cfg(not(slurm_api_kill_job2_sibling))]
+    ustry!(slurm_sys::slurm_kill_job2(
+        name.as_ptr(),
+        signal as u16,
+        flags.bits()
+    ));
+    Ok(())
+}
+
+/// Match text against a shell-style pattern containing `*` and `?`
+/// wildcards.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(&'*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                // Let the most recent star absorb one more character.
+                Some((bp, bt)) => {
+                    backtrack = Some((bp, bt + 1));
+                    p = bp + 1;
+                    t = bt + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// The base states that a node can be in.
+///
+/// Slurm sets various flags on top of the base state, such as whether the
+/// node is being drained; `NodeInfo::state_name` includes these.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[non_exhaustive]
+pub enum NodeState {
+    /// The state of the node is not known.
+    Unknown,
+
+    /// The node is unavailable for use.
+    Down,
+
+    /// The node is not allocated to any jobs.
+    Idle,
+
+    /// All of the node's resources are allocated to jobs.
+    Allocated,
+
+    /// The node is in an error state.
+    Error,
+
+    /// Some of the node's resources are allocated to jobs.
+    Mixed,
+
+    /// The node is defined for future use and is not yet active.
+    Future,
+
+    /// Some other state, identified by its raw Slurm code.
+    Other(u32),
+}
+
+impl NodeState {
+    fn from_slurm(raw: u32) -> NodeState {
+        match raw & slurm_sys::NODE_STATE_BASE {
+            slurm_sys::node_states_NODE_STATE_UNKNOWN => NodeState::Unknown,
+            slurm_sys::node_states_NODE_STATE_DOWN => NodeState::Down,
+            slurm_sys::node_states_NODE_STATE_IDLE => NodeState::Idle,
+            slurm_sys::node_states_NODE_STATE_ALLOCATED => NodeState::Allocated,
+            slurm_sys::node_states_NODE_STATE_ERROR => NodeState::Error,
+            slurm_sys::node_states_NODE_STATE_MIXED => NodeState::Mixed,
+            slurm_sys::node_states_NODE_STATE_FUTURE => NodeState::Future,
+            other => NodeState::Other(other),
+        }
+    }
+}
+
+/// Flag bits that Slurm may set on top of a node's base state.
+///
+/// A node whose base state is `Idle` may still be unable to run jobs, for
+/// instance because it is being drained.
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct NodeStateFlags(u32);
+
+impl NodeStateFlags {
+    fn from_slurm(s: u32) -> NodeStateFlags {
+        NodeStateFlags(s & !slurm_sys::NODE_STATE_BASE)
+    }
+
+    /// Get the raw flag bits.
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// Return true if the node is drained or being drained, so that it will
+    /// not be given any new jobs.
+    pub fn drain(&self) -> bool {
+        self.0 & slurm_sys::NODE_STATE_DRAIN != 0
+    }
+
+    /// Return true if the node has been marked as failing, so that it will
+    /// not be given any new jobs.
+    pub fn fail(&self) -> bool {
+        self.0 & slurm_sys::NODE_STATE_FAIL != 0
+    }
+
+    /// Return true if the node is in a maintenance reservation.
+    pub fn maintenance(&self) -> bool {
+        self.0 & slurm_sys::NODE_STATE_MAINT != 0
+    }
+
+    /// Return true if the node is not responding to the controller.
+    pub fn not_responding(&self) -> bool {
+        self.0 & slurm_sys::NODE_STATE_NO_RESPOND != 0
+    }
+
+    /// Return true if the node is in an advanced reservation, so that only
+    /// the reservation's jobs may use it.
+    pub fn reserved(&self) -> bool {
+        self.0 & slurm_sys::NODE_STATE_RES != 0
+    }
+
+    /// Return true if any of the flags keep the node from being given new
+    /// jobs in general: if it is drained, failing, in maintenance, reserved,
+    /// or not responding.
+    pub fn unavailable(&self) -> bool {
+        self.drain()
+            || self.fail()
+            || self.maintenance()
+            || self.reserved()
+            || self.not_responding()
+    }
+}
+
+make_slurm_wrap_struct!(
+    NodeInfo,
+    slurm_sys::node_info_t,
+    "Information about a compute node, as returned by the controller."
+);
+
+impl_wrapper_debug!(NodeInfo; name, state_name, cpus);
+
+impl NodeInfo {
+    /// Get this node's name.
+    pub fn name(&self) -> Cow<str> {
+        unsafe { optional_cstr(self.sys_data().name) }.unwrap_or(Cow::Borrowed(""))
+    }
+
+    /// Get this node's base state.
+    pub fn state(&self) -> NodeState {
+        NodeState::from_slurm(self.sys_data().node_state)
+    }
+
+    /// Get the flags set on top of this node's base state.
+    pub fn state_flags(&self) -> NodeStateFlags {
+        NodeStateFlags::from_slurm(self.sys_data().node_state)
+    }
+
+    /// Get Slurm's name for this node's full state, including flags, such as
+    /// `IDLE+DRAIN`.
+    pub fn state_name(&self) -> Cow<str> {
+        node_state_string(self.sys_data().node_state)
+    }
+
+    /// Get the number of CPUs on this node.
+    pub fn cpus(&self) -> u16 {
+        self.sys_data().cpus
+    }
+
+    /// Get the number of sockets on this node.
+    pub fn sockets(&self) -> u16 {
+        self.sys_data().sockets
+    }
+
+    /// Get the number of cores per socket on this node.
+    pub fn cores(&self) -> u16 {
+        self.sys_data().cores
+    }
+
+    /// Get the number of threads per core on this node.
+    pub fn threads(&self) -> u16 {
+        self.sys_data().threads
+    }
+
+    /// Get the amount of memory on this node, in mebibytes.
+    pub fn real_memory(&self) -> u64 {
+        self.sys_data().real_memory
+    }
+
+    /// Get the amount of free memory on this node, in mebibytes, if it is
+    /// known.
+    pub fn free_memory(&self) -> Option<u64> {
+        match self.sys_data().free_mem {
+            slurm_sys::SLURMRS_NO_VAL64 => None,
+            other => Some(other),
+        }
+    }
+
+    /// Get the CPU load of this node, if it is known.
+    ///
+    /// A value of 1.0 corresponds to one fully busy CPU.
+    pub fn cpu_load(&self) -> Option<f64> {
+        match self.sys_data().cpu_load {
+            slurm_sys::SLURMRS_NO_VAL => None,
+            other => Some(other as f64 / 100.),
+        }
+    }
+
+    /// Get the names of the partitions that this node belongs to.
+    pub fn partitions(&self) -> Vec<String> {
+        split_name_list(self.sys_data().partitions)
+    }
+
+    /// Get the reason that this node is down or drained, if one was given.
+    pub fn reason(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().reason) }
+    }
+
+    /// Get the most recent energy reading for this node, if an energy
+    /// accounting plugin is configured.
+    ///
+    /// The controller only refreshes these readings periodically; use
+    /// `get_node_energy` to ask a node for current values.
+    pub fn energy(&self) -> Option<EnergyReading> {
+        let ptr = self.sys_data().energy;
+
+        if ptr.is_null() {
+            None
+        } else {
+            EnergyReading::from_slurm(unsafe { &*ptr })
+        }
+    }
+
+    /// Format all of this node's information in the `Key=Value` style used
+    /// by `scontrol show node`.
+    ///
+    /// If `one_liner` is true, all of the fields are put on a single line.
+    pub fn sprint(&self, one_liner: bool) -> Result<String, SlurmError> {
+        let mut ptr = pstry!(slurm_sys::slurm_sprint_node_table(
+            self.0,
+            one_liner as c_int
+        ));
+        let text = unsafe { CStr::from_ptr(ptr) }
+            .to_string_lossy()
+            .into_owned();
+        slurm_free(&mut ptr);
+        Ok(text)
+    }
+}
+
+impl NodeInfo {
+    /// Copy the data in this structure into a plain-Rust structure.
+    ///
+    /// The result does not refer to any Slurm memory, so it may outlive the
+    /// query result that contained this structure.
+    pub fn to_snapshot(&self) -> NodeInfoSnapshot {
+        NodeInfoSnapshot {
+            cores: self.cores(),
+            cpu_load: self.cpu_load(),
+            cpus: self.cpus(),
+            energy: self.energy(),
+            free_memory: self.free_memory(),
+            name: self.name().into_owned(),
+            real_memory: self.real_memory(),
+            reason: self.reason().map(|s| s.into_owned()),
+            sockets: self.sockets(),
+            state: self.state(),
+            state_flags: self.state_flags(),
+            state_name: self.state_name().into_owned(),
+            threads: self.threads(),
+        }
+    }
+}
+
+/// A plain-Rust copy of the data in a `NodeInfo`.
+///
+/// See the accessors of `NodeInfo` for the meanings of the fields.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct NodeInfoSnapshot {
+    pub cores: u16,
+    pub cpu_load: Option<f64>,
+    pub cpus: u16,
+    pub energy: Option<EnergyReading>,
+    pub free_memory: Option<u64>,
+    pub name: String,
+    pub real_memory: u64,
+    pub reason: Option<String>,
+    pub sockets: u16,
+    pub state: NodeState,
+    pub state_flags: NodeStateFlags,
+    pub state_name: String,
+    pub threads: u16,
+}
+
+impl_snapshot_traits!(NodeInfo, NodeInfoSnapshot);
+
+impl Display for NodeInfo {
+    /// Format the node exactly as `scontrol show node` does.
+    ///
+    /// If Slurm is unable to format the node, only its name and state are
+    /// printed. Use `sprint` to detect that case.
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+        match self.sprint(false) {
+            Ok(text) => write!(f, "{}", text.trim_end()),
+            Err(_) => write!(f, "NodeName={} State={}", self.name(), self.state_name()),
+        }
+    }
+}
+
+/// Expand a Slurm hostlist expression, such as `node[01-03],login`, into
+/// the names of the individual hosts.
+pub fn expand_hostlist<S: AsRef<str>>(hostlist: S) -> Result<Vec<String>, Error> {
+    let text = hostlist.as_ref();
+    let c = CString::new(text).map_err(|_| parse_err!("illegal hostlist {:?}", text))?;
+    let hl = unsafe { slurm_sys::slurm_hostlist_create(c.as_ptr()) };
+
+    if hl.is_null() {
+        return Err(parse_err!("malformed hostlist {:?}", text));
+    }
+
+    let mut hosts = Vec::new();
+
+    loop {
+        let host = unsafe { slurm_sys::slurm_hostlist_shift(hl) };
+
+        if host.is_null() {
+            break;
+        }
+
+        hosts.push(
+            unsafe { CStr::from_ptr(host) }
+                .to_string_lossy()
+                .into_owned(),
+        );
+
+        // Per the Slurm docs, the host name is freed with plain `free()`.
+        unsafe { libc::free(host as _) };
+    }
+
+    unsafe { slurm_sys::slurm_hostlist_destroy(hl) };
+    Ok(hosts)
+}
+
+/// Get information about all compute nodes known to the controller.
+pub fn get_nodes() -> Result<NodeInfoMessageOwned, Error> {
+    match load_nodes(0)? {
+        LoadResult::Updated(msg) => Ok(msg),
+        LoadResult::NotChanged => Err(format_err!("controller reported no change in node data")),
+    }
+}
+
+/// Get information about all compute nodes known to the controller, if it
+/// has changed since the specified time.
+///
+/// See `get_all_jobs_since` for details.
+pub fn get_nodes_since(last: DateTime<Utc>) -> Result<LoadResult<NodeInfoMessageOwned>, Error> {
+    load_nodes(last.timestamp())
+}
+
+fn load_nodes(update_time: i64) -> Result<LoadResult<NodeInfoMessageOwned>, Error> {
+    let mut msg: *mut slurm_sys::node_info_msg_t = 0 as _;
+
+    if !load_rc_to_result(
+        unsafe { slurm_sys::slurm_load_node(update_time as _, &mut msg, 0) } as c_int,
+    )? {
+        return Ok(LoadResult::NotChanged);
+    }
+
+    Ok(LoadResult::Updated(unsafe {
+        NodeInfoMessageOwned::assume_ownership(msg as _)
+    }))
+}
+
+make_slurm_wrap_struct!(
+    NodeInfoMessage,
+    slurm_sys::node_info_msg_t,
+    "Information about a set of compute nodes, as returned by the controller."
+);
+
+impl_wrapper_debug!(NodeInfoMessage; len, last_update);
+
+impl NodeInfoMessage {
+    /// Get the number of nodes in this message.
+    pub fn len(&self) -> usize {
+        self.sys_data().record_count as usize
+    }
+
+    /// Return true if this message contains no nodes.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the time at which the controller's node information was last
+    /// updated.
+    pub fn last_update(&self) -> DateTime<Utc> {
+        unix_time(self.sys_data().last_update as i64)
+    }
+
+    /// Iterate over the nodes in this message.
+    ///
+    /// Slurm may include empty records for nodes that are not currently
+    /// defined; these are skipped.
+    pub fn nodes<'a>(&'a self) -> impl Iterator<Item = SlurmListItem<'a, NodeInfo>> + 'a {
+        let base = self.sys_data().node_array;
+        (0..self.len())
+            .map(move |i| SlurmListItem(NodeInfo(unsafe { base.add(i) }), PhantomData))
+            .filter(|node| !node.sys_data().name.is_null())
+    }
+}
+
+make_owned_version!(@customdrop NodeInfoMessage, NodeInfoMessageOwned,
+                    "An owned version of `NodeInfoMessage`.");
+
+impl Drop for NodeInfoMessageOwned {
+    fn drop(&mut self) {
+        unsafe { slurm_sys::slurm_free_node_info_msg((self.0).0) };
+    }
+}
+
+/// A reading from a node's energy accounting sensors.
+///
+/// Readings are only available if an `AcctGatherEnergyType` plugin is
+/// configured. Values that the plugin did not report are None.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct EnergyReading {
+    /// The energy consumed since the node's energy accounting started, in
+    /// joules.
+    pub consumed_energy: Option<u64>,
+
+    /// The power being drawn when the reading was taken, in watts.
+    pub current_watts: Option<u32>,
+
+    /// The average power drawn since the node's energy accounting started,
+    /// in watts.
+    pub ave_watts: Option<u32>,
+
+    /// The time at which the sensors were read.
+    pub poll_time: Option<DateTime<Utc>>,
+}
+
+impl EnergyReading {
+    /// Convert a Slurm energy structure, returning None if it contains no
+    /// data at all, as is the case when no plugin is configured.
+    fn from_slurm(e: &slurm_sys::acct_gather_energy_t) -> Option<EnergyReading> {
+        let reading = EnergyReading {
+            consumed_energy: match e.consumed_energy {
+                slurm_sys::SLURMRS_NO_VAL64 => None,
+                v => Some(v),
+            },
+            current_watts: match e.current_watts {
+                slurm_sys::SLURMRS_NO_VAL => None,
+                v => Some(v),
+            },
+            ave_watts: match e.ave_watts {
+                slurm_sys::SLURMRS_NO_VAL => None,
+                v => Some(v),
+            },
+            poll_time: match e.poll_time {
+                0 => None,
+                t => Some(unix_time(t as i64)),
+            },
+        };
+
+        if reading.poll_time.is_none() && reading.consumed_energy.unwrap_or(0) == 0 {
+            None
+        } else {
+            Some(reading)
+        }
+    }
+}
+
+/// Ask a node's `slurmd` for the readings of its energy sensors.
+///
+/// If `host` is None, the `slurmd` on the local machine is queried. The
+/// daemon will return cached readings if they are younger than `max_age`,
+/// and otherwise poll its sensors anew. There is one reading per sensor; most
+/// configurations have exactly one.
+///
+/// This talks to the node directly rather than going through the
+/// controller, so it gives fresher values than `NodeInfo::energy`, but it
+/// can only be used from hosts that are allowed to contact the node's
+/// daemon.
+pub fn get_node_energy(host: Option<&str>, max_age: Duration) -> Result<Vec<EnergyReading>, Error> {
+    let host = match host {
+        Some(h) => Some(CString::new(h).map_err(|_| parse_err!("illegal host name {:?}", h))?),
+        None => None,
+    };
+
+    let host_ptr = match host {
+        Some(ref c) => c.as_ptr() as *mut c_char,
+        None => std::ptr::null_mut(),
+    };
+
+    let delta = std::cmp::min(std::cmp::max(max_age.num_seconds(), 0), u16::MAX as i64) as u16;
+    let mut count: u16 = 0;
+    let mut energy: *mut slurm_sys::acct_gather_energy_t = 0 as _;
+
+    ustry!(slurm_sys::slurm_get_node_energy(
+        host_ptr,
+        0,
+        delta,
+        &mut count,
+        &mut energy
+    ));
+
+    // The array elements hold no pointers, so the array can be freed as one
+    // block.
+    let readings = if energy.is_null() {
+        Vec::new()
+    } else {
+        unsafe { std::slice::from_raw_parts(energy, count as usize) }
+            .iter()
+            .filter_map(EnergyReading::from_slurm)
+            .collect()
+    };
+
+    slurm_free(&mut energy);
+    Ok(readings)
+}
+
+/// The special value of a raw fairshare “shares” count indicating that an
+/// association uses the fairshare of its parent (Slurm’s
+/// `SLURMDB_FS_USE_PARENT`).
+pub const FS_USE_PARENT: u32 = 0x7FFF_FFFF;
+
+/// Get fairshare information about all of the associations known to the
+/// controller, as with `sshare -a`.
+///
+/// Depending on the controller’s `PrivateData` settings, users may only be
+/// able to see their own associations.
+pub fn get_all_shares() -> Result<SharesResponseMessageOwned, Error> {
+    get_shares(std::iter::empty::<&str>(), std::iter::empty::<&str>())
+}
+
+/// Get fairshare information about the associations of the specified
+/// accounts and users.
+///
+/// An empty list matches everything. Note that filtering by account does not
+/// return the parents of the matching accounts.
+pub fn get_shares<A, U>(accounts: A, users: U) -> Result<SharesResponseMessageOwned, Error>
+where
+    A: IntoIterator,
+    A::Item: AsRef<str>,
+    U: IntoIterator,
+    U::Item: AsRef<str>,
+{
+    let mut acct_list: SlurmListOwned<*mut c_char> =
+        unsafe { SlurmListOwned::assume_ownership(0 as _) };
+    let mut user_list: SlurmListOwned<*mut c_char> =
+        unsafe { SlurmListOwned::assume_ownership(0 as _) };
+
+    for a in accounts {
+        acct_list.append(a)?;
+    }
+
+    for u in users {
+        user_list.append(u)?;
+    }
+
+    // The request only borrows the lists, which we continue to own.
+    let mut req: slurm_sys::shares_request_msg_t = unsafe { std::mem::zeroed() };
+    req.acct_list = (acct_list.0).0;
+    req.user_list = (user_list.0).0;
+
+    let mut msg: *mut slurm_sys::shares_response_msg_t = 0 as _;
+    ustry!(slurm_sys::slurm_associations_get_shares(&mut req, &mut msg));
+    Ok(unsafe { SharesResponseMessageOwned::assume_ownership(msg as _) })
+}
+
+make_slurm_wrap_struct!(
+    SharesResponseMessage,
+    slurm_sys::shares_response_msg_t,
+    "\
+Fairshare information about a set of associations, as returned by the
+controller.
+
+The following items in the Slurm API are *not* exposed in these Rust bindings:
+
+```ignore
+pub struct shares_response_msg_t {
+    pub tres_cnt: u64,
+    pub tres_names: *mut *mut c_char,
+}
+```
+
+"
+);
+
+impl_wrapper_debug!(SharesResponseMessage; len);
+
+impl SharesResponseMessage {
+    /// Get the number of associations in this message.
+    pub fn len(&self) -> usize {
+        self.shares().len()
+    }
+
+    /// Return true if this message contains no associations.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the list of association fairshare records.
+    ///
+    /// The controller returns them in the order of its association tree,
+    /// with the children of each account following it.
+    pub fn shares(&self) -> &SlurmList<SharesRecord> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().assoc_shares_list) }
+    }
+}
+
+make_owned_version!(@customdrop SharesResponseMessage, SharesResponseMessageOwned,
+                    "An owned version of `SharesResponseMessage`.");
+
+impl Drop for SharesResponseMessageOwned {
+    fn drop(&mut self) {
+        unsafe { slurm_sys::slurm_free_shares_response_msg((self.0).0) };
+    }
+}
+
+make_slurm_wrap_struct!(
+    SharesRecord,
+    slurm_sys::assoc_shares_object_t,
+    "\
+Fairshare information about an association, as reported by `sshare`.
+
+The following items in the Slurm API are *not* exposed in these Rust bindings:
+
+```ignore
+pub struct assoc_shares_object_t {
+    pub tres_run_secs: *mut u64,
+    pub tres_grp_mins: *mut u64,
+    pub usage_tres_raw: *mut f64,
+}
+```
+
+"
+);
+
+impl_wrapper_debug!(SharesRecord; assoc_id, name, parent, is_user);
+
+impl SharesRecord {
+    /// Get the ID number of the association.
+    pub fn assoc_id(&self) -> u32 {
+        self.sys_data().assoc_id
+    }
+
+    /// Get the name of the cluster of the association.
+    pub fn cluster(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().cluster) }
+    }
+
+    /// Get the effective usage of the association.
+    ///
+    /// This is the normalized usage, adjusted to account for the usage of
+    /// its siblings, as used by the classic fairshare algorithm.
+    pub fn effective_usage(&self) -> f64 {
+        self.sys_data().usage_efctv
+    }
+
+    /// Get the fairshare factor of the association.
+    ///
+    /// This ranges from 0 to 1 and is what enters into the priority of the
+    /// association’s jobs. It is only meaningful for user associations.
+    pub fn fairshare_factor(&self) -> f64 {
+        self.sys_data().fs_factor
+    }
+
+    /// Return true if this is a user association, or false if it is an
+    /// account association.
+    pub fn is_user(&self) -> bool {
+        self.sys_data().user != 0
+    }
+
+    /// Get the fairshare factor of the association relative to its siblings.
+    ///
+    /// This is used by the Fair Tree fairshare algorithm.
+    pub fn level_fairshare(&self) -> f64 {
+        self.sys_data().level_fs
+    }
+
+    /// Get the name of the association: a user name for a user
+    /// association, or an account name otherwise.
+    pub fn name(&self) -> Cow<str> {
+        unsafe { CStr::from_ptr(self.sys_data().name) }.to_string_lossy()
+    }
+
+    /// Get the name of the parent account of the association.
+    ///
+    /// For user associations, this is the account of the association. The
+    /// root association has no parent.
+    pub fn parent(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().parent) }.and_then(|p| {
+            if p.is_empty() {
+                None
+            } else {
+                Some(p)
+            }
+        })
+    }
+
+    /// Get the partition of the association, if it is specific to one.
+    pub fn partition(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().partition) }.and_then(|p| {
+            if p.is_empty() {
+                None
+            } else {
+                Some(p)
+            }
+        })
+    }
+
+    /// Get the normalized shares of the association.
+    ///
+    /// This is the fraction of the whole cluster that the association is
+    /// entitled to.
+    pub fn shares_norm(&self) -> f64 {
+        self.sys_data().shares_norm
+    }
+
+    /// Get the raw number of fairshare “shares” assigned to this
+    /// association.
+    ///
+    /// The special value `FS_USE_PARENT` indicates that the association uses
+    /// the fairshare of its parent.
+    pub fn shares_raw(&self) -> u32 {
+        self.sys_data().shares_raw
+    }
+
+    /// Get the normalized usage of the association.
+    ///
+    /// This is the fraction of the whole cluster’s decayed usage that is due
+    /// to the association.
+    pub fn usage_norm(&self) -> f64 {
+        self.sys_data().usage_norm
+    }
+
+    /// Get the raw, decayed usage of the association, in TRES-billing-
+    /// weighted seconds.
+    pub fn usage_raw(&self) -> u64 {
+        self.sys_data().usage_raw
+    }
+
+    /// Copy the data in this record into a plain-Rust structure.
+    ///
+    /// The result does not refer to any Slurm memory, so it may outlive the
+    /// response that contained this record.
+    pub fn to_snapshot(&self) -> SharesRecordSnapshot {
+        SharesRecordSnapshot {
+            assoc_id: self.assoc_id(),
+            cluster: self.cluster().map(|s| s.into_owned()),
+            effective_usage: self.effective_usage(),
+            fairshare_factor: self.fairshare_factor(),
+            is_user: self.is_user(),
+            level_fairshare: self.level_fairshare(),
+            name: self.name().into_owned(),
+            parent: self.parent().map(|s| s.into_owned()),
+            partition: self.partition().map(|s| s.into_owned()),
+            shares_norm: self.shares_norm(),
+            shares_raw: self.shares_raw(),
+            usage_norm: self.usage_norm(),
+            usage_raw: self.usage_raw(),
+        }
+    }
+}
+
+/// A plain-Rust copy of the data in a `SharesRecord`.
+///
+/// See the accessors of `SharesRecord` for the meanings of the fields.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct SharesRecordSnapshot {
+    pub assoc_id: u32,
+    pub cluster: Option<String>,
+    pub effective_usage: f64,
+    pub fairshare_factor: f64,
+    pub is_user: bool,
+    pub level_fairshare: f64,
+    pub name: String,
+    pub parent: Option<String>,
+    pub partition: Option<String>,
+    pub shares_norm: f64,
+    pub shares_raw: u32,
+    pub usage_norm: f64,
+    pub usage_raw: u64,
+}
+
+impl_snapshot_traits!(SharesRecord, SharesRecordSnapshot);
+
+/// Get the priority factors of all of the pending jobs known to the
+/// controller, as with `sprio`.
+///
+/// This fails if the controller's priority plugin does not compute factors,
+/// as with `PriorityType=priority/basic`. Depending on the controller's
+/// `PrivateData` settings, users may only be able to see their own jobs.
+pub fn get_all_priority_factors() -> Result<PriorityFactorsMessageOwned, Error> {
+    let mut msg: *mut slurm_sys::priority_factors_response_msg_t = 0 as _;
+
+    // Older versions of Slurm can filter the jobs on the controller; a
+    // zeroed request matches all of them.
+    #[cfg(slurm_api_load_priority_factors_request)]
+    {
+        let mut req: slurm_sys::priority_factors_request_msg_t = unsafe { std::mem::zeroed() };
+        ustry!(slurm_sys::slurm_load_priority_factors(&mut req, &mut msg));
+    }
+    #[cfg(not(slurm_api_load_priority_factors_request))]
+    ustry!(slurm_sys::slurm_load_priority_factors(&mut msg));
+
+    Ok(unsafe { PriorityFactorsMessageOwned::assume_ownership(msg as _) })
+}
+
+make_slurm_wrap_struct!(
+    PriorityFactorsMessage,
+    slurm_sys::priority_factors_response_msg_t,
+    "The priority factors of a set of pending jobs, as returned by the controller."
+);
+
+impl_wrapper_debug!(PriorityFactorsMessage; len);
+
+impl PriorityFactorsMessage {
+    /// Get the number of records in this message.
+    pub fn len(&self) -> usize {
+        self.factors().len()
+    }
+
+    /// Return true if this message contains no records.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the list of priority factor records.
+    ///
+    /// A job that may run in several partitions has one record for each.
+    pub fn factors(&self) -> &SlurmList<PriorityFactors> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().priority_factors_list) }
+    }
+}
+
+make_owned_version!(@customdrop PriorityFactorsMessage, PriorityFactorsMessageOwned,
+                    "An owned version of `PriorityFactorsMessage`.");
+
+impl Drop for PriorityFactorsMessageOwned {
+    fn drop(&mut self) {
+        unsafe { slurm_sys::slurm_free_priority_factors_response_msg((self.0).0) };
+    }
+}
+
+make_slurm_wrap_struct!(
+    PriorityFactors,
+    slurm_sys::priority_factors_object_t,
+    "\
+The factors that make up the priority of a pending job, as reported by
+`sprio`.
+
+Each factor is given as its weighted contribution to the job's priority. The
+TRES factors are not exposed in these Rust bindings.
+"
+);
+
+impl_wrapper_debug!(PriorityFactors; job_id, partition);
+
+/// The structure holding the factors themselves.
+///
+/// Slurm 23.02 moved them out of the main record into a separate structure
+/// with the same field names.
+#[cfg(slurm_api_priority_factors_t)]
+type PriorityFactorValues = slurm_sys::priority_factors_t;
+#[cfg(not(slurm_api_priority_factors_t))]
+type PriorityFactorValues = slurm_sys::priority_factors_object_t;
+
+impl PriorityFactors {
+    #[cfg(slurm_api_priority_factors_t)]
+    fn values(&self) -> &PriorityFactorValues {
+        unsafe { &*self.sys_data().prio_factors }
+    }
+
+    #[cfg(not(slurm_api_priority_factors_t))]
+    fn values(&self) -> &PriorityFactorValues {
+        self.sys_data()
+    }
+
+    /// Get the ID of the job.
+    pub fn job_id(&self) -> JobId {
+        self.sys_data().job_id
+    }
+
+    /// Get the numeric ID of the user that owns the job.
+    pub fn user_id(&self) -> u32 {
+        self.sys_data().user_id
+    }
+
+    /// Get the partition to which these factors apply.
+    pub fn partition(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().partition) }
+    }
+
+    /// Get the contribution of the job's age.
+    pub fn age(&self) -> f64 {
+        self.values().priority_age
+    }
+
+    /// Get the contribution of the fairshare of the job's association.
+    pub fn fairshare(&self) -> f64 {
+        self.values().priority_fs
+    }
+
+    /// Get the contribution of the job's size.
+    pub fn job_size(&self) -> f64 {
+        self.values().priority_js
+    }
+
+    /// Get the contribution of the partition's priority tier.
+    pub fn partition_factor(&self) -> f64 {
+        self.values().priority_part
+    }
+
+    /// Get the contribution of the job's QOS.
+    pub fn qos(&self) -> f64 {
+        self.values().priority_qos
+    }
+
+    /// Get the site-specific contribution.
+    pub fn site(&self) -> u32 {
+        self.values().priority_site
+    }
+
+    /// Get the job's "nice" adjustment.
+    ///
+    /// Slurm stores this offset by `NICE_OFFSET`; the value returned here
+    /// has the offset removed, so positive values lower the priority.
+    pub fn nice(&self) -> i64 {
+        self.values().nice as i64 - NICE_OFFSET
+    }
+
+    /// Copy the data in this record into a plain-Rust structure.
+    ///
+    /// The result does not refer to any Slurm memory, so it may outlive the
+    /// response that contained this record.
+    pub fn to_snapshot(&self) -> PriorityFactorsSnapshot {
+        PriorityFactorsSnapshot {
+            age: self.age(),
+            fairshare: self.fairshare(),
+            job_id: self.job_id(),
+            job_size: self.job_size(),
+            nice: self.nice(),
+            partition: self.partition().map(|s| s.into_owned()),
+            partition_factor: self.partition_factor(),
+            qos: self.qos(),
+            site: self.site(),
+            user_id: self.user_id(),
+        }
+    }
+}
+
+/// The offset that Slurm adds to "nice" values so that they can be stored
+/// unsigned.
+const NICE_OFFSET: i64 = 0x8000_0000;
+
+/// A plain-Rust copy of the data in a `PriorityFactors` record.
+///
+/// See the accessors of `PriorityFactors` for the meanings of the fields.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct PriorityFactorsSnapshot {
+    pub age: f64,
+    pub fairshare: f64,
+    pub job_id: JobId,
+    pub job_size: f64,
+    pub nice: i64,
+    pub partition: Option<String>,
+    pub partition_factor: f64,
+    pub qos: f64,
+    pub site: u32,
+    pub user_id: u32,
+}
+
+impl_snapshot_traits!(PriorityFactors, PriorityFactorsSnapshot);
+
+/// Ask the database connection to transparently reconnect if it is broken.
+#[cfg(feature = "slurmdb")]
+pub const PERSIST_FLAG_RECONNECT: u16 = 0x0002;
+
+/// Flag set by the server if user names are case-sensitive in the database.
+#[cfg(feature = "slurmdb")]
+pub const PERSIST_FLAG_P_USER_CASE: u16 = 0x0008;
+
+/// Ask the database connection not to log errors when connecting fails.
+#[cfg(feature = "slurmdb")]
+pub const PERSIST_FLAG_SUPPRESS_ERR: u16 = 0x0010;
+
+/// Options for connecting to the Slurm accounting database.
+///
+/// The persistent-connection flag values (`PERSIST_FLAG_*`) are not exposed
+/// by the Slurm headers, so we hardcode the ones that are useful to clients.
+#[cfg(feature = "slurmdb")]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DatabaseConnectionOptions {
+    cluster: Option<String>,
+    persist_flags: u16,
+}
+
+#[cfg(feature = "slurmdb")]
+impl DatabaseConnectionOptions {
+    /// Create a new, defaulted set of options.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Target queries made through the connection at the specified cluster.
+    ///
+    /// A single `slurmdbd` may serve several clusters. If this option is
+    /// set, queries that do not explicitly list the clusters of interest
+    /// will be restricted to this cluster. Otherwise, such queries will
+    /// match all of the clusters known to the database.
+    pub fn cluster<S: Into<String>>(&mut self, name: S) -> &mut Self {
+        self.cluster = Some(name.into());
+        self
+    }
+
+    /// Set the persistent-connection flags to use.
+    ///
+    /// This should be a bitwise-or of `PERSIST_FLAG_*` values.
+    pub fn persist_flags(&mut self, flags: u16) -> &mut Self {
+        self.persist_flags = flags;
+        self
+    }
+}
+
+/// Run a database query whose filter structure has a `cluster_list` field,
+/// applying the connection's target cluster if the filters do not list any
+/// clusters.
+///
+/// We do this on a shallow copy of the filter structure so that the caller's
+/// value is untouched. The temporary cluster list must outlive the query.
+#[cfg(feature = "slurmdb")]
+macro_rules! query_with_default_cluster {
+    ($conn:ident, $filters:ident, $func:path $(, $extra:expr)*) => {{
+        let mut cond = *$filters.sys_data();
+        let _clusters = if $filters.cluster_list().is_empty() {
+            $conn.default_cluster_list()?.map(|list| {
+                cond.cluster_list = (list.0).0;
+                list
+            })
+        } else {
+            None
+        };
+
+        let ptr = pstry!($func($conn.ptr, &mut cond $(, $extra)*));
+        Ok(unsafe { SlurmListOwned::assume_ownership(ptr as _) })
+    }};
+}
+
+/// A connection to the Slurm accounting database.
+///
+/// Unlike most of the types in this crate, this one does not directly wrap a
+/// Slurm structure: the C API represents connections as opaque pointers, and
+/// we need to carry some extra state alongside them.
+#[cfg(feature = "slurmdb")]
+#[derive(Debug)]
+pub struct DatabaseConnection {
+    ptr: *mut c_void,
+    cluster: Option<String>,
+    server_flags: u16,
+}
+
+#[cfg(feature = "slurmdb")]
+impl DatabaseConnection {
+    /// Get the name of the cluster that queries are targeted at, if one was
+    /// specified when connecting.
+    pub fn cluster(&self) -> Option<&str> {
+        self.cluster.as_ref().map(|s| s.as_str())
+    }
+
+    /// Get the persistent-connection flags reported by the server.
+    ///
+    /// Check this against `PERSIST_FLAG_P_USER_CASE` to learn whether the
+    /// database treats user names case-sensitively.
+    pub fn server_persist_flags(&self) -> u16 {
+        self.server_flags
+    }
+
+    /// Commit any pending modifications made through this connection.
+    ///
+    /// Administrative changes to the database are not made permanent until
+    /// they are committed. If the connection is closed without committing,
+    /// they are discarded.
+    pub fn commit(&self) -> Result<(), SlurmError> {
+        ustry!(slurm_sys::slurmdb_connection_commit(self.ptr, true));
+        Ok(())
+    }
+
+    /// Discard any pending modifications made through this connection.
+    pub fn rollback(&self) -> Result<(), SlurmError> {
+        ustry!(slurm_sys::slurmdb_connection_commit(self.ptr, false));
+        Ok(())
+    }
+
+    /// Query for information about accounts.
+    pub fn get_accounts(
+        &self,
+        filters: &AccountFilters,
+    ) -> Result<SlurmListOwned<AccountRecord>, SlurmError> {
+        let ptr = pstry!(slurm_sys::slurmdb_accounts_get(self.ptr, filters.0));
+        Ok(unsafe { SlurmListOwned::assume_ownership(ptr as _) })
+    }
+
+    /// Query for information about users.
+    pub fn get_users(
+        &self,
+        filters: &UserFilters,
+    ) -> Result<SlurmListOwned<UserRecord>, SlurmError> {
+        let ptr = pstry!(slurm_sys::slurmdb_users_get(self.ptr, filters.0));
+        Ok(unsafe { SlurmListOwned::assume_ownership(ptr as _) })
+    }
+
+    /// Query for information about associations.
+    ///
+    /// As with `get_jobs`, if this connection was created with a target
+    /// cluster and the filters do not list any clusters, the query is
+    /// restricted to the target.
+    pub fn get_associations(
+        &self,
+        filters: &AssociationFilters,
+    ) -> Result<SlurmListOwned<AssociationRecord>, SlurmError> {
+        query_with_default_cluster!(self, filters, slurm_sys::slurmdb_associations_get)
+    }
+
+    /// Query for information about QOS (quality-of-service) definitions.
+    pub fn get_qos(&self, filters: &QosFilters) -> Result<SlurmListOwned<QosRecord>, SlurmError> {
+        let ptr = pstry!(slurm_sys::slurmdb_qos_get(self.ptr, filters.0));
+        Ok(unsafe { SlurmListOwned::assume_ownership(ptr as _) })
+    }
+
+    /// Query for information about the clusters registered in the database.
+    pub fn get_clusters(
+        &self,
+        filters: &ClusterFilters,
+    ) -> Result<SlurmListOwned<ClusterRecord>, SlurmError> {
+        let ptr = pstry!(slurm_sys::slurmdb_clusters_get(self.ptr, filters.0));
+        Ok(unsafe { SlurmListOwned::assume_ownership(ptr as _) })
+    }
+
+    /// Query for the TRES (trackable resource) definitions in the database.
+    ///
+    /// These can be used to resolve the numeric TRES IDs appearing in the
+    /// textual TRES representations used elsewhere in the API.
+    pub fn get_tres(
+        &self,
+        filters: &TresFilters,
+    ) -> Result<SlurmListOwned<TresRecord>, SlurmError> {
+        let ptr = pstry!(slurm_sys::slurmdb_tres_get(self.ptr, filters.0));
+        Ok(unsafe { SlurmListOwned::assume_ownership(ptr as _) })
+    }
+
+    /// Query for events in the history of clusters and their nodes, such as
+    /// nodes going down or being drained.
+    ///
+    /// As with `get_jobs`, if this connection was created with a target
+    /// cluster and the filters do not list any clusters, the query is
+    /// restricted to the target.
+    pub fn get_events(
+        &self,
+        filters: &EventFilters,
+    ) -> Result<SlurmListOwned<EventRecord>, SlurmError> {
+        query_with_default_cluster!(self, filters, slurm_sys::slurmdb_events_get)
+    }
+
+    /// Query for the history of reservations.
+    ///
+    /// As with `get_jobs`, if this connection was created with a target
+    /// cluster and the filters do not list any clusters, the query is
+    /// restricted to the target.
+    pub fn get_reservations(
+        &self,
+        filters: &ReservationFilters,
+    ) -> Result<SlurmListOwned<ReservationRecord>, SlurmError> {
+        query_with_default_cluster!(self, filters, slurm_sys::slurmdb_reservations_get)
+    }
+
+    /// Query for information about WCKeys (workload characterization keys).
+    ///
+    /// As with `get_jobs`, if this connection was created with a target
+    /// cluster and the filters do not list any clusters, the query is
+    /// restricted to the target.
+    pub fn get_wckeys(
+        &self,
+        filters: &WckeyFilters,
+    ) -> Result<SlurmListOwned<WckeyRecord>, SlurmError> {
+        query_with_default_cluster!(self, filters, slurm_sys::slurmdb_wckeys_get)
+    }
+
+    /// Query the log of transactions that modified the database.
+    ///
+    /// As with `get_jobs`, if this connection was created with a target
+    /// cluster and the filters do not list any clusters, the query is
+    /// restricted to the target.
+    pub fn get_transactions(
+        &self,
+        filters: &TransactionFilters,
+    ) -> Result<SlurmListOwned<TransactionRecord>, SlurmError> {
+        query_with_default_cluster!(self, filters, slurm_sys::slurmdb_txn_get)
+    }
+
+    /// Query for inconsistencies detected in the database, such as accounts
+    /// without users.
+    ///
+    /// As with `get_jobs`, if this connection was created with a target
+    /// cluster and the filters do not list any clusters, the query is
+    /// restricted to the target.
+    pub fn get_problems(
+        &self,
+        filters: &AssociationFilters,
+    ) -> Result<SlurmListOwned<ProblemRecord>, SlurmError> {
+        query_with_default_cluster!(self, filters, slurm_sys::slurmdb_problems_get)
+    }
+
+    /// Run a sequence of modifications as a unit.
+    ///
+    /// If the closure succeeds, the modifications are committed; otherwise,
+    /// they are rolled back.
+    pub fn transaction<T, E, F>(&self, f: F) -> Result<T, E>
+    where
+        F: FnOnce(&Self) -> Result<T, E>,
+        E: From<SlurmError>,
+    {
+        match f(self) {
+            Ok(v) => {
+                self.commit()?;
+                Ok(v)
+            }
+
+            Err(e) => {
+                // If the rollback fails, the original error is more useful.
+                let _ignored = self.rollback();
+                Err(e)
+            }
+        }
+    }
+
+    /// Add new accounts to the database.
+    ///
+    /// The changes are not made permanent until they are committed.
+    pub fn add_accounts(&self, accounts: &SlurmList<AccountRecord>) -> Result<(), SlurmError> {
+        ustry!(slurm_sys::slurmdb_accounts_add(self.ptr, accounts.0));
+        Ok(())
+    }
+
+    /// Modify the accounts matching the filters.
+    ///
+    /// Only the fields that are set in `changes` are modified. The return
+    /// value lists the names of the accounts that were modified. The changes
+    /// are not made permanent until they are committed.
+    pub fn modify_accounts(
+        &self,
+        filters: &AccountFilters,
+        changes: &AccountRecord,
+    ) -> Result<SlurmListOwned<*mut c_char>, SlurmError> {
+        let ptr = pstry!(slurm_sys::slurmdb_accounts_modify(
+            self.ptr, filters.0, changes.0
+        ));
+        Ok(unsafe { SlurmListOwned::assume_ownership(ptr as _) })
+    }
+
+    /// Remove the accounts matching the filters.
+    ///
+    /// The return value lists the names of the accounts that were removed.
+    /// The changes are not made permanent until they are committed.
+    pub fn remove_accounts(
+        &self,
+        filters: &AccountFilters,
+    ) -> Result<SlurmListOwned<*mut c_char>, SlurmError> {
+        let ptr = pstry!(slurm_sys::slurmdb_accounts_remove(self.ptr, filters.0));
+        Ok(unsafe { SlurmListOwned::assume_ownership(ptr as _) })
+    }
+
+    /// Add new users to the database.
+    ///
+    /// The changes are not made permanent until they are committed.
+    pub fn add_users(&self, users: &SlurmList<UserRecord>) -> Result<(), SlurmError> {
+        ustry!(slurm_sys::slurmdb_users_add(self.ptr, users.0));
+        Ok(())
+    }
+
+    /// Modify the users matching the filters.
+    ///
+    /// Only the fields that are set in `changes` are modified. The return
+    /// value lists the names of the users that were modified. The changes
+    /// are not made permanent until they are committed.
+    pub fn modify_users(
+        &self,
+        filters: &UserFilters,
+        changes: &UserRecord,
+    ) -> Result<SlurmListOwned<*mut c_char>, SlurmError> {
+        let ptr = pstry!(slurm_sys::slurmdb_users_modify(
+            self.ptr, filters.0, changes.0
+        ));
+        Ok(unsafe { SlurmListOwned::assume_ownership(ptr as _) })
+    }
+
+    /// Remove the users matching the filters.
+    ///
+    /// The return value lists the names of the users that were removed. The
+    /// changes are not made permanent until they are committed.
+    pub fn remove_users(
+        &self,
+        filters: &UserFilters,
+    ) -> Result<SlurmListOwned<*mut c_char>, SlurmError> {
+        let ptr = pstry!(slurm_sys::slurmdb_users_remove(self.ptr, filters.0));
+        Ok(unsafe { SlurmListOwned::assume_ownership(ptr as _) })
+    }
+
+    /// Add new associations to the database.
+    ///
+    /// The changes are not made permanent until they are committed.
+    pub fn add_associations(
+        &self,
+        associations: &SlurmList<AssociationRecord>,
+    ) -> Result<(), SlurmError> {
+        ustry!(slurm_sys::slurmdb_associations_add(
+            self.ptr,
+            associations.0
+        ));
+        Ok(())
+    }
+
+    /// Modify the associations matching the filters.
+    ///
+    /// Only the fields that are set in `changes` are modified. The return
+    /// value contains textual descriptions of the associations that were
+    /// modified. The changes are not made permanent until they are
+    /// committed.
+    pub fn modify_associations(
+        &self,
+        filters: &AssociationFilters,
+        changes: &AssociationRecord,
+    ) -> Result<SlurmListOwned<*mut c_char>, SlurmError> {
+        let ptr = pstry!(slurm_sys::slurmdb_associations_modify(
+            self.ptr, filters.0, changes.0
+        ));
+        Ok(unsafe { SlurmListOwned::assume_ownership(ptr as _) })
+    }
+
+    /// Remove the associations matching the filters.
+    ///
+    /// The return value contains textual descriptions of the associations
+    /// that were removed. The changes are not made permanent until they are
+    /// committed.
+    pub fn remove_associations(
+        &self,
+        filters: &AssociationFilters,
+    ) -> Result<SlurmListOwned<*mut c_char>, SlurmError> {
+        let ptr = pstry!(slurm_sys::slurmdb_associations_remove(self.ptr, filters.0));
+        Ok(unsafe { SlurmListOwned::assume_ownership(ptr as _) })
+    }
+
+    /// Add new QOS definitions to the database.
+    ///
+    /// The changes are not made permanent until they are committed.
+    pub fn add_qos(&self, qos: &SlurmList<QosRecord>) -> Result<(), SlurmError> {
+        // Older versions of Slurm want the UID of the user making the change.
+        #[cfg(slurm_api_qos_add_uid)]
+        ustry!(slurm_sys::slurmdb_qos_add(self.ptr, libc::getuid(), qos.0));
+        #[cfg(not(slurm_api_qos_add_uid))]
+        ustry!(slurm_sys::slurmdb_qos_add(self.ptr, qos.0));
+        Ok(())
+    }
+
+    /// Modify the QOS definitions matching the filters.
+    ///
+    /// Only the fields that are set in `changes` are modified. The return
+    /// value lists the names of the QOS that were modified. The changes are
+    /// not made permanent until they are committed.
+    pub fn modify_qos(
+        &self,
+        filters: &QosFilters,
+        changes: &QosRecord,
+    ) -> Result<SlurmListOwned<*mut c_char>, SlurmError> {
+        let ptr = pstry!(slurm_sys::slurmdb_qos_modify(
+            self.ptr, filters.0, changes.0
+        ));
+        Ok(unsafe { SlurmListOwned::assume_ownership(ptr as _) })
+    }
+
+    /// Remove the QOS definitions matching the filters.
+    ///
+    /// The return value lists the names of the QOS that were removed. The
+    /// changes are not made permanent until they are committed.
+    pub fn remove_qos(
+        &self,
+        filters: &QosFilters,
+    ) -> Result<SlurmListOwned<*mut c_char>, SlurmError> {
+        let ptr = pstry!(slurm_sys::slurmdb_qos_remove(self.ptr, filters.0));
+        Ok(unsafe { SlurmListOwned::assume_ownership(ptr as _) })
+    }
+
+    /// Report the distribution of job sizes, grouped by top-level account.
+    ///
+    /// The jobs to consider are selected with `filters`. They are binned
+    /// according to the number of CPUs that they used, with the bin edges
+    /// specified in `sizes`: for instance, `[50, 250, 500]` yields the bins
+    /// 0–49, 50–249, 250–499, and 500 and up. If `flat_view` is true, all
+    /// accounts are reported, rather than only the top-level ones. As with
+    /// `get_jobs`, the connection's target cluster is applied if needed.
+    pub fn report_job_sizes_by_account(
+        &self,
+        filters: &JobFilters,
+        sizes: &[u32],
+        flat_view: bool,
+    ) -> Result<SlurmListOwned<ReportClusterGrouping>, SlurmError> {
+        let grouping = job_size_grouping_list(sizes)?;
+        query_with_default_cluster!(
+            self,
+            filters,
+            slurm_sys::slurmdb_report_job_sizes_grouped_by_top_account,
+            (grouping.0).0,
+            flat_view
+        )
+    }
+
+    /// Report the distribution of job sizes, grouped by WCKey.
+    ///
+    /// See `report_job_sizes_by_account` for a description of the
+    /// arguments. In the results, the “account” name of each
+    /// `ReportAccountGrouping` is the WCKey name.
+    pub fn report_job_sizes_by_wckey(
+        &self,
+        filters: &JobFilters,
+        sizes: &[u32],
+    ) -> Result<SlurmListOwned<ReportClusterGrouping>, SlurmError> {
+        let grouping = job_size_grouping_list(sizes)?;
+        query_with_default_cluster!(
+            self,
+            filters,
+            slurm_sys::slurmdb_report_job_sizes_grouped_by_wckey,
+            (grouping.0).0
+        )
+    }
+
+    /// Report the distribution of job sizes, grouped by top-level account
+    /// and then by WCKey.
+    ///
+    /// See `report_job_sizes_by_account` for a description of the
+    /// arguments. In the results, the “account” name of each
+    /// `ReportAccountGrouping` has the form `account:wckey`.
+    pub fn report_job_sizes_by_account_then_wckey(
+        &self,
+        filters: &JobFilters,
+        sizes: &[u32],
+        flat_view: bool,
+    ) -> Result<SlurmListOwned<ReportClusterGrouping>, SlurmError> {
+        let grouping = job_size_grouping_list(sizes)?;
+        query_with_default_cluster!(
+            self,
+            filters,
+            slurm_sys::slurmdb_report_job_sizes_grouped_by_top_account_then_wckey,
+            (grouping.0).0,
+            flat_view
+        )
+    }
+
+    /// Create a list containing just our target cluster name, if we have one.
+    fn default_cluster_list(&self) -> Result<Option<SlurmListOwned<*mut c_char>>, SlurmError> {
+        match self.cluster {
+            Some(ref name) => {
+                let mut list = unsafe { SlurmListOwned::assume_ownership(0 as _) };
+                list.append(name)?;
+                Ok(Some(list))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Query for information about jobs.
+    ///
+    /// If this connection was created with a target cluster and the filters
+    /// do not list any clusters, the query is restricted to the target.
+    pub fn get_jobs(&self, filters: &JobFilters) -> Result<SlurmListOwned<JobRecord>, SlurmError> {
+        query_with_default_cluster!(self, filters, slurm_sys::slurmdb_jobs_get)
+    }
+}
+
+/// An owned version of `DatabaseConnection`.
+#[cfg(feature = "slurmdb")]
+#[derive(Debug)]
+#[must_use]
+pub struct DatabaseConnectionOwned(DatabaseConnection);
+
+#[cfg(feature = "slurmdb")]
+impl Deref for DatabaseConnectionOwned {
+    type Target = DatabaseConnection;
+
+    fn deref(&self) -> &DatabaseConnection {
+        &self.0
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl DerefMut for DatabaseConnectionOwned {
+    fn deref_mut(&mut self) -> &mut DatabaseConnection {
+        &mut self.0
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl DatabaseConnectionOwned {
+    /// Connect to the Slurm database.
+    pub fn new() -> Result<Self, SlurmError> {
+        Self::new_with(&DatabaseConnectionOptions::default())
+    }
+
+    /// Connect to the Slurm database with customized options.
+    pub fn new_with(options: &DatabaseConnectionOptions) -> Result<Self, SlurmError> {
+        // The flags are an in/out parameter: on return, they contain flags
+        // describing the server.
+        let mut flags = options.persist_flags;
+        let ptr = pstry!(slurm_sys::slurmdb_connection_get2(&mut flags));
+
+        Ok(DatabaseConnectionOwned(DatabaseConnection {
+            ptr,
+            cluster: options.cluster.clone(),
+            server_flags: flags,
+        }))
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl Drop for DatabaseConnectionOwned {
+    fn drop(&mut self) {
+        // This function can return error codes, but we're not in a position
+        // to do anything about it in the Drop call.
+        let _ignored = unsafe { slurm_sys::slurmdb_connection_close(&mut (self.0).ptr) };
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+make_slurm_wrap_struct!(
+    JobFilters,
+    slurm_sys::slurmdb_job_cond_t,
+    "\
+A set of filters for identifying jobs of interest when querying the Slurm
+accounting database.
+
+The following items in the Slurm API are *not* exposed in these Rust bindings:
+
+```ignore
+pub struct slurmdb_job_cond_t {
+    pub associd_list: List,
+    pub exitcode: i32,
+    pub groupid_list: List,
+    pub jobname_list: List,
+    pub partition_list: List,
+    pub qos_list: List,
+    pub resv_list: List,
+    pub resvid_list: List,
+    pub state_list: List,
+    pub wckey_list: List,
+    pub without_steps: u16,
+}
+```
+
+"
+);
+
+#[cfg(feature = "slurmdb")]
+impl_wrapper_debug!(JobFilters);
+
+#[cfg(feature = "slurmdb")]
+impl JobFilters {
+    /// Access the list of account names that will match this set of filters.
+    pub fn acct_list(&self) -> &SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().acct_list) }
+    }
+
+    /// Mutably access the list of account names that will match this set of
+    /// filters.
+    pub fn acct_list_mut(&mut self) -> &mut SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr_mut(&mut self.sys_data_mut().acct_list) }
+    }
+
+    /// Access the list of cluster names that will match this set of filters.
+    pub fn cluster_list(&self) -> &SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().cluster_list) }
+    }
+
+    /// Mutably access the list of cluster names that will match this set of
+    /// filters.
+    pub fn cluster_list_mut(&mut self) -> &mut SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr_mut(&mut self.sys_data_mut().cluster_list) }
+    }
+
+    /// Access the list of fields that the database will be asked to return.
+    pub fn format_list(&self) -> &SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().format_list) }
+    }
+
+    /// Mutably access the list of fields that the database will be asked to
+    /// return.
+    ///
+    /// If this list is empty, all fields are returned. Otherwise, it should
+    /// contain `sacct`-style field names (e.g. `JobID`, `State`, `Start`),
+    /// and the database may leave the other fields of the resulting
+    /// `JobRecord`s empty, which can substantially reduce the amount of data
+    /// transferred for large queries.
+    ///
+    /// # Safety
+    ///
+    /// Many `JobRecord` accessors assume that Slurm has filled in the fields
+    /// that they read; for instance, string fields that are always set in a
+    /// full query are dereferenced without checking for null. If this list
+    /// is non-empty, the caller must only use the accessors of the resulting
+    /// records (and of their steps) that correspond to the fields it
+    /// requested. This includes indirect uses such as `Debug` formatting,
+    /// serialization, and `to_snapshot`, which read every field.
+    pub unsafe fn format_list_mut(&mut self) -> &mut SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr_mut(&mut self.sys_data_mut().format_list) }
+    }
+
+    pub fn step_list(&self) -> &SlurmList<JobStepFilter> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().step_list) }
+    }
+
+    pub fn step_list_mut(&mut self) -> &mut SlurmList<JobStepFilter> {
+        unsafe { SlurmList::transmute_ptr_mut(&mut self.sys_data_mut().step_list) }
+    }
+
+    /// Filter on the number of CPUs allocated to the job.
+    ///
+    /// If no maximum is set with `cpus_max`, Slurm will only match jobs that
+    /// used exactly this many CPUs. To match all jobs using at least this
+    /// many CPUs, set the maximum to `u32::MAX`.
+    pub fn cpus_min(&mut self, count: u32) -> &mut Self {
+        self.sys_data_mut().cpus_min = count;
+        self
+    }
+
+    /// Filter on the maximum number of CPUs allocated to the job.
+    ///
+    /// This setting has no effect unless `cpus_min` is also set.
+    pub fn cpus_max(&mut self, count: u32) -> &mut Self {
+        self.sys_data_mut().cpus_max = count;
+        self
+    }
+
+    /// Filter on the number of nodes allocated to the job.
+    ///
+    /// As with `cpus_min`, if no maximum is set, Slurm will only match jobs
+    /// that used exactly this many nodes.
+    pub fn nodes_min(&mut self, count: u32) -> &mut Self {
+        self.sys_data_mut().nodes_min = count;
+        self
+    }
+
+    /// Filter on the maximum number of nodes allocated to the job.
+    ///
+    /// This setting has no effect unless `nodes_min` is also set.
+    pub fn nodes_max(&mut self, count: u32) -> &mut Self {
+        self.sys_data_mut().nodes_max = count;
+        self
+    }
+
+    /// Filter on the job's time limit, measured in minutes.
+    ///
+    /// As with `cpus_min`, if no maximum is set, Slurm will only match jobs
+    /// with exactly this time limit.
+    pub fn timelimit_min(&mut self, minutes: u32) -> &mut Self {
+        self.sys_data_mut().timelimit_min = minutes;
+        self
+    }
+
+    /// Filter on the job's maximum time limit, measured in minutes.
+    ///
+    /// This setting has no effect unless `timelimit_min` is also set.
+    pub fn timelimit_max(&mut self, minutes: u32) -> &mut Self {
+        self.sys_data_mut().timelimit_max = minutes;
+        self
+    }
+
+    /// Add a filter on the earliest job "usage time".
+    ///
+    /// TODO: what is "usage time" really?
+    pub fn usage_start(&mut self, time: DateTime<Utc>) -> &Self {
+        self.without_usage_truncation(false);
+        self.sys_data_mut().usage_start = time.timestamp() as _;
+        self
+    }
+
+    /// Add a filter on the latest job "usage time".
+    pub fn usage_end(&mut self, time: DateTime<Utc>) -> &mut Self {
+        self.without_usage_truncation(false);
+        self.sys_data_mut().usage_end = time.timestamp() as _;
+        self
+    }
+
+    /// Set whether the times of the matching jobs are reported as-is.
+    ///
+    /// By default, setting a usage window causes Slurm to clip the start and
+    /// end times of the matching jobs to that window. Call this after
+    /// `usage_start` and `usage_end`, which reset it.
+    pub fn without_usage_truncation(&mut self, value: bool) -> &mut Self {
+        #[cfg(slurm_api_job_cond_t_flags)]
+        self.set_flag(slurm_sys::SLURMRS_JOBCOND_FLAG_NO_TRUNC, value);
+        #[cfg(not(slurm_api_job_cond_t_flags))]
+        {
+            self.sys_data_mut().without_usage_truncation = value as u16;
+        }
+        self
+    }
+
+    /// Set or clear one of the `JOBCOND_FLAG_*` bits used by Slurm 20.02
+    /// and later.
+    #[cfg(slurm_api_job_cond_t_flags)]
+    fn set_flag(&mut self, flag: u32, value: bool) {
+        if value {
+            self.sys_data_mut().flags |= flag;
+        } else {
+            self.sys_data_mut().flags &= !flag;
+        }
+    }
+
+    /// Access the list of user ID numbers that will match this set of filters.
+    ///
+    /// Note that this list should consist of *textual* representations of
+    /// *numeric* user IDs. Yes, it's silly.
+    pub fn userid_list(&self) -> &SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().userid_list) }
+    }
+
+    /// Mutably access the list of user ID numbers that will match this set of
+    /// filters.
+    pub fn userid_list_mut(&mut self) -> &mut SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr_mut(&mut self.sys_data_mut().userid_list) }
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+make_owned_version!(@customdrop JobFilters, JobFiltersOwned, "An owned version of `JobFilters`");
+
+#[cfg(feature = "slurmdb")]
+impl JobFiltersOwned {
+    /// Create a set of filters matching the jobs of the specified user whose
+    /// usage began at or after the specified time.
+    pub fn for_user_since(uid: u32, since: DateTime<Utc>) -> Result<Self, Error> {
+        let mut inst = Self::new()?;
+        // The filter must be a textual representation of the numeric UID.
+        inst.userid_list_mut().append(format!("{}", uid))?;
+        inst.usage_start(since);
+        Ok(inst)
+    }
+
+    /// Create a set of filters matching a single job.
+    ///
+    /// Array tasks and heterogeneous job components may be specified using
+    /// the `JobIdentifier` type; plain job IDs may be passed directly.
+    pub fn for_job<J: Into<JobIdentifier>>(jid: J) -> Result<Self, Error> {
+        let mut inst = Self::new()?;
+        inst.step_list_mut()
+            .append(JobStepFilterOwned::for_identifier(jid.into())?);
+        Ok(inst)
+    }
+
+    /// Create a set of filters matching jobs charged to the specified account.
+    pub fn for_account<S: AsRef<str>>(name: S) -> Result<Self, Error> {
+        let mut inst = Self::new()?;
+        inst.acct_list_mut().append(name)?;
+        Ok(inst)
+    }
+
+    /// Only match jobs that ran on one or more of the specified nodes.
+    ///
+    /// The argument is a Slurm hostlist expression such as `node[01-04]`.
+    /// Slurm only honors this filter if the usage window is bounded, so it
+    /// should be combined with `usage_start`.
+    pub fn used_nodes<S: AsRef<str>>(&mut self, nodes: S) -> Result<&mut Self, Error> {
+        slurm_replace_utf8_string(&mut self.sys_data_mut().used_nodes, nodes)?;
+        Ok(self)
+    }
+
+    /// Set whether to include a record for each run of a job that has been
+    /// requeued.
+    ///
+    /// By default, only the most recent run of each job is returned. With
+    /// this option, the earlier runs are returned as well, in the state in
+    /// which they ended (usually `Requeued`), as with `sacct --duplicates`.
+    pub fn include_requeued(&mut self, include: bool) -> &mut Self {
+        #[cfg(slurm_api_job_cond_t_flags)]
+        self.set_flag(slurm_sys::SLURMRS_JOBCOND_FLAG_DUP, include);
+        #[cfg(not(slurm_api_job_cond_t_flags))]
+        {
+            self.sys_data_mut().duplicates = include as u16;
+        }
+        self
+    }
+
+    /// Set whether to retrieve the batch scripts of the matching jobs.
+    ///
+    /// Scripts are only available if the database is configured to store
+    /// them, which Slurm supports as of version 21.08. With older versions of
+    /// the C API, requesting them is an error; see
+    /// `Capabilities::job_scripts`. See also `JobRecord::script`.
+    pub fn include_script(&mut self, include: bool) -> Result<&mut Self, Error> {
+        #[cfg(slurm_api_job_rec_t_script)]
+        self.set_flag(slurm_sys::SLURMRS_JOBCOND_FLAG_SCRIPT, include);
+
+        #[cfg(not(slurm_api_job_rec_t_script))]
+        {
+            if include {
+                return Err(format_err!(
+                    "this version of Slurm cannot store batch scripts in the database"
+                ));
+            }
+        }
+
+        Ok(self)
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl Drop for JobFiltersOwned {
+    fn drop(&mut self) {
+        // This frees the sub-lists and strings as well as the main structure.
+        unsafe { slurm_sys::slurmdb_destroy_job_cond((self.0).0 as _) };
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl JobFiltersOwned {
+    /// Create a new, empty set of filters.
+    pub fn new() -> Result<Self, Error> {
+        let mut inst = unsafe { Self::alloc_zeroed() }?;
+        inst.without_usage_truncation(true);
+        Ok(inst)
+    }
+}
+
+/// A plain-Rust description of an accounting query for jobs.
+///
+/// Unlike `JobFilters`, this type does not refer to any Slurm memory, so it
+/// can be used with backends that do not go through the Slurm C library,
+/// such as the `slurmrestd` client in the `rest` module. Only the most
+/// commonly used filters are supported. Empty lists match everything.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct JobQuery {
+    users: Vec<String>,
+    accounts: Vec<String>,
+    jobs: Vec<JobId>,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+    without_usage_truncation: bool,
+}
+
+impl JobQuery {
+    /// Create a new query that matches all jobs.
+    #[must_use]
+    pub fn new() -> JobQuery {
+        JobQuery::default()
+    }
+
+    /// Match jobs belonging to the named user.
+    pub fn user<S: Into<String>>(&mut self, name: S) -> &mut Self {
+        self.users.push(name.into());
+        self
+    }
+
+    /// Match jobs charged to the named account.
+    pub fn account<S: Into<String>>(&mut self, name: S) -> &mut Self {
+        self.accounts.push(name.into());
+        self
+    }
+
+    /// Match the job with the specified ID.
+    pub fn job(&mut self, jid: JobId) -> &mut Self {
+        self.jobs.push(jid);
+        self
+    }
+
+    /// Match jobs that were active at or after the specified time.
+    pub fn usage_start(&mut self, time: DateTime<Utc>) -> &mut Self {
+        self.start = Some(time);
+        self
+    }
+
+    /// Match jobs that were active before the specified time.
+    pub fn usage_end(&mut self, time: DateTime<Utc>) -> &mut Self {
+        self.end = Some(time);
+        self
+    }
+
+    /// Set whether the times of the matching jobs are reported as-is.
+    ///
+    /// By default, the start and end times of jobs that were active across
+    /// the edges of the usage window are clipped to the window, as with
+    /// `sacct --truncate`.
+    pub fn without_usage_truncation(&mut self, value: bool) -> &mut Self {
+        self.without_usage_truncation = value;
+        self
+    }
+
+    /// Convert this query into a set of filters for use with the Slurm C
+    /// library.
+    ///
+    /// User names are converted into numeric user IDs using the local
+    /// password database.
+    #[cfg(feature = "slurmdb")]
+    pub fn to_filters(&self) -> Result<JobFiltersOwned, Error> {
+        let mut filters = JobFiltersOwned::new()?;
+
+        for name in &self.users {
+            filters
+                .userid_list_mut()
+                .append(format!("{}", lookup_uid(name)?))?;
+        }
+
+        for name in &self.accounts {
+            filters.acct_list_mut().append(name)?;
+        }
+
+        for jid in &self.jobs {
+            filters
+                .step_list_mut()
+                .append(JobStepFilterOwned::for_identifier((*jid).into())?);
+        }
+
+        if let Some(t) = self.start {
+            filters.usage_start(t);
+        }
+
+        if let Some(t) = self.end {
+            filters.usage_end(t);
+        }
+
+        if self.without_usage_truncation {
+            filters.without_usage_truncation(true);
+        }
+
+        Ok(filters)
+    }
+}
+
+/// Look up the numeric user ID of the named user.
+///
+/// Numeric names are passed through unchanged, as Slurm's tools do.
+fn lookup_uid(name: &str) -> Result<u32, Error> {
+    if let Ok(uid) = name.parse() {
+        return Ok(uid);
+    }
+
+    let cname = CString::new(name).map_err(|_| parse_err!("illegal user name {:?}", name))?;
+
+    // Use the reentrant variant, since queries may be built on several
+    // threads at once (e.g. by `backend::get_jobs_sharded`).
+    let mut pwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut buf: Vec<c_char> = vec![0; 1024];
+
+    loop {
+        let mut result = std::ptr::null_mut();
+        let rv = unsafe {
+            libc::getpwnam_r(
+                cname.as_ptr(),
+                &mut pwd,
+                buf.as_mut_ptr(),
+                buf.len(),
+                &mut result,
+            )
+        };
+
+        if rv == libc::ERANGE {
+            let new_len = buf.len() * 2;
+            buf.resize(new_len, 0);
+            continue;
+        }
+
+        if rv != 0 {
+            return Err(std::io::Error::from_raw_os_error(rv).into());
+        }
+
+        return if result.is_null() {
+            Err(format_err!("no such user {:?}", name))
+        } else {
+            Ok(pwd.pw_uid)
+        };
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+make_slurm_wrap_struct!(
+    JobStepFilter,
+    slurm_sys::slurmdb_selected_step_t,
+    "A filter for selecting jobs and job steps."
+);
+
+#[cfg(feature = "slurmdb")]
+impl_wrapper_debug!(JobStepFilter);
+
+#[cfg(feature = "slurmdb")]
+make_owned_version!(@customdrop JobStepFilter, JobStepFilterOwned, "An owned version of `JobStepFilter`.");
+
+#[cfg(feature = "slurmdb")]
+impl Drop for JobStepFilterOwned {
+    fn drop(&mut self) {
+        unsafe { slurm_sys::slurmdb_destroy_selected_step((self.0).0 as _) };
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl JobStepFilterOwned {
+    /// Create a new job step filter.
+    pub fn new(jid: JobId) -> Result<Self, Error> {
+        let mut inst = unsafe { Self::alloc_zeroed() }?;
+        {
+            let sdm = inst.sys_data_mut();
+            sdm.array_task_id = slurm_sys::SLURMRS_NO_VAL;
+            #[cfg(slurm_api_selected_step_t_pack_job_offset)]
+            {
+                sdm.pack_job_offset = slurm_sys::SLURMRS_NO_VAL;
+            }
+            #[cfg(slurm_api_selected_step_t_het_job_offset)]
+            {
+                sdm.het_job_offset = slurm_sys::SLURMRS_NO_VAL;
+            }
+            #[cfg(slurm_api_selected_step_t_step_id)]
+            {
+                sdm.step_id.job_id = jid;
+                sdm.step_id.step_het_comp = slurm_sys::SLURMRS_NO_VAL;
+                sdm.step_id.step_id = slurm_sys::SLURMRS_NO_VAL;
+            }
+            #[cfg(not(slurm_api_selected_step_t_step_id))]
+            {
+                sdm.jobid = jid;
+                sdm.stepid = slurm_sys::SLURMRS_NO_VAL;
+            }
+        }
+        Ok(inst)
+    }
+
+    /// Create a new job step filter matching a job that may be an array task
+    /// or heterogeneous job component.
+    ///
+    /// In versions of Slurm that do not support heterogeneous jobs, the
+    /// component offset is ignored.
+    pub fn for_identifier(id: JobIdentifier) -> Result<Self, Error> {
+        let mut inst = Self::new(id.base_id())?;
+
+        match id {
+            JobIdentifier::Plain(_) => {}
+            JobIdentifier::ArrayTask(_, task) => {
+                inst.sys_data_mut().array_task_id = task;
+            }
+            #[cfg(slurm_api_selected_step_t_pack_job_offset)]
+            JobIdentifier::PackComponent(_, offset) => {
+                inst.sys_data_mut().pack_job_offset = offset;
+            }
+            #[cfg(slurm_api_selected_step_t_het_job_offset)]
+            JobIdentifier::PackComponent(_, offset) => {
+                inst.sys_data_mut().het_job_offset = offset;
+            }
+            #[cfg(not(any(
+                slurm_api_selected_step_t_pack_job_offset,
+                slurm_api_selected_step_t_het_job_offset
+            )))]
+            JobIdentifier::PackComponent(_, _) => {}
+        }
+
+        Ok(inst)
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl SlurmList<JobStepFilter> {
+    pub fn append(&mut self, item: JobStepFilterOwned) {
+        let item = unsafe { item.give_up_ownership() };
+
+        if self.0.is_null() {
+            // XXX if malloc fails, I think this function will abort under us.
+            self.0 = unsafe {
+                slurm_sys::slurm_list_create(Some(slurm_sys::slurmdb_destroy_selected_step))
+            };
+        }
+
+        unsafe {
+            slurm_sys::slurm_list_append(self.0, item.0 as _);
+        }
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl_list_collection_traits!(JobStepFilter, JobStepFilterOwned);
+#[cfg(feature = "slurmdb")]
+impl_owned_list_item!(JobStepFilter, JobStepFilterOwned);
+
+#[cfg(feature = "slurmdb")]
+make_slurm_wrap_struct!(
+    JobRecord,
+    slurm_sys::slurmdb_job_rec_t,
+    "\
+Accounting information about a job.
+
+The following items in the Slurm API are *not* exposed in these Rust bindings:
+
+```ignore
+pub struct slurmdb_job_rec_t {
+    pub alloc_gres: *mut c_char,
+    pub array_max_tasks: u32,
+    pub array_task_str: *mut c_char,
+    pub associd: u32,
+    pub blockid: *mut c_char,
+    pub first_step_ptr: *mut c_void,
+    pub gid: u32,
+    pub lft: u32,
     pub pack_job_id: u32,
-    pub pack_job_id_set: *mut c_char,
     pub pack_job_offset: u32,
-    pub pn_min_memory: u64,
-    pub pn_min_cpus: u16,
-    pub pn_min_tmp_disk: u32,
-    pub power_flags: u8,
-    pub preempt_time: time_t,
-    pub pre_sus_time: time_t,
+    pub req_gres: *mut c_char,
+    pub resvid: u32,
+    pub resv_name: *mut c_char,
+    pub show_full: u32,
+    pub steps: List,
+    pub track_steps: u16,
+    pub used_gres: *mut c_char,
+    pub wckeyid: u32,
+}
+```
+
+(The above listing omits fields that would be handled by the
+`JobStepRecordSharedFields` trait.)
+"
+);
+
+#[cfg(feature = "slurmdb")]
+impl_wrapper_debug!(JobRecord; job_id, job_name, user, account, state);
+
+/// The decoded exit status of a job or step.
+///
+/// Slurm records exit statuses in the encoding used by the `wait(2)` family
+/// of system calls. This type separates out the two cases that this encoding
+/// can represent.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum ExitStatus {
+    /// The process exited normally with the specified exit code.
+    Exited(u8),
+
+    /// The process was terminated by the specified signal.
+    Signaled(u8),
+}
+
+impl ExitStatus {
+    /// Decode a raw `wait(2)`-style status value, as stored by Slurm.
+    pub fn from_slurm(raw: u32) -> ExitStatus {
+        // These are the standard WIFSIGNALED/WTERMSIG/WEXITSTATUS semantics.
+        let signal = (raw & 0x7f) as u8;
+
+        if signal != 0 {
+            ExitStatus::Signaled(signal)
+        } else {
+            ExitStatus::Exited(((raw >> 8) & 0xff) as u8)
+        }
+    }
+
+    /// Return true if the process exited normally with a zero exit code.
+    pub fn success(&self) -> bool {
+        *self == ExitStatus::Exited(0)
+    }
+}
+
+impl Display for ExitStatus {
+    /// Format the status in the `code:signal` style used by `sacct`.
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+        match *self {
+            ExitStatus::Exited(code) => write!(f, "{}:0", code),
+            ExitStatus::Signaled(signal) => write!(f, "0:{}", signal),
+        }
+    }
+}
+
+/// The position of the task in which a job/step statistic reached its extreme
+/// value.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct StatsLocation {
+    /// The index of the node, relative to the job/step's allocation.
+    pub node_id: u32,
+
+    /// The ID of the task.
+    pub task_id: u32,
+}
+
+/// Resource usage statistics of a job or step, as gathered by Slurm's
+/// accounting plugins.
+///
+/// Statistics are not available until the job/step has finished running;
+/// until then, the accessors return None.
+#[cfg(feature = "slurmdb")]
+#[derive(Copy, Clone, Debug)]
+pub struct StepStats<'a>(&'a slurm_sys::slurmdb_stats_t);
+
+#[cfg(feature = "slurmdb")]
+macro_rules! impl_stats_maximum {
+    ($max:ident, $ave:ident, $loc:ident, $max_field:ident, $ave_field:ident, $node_field:ident,
+     $task_field:ident, $max_ty:ty, $quantity:tt) => {
+        #[doc = "Get the maximum "]
+        #[doc = $quantity]
+        #[doc = " of any task."]
+        pub fn $max(&self) -> Option<$max_ty> {
+            if self.0.$max_field == slurm_sys::SLURMRS_NO_VAL64 as $max_ty {
+                None
+            } else {
+                Some(self.0.$max_field)
+            }
+        }
+
+        #[doc = "Get the average "]
+        #[doc = $quantity]
+        #[doc = " of all tasks."]
+        pub fn $ave(&self) -> Option<f64> {
+            self.$max().map(|_| self.0.$ave_field as f64)
+        }
+
+        #[doc = "Get the location of the task with the maximum "]
+        #[doc = $quantity]
+        #[doc = "."]
+        pub fn $loc(&self) -> Option<StatsLocation> {
+            self.$max().map(|_| StatsLocation {
+                node_id: self.0.$node_field,
+                task_id: self.0.$task_field,
+            })
+        }
+    };
+}
+
+#[cfg(feature = "slurmdb")]
+impl<'a> StepStats<'a> {
+    impl_stats_maximum!(
+        disk_read_max,
+        disk_read_ave,
+        disk_read_max_location,
+        disk_read_max,
+        disk_read_ave,
+        disk_read_max_nodeid,
+        disk_read_max_taskid,
+        f64,
+        "amount of data read from disk, in megabytes,"
+    );
+    impl_stats_maximum!(
+        disk_write_max,
+        disk_write_ave,
+        disk_write_max_location,
+        disk_write_max,
+        disk_write_ave,
+        disk_write_max_nodeid,
+        disk_write_max_taskid,
+        f64,
+        "amount of data written to disk, in megabytes,"
+    );
+    impl_stats_maximum!(
+        pages_max,
+        pages_ave,
+        pages_max_location,
+        pages_max,
+        pages_ave,
+        pages_max_nodeid,
+        pages_max_taskid,
+        u64,
+        "number of page faults"
+    );
+    impl_stats_maximum!(
+        rss_max,
+        rss_ave,
+        rss_max_location,
+        rss_max,
+        rss_ave,
+        rss_max_nodeid,
+        rss_max_taskid,
+        u64,
+        "resident set size, in kibibytes,"
+    );
+    impl_stats_maximum!(
+        vm_size_max,
+        vm_size_ave,
+        vm_size_max_location,
+        vsize_max,
+        vsize_ave,
+        vsize_max_nodeid,
+        vsize_max_taskid,
+        u64,
+        "virtual memory size, in kibibytes,"
+    );
+
+    /// Get the average actual CPU frequency of the tasks, in kHz.
+    pub fn act_cpu_freq(&self) -> f64 {
+        self.0.act_cpufreq
+    }
+
+    /// Get the energy consumed, in joules, if it was measured.
+    pub fn consumed_energy(&self) -> Option<u64> {
+        match self.0.consumed_energy {
+            slurm_sys::SLURMRS_NO_VAL64 => None,
+            other => Some(other),
+        }
+    }
+
+    /// Get the average CPU time used by the tasks, in seconds.
+    pub fn cpu_ave(&self) -> f64 {
+        self.0.cpu_ave
+    }
+
+    /// Get the minimum CPU time used by any task, in seconds.
+    pub fn cpu_min(&self) -> Option<u32> {
+        match self.0.cpu_min {
+            slurm_sys::SLURMRS_NO_VAL => None,
+            other => Some(other),
+        }
+    }
+
+    /// Get the location of the task that used the least CPU time.
+    pub fn cpu_min_location(&self) -> Option<StatsLocation> {
+        self.cpu_min().map(|_| StatsLocation {
+            node_id: self.0.cpu_min_nodeid,
+            task_id: self.0.cpu_min_taskid,
+        })
+    }
+}
+
+/// A trait for accessing fields common to SlurmDB job records and step
+/// records.
+#[cfg(feature = "slurmdb")]
+pub trait JobStepRecordSharedFields {
+    /// Get the fraction of the job/step's allocated CPU time that it
+    /// actually used.
+    ///
+    /// This is the total CPU time divided by the product of the elapsed time
+    /// and the number of allocated CPUs, as reported by the `seff` tool.
+    /// Returns None if the job/step has not run for any time or the number
+    /// of allocated CPUs is unknown.
+    fn cpu_efficiency(&self) -> Option<f64> {
+        let cpus = match self.tres_alloc().cpus() {
+            Some(0) | None => return None,
+            Some(n) => n,
+        };
+
+        let elapsed = match self.elapsed().num_microseconds() {
+            Some(0) | None => return None,
+            Some(us) => us as f64,
+        };
+
+        self.tot_cpu()
+            .num_microseconds()
+            .map(|us| us as f64 / (elapsed * cpus as f64))
+    }
+
+    /// Get the wallclock time that the job/step has run, excluding any time
+    /// that it spent suspended.
+    fn elapsed(&self) -> Duration;
+
+    /// Get the job/step's end time, or None if it has not yet ended.
+    fn end_time(&self) -> Option<DateTime<Utc>>;
+
+    /// Get the job/step's exit code, or None if it has not yet ended.
+    ///
+    /// This is the raw value recorded by Slurm. Use `exit_status` to decode
+    /// it.
+    fn exit_code(&self) -> Option<i32>;
+
+    /// Get the job/step's decoded exit status, or None if it has not yet
+    /// ended.
+    fn exit_status(&self) -> Option<ExitStatus> {
+        self.exit_code().map(|c| ExitStatus::from_slurm(c as u32))
+    }
+
+    /// Get the maximum "virtual memory size" of the job/step, in kibibytes.
+    ///
+    /// This quantity is not available (i.e., the function returns `None`)
+    /// until the job has finished running.
+    fn max_vm_size(&self) -> Option<u64>;
+
+    /// Get the list of nodes used by the job/step, in Slurm's compressed
+    /// hostlist syntax (e.g. `node[01-04]`), or None if it is not known.
+    fn node_list(&self) -> Option<Cow<str>>;
+
+    /// Get the job/step's start time, or None if it has not yet started.
+    fn start_time(&self) -> Option<DateTime<Utc>>;
+
+    /// Get the job/step's resource usage statistics.
+    fn stats(&self) -> StepStats;
+
+    /// Get the trackable resources allocated to the job/step.
+    ///
+    /// The result is empty if nothing has been allocated yet.
+    fn tres_alloc(&self) -> TresAmounts;
+
+    /// Get the job/step's state.
+    fn state(&self) -> JobState;
+
+    /// Get the flag bits set on top of the job/step's base state.
+    fn state_flags(&self) -> JobStateFlags;
+
+    /// Get the amount of system (kernel) CPU time used by the job/step.
+    fn sys_cpu(&self) -> Duration;
+
+    /// Get the total amount of CPU time used by the job/step.
+    ///
+    /// This is the sum of `user_cpu` and `sys_cpu`.
+    fn tot_cpu(&self) -> Duration;
+
+    /// Get the amount of user CPU time used by the job/step.
+    fn user_cpu(&self) -> Duration;
+
+    /// Get the wallclock time taken by the job/step: end time minus start time.
+    ///
+    /// Returns None if the job/step has not yet completed (or even started).
+    fn wallclock_duration(&self) -> Option<Duration>;
+}
+
+/// We implement the JobStepRecordSharedFields trait with a macro; that seems
+/// like the easiest thing to do.
+///
+/// The complete list of overlapping fields is:
+///
+/// ```ignore
+/// {
+///    elapsed: u32,
+///    end: i64,
+///    exitcode: i32,
+///    nodes: *mut c_char,
+///    requid: u32,
+///    start: i64,
+///    state: u32,
+///    stats: &slurm_sys::slurmdb_stats_t,
+///    suspended: u32,
+///    sys_cpu_sec: u32,
+///    sys_cpu_usec: u32,
+///    tot_cpu_sec: u32,
+///    tot_cpu_usec: u32,
+///    tres_alloc_str: *mut c_char,
+///    user_cpu_sec: u32,
+///    user_cpu_usec: u32,
+/// }
+/// ```
+#[cfg(feature = "slurmdb")]
+macro_rules! impl_job_step_record_shared_fields {
+    ($type:path) => {
+        impl JobStepRecordSharedFields for $type {
+            fn elapsed(&self) -> Duration {
+                Duration::seconds(self.sys_data().elapsed as i64)
+            }
+
+            fn end_time(&self) -> Option<DateTime<Utc>> {
+                match self.sys_data().end as i64 {
+                    0 => None,
+                    t => Some(unix_time(t)),
+                }
+            }
+
+            fn exit_code(&self) -> Option<i32> {
+                match self.sys_data().end as i64 {
+                    0 => None,
+                    _ => Some(self.sys_data().exitcode as i32),
+                }
+            }
+
+            fn max_vm_size(&self) -> Option<u64> {
+                match self.sys_data().stats.vsize_max {
+                    slurm_sys::SLURMRS_NO_VAL64 => None,
+                    other => Some(other),
+                }
+            }
+
+            fn node_list(&self) -> Option<Cow<str>> {
+                unsafe { optional_cstr(self.sys_data().nodes) }
+            }
+
+            fn start_time(&self) -> Option<DateTime<Utc>> {
+                match self.sys_data().start as i64 {
+                    0 => None,
+                    t => Some(unix_time(t)),
+                }
+            }
+
+            fn stats(&self) -> StepStats {
+                StepStats(&self.sys_data().stats)
+            }
+
+            fn tres_alloc(&self) -> TresAmounts {
+                unsafe { TresAmounts::from_slurm(self.sys_data().tres_alloc_str) }
+            }
+
+            fn state(&self) -> JobState {
+                JobState::from_slurm_full(self.sys_data().state)
+            }
+
+            fn state_flags(&self) -> JobStateFlags {
+                JobStateFlags::from_slurm(self.sys_data().state)
+            }
+
+            fn sys_cpu(&self) -> Duration {
+                Duration::seconds(self.sys_data().sys_cpu_sec as i64)
+                    + Duration::microseconds(self.sys_data().sys_cpu_usec as i64)
+            }
+
+            fn tot_cpu(&self) -> Duration {
+                Duration::seconds(self.sys_data().tot_cpu_sec as i64)
+                    + Duration::microseconds(self.sys_data().tot_cpu_usec as i64)
+            }
+
+            fn user_cpu(&self) -> Duration {
+                Duration::seconds(self.sys_data().user_cpu_sec as i64)
+                    + Duration::microseconds(self.sys_data().user_cpu_usec as i64)
+            }
+
+            fn wallclock_duration(&self) -> Option<Duration> {
+                match (self.start_time(), self.end_time()) {
+                    (Some(start), Some(end)) => Some(end.signed_duration_since(start)),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+#[cfg(feature = "slurmdb")]
+impl_job_step_record_shared_fields!(JobRecord);
+
+#[cfg(feature = "slurmdb")]
+impl JobRecord {
+    /// Get the name of the account that the job was charged to, if known.
+    pub fn account(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().account) }
+    }
+
+    /// Get the comment attached to this job by an administrator, if any.
+    pub fn admin_comment(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().admin_comment) }
+    }
+
+    /// Get the comment recorded for this job in the database, if any.
+    ///
+    /// This is what `sacct` reports as the `Comment` field. It is set with
+    /// `sjobexitmod`, or from the submitter's comment if the database is
+    /// configured to store it.
+    pub fn comment(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().derived_es) }
+    }
+
+    /// Get the number of nodes allocated to the job.
+    ///
+    /// This is zero if the job has not yet been allocated any resources.
+    pub fn alloc_nodes(&self) -> u32 {
+        self.sys_data().alloc_nodes
+    }
+
+    /// Get the unique identifier of the array group this job belonged to.
+    ///
+    /// Returns None if this job was not part of an array.
+    pub fn array_job_id(&self) -> Option<JobId> {
+        match self.sys_data().array_job_id {
+            0 => None,
+            other => Some(other),
+        }
+    }
+
+    /// Get the index of this job within its array.
+    ///
+    /// Returns None if this job was not part of an array.
+    pub fn array_task_id(&self) -> Option<u32> {
+        match self.sys_data().array_task_id {
+            slurm_sys::SLURMRS_NO_VAL => None,
+            other => Some(other),
+        }
+    }
+
+    /// Get the name of the cluster that ran the job, if known.
+    pub fn cluster(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().cluster) }
+    }
+
+    /// Get the job's "derived" exit code.
+    ///
+    /// This is the highest exit code of any of the job's steps, unless it
+    /// has been modified by an administrator. It is encoded in the same way
+    /// as the value returned by `exit_code`.
+    pub fn derived_exit_code(&self) -> u32 {
+        self.sys_data().derived_ec
+    }
+
+    /// Get the job's decoded "derived" exit status.
+    pub fn derived_exit_status(&self) -> ExitStatus {
+        ExitStatus::from_slurm(self.derived_exit_code())
+    }
+
+    /// Get the job's "eligible" time, or None if the job is not yet eligible to run.
+    pub fn eligible_time(&self) -> Option<DateTime<Utc>> {
+        match self.sys_data().eligible as i64 {
+            0 => None,
+            t => Some(unix_time(t)),
+        }
+    }
+
+    /// Get the job's ID number.
+    pub fn job_id(&self) -> JobId {
+        self.sys_data().jobid
+    }
+
+    /// Get the job's name.
+    pub fn job_name(&self) -> Cow<str> {
+        unsafe { CStr::from_ptr(self.sys_data().jobname) }.to_string_lossy()
+    }
+
+    /// Get the job's MCS (multi-category security) label, if any.
+    pub fn mcs_label(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().mcs_label) }.filter(|s| !s.is_empty())
+    }
+
+    /// Get the name of the partition that the job ran in, if known.
+    pub fn partition(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().partition) }
+    }
+
+    /// Get the job's scheduling priority.
+    pub fn priority(&self) -> u32 {
+        self.sys_data().priority
+    }
+
+    /// Get the numeric ID of the QOS that the job ran under.
+    ///
+    /// Use `DatabaseConnection::get_qos` to map this to a QOS record.
+    pub fn qos_id(&self) -> u32 {
+        self.sys_data().qosid
+    }
+
+    /// Get the number of CPUs requested by the job.
+    pub fn req_cpus(&self) -> u32 {
+        self.sys_data().req_cpus
+    }
+
+    /// Get the amount of memory requested by the job, if known.
+    pub fn req_mem(&self) -> Option<MemSize> {
+        MemSize::from_slurm(self.sys_data().req_mem)
+    }
+
+    /// Get the job's batch script, if it was stored in the database.
+    ///
+    /// The script is only retrieved if it was requested with
+    /// `JobFiltersOwned::include_script`. This always returns None with
+    /// versions of the C API that predate Slurm 21.08.
+    pub fn script(&self) -> Option<Cow<str>> {
+        #[cfg(slurm_api_job_rec_t_script)]
+        let ptr = self.sys_data().script;
+        #[cfg(not(slurm_api_job_rec_t_script))]
+        let ptr: *mut c_char = 0 as _;
+
+        unsafe { optional_cstr(ptr) }
+    }
+
+    /// Get the trackable resources requested by the job.
+    pub fn tres_req(&self) -> TresAmounts {
+        unsafe { TresAmounts::from_slurm(self.sys_data().tres_req_str) }
+    }
+
+    /// Get the job's submission time.
+    pub fn submit_time(&self) -> DateTime<Utc> {
+        unix_time(self.sys_data().submit as i64)
+    }
+
+    /// Get the wallclock time spent waiting for the job to become eligible,
+    /// or None if the job has not yet become eligible to run.
+    pub fn eligible_wait_duration(&self) -> Option<Duration> {
+        self.eligible_time()
+            .map(|t| t.signed_duration_since(self.submit_time()))
+    }
+
+    /// Get the job's time limit, or None if it was not specified.
+    pub fn time_limit(&self) -> Option<TimeLimit> {
+        TimeLimit::from_slurm(self.sys_data().timelimit)
+    }
+
+    /// Get the numeric ID of the user that ran the job.
+    pub fn uid(&self) -> u32 {
+        self.sys_data().uid
+    }
+
+    /// Get the name of the user that ran the job, if known.
+    pub fn user(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().user) }
+    }
+
+    /// Get the wallclock time spent waiting for the job to start, or None
+    /// if the job has not yet started.
+    ///
+    /// This includes time that the job spent waiting to become eligible to run.
+    pub fn wait_duration(&self) -> Option<Duration> {
+        self.start_time()
+            .map(|t| t.signed_duration_since(self.submit_time()))
+    }
+
+    /// Steps.
+    pub fn steps(&self) -> &SlurmList<StepRecord> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().steps) }
+    }
+
+    /// Get the name of the WCKey associated with the job, if any.
+    pub fn wckey(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().wckey) }
+    }
+
+    /// Get the job's working directory, if known.
+    pub fn work_dir(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().work_dir) }
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+make_owned_version!(@customdrop JobRecord, JobRecordOwned, "An owned version of `JobRecord`.");
+
+#[cfg(feature = "slurmdb")]
+impl Drop for JobRecordOwned {
+    fn drop(&mut self) {
+        unsafe { slurm_sys::slurmdb_destroy_job_rec((self.0).0 as _) };
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl_owned_list_item!(JobRecord, JobRecordOwned);
+
+#[cfg(feature = "slurmdb")]
+make_slurm_wrap_struct!(
+    StepRecord,
+    slurm_sys::slurmdb_step_rec_t,
+    "\
+Accounting information about a step within a job.
+
+The following items in the Slurm API are *not* exposed in these Rust bindings:
+
+```ignore
+pub struct slurmdb_step_rec_t {
+    pub job_ptr: *mut slurmdb_job_rec_t,
+    pub pid_str: *mut c_char,
+}
+```
+
+(The above listing omits fields that would be handled by the
+`JobStepRecordSharedFields` trait.)
+"
+);
+
+#[cfg(feature = "slurmdb")]
+impl_wrapper_debug!(StepRecord; step_id, step_name, state);
+
+#[cfg(feature = "slurmdb")]
+impl_job_step_record_shared_fields!(StepRecord);
+
+/// The node-level method used to distribute the tasks of a job step.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[non_exhaustive]
+pub enum TaskDistribution {
+    /// Tasks are assigned to nodes in a round-robin fashion.
+    Cyclic,
+
+    /// Tasks are assigned to each node in turn until it is full.
+    Block,
+
+    /// Tasks are assigned according to a user-specified node list.
+    Arbitrary,
+
+    /// Tasks are assigned in blocks of a specified size.
+    Plane,
+}
+
+impl TaskDistribution {
+    fn from_slurm(dist: u32) -> Option<TaskDistribution> {
+        // The low nibble of the distribution code encodes the node-level
+        // method; higher bits specify the socket- and core-level methods, as
+        // well as various flags.
+        match dist & 0xF {
+            slurm_sys::task_dist_states_SLURM_DIST_CYCLIC => Some(TaskDistribution::Cyclic),
+            slurm_sys::task_dist_states_SLURM_DIST_BLOCK => Some(TaskDistribution::Block),
+            slurm_sys::task_dist_states_SLURM_DIST_ARBITRARY => Some(TaskDistribution::Arbitrary),
+            slurm_sys::task_dist_states_SLURM_DIST_PLANE => Some(TaskDistribution::Plane),
+            _ => None,
+        }
+    }
+}
+
+/// Convert a CPU frequency setting into an Option.
+///
+/// Zero and `NO_VAL` both indicate that no value was requested.
+#[cfg(feature = "slurmdb")]
+fn optional_cpu_freq(value: u32) -> Option<u32> {
+    match value {
+        0 | slurm_sys::SLURMRS_NO_VAL => None,
+        other => Some(other),
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl StepRecord {
+    /// Get the number of nodes used by the step.
+    pub fn nnodes(&self) -> u32 {
+        self.sys_data().nnodes
+    }
+
+    /// Get the number of tasks launched by the step.
+    pub fn ntasks(&self) -> u32 {
+        self.sys_data().ntasks
+    }
+
+    /// Get the governor requested for the step's CPU frequency, if any.
+    ///
+    /// This is a raw Slurm `CPU_FREQ_*` governor flag value.
+    pub fn req_cpu_freq_gov(&self) -> Option<u32> {
+        optional_cpu_freq(self.sys_data().req_cpufreq_gov)
+    }
+
+    /// Get the maximum CPU frequency requested for the step, if any.
+    ///
+    /// This is either a frequency in kHz, or a special value with the high
+    /// bit set, such as Slurm's `CPU_FREQ_HIGH`.
+    pub fn req_cpu_freq_max(&self) -> Option<u32> {
+        optional_cpu_freq(self.sys_data().req_cpufreq_max)
+    }
+
+    /// Get the minimum CPU frequency requested for the step, if any.
+    ///
+    /// See `req_cpu_freq_max` for the interpretation of the value.
+    pub fn req_cpu_freq_min(&self) -> Option<u32> {
+        optional_cpu_freq(self.sys_data().req_cpufreq_min)
+    }
+
+    /// Get the step's ID.
+    ///
+    /// Slurm 20.11 moved the step ID into a `slurm_step_id_t` structure;
+    /// this works with both layouts.
+    pub fn step_id(&self) -> StepId {
+        #[cfg(slurm_api_step_rec_t_step_id)]
+        {
+            StepId::from(self.sys_data().step_id.step_id)
+        }
+        #[cfg(not(slurm_api_step_rec_t_step_id))]
+        {
+            StepId::from(self.sys_data().stepid)
+        }
+    }
+
+    /// Get the step's name.
+    pub fn step_name(&self) -> Cow<str> {
+        unsafe { CStr::from_ptr(self.sys_data().stepname) }.to_string_lossy()
+    }
+
+    /// Get the node-level method used to distribute the step's tasks, or
+    /// None if it is not known.
+    pub fn task_distribution(&self) -> Option<TaskDistribution> {
+        TaskDistribution::from_slurm(self.sys_data().task_dist)
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+make_owned_version!(@customdrop StepRecord, StepRecordOwned, "An owned version of `StepRecord`.");
+
+#[cfg(feature = "slurmdb")]
+impl Drop for StepRecordOwned {
+    fn drop(&mut self) {
+        unsafe { slurm_sys::slurmdb_destroy_step_rec((self.0).0 as _) };
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl_owned_list_item!(StepRecord, StepRecordOwned);
+
+#[cfg(feature = "slurmdb")]
+impl JobRecord {
+    /// Copy the data in this record into a plain-Rust structure.
+    ///
+    /// The result does not refer to any Slurm memory, so it may outlive the
+    /// query result that contained this record and may be sent to other
+    /// threads. The job's steps are copied as well.
+    pub fn to_snapshot(&self) -> JobRecordSnapshot {
+        JobRecordSnapshot {
+            account: self.account().map(|s| s.into_owned()),
+            alloc_nodes: self.alloc_nodes(),
+            array_job_id: self.array_job_id(),
+            cluster: self.cluster().map(|s| s.into_owned()),
+            derived_exit_code: self.derived_exit_code(),
+            elapsed: self.elapsed(),
+            eligible_time: self.eligible_time(),
+            end_time: self.end_time(),
+            exit_code: self.exit_code(),
+            job_id: self.job_id(),
+            job_name: self.job_name().into_owned(),
+            max_rss: self.stats().rss_max(),
+            max_vm_size: self.max_vm_size(),
+            node_list: self.node_list().map(|s| s.into_owned()),
+            partition: self.partition().map(|s| s.into_owned()),
+            priority: self.priority(),
+            qos_id: self.qos_id(),
+            req_cpus: self.req_cpus(),
+            req_mem: self.req_mem(),
+            start_time: self.start_time(),
+            state: self.state(),
+            state_flags: self.state_flags(),
+            steps: self.steps().iter().map(|s| s.to_snapshot()).collect(),
+            submit_time: self.submit_time(),
+            sys_cpu: self.sys_cpu(),
+            time_limit: self.time_limit(),
+            tot_cpu: self.tot_cpu(),
+            tres_alloc: self.tres_alloc(),
+            tres_req: self.tres_req(),
+            uid: self.uid(),
+            user: self.user().map(|s| s.into_owned()),
+            user_cpu: self.user_cpu(),
+            wckey: self.wckey().map(|s| s.into_owned()),
+            work_dir: self.work_dir().map(|s| s.into_owned()),
+        }
+    }
+}
+
+/// A plain-Rust copy of the data in a `JobRecord`.
+///
+/// See the accessors of `JobRecord` and `JobStepRecordSharedFields` for the
+/// meanings of the fields.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct JobRecordSnapshot {
+    pub account: Option<String>,
+    pub alloc_nodes: u32,
+    pub array_job_id: Option<JobId>,
+    pub cluster: Option<String>,
+    pub derived_exit_code: u32,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_duration_secs"))]
+    pub elapsed: Duration,
+    pub eligible_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub exit_code: Option<i32>,
+    pub job_id: JobId,
+    pub job_name: String,
+    /// The maximum resident set size of any task, in kibibytes.
+    pub max_rss: Option<u64>,
+    pub max_vm_size: Option<u64>,
+    pub node_list: Option<String>,
+    pub partition: Option<String>,
+    pub priority: u32,
+    pub qos_id: u32,
+    pub req_cpus: u32,
+    pub req_mem: Option<MemSize>,
+    pub start_time: Option<DateTime<Utc>>,
+    pub state: JobState,
+    pub state_flags: JobStateFlags,
+    pub steps: Vec<StepRecordSnapshot>,
+    pub submit_time: DateTime<Utc>,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_duration_secs"))]
+    pub sys_cpu: Duration,
+    pub time_limit: Option<TimeLimit>,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_duration_secs"))]
+    pub tot_cpu: Duration,
+    pub tres_alloc: TresAmounts,
+    pub tres_req: TresAmounts,
+    pub uid: u32,
+    pub user: Option<String>,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_duration_secs"))]
+    pub user_cpu: Duration,
+    pub wckey: Option<String>,
+    pub work_dir: Option<String>,
+}
+
+#[cfg(feature = "slurmdb")]
+impl_snapshot_traits!(JobRecord, JobRecordSnapshot);
+
+/// Format an optional value for `scontrol`-style output, which writes
+/// missing values as `(null)`.
+#[cfg(feature = "slurmdb")]
+fn display_or_null<T: Display>(value: Option<T>) -> String {
+    value
+        .map(|v| v.to_string())
+        .unwrap_or_else(|| "(null)".to_owned())
+}
+
+#[cfg(feature = "slurmdb")]
+impl Display for JobRecord {
+    /// Format the record in the `Key=Value` style used by `scontrol show
+    /// job`. Only the fields available in the accounting database are shown.
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+        writeln!(f, "JobId={} JobName={}", self.job_id(), self.job_name())?;
+        writeln!(
+            f,
+            "   UserId={}({}) Account={} QOS={} WCKey={}",
+            display_or_null(self.user()),
+            self.uid(),
+            display_or_null(self.account()),
+            self.qos_id(),
+            display_or_null(self.wckey())
+        )?;
+        writeln!(
+            f,
+            "   Priority={} Cluster={}",
+            self.priority(),
+            display_or_null(self.cluster())
+        )?;
+        writeln!(
+            f,
+            "   JobState={} ExitCode={} DerivedExitCode={}",
+            self.state().slurm_name(),
+            display_or_null(self.exit_status()),
+            self.derived_exit_status()
+        )?;
+        writeln!(
+            f,
+            "   RunTime={} TimeLimit={}",
+            format_duration(self.elapsed()),
+            display_or_null(self.time_limit())
+        )?;
+        writeln!(
+            f,
+            "   SubmitTime={} EligibleTime={}",
+            format_time(Some(self.submit_time())),
+            format_time(self.eligible_time())
+        )?;
+        writeln!(
+            f,
+            "   StartTime={} EndTime={}",
+            format_time(self.start_time()),
+            format_time(self.end_time())
+        )?;
+        writeln!(
+            f,
+            "   Partition={} NodeList={}",
+            display_or_null(self.partition()),
+            display_or_null(self.node_list())
+        )?;
+        writeln!(
+            f,
+            "   NumNodes={} NumCPUs={} MinMemory={}",
+            self.alloc_nodes(),
+            self.req_cpus(),
+            display_or_null(self.req_mem())
+        )?;
+        writeln!(
+            f,
+            "   ReqTRES={} AllocTRES={}",
+            self.tres_req(),
+            self.tres_alloc()
+        )?;
+        write!(f, "   WorkDir={}", display_or_null(self.work_dir()))
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl StepRecord {
+    /// Copy the data in this record into a plain-Rust structure.
+    ///
+    /// The result does not refer to any Slurm memory, so it may outlive the
+    /// query result that contained this record and may be sent to other
+    /// threads.
+    pub fn to_snapshot(&self) -> StepRecordSnapshot {
+        StepRecordSnapshot {
+            elapsed: self.elapsed(),
+            end_time: self.end_time(),
+            exit_code: self.exit_code(),
+            max_rss: self.stats().rss_max(),
+            max_vm_size: self.max_vm_size(),
+            nnodes: self.nnodes(),
+            node_list: self.node_list().map(|s| s.into_owned()),
+            ntasks: self.ntasks(),
+            start_time: self.start_time(),
+            state: self.state(),
+            state_flags: self.state_flags(),
+            step_id: self.step_id(),
+            step_name: self.step_name().into_owned(),
+            sys_cpu: self.sys_cpu(),
+            task_distribution: self.task_distribution(),
+            tot_cpu: self.tot_cpu(),
+            tres_alloc: self.tres_alloc(),
+            user_cpu: self.user_cpu(),
+        }
+    }
+}
+
+/// A plain-Rust copy of the data in a `StepRecord`.
+///
+/// See the accessors of `StepRecord` and `JobStepRecordSharedFields` for the
+/// meanings of the fields.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct StepRecordSnapshot {
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_duration_secs"))]
+    pub elapsed: Duration,
+    pub end_time: Option<DateTime<Utc>>,
+    pub exit_code: Option<i32>,
+    /// The maximum resident set size of any task, in kibibytes.
+    pub max_rss: Option<u64>,
+    pub max_vm_size: Option<u64>,
+    pub nnodes: u32,
+    pub node_list: Option<String>,
+    pub ntasks: u32,
+    pub start_time: Option<DateTime<Utc>>,
+    pub state: JobState,
+    pub state_flags: JobStateFlags,
+    pub step_id: StepId,
+    pub step_name: String,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_duration_secs"))]
+    pub sys_cpu: Duration,
+    pub task_distribution: Option<TaskDistribution>,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_duration_secs"))]
+    pub tot_cpu: Duration,
+    pub tres_alloc: TresAmounts,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_duration_secs"))]
+    pub user_cpu: Duration,
+}
+
+#[cfg(feature = "slurmdb")]
+impl_snapshot_traits!(StepRecord, StepRecordSnapshot);
+
+#[cfg(feature = "slurmdb")]
+impl Display for StepRecord {
+    /// Format the record in the `Key=Value` style used by `scontrol show
+    /// step`. Step records do not know the ID of their job, so only the
+    /// step number is shown.
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+        writeln!(f, "StepId={} StepName={}", self.step_id(), self.step_name())?;
+        writeln!(
+            f,
+            "   State={} ExitCode={}",
+            self.state().slurm_name(),
+            display_or_null(self.exit_status())
+        )?;
+        writeln!(
+            f,
+            "   StartTime={} EndTime={} RunTime={}",
+            format_time(self.start_time()),
+            format_time(self.end_time()),
+            format_duration(self.elapsed())
+        )?;
+        writeln!(
+            f,
+            "   NodeList={} Nodes={} Tasks={}",
+            display_or_null(self.node_list()),
+            self.nnodes(),
+            self.ntasks()
+        )?;
+        write!(f, "   TRES={}", self.tres_alloc())
+    }
+}
+
+/// Serialize a duration as a (possibly fractional) number of seconds.
+///
+/// Chrono does not provide serialization for durations.
+#[cfg(feature = "serde")]
+fn serialize_duration_secs<S: Serializer>(d: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    let secs = match d.num_microseconds() {
+        Some(us) => us as f64 * 1e-6,
+        None => d.num_seconds() as f64,
+    };
+    serializer.serialize_f64(secs)
+}
+
+#[cfg(feature = "slurmdb")]
+make_slurm_wrap_struct!(
+    AccountFilters,
+    slurm_sys::slurmdb_account_cond_t,
+    "\
+A set of filters for identifying accounts of interest when querying the Slurm
+accounting database.
+
+The following items in the Slurm API are *not* exposed in these Rust bindings:
+
+```ignore
+pub struct slurmdb_account_cond_t {
+    pub with_coords: u16,
+}
+```
+
+The embedded `assoc_cond` association filter is only exposed through the
+`acct_list` accessors.
+"
+);
+
+#[cfg(feature = "slurmdb")]
+impl_wrapper_debug!(AccountFilters);
+
+#[cfg(feature = "slurmdb")]
+impl AccountFilters {
+    /// Access the list of account names that will match this set of filters.
+    pub fn acct_list(&self) -> &SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr(&(*self.sys_data().assoc_cond).acct_list) }
+    }
+
+    /// Mutably access the list of account names that will match this set of
+    /// filters.
+    pub fn acct_list_mut(&mut self) -> &mut SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr_mut(&mut (*self.sys_data_mut().assoc_cond).acct_list) }
+    }
+
+    /// Access the list of account descriptions that will match this set of
+    /// filters.
+    pub fn description_list(&self) -> &SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().description_list) }
+    }
+
+    /// Mutably access the list of account descriptions that will match this
+    /// set of filters.
+    pub fn description_list_mut(&mut self) -> &mut SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr_mut(&mut self.sys_data_mut().description_list) }
+    }
+
+    /// Access the list of organization names that will match this set of
+    /// filters.
+    pub fn organization_list(&self) -> &SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().organization_list) }
+    }
+
+    /// Mutably access the list of organization names that will match this set
+    /// of filters.
+    pub fn organization_list_mut(&mut self) -> &mut SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr_mut(&mut self.sys_data_mut().organization_list) }
+    }
+
+    /// Specify whether the query should return the associations of each
+    /// account.
+    pub fn with_assocs(&mut self, value: bool) -> &mut Self {
+        self.sys_data_mut().with_assocs = value as u16;
+        self
+    }
+
+    /// Specify whether the query should include deleted accounts.
+    pub fn with_deleted(&mut self, value: bool) -> &mut Self {
+        self.sys_data_mut().with_deleted = value as u16;
+        self
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+make_owned_version!(@customdrop AccountFilters, AccountFiltersOwned, "An owned version of `AccountFilters`.");
+
+#[cfg(feature = "slurmdb")]
+impl AccountFiltersOwned {
+    /// Create a new, empty set of filters.
+    pub fn new() -> Result<Self, Error> {
+        let mut inst = unsafe { Self::alloc_zeroed() }?;
+        inst.sys_data_mut().assoc_cond = slurm_alloc()?;
+        Ok(inst)
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl Drop for AccountFiltersOwned {
+    fn drop(&mut self) {
+        // This frees the association filter and sub-lists as well.
+        unsafe { slurm_sys::slurmdb_destroy_account_cond((self.0).0 as _) };
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+make_slurm_wrap_struct!(
+    AccountRecord,
+    slurm_sys::slurmdb_account_rec_t,
+    "\
+Accounting information about an account.
+
+The following items in the Slurm API are *not* exposed in these Rust bindings:
+
+```ignore
+pub struct slurmdb_account_rec_t {
+    pub coordinators: List,
+}
+```
+
+"
+);
+
+#[cfg(feature = "slurmdb")]
+impl_wrapper_debug!(AccountRecord; name, description, organization);
+
+#[cfg(feature = "slurmdb")]
+impl AccountRecord {
+    /// Get the associations of this account.
+    ///
+    /// This list will be empty unless the query asked for associations using
+    /// `AccountFilters::with_assocs`.
+    pub fn associations(&self) -> &SlurmList<AssociationRecord> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().assoc_list) }
+    }
+
+    /// Get the account's description, if it has one.
+    pub fn description(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().description) }
+    }
+
+    /// Get the account's name.
+    ///
+    /// This is empty for records that are used to describe modifications.
+    pub fn name(&self) -> Cow<str> {
+        unsafe { optional_cstr(self.sys_data().name) }.unwrap_or(Cow::Borrowed(""))
+    }
+
+    /// Get the organization that the account belongs to, if it is set.
+    pub fn organization(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().organization) }
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+make_owned_version!(@customdrop AccountRecord, AccountRecordOwned, "An owned version of `AccountRecord`.");
+
+#[cfg(feature = "slurmdb")]
+impl AccountRecordOwned {
+    /// Create a new account record with the specified name, suitable for
+    /// adding to the database with `DatabaseConnection::add_accounts`.
+    pub fn new<S: AsRef<str>>(name: S) -> Result<Self, Error> {
+        let mut inst = Self::empty()?;
+        inst.sys_data_mut().name = slurm_alloc_utf8_string(name)?;
+        Ok(inst)
+    }
+
+    /// Set the account's description.
+    pub fn set_description<S: AsRef<str>>(&mut self, description: S) -> Result<&mut Self, Error> {
+        slurm_replace_utf8_string(&mut self.sys_data_mut().description, description)?;
+        Ok(self)
+    }
+
+    /// Set the organization that the account belongs to.
+    pub fn set_organization<S: AsRef<str>>(&mut self, organization: S) -> Result<&mut Self, Error> {
+        slurm_replace_utf8_string(&mut self.sys_data_mut().organization, organization)?;
+        Ok(self)
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl AccountRecordOwned {
+    /// Create an empty account record. In this form, the record is suitable
+    /// for describing changes with `DatabaseConnection::modify_accounts`:
+    /// only the fields that are set will be modified.
+    pub fn empty() -> Result<Self, Error> {
+        Ok(unsafe { Self::alloc_zeroed() }?)
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl Drop for AccountRecordOwned {
+    fn drop(&mut self) {
+        unsafe { slurm_sys::slurmdb_destroy_account_rec((self.0).0 as _) };
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl SlurmList<AccountRecord> {
+    pub fn append(&mut self, item: AccountRecordOwned) {
+        let item = unsafe { item.give_up_ownership() };
+
+        if self.0.is_null() {
+            self.0 = unsafe {
+                slurm_sys::slurm_list_create(Some(slurm_sys::slurmdb_destroy_account_rec))
+            };
+        }
+
+        unsafe {
+            slurm_sys::slurm_list_append(self.0, item.0 as _);
+        }
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl_list_collection_traits!(AccountRecord, AccountRecordOwned);
+#[cfg(feature = "slurmdb")]
+impl_owned_list_item!(AccountRecord, AccountRecordOwned);
+
+#[cfg(feature = "slurmdb")]
+impl AccountRecord {
+    /// Copy the data in this record into a plain-Rust structure.
+    ///
+    /// The result does not refer to any Slurm memory, so it may outlive the
+    /// query result that contained this record. The account's associations
+    /// are copied as well.
+    pub fn to_snapshot(&self) -> AccountRecordSnapshot {
+        AccountRecordSnapshot {
+            associations: self
+                .associations()
+                .iter()
+                .map(|a| a.to_snapshot())
+                .collect(),
+            description: self.description().map(|s| s.into_owned()),
+            name: self.name().into_owned(),
+            organization: self.organization().map(|s| s.into_owned()),
+        }
+    }
+}
+
+/// A plain-Rust copy of the data in an `AccountRecord`.
+///
+/// See the accessors of `AccountRecord` for the meanings of the fields.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct AccountRecordSnapshot {
+    pub associations: Vec<AssociationRecordSnapshot>,
+    pub description: Option<String>,
+    pub name: String,
+    pub organization: Option<String>,
+}
+
+#[cfg(feature = "slurmdb")]
+impl_snapshot_traits!(AccountRecord, AccountRecordSnapshot);
+
+/// The administrative privilege levels that a user can have in the
+/// accounting database.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[non_exhaustive]
+pub enum AdminLevel {
+    /// The level has not been specified.
+    NotSet,
+
+    /// The user has no administrative privileges.
+    None,
+
+    /// The user may perform operator tasks such as adding accounts and users.
+    Operator,
+
+    /// The user may do anything.
+    SuperUser,
+
+    /// Some other level, identified by its raw Slurm code.
+    Other(u16),
+}
+
+impl AdminLevel {
+    fn from_slurm(level: u16) -> AdminLevel {
+        match level as u32 {
+            slurm_sys::slurmdb_admin_level_t_SLURMDB_ADMIN_NOTSET => AdminLevel::NotSet,
+            slurm_sys::slurmdb_admin_level_t_SLURMDB_ADMIN_NONE => AdminLevel::None,
+            slurm_sys::slurmdb_admin_level_t_SLURMDB_ADMIN_OPERATOR => AdminLevel::Operator,
+            slurm_sys::slurmdb_admin_level_t_SLURMDB_ADMIN_SUPER_USER => AdminLevel::SuperUser,
+            _ => AdminLevel::Other(level),
+        }
+    }
+
+    #[allow(unused)]
+    fn to_slurm(&self) -> u16 {
+        match self {
+            &AdminLevel::NotSet => slurm_sys::slurmdb_admin_level_t_SLURMDB_ADMIN_NOTSET as u16,
+            &AdminLevel::None => slurm_sys::slurmdb_admin_level_t_SLURMDB_ADMIN_NONE as u16,
+            &AdminLevel::Operator => slurm_sys::slurmdb_admin_level_t_SLURMDB_ADMIN_OPERATOR as u16,
+            &AdminLevel::SuperUser => {
+                slurm_sys::slurmdb_admin_level_t_SLURMDB_ADMIN_SUPER_USER as u16
+            }
+            &AdminLevel::Other(code) => code,
+        }
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+make_slurm_wrap_struct!(
+    UserFilters,
+    slurm_sys::slurmdb_user_cond_t,
+    "\
+A set of filters for identifying users of interest when querying the Slurm
+accounting database.
+
+The following items in the Slurm API are *not* exposed in these Rust bindings:
+
+```ignore
+pub struct slurmdb_user_cond_t {
+    pub def_wckey_list: List,
+    pub with_coords: u16,
+    pub with_wckeys: u16,
+}
+```
+
+The embedded `assoc_cond` association filter is only exposed through the
+`user_list` accessors.
+"
+);
+
+#[cfg(feature = "slurmdb")]
+impl_wrapper_debug!(UserFilters);
+
+#[cfg(feature = "slurmdb")]
+impl UserFilters {
+    /// Only match users with the specified administrative level.
+    pub fn admin_level(&mut self, level: AdminLevel) -> &mut Self {
+        self.sys_data_mut().admin_level = level.to_slurm();
+        self
+    }
+
+    /// Access the list of default account names that will match this set of
+    /// filters.
+    pub fn def_acct_list(&self) -> &SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().def_acct_list) }
+    }
+
+    /// Mutably access the list of default account names that will match this
+    /// set of filters.
+    pub fn def_acct_list_mut(&mut self) -> &mut SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr_mut(&mut self.sys_data_mut().def_acct_list) }
+    }
+
+    /// Access the list of user names that will match this set of filters.
+    pub fn user_list(&self) -> &SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr(&(*self.sys_data().assoc_cond).user_list) }
+    }
+
+    /// Mutably access the list of user names that will match this set of
+    /// filters.
+    pub fn user_list_mut(&mut self) -> &mut SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr_mut(&mut (*self.sys_data_mut().assoc_cond).user_list) }
+    }
+
+    /// Specify whether the query should return the associations of each user.
+    pub fn with_assocs(&mut self, value: bool) -> &mut Self {
+        self.sys_data_mut().with_assocs = value as u16;
+        self
+    }
+
+    /// Specify whether the query should include deleted users.
+    pub fn with_deleted(&mut self, value: bool) -> &mut Self {
+        self.sys_data_mut().with_deleted = value as u16;
+        self
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+make_owned_version!(@customdrop UserFilters, UserFiltersOwned, "An owned version of `UserFilters`.");
+
+#[cfg(feature = "slurmdb")]
+impl UserFiltersOwned {
+    /// Create a new, empty set of filters.
+    pub fn new() -> Result<Self, Error> {
+        let mut inst = unsafe { Self::alloc_zeroed() }?;
+        inst.sys_data_mut().assoc_cond = slurm_alloc()?;
+        Ok(inst)
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl Drop for UserFiltersOwned {
+    fn drop(&mut self) {
+        // This frees the association filter and sub-lists as well.
+        unsafe { slurm_sys::slurmdb_destroy_user_cond((self.0).0 as _) };
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+make_slurm_wrap_struct!(
+    UserRecord,
+    slurm_sys::slurmdb_user_rec_t,
+    "\
+Accounting information about a user.
+
+The following items in the Slurm API are *not* exposed in these Rust bindings:
+
+```ignore
+pub struct slurmdb_user_rec_t {
+    pub coord_accts: List,
+    pub default_wckey: *mut c_char,
+    pub old_name: *mut c_char,
+    pub wckey_list: List,
+}
+```
+
+"
+);
+
+#[cfg(feature = "slurmdb")]
+impl_wrapper_debug!(UserRecord; name, uid, default_account, admin_level);
+
+#[cfg(feature = "slurmdb")]
+impl UserRecord {
+    /// Get the user's administrative privilege level.
+    pub fn admin_level(&self) -> AdminLevel {
+        AdminLevel::from_slurm(self.sys_data().admin_level)
+    }
+
+    /// Get the associations of this user.
+    ///
+    /// This list will be empty unless the query asked for associations using
+    /// `UserFilters::with_assocs`.
+    pub fn associations(&self) -> &SlurmList<AssociationRecord> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().assoc_list) }
+    }
+
+    /// Get the name of the user's default account, if one is set.
+    pub fn default_account(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().default_acct) }
+    }
+
+    /// Get the user's name.
+    ///
+    /// This is empty for records that are used to describe modifications.
+    pub fn name(&self) -> Cow<str> {
+        unsafe { optional_cstr(self.sys_data().name) }.unwrap_or(Cow::Borrowed(""))
+    }
+
+    /// Get the user's numeric UID.
+    pub fn uid(&self) -> u32 {
+        self.sys_data().uid
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+make_owned_version!(@customdrop UserRecord, UserRecordOwned, "An owned version of `UserRecord`.");
+
+#[cfg(feature = "slurmdb")]
+impl UserRecordOwned {
+    /// Create a new user record with the specified name, suitable for adding
+    /// to the database with `DatabaseConnection::add_users`.
+    pub fn new<S: AsRef<str>>(name: S) -> Result<Self, Error> {
+        let mut inst = Self::empty()?;
+        inst.sys_data_mut().name = slurm_alloc_utf8_string(name)?;
+        Ok(inst)
+    }
+
+    /// Set the user's administrative privilege level.
+    pub fn set_admin_level(&mut self, level: AdminLevel) -> &mut Self {
+        self.sys_data_mut().admin_level = level.to_slurm();
+        self
+    }
+
+    /// Set the name of the user's default account.
+    pub fn set_default_account<S: AsRef<str>>(&mut self, account: S) -> Result<&mut Self, Error> {
+        slurm_replace_utf8_string(&mut self.sys_data_mut().default_acct, account)?;
+        Ok(self)
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl UserRecordOwned {
+    /// Create an empty user record. In this form, the record is suitable for
+    /// describing changes with `DatabaseConnection::modify_users`: only the
+    /// fields that are set will be modified.
+    pub fn empty() -> Result<Self, Error> {
+        let mut inst = unsafe { Self::alloc_zeroed() }?;
+        inst.sys_data_mut().uid = slurm_sys::SLURMRS_NO_VAL;
+        Ok(inst)
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl Drop for UserRecordOwned {
+    fn drop(&mut self) {
+        unsafe { slurm_sys::slurmdb_destroy_user_rec((self.0).0 as _) };
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl SlurmList<UserRecord> {
+    pub fn append(&mut self, item: UserRecordOwned) {
+        let item = unsafe { item.give_up_ownership() };
+
+        if self.0.is_null() {
+            self.0 =
+                unsafe { slurm_sys::slurm_list_create(Some(slurm_sys::slurmdb_destroy_user_rec)) };
+        }
+
+        unsafe {
+            slurm_sys::slurm_list_append(self.0, item.0 as _);
+        }
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl_list_collection_traits!(UserRecord, UserRecordOwned);
+#[cfg(feature = "slurmdb")]
+impl_owned_list_item!(UserRecord, UserRecordOwned);
+
+#[cfg(feature = "slurmdb")]
+impl UserRecord {
+    /// Copy the data in this record into a plain-Rust structure.
+    ///
+    /// The result does not refer to any Slurm memory, so it may outlive the
+    /// query result that contained this record. The user's associations
+    /// are copied as well.
+    pub fn to_snapshot(&self) -> UserRecordSnapshot {
+        UserRecordSnapshot {
+            admin_level: self.admin_level(),
+            associations: self
+                .associations()
+                .iter()
+                .map(|a| a.to_snapshot())
+                .collect(),
+            default_account: self.default_account().map(|s| s.into_owned()),
+            name: self.name().into_owned(),
+            uid: self.uid(),
+        }
+    }
+}
+
+/// A plain-Rust copy of the data in a `UserRecord`.
+///
+/// See the accessors of `UserRecord` for the meanings of the fields.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct UserRecordSnapshot {
+    pub admin_level: AdminLevel,
+    pub associations: Vec<AssociationRecordSnapshot>,
+    pub default_account: Option<String>,
+    pub name: String,
+    pub uid: u32,
+}
+
+#[cfg(feature = "slurmdb")]
+impl_snapshot_traits!(UserRecord, UserRecordSnapshot);
+
+#[cfg(feature = "slurmdb")]
+make_slurm_wrap_struct!(
+    AssociationFilters,
+    slurm_sys::slurmdb_assoc_cond_t,
+    "\
+A set of filters for identifying associations of interest when querying the
+Slurm accounting database.
+
+The following items in the Slurm API are *not* exposed in these Rust bindings:
+
+```ignore
+pub struct slurmdb_assoc_cond_t {
+    pub def_qos_id_list: List,
+    pub format_list: List,
+    pub id_list: List,
+    pub parent_acct_list: List,
+    pub qos_list: List,
+    pub usage_end: time_t,
+    pub usage_start: time_t,
+    pub with_usage: u16,
+    pub with_raw_qos: u16,
+    pub without_parent_info: u16,
+    pub without_parent_limits: u16,
+}
+```
+
+"
+);
+
+#[cfg(feature = "slurmdb")]
+impl_wrapper_debug!(AssociationFilters);
+
+#[cfg(feature = "slurmdb")]
+impl AssociationFilters {
+    /// Access the list of account names that will match this set of filters.
+    pub fn acct_list(&self) -> &SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().acct_list) }
+    }
+
+    /// Mutably access the list of account names that will match this set of
+    /// filters.
+    pub fn acct_list_mut(&mut self) -> &mut SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr_mut(&mut self.sys_data_mut().acct_list) }
+    }
+
+    /// Access the list of cluster names that will match this set of filters.
+    pub fn cluster_list(&self) -> &SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().cluster_list) }
+    }
+
+    /// Mutably access the list of cluster names that will match this set of
+    /// filters.
+    pub fn cluster_list_mut(&mut self) -> &mut SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr_mut(&mut self.sys_data_mut().cluster_list) }
+    }
+
+    /// Specify whether to only match the default associations of each user.
+    pub fn only_defs(&mut self, value: bool) -> &mut Self {
+        self.sys_data_mut().only_defs = value as u16;
+        self
+    }
+
+    /// Access the list of partition names that will match this set of
+    /// filters.
+    pub fn partition_list(&self) -> &SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().partition_list) }
+    }
+
+    /// Mutably access the list of partition names that will match this set of
+    /// filters.
+    pub fn partition_list_mut(&mut self) -> &mut SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr_mut(&mut self.sys_data_mut().partition_list) }
+    }
+
+    /// Access the list of user names that will match this set of filters.
+    pub fn user_list(&self) -> &SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().user_list) }
+    }
+
+    /// Mutably access the list of user names that will match this set of
+    /// filters.
+    pub fn user_list_mut(&mut self) -> &mut SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr_mut(&mut self.sys_data_mut().user_list) }
+    }
+
+    /// Specify whether the query should include deleted associations.
+    pub fn with_deleted(&mut self, value: bool) -> &mut Self {
+        self.sys_data_mut().with_deleted = value as u16;
+        self
+    }
+
+    /// Specify whether the query should include the associations of
+    /// sub-accounts of the accounts that are matched.
+    pub fn with_sub_accts(&mut self, value: bool) -> &mut Self {
+        self.sys_data_mut().with_sub_accts = value as u16;
+        self
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+make_owned_version!(@customdrop AssociationFilters, AssociationFiltersOwned, "An owned version of `AssociationFilters`.");
+
+#[cfg(feature = "slurmdb")]
+impl AssociationFiltersOwned {
+    /// Create a new, empty set of filters.
+    pub fn new() -> Result<Self, Error> {
+        Ok(unsafe { Self::alloc_zeroed() }?)
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl Drop for AssociationFiltersOwned {
+    fn drop(&mut self) {
+        unsafe { slurm_sys::slurmdb_destroy_assoc_cond((self.0).0 as _) };
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+make_slurm_wrap_struct!(
+    AssociationRecord,
+    slurm_sys::slurmdb_assoc_rec_t,
+    "\
+Accounting information about an association: a tuple of cluster, account,
+user, and (optionally) partition to which usage limits may be attached.
+
+Methods returning limits return None if the limit is not set. Limits on
+“TRES” (trackable resources) are returned as the textual representations
+used by Slurm, in which the resources are identified by their numeric TRES
+IDs: for instance, `1=16,4=2` for 16 CPUs and 2 nodes.
+
+The following items in the Slurm API are *not* exposed in these Rust bindings:
+
+```ignore
+pub struct slurmdb_assoc_rec_t {
+    pub accounting_list: List,
+    pub assoc_next: *mut slurmdb_assoc_rec_t,
+    pub assoc_next_id: *mut slurmdb_assoc_rec_t,
+    pub def_qos_id: u32,
+    pub grp_tres_ctld: *mut u64,
+    pub grp_tres_mins_ctld: *mut u64,
+    pub grp_tres_run_mins_ctld: *mut u64,
+    pub is_def: u16,
+    pub lft: u32,
+    pub max_tres_mins_ctld: *mut u64,
+    pub max_tres_run_mins_ctld: *mut u64,
+    pub max_tres_ctld: *mut u64,
+    pub max_tres_pn_ctld: *mut u64,
+    pub parent_id: u32,
+    pub rgt: u32,
+    pub uid: u32,
+    pub usage: *mut slurmdb_assoc_usage_t,
+}
+```
+
+"
+);
+
+#[cfg(feature = "slurmdb")]
+impl_wrapper_debug!(AssociationRecord; id, cluster, account, user, partition);
+
+#[cfg(feature = "slurmdb")]
+impl AssociationRecord {
+    /// Get the name of the account of this association.
+    pub fn account(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().acct) }
+    }
+
+    /// Get the name of the cluster of this association.
+    pub fn cluster(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().cluster) }
+    }
+
+    /// Get the maximum number of jobs that may run at once across all users
+    /// of this association and its children.
+    pub fn grp_jobs(&self) -> Option<u32> {
+        optional_limit(self.sys_data().grp_jobs)
+    }
+
+    /// Get the maximum number of jobs that may be pending or running at once
+    /// across all users of this association and its children.
+    pub fn grp_submit_jobs(&self) -> Option<u32> {
+        optional_limit(self.sys_data().grp_submit_jobs)
+    }
+
+    /// Get the limit on TRES that may be in use at once across all users of
+    /// this association and its children.
+    pub fn grp_tres(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().grp_tres) }
+    }
+
+    /// Get the limit on TRES-minutes that may be consumed in total across
+    /// all users of this association and its children.
+    pub fn grp_tres_mins(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().grp_tres_mins) }
+    }
+
+    /// Get the limit on TRES-minutes that may be allocated to running jobs
+    /// at once across all users of this association and its children.
+    pub fn grp_tres_run_mins(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().grp_tres_run_mins) }
+    }
+
+    /// Get the limit on the wallclock time, in minutes, that may be allocated
+    /// to running jobs at once across this association and its children.
+    pub fn grp_wall(&self) -> Option<u32> {
+        optional_limit(self.sys_data().grp_wall)
+    }
+
+    /// Get the unique ID number of this association.
+    pub fn id(&self) -> u32 {
+        self.sys_data().id
+    }
+
+    /// Get the maximum number of jobs that each user of this association may
+    /// run at once.
+    pub fn max_jobs(&self) -> Option<u32> {
+        optional_limit(self.sys_data().max_jobs)
+    }
+
+    /// Get the maximum number of jobs that each user of this association may
+    /// have pending or running at once.
+    pub fn max_submit_jobs(&self) -> Option<u32> {
+        optional_limit(self.sys_data().max_submit_jobs)
+    }
+
+    /// Get the limit on TRES-minutes that each job may consume.
+    pub fn max_tres_mins_per_job(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().max_tres_mins_pj) }
+    }
+
+    /// Get the limit on TRES-minutes that may be allocated to the running
+    /// jobs of each user at once.
+    pub fn max_tres_run_mins(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().max_tres_run_mins) }
+    }
+
+    /// Get the limit on TRES that each job may use.
+    pub fn max_tres_per_job(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().max_tres_pj) }
+    }
+
+    /// Get the limit on TRES that each job may use on each node.
+    pub fn max_tres_per_node(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().max_tres_pn) }
+    }
+
+    /// Get the maximum wallclock time limit, in minutes, of each job.
+    pub fn max_wall_per_job(&self) -> Option<u32> {
+        optional_limit(self.sys_data().max_wall_pj)
+    }
+
+    /// Get the name of the parent account of this association.
+    pub fn parent_account(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().parent_acct) }
+    }
+
+    /// Get the name of the partition of this association, if it is specific
+    /// to one partition.
+    pub fn partition(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().partition) }
+    }
+
+    /// Get the list of QOS that jobs running under this association may use.
+    ///
+    /// Note that the items in this list are textual representations of
+    /// *numeric* QOS IDs.
+    pub fn qos_list(&self) -> &SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().qos_list) }
+    }
+
+    /// Get the raw number of fairshare “shares” assigned to this
+    /// association.
+    ///
+    /// The special value `0x7FFFFFFF` (Slurm’s `SLURMDB_FS_USE_PARENT`)
+    /// indicates that the association uses the fairshare of its parent.
+    pub fn shares_raw(&self) -> u32 {
+        self.sys_data().shares_raw
+    }
+
+    /// Get the name of the user of this association.
+    ///
+    /// This is None for the associations that describe accounts themselves.
+    pub fn user(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().user) }
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+make_owned_version!(@customdrop AssociationRecord, AssociationRecordOwned, "An owned version of `AssociationRecord`.");
+
+/// Limits are set using Options: None clears the limit.
+#[cfg(feature = "slurmdb")]
+fn limit_to_slurm(value: Option<u32>) -> u32 {
+    value.unwrap_or(slurm_sys::SLURMRS_INFINITE)
+}
+
+#[cfg(feature = "slurmdb")]
+impl AssociationRecordOwned {
+    /// Create a new association record.
+    ///
+    /// All of the fields of the record start out unset, so that it can be
+    /// used either to describe a new association for
+    /// `DatabaseConnection::add_associations`, or a set of changes for
+    /// `DatabaseConnection::modify_associations`. In the latter case, only
+    /// the fields that are set are modified.
+    ///
+    /// The numeric limit setters take Options: passing None *clears* the
+    /// limit in question, while leaving a limit unset leaves it unchanged.
+    /// TRES limits must be expressed using numeric TRES IDs, e.g. `1=16,4=2`;
+    /// use `DatabaseConnection::get_tres` to look them up. To clear the limit
+    /// on a particular TRES, give it a value of -1, e.g. `1=-1`.
+    pub fn new() -> Result<Self, Error> {
+        let inst = unsafe { Self::alloc_zeroed() }?;
+        unsafe { slurm_sys::slurmdb_init_assoc_rec((inst.0).0, false) };
+        Ok(inst)
+    }
+
+    /// Set the name of the account of this association.
+    pub fn set_account<S: AsRef<str>>(&mut self, account: S) -> Result<&mut Self, Error> {
+        slurm_replace_utf8_string(&mut self.sys_data_mut().acct, account)?;
+        Ok(self)
+    }
+
+    /// Set the name of the cluster of this association.
+    pub fn set_cluster<S: AsRef<str>>(&mut self, cluster: S) -> Result<&mut Self, Error> {
+        slurm_replace_utf8_string(&mut self.sys_data_mut().cluster, cluster)?;
+        Ok(self)
+    }
+
+    /// Set the maximum number of jobs that may run at once across all users
+    /// of this association and its children.
+    pub fn set_grp_jobs(&mut self, value: Option<u32>) -> &mut Self {
+        self.sys_data_mut().grp_jobs = limit_to_slurm(value);
+        self
+    }
+
+    /// Set the maximum number of jobs that may be pending or running at once
+    /// across all users of this association and its children.
+    pub fn set_grp_submit_jobs(&mut self, value: Option<u32>) -> &mut Self {
+        self.sys_data_mut().grp_submit_jobs = limit_to_slurm(value);
+        self
+    }
+
+    /// Set the limit on TRES that may be in use at once across all users of
+    /// this association and its children.
+    pub fn set_grp_tres<S: AsRef<str>>(&mut self, value: S) -> Result<&mut Self, Error> {
+        slurm_replace_utf8_string(&mut self.sys_data_mut().grp_tres, value)?;
+        Ok(self)
+    }
+
+    /// Set the limit on the wallclock time, in minutes, that may be
+    /// allocated to running jobs at once across this association and its
+    /// children.
+    pub fn set_grp_wall(&mut self, value: Option<u32>) -> &mut Self {
+        self.sys_data_mut().grp_wall = limit_to_slurm(value);
+        self
+    }
+
+    /// Set the maximum number of jobs that each user of this association may
+    /// run at once.
+    pub fn set_max_jobs(&mut self, value: Option<u32>) -> &mut Self {
+        self.sys_data_mut().max_jobs = limit_to_slurm(value);
+        self
+    }
+
+    /// Set the maximum number of jobs that each user of this association may
+    /// have pending or running at once.
+    pub fn set_max_submit_jobs(&mut self, value: Option<u32>) -> &mut Self {
+        self.sys_data_mut().max_submit_jobs = limit_to_slurm(value);
+        self
+    }
+
+    /// Set the limit on TRES-minutes that each job may consume.
+    pub fn set_max_tres_mins_per_job<S: AsRef<str>>(
+        &mut self,
+        value: S,
+    ) -> Result<&mut Self, Error> {
+        slurm_replace_utf8_string(&mut self.sys_data_mut().max_tres_mins_pj, value)?;
+        Ok(self)
+    }
+
+    /// Set the limit on TRES-minutes that may be allocated to the running
+    /// jobs of each user at once.
+    pub fn set_max_tres_run_mins<S: AsRef<str>>(&mut self, value: S) -> Result<&mut Self, Error> {
+        slurm_replace_utf8_string(&mut self.sys_data_mut().max_tres_run_mins, value)?;
+        Ok(self)
+    }
+
+    /// Set the limit on TRES that each job may use.
+    ///
+    /// Note that Slurm does not support per-user TRES limits on associations;
+    /// those can only be set on QOS definitions.
+    pub fn set_max_tres_per_job<S: AsRef<str>>(&mut self, value: S) -> Result<&mut Self, Error> {
+        slurm_replace_utf8_string(&mut self.sys_data_mut().max_tres_pj, value)?;
+        Ok(self)
+    }
+
+    /// Set the limit on TRES that each job may use on each node.
+    pub fn set_max_tres_per_node<S: AsRef<str>>(&mut self, value: S) -> Result<&mut Self, Error> {
+        slurm_replace_utf8_string(&mut self.sys_data_mut().max_tres_pn, value)?;
+        Ok(self)
+    }
+
+    /// Set the maximum wallclock time limit, in minutes, of each job.
+    pub fn set_max_wall_per_job(&mut self, value: Option<u32>) -> &mut Self {
+        self.sys_data_mut().max_wall_pj = limit_to_slurm(value);
+        self
+    }
+
+    /// Set the name of the parent account of this association.
+    pub fn set_parent_account<S: AsRef<str>>(&mut self, account: S) -> Result<&mut Self, Error> {
+        slurm_replace_utf8_string(&mut self.sys_data_mut().parent_acct, account)?;
+        Ok(self)
+    }
+
+    /// Set the name of the partition of this association.
+    pub fn set_partition<S: AsRef<str>>(&mut self, partition: S) -> Result<&mut Self, Error> {
+        slurm_replace_utf8_string(&mut self.sys_data_mut().partition, partition)?;
+        Ok(self)
+    }
+
+    /// Set the raw number of fairshare “shares” assigned to this association.
+    pub fn set_shares_raw(&mut self, shares: u32) -> &mut Self {
+        self.sys_data_mut().shares_raw = shares;
+        self
+    }
+
+    /// Set the name of the user of this association.
+    pub fn set_user<S: AsRef<str>>(&mut self, user: S) -> Result<&mut Self, Error> {
+        slurm_replace_utf8_string(&mut self.sys_data_mut().user, user)?;
+        Ok(self)
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl Drop for AssociationRecordOwned {
+    fn drop(&mut self) {
+        unsafe { slurm_sys::slurmdb_destroy_assoc_rec((self.0).0 as _) };
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl SlurmList<AssociationRecord> {
+    pub fn append(&mut self, item: AssociationRecordOwned) {
+        let item = unsafe { item.give_up_ownership() };
+
+        if self.0.is_null() {
+            self.0 =
+                unsafe { slurm_sys::slurm_list_create(Some(slurm_sys::slurmdb_destroy_assoc_rec)) };
+        }
+
+        unsafe {
+            slurm_sys::slurm_list_append(self.0, item.0 as _);
+        }
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl_list_collection_traits!(AssociationRecord, AssociationRecordOwned);
+#[cfg(feature = "slurmdb")]
+impl_owned_list_item!(AssociationRecord, AssociationRecordOwned);
+
+#[cfg(feature = "slurmdb")]
+impl AssociationRecord {
+    /// Copy the data in this record into a plain-Rust structure.
+    ///
+    /// The result does not refer to any Slurm memory, so it may outlive the
+    /// query result that contained this record.
+    pub fn to_snapshot(&self) -> AssociationRecordSnapshot {
+        AssociationRecordSnapshot {
+            account: self.account().map(|s| s.into_owned()),
+            cluster: self.cluster().map(|s| s.into_owned()),
+            grp_jobs: self.grp_jobs(),
+            grp_submit_jobs: self.grp_submit_jobs(),
+            grp_tres: self.grp_tres().map(|s| s.into_owned()),
+            grp_tres_mins: self.grp_tres_mins().map(|s| s.into_owned()),
+            grp_tres_run_mins: self.grp_tres_run_mins().map(|s| s.into_owned()),
+            grp_wall: self.grp_wall(),
+            id: self.id(),
+            max_jobs: self.max_jobs(),
+            max_submit_jobs: self.max_submit_jobs(),
+            max_tres_mins_per_job: self.max_tres_mins_per_job().map(|s| s.into_owned()),
+            max_tres_run_mins: self.max_tres_run_mins().map(|s| s.into_owned()),
+            max_tres_per_job: self.max_tres_per_job().map(|s| s.into_owned()),
+            max_tres_per_node: self.max_tres_per_node().map(|s| s.into_owned()),
+            max_wall_per_job: self.max_wall_per_job(),
+            parent_account: self.parent_account().map(|s| s.into_owned()),
+            partition: self.partition().map(|s| s.into_owned()),
+            qos_list: self.qos_list().iter().map(|s| s.into_owned()).collect(),
+            shares_raw: self.shares_raw(),
+            user: self.user().map(|s| s.into_owned()),
+        }
+    }
+}
+
+/// A plain-Rust copy of the data in an `AssociationRecord`.
+///
+/// See the accessors of `AssociationRecord` for the meanings of the fields.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct AssociationRecordSnapshot {
+    pub account: Option<String>,
+    pub cluster: Option<String>,
+    pub grp_jobs: Option<u32>,
+    pub grp_submit_jobs: Option<u32>,
+    pub grp_tres: Option<String>,
+    pub grp_tres_mins: Option<String>,
+    pub grp_tres_run_mins: Option<String>,
+    pub grp_wall: Option<u32>,
+    pub id: u32,
+    pub max_jobs: Option<u32>,
+    pub max_submit_jobs: Option<u32>,
+    pub max_tres_mins_per_job: Option<String>,
+    pub max_tres_run_mins: Option<String>,
+    pub max_tres_per_job: Option<String>,
+    pub max_tres_per_node: Option<String>,
+    pub max_wall_per_job: Option<u32>,
+    pub parent_account: Option<String>,
+    pub partition: Option<String>,
+    pub qos_list: Vec<String>,
+    pub shares_raw: u32,
+    pub user: Option<String>,
+}
+
+#[cfg(feature = "slurmdb")]
+impl_snapshot_traits!(AssociationRecord, AssociationRecordSnapshot);
+
+/// The kinds of problems that the accounting database can report.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[non_exhaustive]
+pub enum ProblemType {
+    /// An account has no associations.
+    AccountNoAssociations,
+
+    /// An account has no users.
+    AccountNoUsers,
+
+    /// A user has no associations.
+    UserNoAssociations,
+
+    /// A user does not correspond to a UID on the system.
+    UserNoUid,
+
+    /// Some other kind of problem, identified by its raw Slurm code.
+    Other(u32),
+}
+
+impl ProblemType {
+    fn from_slurm(code: u32) -> ProblemType {
+        match code {
+            slurm_sys::slurmdb_problem_type_t_SLURMDB_PROBLEM_ACCT_NO_ASSOC => {
+                ProblemType::AccountNoAssociations
+            }
+            slurm_sys::slurmdb_problem_type_t_SLURMDB_PROBLEM_ACCT_NO_USERS => {
+                ProblemType::AccountNoUsers
+            }
+            slurm_sys::slurmdb_problem_type_t_SLURMDB_PROBLEM_USER_NO_ASSOC => {
+                ProblemType::UserNoAssociations
+            }
+            slurm_sys::slurmdb_problem_type_t_SLURMDB_PROBLEM_USER_NO_UID => ProblemType::UserNoUid,
+            other => ProblemType::Other(other),
+        }
+    }
+}
+
+impl Display for ProblemType {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+        match self {
+            &ProblemType::AccountNoAssociations => write!(f, "account has no associations"),
+            &ProblemType::AccountNoUsers => write!(f, "account has no users"),
+            &ProblemType::UserNoAssociations => write!(f, "user has no associations"),
+            &ProblemType::UserNoUid => write!(f, "user does not have a UID"),
+            &ProblemType::Other(code) => write!(f, "unrecognized problem (code {})", code),
+        }
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+make_slurm_wrap_struct!(
+    ProblemRecord,
+    slurm_sys::slurmdb_assoc_rec_t,
+    "\
+A problem detected in the Slurm accounting database.
+
+Slurm reports problems using association records, in which the `id` field
+is repurposed to hold the problem type.
+"
+);
+
+#[cfg(feature = "slurmdb")]
+impl_wrapper_debug!(ProblemRecord; problem, cluster, account, user);
+
+#[cfg(feature = "slurmdb")]
+impl ProblemRecord {
+    /// Get the name of the account with the problem, if relevant.
+    pub fn account(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().acct) }
+    }
+
+    /// Get the name of the cluster with the problem, if relevant.
+    pub fn cluster(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().cluster) }
+    }
+
+    /// Get the type of this problem.
+    pub fn problem(&self) -> ProblemType {
+        ProblemType::from_slurm(self.sys_data().id)
+    }
+
+    /// Get the name of the user with the problem, if relevant.
+    pub fn user(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().user) }
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+make_owned_version!(@customdrop ProblemRecord, ProblemRecordOwned, "An owned version of `ProblemRecord`.");
+
+#[cfg(feature = "slurmdb")]
+impl Drop for ProblemRecordOwned {
+    fn drop(&mut self) {
+        unsafe { slurm_sys::slurmdb_destroy_assoc_rec((self.0).0 as _) };
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl_owned_list_item!(ProblemRecord, ProblemRecordOwned);
+
+#[cfg(feature = "slurmdb")]
+impl ProblemRecord {
+    /// Copy the data in this record into a plain-Rust structure.
+    ///
+    /// The result does not refer to any Slurm memory, so it may outlive the
+    /// query result that contained this record.
+    pub fn to_snapshot(&self) -> ProblemRecordSnapshot {
+        ProblemRecordSnapshot {
+            account: self.account().map(|s| s.into_owned()),
+            cluster: self.cluster().map(|s| s.into_owned()),
+            problem: self.problem(),
+            user: self.user().map(|s| s.into_owned()),
+        }
+    }
+}
+
+/// A plain-Rust copy of the data in a `ProblemRecord`.
+///
+/// See the accessors of `ProblemRecord` for the meanings of the fields.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ProblemRecordSnapshot {
+    pub account: Option<String>,
+    pub cluster: Option<String>,
+    pub problem: ProblemType,
+    pub user: Option<String>,
+}
+
+#[cfg(feature = "slurmdb")]
+impl_snapshot_traits!(ProblemRecord, ProblemRecordSnapshot);
+
+#[cfg(feature = "slurmdb")]
+make_slurm_wrap_struct!(
+    QosFilters,
+    slurm_sys::slurmdb_qos_cond_t,
+    "\
+A set of filters for identifying QOS definitions of interest when querying
+the Slurm accounting database.
+
+The following items in the Slurm API are *not* exposed in these Rust bindings:
+
+```ignore
+pub struct slurmdb_qos_cond_t {
+    pub description_list: List,
+    pub format_list: List,
+    pub preempt_mode: u16,
+}
+```
+
+"
+);
+
+#[cfg(feature = "slurmdb")]
+impl_wrapper_debug!(QosFilters);
+
+#[cfg(feature = "slurmdb")]
+impl QosFilters {
+    /// Access the list of QOS ID numbers that will match this set of filters.
+    ///
+    /// Note that this list should consist of *textual* representations of
+    /// *numeric* QOS IDs.
+    pub fn id_list(&self) -> &SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().id_list) }
+    }
+
+    /// Mutably access the list of QOS ID numbers that will match this set of
+    /// filters.
+    pub fn id_list_mut(&mut self) -> &mut SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr_mut(&mut self.sys_data_mut().id_list) }
+    }
+
+    /// Access the list of QOS names that will match this set of filters.
+    pub fn name_list(&self) -> &SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().name_list) }
+    }
+
+    /// Mutably access the list of QOS names that will match this set of
+    /// filters.
+    pub fn name_list_mut(&mut self) -> &mut SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr_mut(&mut self.sys_data_mut().name_list) }
+    }
+
+    /// Specify whether the query should include deleted QOS.
+    pub fn with_deleted(&mut self, value: bool) -> &mut Self {
+        self.sys_data_mut().with_deleted = value as u16;
+        self
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+make_owned_version!(@customdrop QosFilters, QosFiltersOwned, "An owned version of `QosFilters`.");
+
+#[cfg(feature = "slurmdb")]
+impl QosFiltersOwned {
+    /// Create a new, empty set of filters.
+    pub fn new() -> Result<Self, Error> {
+        Ok(unsafe { Self::alloc_zeroed() }?)
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl Drop for QosFiltersOwned {
+    fn drop(&mut self) {
+        unsafe { slurm_sys::slurmdb_destroy_qos_cond((self.0).0 as _) };
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+make_slurm_wrap_struct!(
+    QosRecord,
+    slurm_sys::slurmdb_qos_rec_t,
+    "\
+Accounting information about a QOS (quality-of-service) definition.
+
+As with `AssociationRecord`, methods returning limits return None if the
+limit is not set, and TRES limits are returned as the textual representations
+used by Slurm.
+
+The following items in the Slurm API are *not* exposed in these Rust bindings:
+
+```ignore
+pub struct slurmdb_qos_rec_t {
+    pub flags: u32,
+    pub grace_time: u32,
+    pub grp_tres_ctld: *mut u64,
+    pub grp_tres_mins: *mut c_char,
+    pub grp_tres_mins_ctld: *mut u64,
+    pub grp_tres_run_mins: *mut c_char,
+    pub grp_tres_run_mins_ctld: *mut u64,
+    pub grp_wall: u32,
+    pub max_jobs_pa: u32,
+    pub max_submit_jobs_pa: u32,
+    pub max_tres_mins_pj: *mut c_char,
+    pub max_tres_mins_pj_ctld: *mut u64,
+    pub max_tres_pa: *mut c_char,
+    pub max_tres_pa_ctld: *mut u64,
+    pub max_tres_pj_ctld: *mut u64,
+    pub max_tres_pn_ctld: *mut u64,
+    pub max_tres_pu_ctld: *mut u64,
+    pub max_tres_run_mins_pa: *mut c_char,
+    pub max_tres_run_mins_pa_ctld: *mut u64,
+    pub max_tres_run_mins_pu: *mut c_char,
+    pub max_tres_run_mins_pu_ctld: *mut u64,
+    pub min_tres_pj_ctld: *mut u64,
+    pub preempt_bitstr: *mut bitstr_t,
+    pub usage: *mut slurmdb_qos_usage_t,
+    pub usage_thres: f64,
+}
+```
+
+"
+);
+
+#[cfg(feature = "slurmdb")]
+impl_wrapper_debug!(QosRecord; id, name, priority);
+
+#[cfg(feature = "slurmdb")]
+impl QosRecord {
+    /// Get the QOS’s description, if it has one.
+    pub fn description(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().description) }
+    }
+
+    /// Get the maximum number of jobs that may run at once under this QOS.
+    pub fn grp_jobs(&self) -> Option<u32> {
+        optional_limit(self.sys_data().grp_jobs)
+    }
+
+    /// Get the maximum number of jobs that may be pending or running at once
+    /// under this QOS.
+    pub fn grp_submit_jobs(&self) -> Option<u32> {
+        optional_limit(self.sys_data().grp_submit_jobs)
+    }
+
+    /// Get the limit on TRES that may be in use at once by all jobs running
+    /// under this QOS.
+    pub fn grp_tres(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().grp_tres) }
+    }
+
+    /// Get the unique ID number of this QOS.
+    pub fn id(&self) -> u32 {
+        self.sys_data().id
+    }
+
+    /// Get the maximum number of jobs that each user may run at once under
+    /// this QOS.
+    pub fn max_jobs_per_user(&self) -> Option<u32> {
+        optional_limit(self.sys_data().max_jobs_pu)
+    }
+
+    /// Get the maximum number of jobs that each user may have pending or
+    /// running at once under this QOS.
+    pub fn max_submit_jobs_per_user(&self) -> Option<u32> {
+        optional_limit(self.sys_data().max_submit_jobs_pu)
+    }
+
+    /// Get the limit on TRES that each job may use.
+    pub fn max_tres_per_job(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().max_tres_pj) }
+    }
+
+    /// Get the limit on TRES that each job may use on each node.
+    pub fn max_tres_per_node(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().max_tres_pn) }
+    }
+
+    /// Get the limit on TRES that the running jobs of each user may use at
+    /// once.
+    pub fn max_tres_per_user(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().max_tres_pu) }
+    }
+
+    /// Get the maximum wallclock time limit, in minutes, of each job.
+    pub fn max_wall_per_job(&self) -> Option<u32> {
+        optional_limit(self.sys_data().max_wall_pj)
+    }
+
+    /// Get the minimum TRES that each job must request.
+    pub fn min_tres_per_job(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().min_tres_pj) }
+    }
+
+    /// Get the QOS’s name.
+    ///
+    /// This is empty for records that are used to describe modifications.
+    pub fn name(&self) -> Cow<str> {
+        unsafe { optional_cstr(self.sys_data().name) }.unwrap_or(Cow::Borrowed(""))
+    }
+
+    /// Get the list of QOS that jobs running under this QOS may preempt.
+    ///
+    /// Note that the items in this list are textual representations of
+    /// *numeric* QOS IDs.
+    pub fn preempt_list(&self) -> &SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().preempt_list) }
+    }
+
+    /// Get the raw preemption mode of this QOS.
+    ///
+    /// This is a bitwise-or of the Slurm `PREEMPT_MODE_*` values. Use
+    /// `preempt_mode_name` to obtain a textual version.
+    pub fn preempt_mode(&self) -> u16 {
+        self.sys_data().preempt_mode
+    }
+
+    /// Get the preemption mode of this QOS, rendered as text in the same way
+    /// as Slurm’s tools do (e.g. `SUSPEND,GANG`).
+    pub fn preempt_mode_name(&self) -> Cow<str> {
+        unsafe { CStr::from_ptr(slurm_sys::slurm_preempt_mode_string(self.preempt_mode())) }
+            .to_string_lossy()
+    }
+
+    /// Get the scheduling priority of this QOS.
+    pub fn priority(&self) -> u32 {
+        self.sys_data().priority
+    }
+
+    /// Get the factor by which the usage of jobs running under this QOS is
+    /// scaled for fairshare calculations.
+    pub fn usage_factor(&self) -> f64 {
+        self.sys_data().usage_factor
+    }
+}
+
+/// Preemption mode: jobs are not preempted.
+pub const PREEMPT_MODE_OFF: u16 = slurm_sys::PREEMPT_MODE_OFF as u16;
+
+/// Preemption mode: preempted jobs are suspended.
+pub const PREEMPT_MODE_SUSPEND: u16 = slurm_sys::PREEMPT_MODE_SUSPEND as u16;
+
+/// Preemption mode: preempted jobs are requeued.
+pub const PREEMPT_MODE_REQUEUE: u16 = slurm_sys::PREEMPT_MODE_REQUEUE as u16;
+
+/// Preemption mode: preempted jobs are cancelled.
+pub const PREEMPT_MODE_CANCEL: u16 = slurm_sys::PREEMPT_MODE_CANCEL as u16;
+
+/// Preemption mode flag: suspended jobs are gang-scheduled.
+pub const PREEMPT_MODE_GANG: u16 = slurm_sys::PREEMPT_MODE_GANG as u16;
+
+/// Format a TRES limit specification in the form that Slurm expects when
+/// modifying the database.
+///
+/// The items are pairs of numeric TRES IDs and limit values. A limit value of
+/// None clears the limit on that TRES. For instance, `[(1, Some(16)), (4,
+/// None)]` becomes `1=16,4=-1`.
+pub fn format_tres_limits<I: IntoIterator<Item = (u32, Option<u64>)>>(items: I) -> String {
+    items
+        .into_iter()
+        .map(|(id, limit)| match limit {
+            Some(v) => format!("{}={}", id, v),
+            None => format!("{}=-1", id),
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(feature = "slurmdb")]
+make_owned_version!(@customdrop QosRecord, QosRecordOwned, "An owned version of `QosRecord`.");
+
+#[cfg(feature = "slurmdb")]
+impl QosRecordOwned {
+    /// Create a new QOS record.
+    ///
+    /// All of the fields of the record start out unset, so that it can be
+    /// used either to describe a new QOS for `DatabaseConnection::add_qos`,
+    /// or a set of changes for `DatabaseConnection::modify_qos`. In the
+    /// latter case, only the fields that are set are modified.
+    ///
+    /// As with `AssociationRecordOwned`, the numeric limit setters take
+    /// Options, with None clearing the limit, and TRES limits must be
+    /// expressed using numeric TRES IDs. The function `format_tres_limits`
+    /// can help construct them.
+    pub fn new() -> Result<Self, Error> {
+        let inst = unsafe { Self::alloc_zeroed() }?;
+        unsafe { slurm_sys::slurmdb_init_qos_rec((inst.0).0, false, slurm_sys::SLURMRS_NO_VAL) };
+        Ok(inst)
+    }
+
+    /// Set the QOS’s description.
+    pub fn set_description<S: AsRef<str>>(&mut self, description: S) -> Result<&mut Self, Error> {
+        slurm_replace_utf8_string(&mut self.sys_data_mut().description, description)?;
+        Ok(self)
+    }
+
+    /// Set the maximum number of jobs that may run at once under this QOS.
+    pub fn set_grp_jobs(&mut self, value: Option<u32>) -> &mut Self {
+        self.sys_data_mut().grp_jobs = limit_to_slurm(value);
+        self
+    }
+
+    /// Set the maximum number of jobs that may be pending or running at once
+    /// under this QOS.
+    pub fn set_grp_submit_jobs(&mut self, value: Option<u32>) -> &mut Self {
+        self.sys_data_mut().grp_submit_jobs = limit_to_slurm(value);
+        self
+    }
+
+    /// Set the limit on TRES that may be in use at once by all jobs running
+    /// under this QOS.
+    pub fn set_grp_tres<S: AsRef<str>>(&mut self, value: S) -> Result<&mut Self, Error> {
+        slurm_replace_utf8_string(&mut self.sys_data_mut().grp_tres, value)?;
+        Ok(self)
+    }
+
+    /// Set the maximum number of jobs that each user may run at once under
+    /// this QOS.
+    pub fn set_max_jobs_per_user(&mut self, value: Option<u32>) -> &mut Self {
+        self.sys_data_mut().max_jobs_pu = limit_to_slurm(value);
+        self
+    }
+
+    /// Set the maximum number of jobs that each user may have pending or
+    /// running at once under this QOS.
+    pub fn set_max_submit_jobs_per_user(&mut self, value: Option<u32>) -> &mut Self {
+        self.sys_data_mut().max_submit_jobs_pu = limit_to_slurm(value);
+        self
+    }
+
+    /// Set the limit on TRES that each job may use.
+    pub fn set_max_tres_per_job<S: AsRef<str>>(&mut self, value: S) -> Result<&mut Self, Error> {
+        slurm_replace_utf8_string(&mut self.sys_data_mut().max_tres_pj, value)?;
+        Ok(self)
+    }
+
+    /// Set the limit on TRES that each job may use on each node.
+    pub fn set_max_tres_per_node<S: AsRef<str>>(&mut self, value: S) -> Result<&mut Self, Error> {
+        slurm_replace_utf8_string(&mut self.sys_data_mut().max_tres_pn, value)?;
+        Ok(self)
+    }
+
+    /// Set the limit on TRES that the running jobs of each user may use at
+    /// once.
+    pub fn set_max_tres_per_user<S: AsRef<str>>(&mut self, value: S) -> Result<&mut Self, Error> {
+        slurm_replace_utf8_string(&mut self.sys_data_mut().max_tres_pu, value)?;
+        Ok(self)
+    }
+
+    /// Set the maximum wallclock time limit, in minutes, of each job.
+    pub fn set_max_wall_per_job(&mut self, value: Option<u32>) -> &mut Self {
+        self.sys_data_mut().max_wall_pj = limit_to_slurm(value);
+        self
+    }
+
+    /// Set the minimum TRES that each job must request.
+    pub fn set_min_tres_per_job<S: AsRef<str>>(&mut self, value: S) -> Result<&mut Self, Error> {
+        slurm_replace_utf8_string(&mut self.sys_data_mut().min_tres_pj, value)?;
+        Ok(self)
+    }
+
+    /// Set the QOS’s name.
+    pub fn set_name<S: AsRef<str>>(&mut self, name: S) -> Result<&mut Self, Error> {
+        slurm_replace_utf8_string(&mut self.sys_data_mut().name, name)?;
+        Ok(self)
+    }
+
+    /// Set the preemption mode of this QOS.
+    ///
+    /// This should be one of the `PREEMPT_MODE_*` values, possibly combined
+    /// with `PREEMPT_MODE_GANG`.
+    pub fn set_preempt_mode(&mut self, mode: u16) -> &mut Self {
+        self.sys_data_mut().preempt_mode = mode;
+        self
+    }
+
+    /// Set the scheduling priority of this QOS.
+    pub fn set_priority(&mut self, priority: u32) -> &mut Self {
+        self.sys_data_mut().priority = priority;
+        self
+    }
+
+    /// Set the factor by which the usage of jobs running under this QOS is
+    /// scaled for fairshare calculations.
+    pub fn set_usage_factor(&mut self, factor: f64) -> &mut Self {
+        self.sys_data_mut().usage_factor = factor;
+        self
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl Drop for QosRecordOwned {
+    fn drop(&mut self) {
+        unsafe { slurm_sys::slurmdb_destroy_qos_rec((self.0).0 as _) };
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl SlurmList<QosRecord> {
+    pub fn append(&mut self, item: QosRecordOwned) {
+        let item = unsafe { item.give_up_ownership() };
+
+        if self.0.is_null() {
+            self.0 =
+                unsafe { slurm_sys::slurm_list_create(Some(slurm_sys::slurmdb_destroy_qos_rec)) };
+        }
+
+        unsafe {
+            slurm_sys::slurm_list_append(self.0, item.0 as _);
+        }
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl_list_collection_traits!(QosRecord, QosRecordOwned);
+#[cfg(feature = "slurmdb")]
+impl_owned_list_item!(QosRecord, QosRecordOwned);
+
+#[cfg(feature = "slurmdb")]
+impl QosRecord {
+    /// Copy the data in this record into a plain-Rust structure.
+    ///
+    /// The result does not refer to any Slurm memory, so it may outlive the
+    /// query result that contained this record.
+    pub fn to_snapshot(&self) -> QosRecordSnapshot {
+        QosRecordSnapshot {
+            description: self.description().map(|s| s.into_owned()),
+            grp_jobs: self.grp_jobs(),
+            grp_submit_jobs: self.grp_submit_jobs(),
+            grp_tres: self.grp_tres().map(|s| s.into_owned()),
+            id: self.id(),
+            max_jobs_per_user: self.max_jobs_per_user(),
+            max_submit_jobs_per_user: self.max_submit_jobs_per_user(),
+            max_tres_per_job: self.max_tres_per_job().map(|s| s.into_owned()),
+            max_tres_per_node: self.max_tres_per_node().map(|s| s.into_owned()),
+            max_tres_per_user: self.max_tres_per_user().map(|s| s.into_owned()),
+            max_wall_per_job: self.max_wall_per_job(),
+            min_tres_per_job: self.min_tres_per_job().map(|s| s.into_owned()),
+            name: self.name().into_owned(),
+            preempt_list: self.preempt_list().iter().map(|s| s.into_owned()).collect(),
+            preempt_mode: self.preempt_mode(),
+            priority: self.priority(),
+            usage_factor: self.usage_factor(),
+        }
+    }
+}
+
+/// A plain-Rust copy of the data in a `QosRecord`.
+///
+/// See the accessors of `QosRecord` for the meanings of the fields.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct QosRecordSnapshot {
+    pub description: Option<String>,
+    pub grp_jobs: Option<u32>,
+    pub grp_submit_jobs: Option<u32>,
+    pub grp_tres: Option<String>,
+    pub id: u32,
+    pub max_jobs_per_user: Option<u32>,
+    pub max_submit_jobs_per_user: Option<u32>,
+    pub max_tres_per_job: Option<String>,
+    pub max_tres_per_node: Option<String>,
+    pub max_tres_per_user: Option<String>,
+    pub max_wall_per_job: Option<u32>,
+    pub min_tres_per_job: Option<String>,
+    pub name: String,
+    pub preempt_list: Vec<String>,
+    pub preempt_mode: u16,
     pub priority: u32,
-    pub profile: u32,
-    pub qos: *mut c_char,
-    pub reboot: u8,
-    pub req_nodes: *mut c_char,
-    pub req_node_inx: *mut i32,
-    pub req_switch: u32,
-    pub requeue: u16,
-    pub resize_time: time_t,
-    pub restart_cnt: u16,
-    pub resv_name: *mut c_char,
-    pub sched_nodes: *mut c_char,
-    pub select_jobinfo: *mut dynamic_plugin_data_t,
-    pub shared: u16,
-    pub show_flags: u16,
-    pub sockets_per_board: u16,
-    pub sockets_per_node: u16,
-    pub start_time: time_t,
-    pub start_protocol_ver: u16,
-    pub state_desc: *mut c_char,
-    pub state_reason: u16,
-    pub std_err: *mut c_char,
-    pub std_in: *mut c_char,
-    pub std_out: *mut c_char,
-    pub submit_time: time_t,
-    pub suspend_time: time_t,
-    pub time_limit: u32,
-    pub time_min: u32,
-    pub threads_per_core: u16,
-    pub tres_req_str: *mut c_char,
-    pub tres_alloc_str: *mut c_char,
-    pub user_id: u32,
-    pub user_name: *mut c_char,
-    pub wait4switch: u32,
-    pub wckey: *mut c_char,
-    pub work_dir: *mut c_char,
+    pub usage_factor: f64,
+}
+
+#[cfg(feature = "slurmdb")]
+impl_snapshot_traits!(QosRecord, QosRecordSnapshot);
+
+#[cfg(feature = "slurmdb")]
+make_slurm_wrap_struct!(
+    ClusterFilters,
+    slurm_sys::slurmdb_cluster_cond_t,
+    "\
+A set of filters for identifying clusters of interest when querying the Slurm
+accounting database.
+
+The following items in the Slurm API are *not* exposed in these Rust bindings:
+
+```ignore
+pub struct slurmdb_cluster_cond_t {
+    pub classification: u16,
+    pub flags: u32,
+    pub format_list: List,
+    pub plugin_id_select_list: List,
+    pub rpc_version_list: List,
+    pub usage_end: time_t,
+    pub usage_start: time_t,
+    pub with_usage: u16,
+}
+```
+
+"
+);
+
+#[cfg(feature = "slurmdb")]
+impl_wrapper_debug!(ClusterFilters);
+
+#[cfg(feature = "slurmdb")]
+impl ClusterFilters {
+    /// Access the list of cluster names that will match this set of filters.
+    pub fn cluster_list(&self) -> &SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().cluster_list) }
+    }
+
+    /// Mutably access the list of cluster names that will match this set of
+    /// filters.
+    pub fn cluster_list_mut(&mut self) -> &mut SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr_mut(&mut self.sys_data_mut().cluster_list) }
+    }
+
+    /// Access the list of federation names that will match this set of
+    /// filters.
+    pub fn federation_list(&self) -> &SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().federation_list) }
+    }
+
+    /// Mutably access the list of federation names that will match this set
+    /// of filters.
+    pub fn federation_list_mut(&mut self) -> &mut SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr_mut(&mut self.sys_data_mut().federation_list) }
+    }
+
+    /// Specify whether the query should include deleted clusters.
+    pub fn with_deleted(&mut self, value: bool) -> &mut Self {
+        self.sys_data_mut().with_deleted = value as u16;
+        self
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+make_owned_version!(@customdrop ClusterFilters, ClusterFiltersOwned, "An owned version of `ClusterFilters`.");
+
+#[cfg(feature = "slurmdb")]
+impl ClusterFiltersOwned {
+    /// Create a new, empty set of filters.
+    pub fn new() -> Result<Self, Error> {
+        Ok(unsafe { Self::alloc_zeroed() }?)
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl Drop for ClusterFiltersOwned {
+    fn drop(&mut self) {
+        unsafe { slurm_sys::slurmdb_destroy_cluster_cond((self.0).0 as _) };
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+make_slurm_wrap_struct!(
+    ClusterRecord,
+    slurm_sys::slurmdb_cluster_rec_t,
+    "\
+Accounting information about a cluster.
+
+The following items in the Slurm API are *not* exposed in these Rust bindings:
+
+```ignore
+pub struct slurmdb_cluster_rec_t {
+    pub accounting_list: List,
+    pub classification: u16,
+    pub dimensions: u16,
+    pub dim_size: *mut c_int,
+    pub fed: slurmdb_cluster_fed_t,
+    pub flags: u32,
+    pub lock: pthread_mutex_t,
+    pub plugin_id_select: u32,
+    pub root_assoc: *mut slurmdb_assoc_rec_t,
+    pub send_rpc: *mut c_void,
+}
+```
+
+"
+);
+
+#[cfg(feature = "slurmdb")]
+impl_wrapper_debug!(ClusterRecord; name, control_host, control_port);
+
+#[cfg(feature = "slurmdb")]
+impl ClusterRecord {
+    /// Get the hostname of the cluster’s controller, if it is known.
+    pub fn control_host(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().control_host) }
+    }
+
+    /// Get the port number of the cluster’s controller.
+    pub fn control_port(&self) -> u32 {
+        self.sys_data().control_port
+    }
+
+    /// Get the cluster’s name.
+    pub fn name(&self) -> Cow<str> {
+        unsafe { CStr::from_ptr(self.sys_data().name) }.to_string_lossy()
+    }
+
+    /// Get the hostlist expression of the cluster’s nodes, if it is known.
+    pub fn nodes(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().nodes) }
+    }
+
+    /// Get the Slurm RPC protocol version used by the cluster.
+    pub fn rpc_version(&self) -> u16 {
+        self.sys_data().rpc_version
+    }
+
+    /// Get the cluster’s TRES (trackable resources) inventory.
+    ///
+    /// This is returned as the textual representation used by Slurm, in
+    /// which the resources are identified by their numeric TRES IDs.
+    pub fn tres(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().tres_str) }
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+make_owned_version!(@customdrop ClusterRecord, ClusterRecordOwned, "An owned version of `ClusterRecord`.");
+
+#[cfg(feature = "slurmdb")]
+impl Drop for ClusterRecordOwned {
+    fn drop(&mut self) {
+        unsafe { slurm_sys::slurmdb_destroy_cluster_rec((self.0).0 as _) };
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl_owned_list_item!(ClusterRecord, ClusterRecordOwned);
+
+#[cfg(feature = "slurmdb")]
+impl ClusterRecord {
+    /// Copy the data in this record into a plain-Rust structure.
+    ///
+    /// The result does not refer to any Slurm memory, so it may outlive the
+    /// query result that contained this record.
+    pub fn to_snapshot(&self) -> ClusterRecordSnapshot {
+        ClusterRecordSnapshot {
+            control_host: self.control_host().map(|s| s.into_owned()),
+            control_port: self.control_port(),
+            name: self.name().into_owned(),
+            nodes: self.nodes().map(|s| s.into_owned()),
+            rpc_version: self.rpc_version(),
+            tres: self.tres().map(|s| s.into_owned()),
+        }
+    }
+}
+
+/// A plain-Rust copy of the data in a `ClusterRecord`.
+///
+/// See the accessors of `ClusterRecord` for the meanings of the fields.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ClusterRecordSnapshot {
+    pub control_host: Option<String>,
+    pub control_port: u32,
+    pub name: String,
+    pub nodes: Option<String>,
+    pub rpc_version: u16,
+    pub tres: Option<String>,
+}
+
+#[cfg(feature = "slurmdb")]
+impl_snapshot_traits!(ClusterRecord, ClusterRecordSnapshot);
+
+#[cfg(feature = "slurmdb")]
+make_slurm_wrap_struct!(
+    TresFilters,
+    slurm_sys::slurmdb_tres_cond_t,
+    "\
+A set of filters for identifying TRES (trackable resource) definitions of
+interest when querying the Slurm accounting database.
+
+The following items in the Slurm API are *not* exposed in these Rust bindings:
+
+```ignore
+pub struct slurmdb_tres_cond_t {
+    pub count: u64,
+    pub format_list: List,
+}
+```
+
+"
+);
+
+#[cfg(feature = "slurmdb")]
+impl_wrapper_debug!(TresFilters);
+
+#[cfg(feature = "slurmdb")]
+impl TresFilters {
+    /// Access the list of TRES ID numbers that will match this set of
+    /// filters.
+    ///
+    /// Note that this list should consist of *textual* representations of
+    /// *numeric* TRES IDs.
+    pub fn id_list(&self) -> &SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().id_list) }
+    }
+
+    /// Mutably access the list of TRES ID numbers that will match this set of
+    /// filters.
+    pub fn id_list_mut(&mut self) -> &mut SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr_mut(&mut self.sys_data_mut().id_list) }
+    }
+
+    /// Access the list of TRES names that will match this set of filters.
+    pub fn name_list(&self) -> &SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().name_list) }
+    }
+
+    /// Mutably access the list of TRES names that will match this set of
+    /// filters.
+    pub fn name_list_mut(&mut self) -> &mut SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr_mut(&mut self.sys_data_mut().name_list) }
+    }
+
+    /// Access the list of TRES types that will match this set of filters.
+    pub fn type_list(&self) -> &SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().type_list) }
+    }
+
+    /// Mutably access the list of TRES types (e.g. `cpu`, `gres`) that will
+    /// match this set of filters.
+    pub fn type_list_mut(&mut self) -> &mut SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr_mut(&mut self.sys_data_mut().type_list) }
+    }
+
+    /// Specify whether the query should include deleted TRES.
+    pub fn with_deleted(&mut self, value: bool) -> &mut Self {
+        self.sys_data_mut().with_deleted = value as u16;
+        self
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+make_owned_version!(@customdrop TresFilters, TresFiltersOwned, "An owned version of `TresFilters`.");
+
+#[cfg(feature = "slurmdb")]
+impl TresFiltersOwned {
+    /// Create a new, empty set of filters.
+    pub fn new() -> Result<Self, Error> {
+        Ok(unsafe { Self::alloc_zeroed() }?)
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl Drop for TresFiltersOwned {
+    fn drop(&mut self) {
+        unsafe { slurm_sys::slurmdb_destroy_tres_cond((self.0).0 as _) };
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+make_slurm_wrap_struct!(
+    TresRecord,
+    slurm_sys::slurmdb_tres_rec_t,
+    "\
+Information about a TRES (trackable resource) definition.
+
+The following items in the Slurm API are *not* exposed in these Rust bindings:
+
+```ignore
+pub struct slurmdb_tres_rec_t {
+    pub rec_count: u32,
+    pub count: u64,
+}
+```
+
+"
+);
+
+#[cfg(feature = "slurmdb")]
+impl_wrapper_debug!(TresRecord; id, full_name);
+
+#[cfg(feature = "slurmdb")]
+impl TresRecord {
+    /// Get the allocated usage of this TRES, in TRES-seconds.
+    ///
+    /// This is only meaningful for records returned as part of usage
+    /// reports, such as `ReportJobGrouping::tres_list`.
+    pub fn alloc_secs(&self) -> u64 {
+        self.sys_data().alloc_secs
+    }
+
+    /// Get the full name of this TRES, as used in Slurm’s textual output.
+    ///
+    /// This is the type if the TRES has no name (e.g. `cpu`), or the type and
+    /// name separated by a slash otherwise (e.g. `gres/gpu`).
+    pub fn full_name(&self) -> String {
+        match self.name() {
+            Some(name) => format!("{}/{}", self.tres_type(), name),
+            None => self.tres_type().into_owned(),
+        }
+    }
+
+    /// Get the unique ID number of this TRES.
+    pub fn id(&self) -> u32 {
+        self.sys_data().id
+    }
+
+    /// Get the name of this TRES, if it has one.
+    ///
+    /// Only some types of TRES have names: the `gres` type has names such as
+    /// `gpu`, while the `cpu` type does not.
+    pub fn name(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().name) }.and_then(|n| {
+            if n.is_empty() {
+                None
+            } else {
+                Some(n)
+            }
+        })
+    }
+
+    /// Get the type of this TRES (e.g. `cpu`, `mem`, `gres`).
+    pub fn tres_type(&self) -> Cow<str> {
+        unsafe { CStr::from_ptr(self.sys_data().type_) }.to_string_lossy()
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+make_owned_version!(@customdrop TresRecord, TresRecordOwned, "An owned version of `TresRecord`.");
+
+#[cfg(feature = "slurmdb")]
+impl Drop for TresRecordOwned {
+    fn drop(&mut self) {
+        unsafe { slurm_sys::slurmdb_destroy_tres_rec((self.0).0 as _) };
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl_owned_list_item!(TresRecord, TresRecordOwned);
+
+#[cfg(feature = "slurmdb")]
+impl TresRecord {
+    /// Copy the data in this record into a plain-Rust structure.
+    ///
+    /// The result does not refer to any Slurm memory, so it may outlive the
+    /// query result that contained this record.
+    pub fn to_snapshot(&self) -> TresRecordSnapshot {
+        TresRecordSnapshot {
+            alloc_secs: self.alloc_secs(),
+            id: self.id(),
+            name: self.name().map(|s| s.into_owned()),
+            tres_type: self.tres_type().into_owned(),
+        }
+    }
+}
+
+/// A plain-Rust copy of the data in a `TresRecord`.
+///
+/// See the accessors of `TresRecord` for the meanings of the fields.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct TresRecordSnapshot {
+    pub alloc_secs: u64,
+    pub id: u32,
+    pub name: Option<String>,
+    pub tres_type: String,
+}
+
+#[cfg(feature = "slurmdb")]
+impl_snapshot_traits!(TresRecord, TresRecordSnapshot);
+
+/// The ID number of the CPU TRES.
+pub const TRES_CPU: u32 = 1;
+
+/// The ID number of the memory TRES, measured in mebibytes.
+pub const TRES_MEM: u32 = 2;
+
+/// The ID number of the energy TRES, measured in joules.
+pub const TRES_ENERGY: u32 = 3;
+
+/// The ID number of the node TRES.
+pub const TRES_NODE: u32 = 4;
+
+/// The ID number of the billing TRES.
+pub const TRES_BILLING: u32 = 5;
+
+/// A set of TRES amounts, such as the resources allocated to a job.
+///
+/// Slurm stores these in strings of the form `1=4,2=16000,1001=2`, where
+/// each key is the ID number of a TRES. The built-in TRES types have fixed
+/// ID numbers, provided as the `TRES_*` constants; others, such as GPUs, are
+/// assigned IDs dynamically. Use `by_name` with the results of
+/// `DatabaseConnection::get_tres` to map them to their names.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct TresAmounts(BTreeMap<u32, u64>);
+
+impl TresAmounts {
+    /// Parse a Slurm TRES string of the form `1=4,2=16000`.
+    ///
+    /// An empty string yields an empty set.
+    pub fn parse(text: &str) -> Result<TresAmounts, Error> {
+        let mut amounts = BTreeMap::new();
+
+        for item in text.split(',') {
+            if item.is_empty() {
+                continue;
+            }
+
+            let mut pieces = item.splitn(2, '=');
+            let id = pieces.next().unwrap_or("");
+            let amount = pieces
+                .next()
+                .ok_or_else(|| parse_err!("malformed TRES item {:?}", item))?;
+
+            let id = id
+                .parse()
+                .map_err(|_| parse_err!("malformed TRES ID in item {:?}", item))?;
+            let amount = amount
+                .parse()
+                .map_err(|_| parse_err!("malformed TRES amount in item {:?}", item))?;
+            amounts.insert(id, amount);
+        }
+
+        Ok(TresAmounts(amounts))
+    }
+
+    /// Parse a possibly-null TRES string from a Slurm structure.
+    ///
+    /// Null and unparseable strings yield an empty set.
+    unsafe fn from_slurm(ptr: *const c_char) -> TresAmounts {
+        optional_cstr(ptr)
+            .and_then(|text| TresAmounts::parse(&text).ok())
+            .unwrap_or_default()
+    }
+
+    /// Get the amount of the TRES with the specified ID, if it is present.
+    pub fn get(&self, id: u32) -> Option<u64> {
+        self.0.get(&id).cloned()
+    }
+
+    /// Iterate over the (ID, amount) pairs in this set, in order of ID.
+    pub fn iter<'a>(&'a self) -> impl Iterator<Item = (u32, u64)> + 'a {
+        self.0.iter().map(|(id, amount)| (*id, *amount))
+    }
+
+    /// Return true if this set contains no TRES amounts.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Get the number of CPUs in this set, if present.
+    pub fn cpus(&self) -> Option<u64> {
+        self.get(TRES_CPU)
+    }
+
+    /// Get the amount of memory in this set, in mebibytes, if present.
+    pub fn mem_mib(&self) -> Option<u64> {
+        self.get(TRES_MEM)
+    }
+
+    /// Get the number of nodes in this set, if present.
+    pub fn nodes(&self) -> Option<u64> {
+        self.get(TRES_NODE)
+    }
+
+    /// Get the billing value of this set, if present.
+    pub fn billing(&self) -> Option<u64> {
+        self.get(TRES_BILLING)
+    }
+
+    /// Get the number of GPUs in this set, if present.
+    ///
+    /// GPUs do not have a fixed TRES ID, so the list of TRES definitions
+    /// returned by `DatabaseConnection::get_tres` is needed to identify them.
+    #[cfg(feature = "slurmdb")]
+    pub fn gpus(&self, tres: &SlurmList<TresRecord>) -> Option<u64> {
+        tres.iter()
+            .find(|rec| rec.full_name() == "gres/gpu")
+            .and_then(|rec| self.get(rec.id()))
+    }
+
+    /// Convert this set into a `TresSpec`, keyed by TRES name.
+    ///
+    /// See `by_name` for how the names are determined.
+    #[cfg(feature = "slurmdb")]
+    pub fn to_spec(&self, tres: &SlurmList<TresRecord>) -> TresSpec {
+        TresSpec(self.by_name(tres))
+    }
+
+    /// Convert this set into a map keyed by TRES name.
+    ///
+    /// The names are those returned by `TresRecord::full_name`, such as
+    /// `cpu` or `gres/gpu`. TRES IDs that do not appear in `tres` are keyed
+    /// by their decimal ID number.
+    #[cfg(feature = "slurmdb")]
+    pub fn by_name(&self, tres: &SlurmList<TresRecord>) -> BTreeMap<String, u64> {
+        let names: BTreeMap<u32, String> =
+            tres.iter().map(|rec| (rec.id(), rec.full_name())).collect();
+
+        self.iter()
+            .map(|(id, amount)| {
+                let name = names.get(&id).cloned().unwrap_or_else(|| format!("{}", id));
+                (name, amount)
+            })
+            .collect()
+    }
+}
+
+impl Display for TresAmounts {
+    /// Format the set in Slurm's own `1=4,2=16000` form.
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+        let mut first = true;
+
+        for (id, amount) in self.iter() {
+            if !first {
+                write!(f, ",")?;
+            }
+            write!(f, "{}={}", id, amount)?;
+            first = false;
+        }
+
+        Ok(())
+    }
+}
+
+/// A set of TRES amounts keyed by TRES name, such as `cpu=4,mem=16G,gres/gpu=2`.
+///
+/// This type parses and formats the human-readable TRES strings used in
+/// Slurm's command-line tools and configuration files. Memory amounts (the
+/// `mem` and `vmem` TRES) are normalized to mebibytes, so that `mem=16G` and
+/// `mem=16384M` compare equal; they are formatted with an `M` suffix. Other
+/// amounts may use the suffixes `K`, `M`, `G`, `T`, and `P`, which denote
+/// powers of 1024. Amounts may have a fractional part, as in `mem=3.50G`;
+/// after scaling, they are rounded up to a whole number of units.
+///
+/// Sets can be added together, which is useful for summing the resources
+/// used by a group of jobs. When formatted, items are sorted by name.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct TresSpec(BTreeMap<String, u64>);
+
+impl TresSpec {
+    /// Create a new, empty set.
+    #[must_use]
+    pub fn new() -> TresSpec {
+        TresSpec::default()
+    }
+
+    /// Return true if the named TRES is measured in units of memory.
+    fn is_memory(name: &str) -> bool {
+        name == "mem" || name == "vmem"
+    }
+
+    /// Get the amount of the named TRES, if present.
+    ///
+    /// Memory amounts are in mebibytes.
+    pub fn get(&self, name: &str) -> Option<u64> {
+        self.0.get(name).cloned()
+    }
+
+    /// Set the amount of the named TRES.
+    ///
+    /// Memory amounts are in mebibytes.
+    pub fn set<S: Into<String>>(&mut self, name: S, amount: u64) -> &mut Self {
+        self.0.insert(name.into(), amount);
+        self
+    }
+
+    /// Iterate over the (name, amount) pairs in this set, in order of name.
+    pub fn iter<'a>(&'a self) -> impl Iterator<Item = (&'a str, u64)> + 'a {
+        self.0.iter().map(|(name, amount)| (name.as_ref(), *amount))
+    }
+
+    /// Return true if this set contains no TRES amounts.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Get the number of CPUs in this set, if present.
+    pub fn cpus(&self) -> Option<u64> {
+        self.get("cpu")
+    }
+
+    /// Get the amount of memory in this set, in mebibytes, if present.
+    pub fn mem_mib(&self) -> Option<u64> {
+        self.get("mem")
+    }
+
+    /// Get the number of nodes in this set, if present.
+    pub fn nodes(&self) -> Option<u64> {
+        self.get("node")
+    }
+
+    /// Get the billing value of this set, if present.
+    pub fn billing(&self) -> Option<u64> {
+        self.get("billing")
+    }
+
+    /// Get the number of GPUs in this set, if present.
+    pub fn gpus(&self) -> Option<u64> {
+        self.get("gres/gpu")
+    }
+}
+
+impl FromStr for TresSpec {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<TresSpec, Error> {
+        let mut spec = TresSpec::new();
+
+        for item in text.split(',') {
+            let item = item.trim();
+
+            if item.is_empty() {
+                continue;
+            }
+
+            let mut pieces = item.splitn(2, '=');
+            let name = pieces.next().unwrap_or("");
+            let value = pieces
+                .next()
+                .ok_or_else(|| parse_err!("malformed TRES item {:?}", item))?;
+
+            if name.is_empty() {
+                return Err(parse_err!("missing TRES name in item {:?}", item));
+            }
+
+            let (digits, shift) = match value.char_indices().last() {
+                Some((i, 'K')) | Some((i, 'k')) => (&value[..i], 10),
+                Some((i, 'M')) | Some((i, 'm')) => (&value[..i], 20),
+                Some((i, 'G')) | Some((i, 'g')) => (&value[..i], 30),
+                Some((i, 'T')) | Some((i, 't')) => (&value[..i], 40),
+                Some((i, 'P')) | Some((i, 'p')) => (&value[..i], 50),
+                _ => (value, 0),
+            };
+
+            let (whole, frac) = match digits.find('.') {
+                Some(i) => (&digits[..i], &digits[i + 1..]),
+                None => (digits, "0"),
+            };
+
+            let is_digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+
+            if !is_digits(whole) || !is_digits(frac) {
+                return Err(parse_err!("malformed TRES amount in item {:?}", item));
+            }
+
+            let frac = frac.trim_end_matches('0');
+
+            // Unsuffixed memory amounts are in mebibytes, as in Slurm.
+            let shift: i32 = if TresSpec::is_memory(name) && shift != 0 {
+                shift - 20
+            } else {
+                shift
+            };
+
+            // Compute `whole.frac * 2**shift` exactly as the fraction
+            // `numer / denom`, then round up.
+            let too_large = || parse_err!("TRES amount in item {:?} is too large", item);
+            let mut numer: u128 = 0;
+            let mut denom: u128 = 1;
+
+            for b in whole.bytes() {
+                numer = numer
+                    .checked_mul(10)
+                    .and_then(|n| n.checked_add(u128::from(b - b'0')))
+                    .ok_or_else(too_large)?;
+            }
+
+            for b in frac.bytes() {
+                numer = numer
+                    .checked_mul(10)
+                    .and_then(|n| n.checked_add(u128::from(b - b'0')))
+                    .ok_or_else(too_large)?;
+                denom = denom.checked_mul(10).ok_or_else(too_large)?;
+            }
+
+            if shift >= 0 {
+                numer = numer.checked_mul(1 << shift).ok_or_else(too_large)?;
+            } else {
+                denom = denom.checked_mul(1 << -shift).ok_or_else(too_large)?;
+            }
+
+            let amount = numer.checked_add(denom - 1).ok_or_else(too_large)? / denom;
+
+            if amount > u128::from(u64::MAX) {
+                return Err(too_large());
+            }
+
+            let amount = amount as u64;
+            spec.0.insert(name.to_owned(), amount);
+        }
+
+        Ok(spec)
+    }
+}
+
+impl Display for TresSpec {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+        let mut first = true;
+
+        for (name, amount) in self.iter() {
+            if !first {
+                write!(f, ",")?;
+            }
+
+            first = false;
+
+            if TresSpec::is_memory(name) {
+                write!(f, "{}={}M", name, amount)?;
+            } else {
+                write!(f, "{}={}", name, amount)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> AddAssign<&'a TresSpec> for TresSpec {
+    fn add_assign(&mut self, other: &'a TresSpec) {
+        for (name, amount) in other.iter() {
+            let entry = self.0.entry(name.to_owned()).or_insert(0);
+            *entry = entry.saturating_add(amount);
+        }
+    }
+}
+
+impl AddAssign for TresSpec {
+    fn add_assign(&mut self, other: TresSpec) {
+        *self += &other;
+    }
+}
+
+impl<'a> Add<&'a TresSpec> for TresSpec {
+    type Output = TresSpec;
+
+    fn add(mut self, other: &'a TresSpec) -> TresSpec {
+        self += other;
+        self
+    }
+}
+
+impl Add for TresSpec {
+    type Output = TresSpec;
+
+    fn add(mut self, other: TresSpec) -> TresSpec {
+        self += &other;
+        self
+    }
+}
+
+impl Sum for TresSpec {
+    fn sum<I: Iterator<Item = TresSpec>>(iter: I) -> TresSpec {
+        iter.fold(TresSpec::new(), |acc, spec| acc + spec)
+    }
+}
+
+impl<'a> Sum<&'a TresSpec> for TresSpec {
+    fn sum<I: Iterator<Item = &'a TresSpec>>(iter: I) -> TresSpec {
+        iter.fold(TresSpec::new(), |acc, spec| acc + spec)
+    }
+}
+
+/// The kinds of events recorded in the accounting database.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[non_exhaustive]
+pub enum EventType {
+    /// An event affecting a whole cluster, such as a change in its size.
+    Cluster,
+
+    /// An event affecting a node, such as it going down or being drained.
+    Node,
+
+    /// Some other kind of event, identified by its raw Slurm code.
+    Other(u16),
+}
+
+impl EventType {
+    fn from_slurm(t: u16) -> EventType {
+        match t as u32 {
+            slurm_sys::slurmdb_event_type_t_SLURMDB_EVENT_CLUSTER => EventType::Cluster,
+            slurm_sys::slurmdb_event_type_t_SLURMDB_EVENT_NODE => EventType::Node,
+            _ => EventType::Other(t),
+        }
+    }
+
+    fn to_slurm(&self) -> u16 {
+        match self {
+            &EventType::Cluster => slurm_sys::slurmdb_event_type_t_SLURMDB_EVENT_CLUSTER as u16,
+            &EventType::Node => slurm_sys::slurmdb_event_type_t_SLURMDB_EVENT_NODE as u16,
+            &EventType::Other(code) => code,
+        }
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+make_slurm_wrap_struct!(
+    EventFilters,
+    slurm_sys::slurmdb_event_cond_t,
+    "\
+A set of filters for identifying events of interest when querying the Slurm
+accounting database.
+
+The following items in the Slurm API are *not* exposed in these Rust bindings:
+
+```ignore
+pub struct slurmdb_event_cond_t {
+    pub cpus_max: u32,
+    pub cpus_min: u32,
+    pub format_list: List,
+    pub reason_uid_list: List,
+    pub state_list: List,
+}
+```
+
+"
+);
+
+#[cfg(feature = "slurmdb")]
+impl_wrapper_debug!(EventFilters);
+
+#[cfg(feature = "slurmdb")]
+impl EventFilters {
+    /// Access the list of cluster names that will match this set of filters.
+    pub fn cluster_list(&self) -> &SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().cluster_list) }
+    }
+
+    /// Mutably access the list of cluster names that will match this set of
+    /// filters.
+    pub fn cluster_list_mut(&mut self) -> &mut SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr_mut(&mut self.sys_data_mut().cluster_list) }
+    }
+
+    /// Only match events of the specified type.
+    ///
+    /// By default, events of all types are matched.
+    pub fn event_type(&mut self, value: EventType) -> &mut Self {
+        self.sys_data_mut().event_type = value.to_slurm();
+        self
+    }
+
+    /// Only match events that were ongoing at or before the specified time.
+    pub fn period_end(&mut self, time: DateTime<Utc>) -> &mut Self {
+        self.sys_data_mut().period_end = time.timestamp() as _;
+        self
+    }
+
+    /// Only match events that were ongoing at or after the specified time.
+    pub fn period_start(&mut self, time: DateTime<Utc>) -> &mut Self {
+        self.sys_data_mut().period_start = time.timestamp() as _;
+        self
+    }
+
+    /// Access the list of event reasons that will match this set of filters.
+    pub fn reason_list(&self) -> &SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().reason_list) }
+    }
+
+    /// Mutably access the list of event reasons that will match this set of
+    /// filters.
+    pub fn reason_list_mut(&mut self) -> &mut SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr_mut(&mut self.sys_data_mut().reason_list) }
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+make_owned_version!(@customdrop EventFilters, EventFiltersOwned, "An owned version of `EventFilters`.");
+
+#[cfg(feature = "slurmdb")]
+impl EventFiltersOwned {
+    /// Only match events affecting the specified nodes.
+    ///
+    /// The argument is a Slurm hostlist expression such as `node[01-04]`.
+    pub fn node_list<S: AsRef<str>>(&mut self, nodes: S) -> Result<&mut Self, Error> {
+        slurm_replace_utf8_string(&mut self.sys_data_mut().node_list, nodes)?;
+        Ok(self)
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl EventFiltersOwned {
+    /// Create a new, empty set of filters.
+    pub fn new() -> Result<Self, Error> {
+        Ok(unsafe { Self::alloc_zeroed() }?)
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl Drop for EventFiltersOwned {
+    fn drop(&mut self) {
+        unsafe { slurm_sys::slurmdb_destroy_event_cond((self.0).0 as _) };
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+make_slurm_wrap_struct!(
+    EventRecord,
+    slurm_sys::slurmdb_event_rec_t,
+    "\
+Accounting information about an event affecting a cluster or node.
+
+The following items in the Slurm API are *not* exposed in these Rust bindings:
+
+```ignore
+pub struct slurmdb_event_rec_t {
+    pub cluster_nodes: *mut c_char,
+    pub reason_uid: u32,
+    pub tres_str: *mut c_char,
+}
+```
+
+"
+);
+
+#[cfg(feature = "slurmdb")]
+impl_wrapper_debug!(EventRecord; event_type, cluster, node_name, period_start, period_end);
+
+#[cfg(feature = "slurmdb")]
+impl EventRecord {
+    /// Get the name of the cluster affected by this event.
+    pub fn cluster(&self) -> Cow<str> {
+        unsafe { CStr::from_ptr(self.sys_data().cluster) }.to_string_lossy()
+    }
+
+    /// Get the type of this event.
+    pub fn event_type(&self) -> EventType {
+        EventType::from_slurm(self.sys_data().event_type)
+    }
+
+    /// Get the name of the node affected by this event, if it affects a node.
+    pub fn node_name(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().node_name) }.and_then(|n| {
+            if n.is_empty() {
+                None
+            } else {
+                Some(n)
+            }
+        })
+    }
+
+    /// Get the time at which this event ended, or None if it is ongoing.
+    pub fn period_end(&self) -> Option<DateTime<Utc>> {
+        match self.sys_data().period_end as i64 {
+            0 => None,
+            t => Some(unix_time(t)),
+        }
+    }
+
+    /// Get the time at which this event started.
+    pub fn period_start(&self) -> DateTime<Utc> {
+        unix_time(self.sys_data().period_start as i64)
+    }
+
+    /// Get the reason given for this event, if any.
+    pub fn reason(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().reason) }
+    }
+
+    /// Get the raw Slurm state code of the node during this event.
+    ///
+    /// This is a node state code (`NODE_STATE_*`), and is only meaningful
+    /// for node events.
+    pub fn state(&self) -> u32 {
+        self.sys_data().state as u32
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+make_owned_version!(@customdrop EventRecord, EventRecordOwned, "An owned version of `EventRecord`.");
+
+#[cfg(feature = "slurmdb")]
+impl Drop for EventRecordOwned {
+    fn drop(&mut self) {
+        unsafe { slurm_sys::slurmdb_destroy_event_rec((self.0).0 as _) };
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl_owned_list_item!(EventRecord, EventRecordOwned);
+
+#[cfg(feature = "slurmdb")]
+impl EventRecord {
+    /// Copy the data in this record into a plain-Rust structure.
+    ///
+    /// The result does not refer to any Slurm memory, so it may outlive the
+    /// query result that contained this record.
+    pub fn to_snapshot(&self) -> EventRecordSnapshot {
+        EventRecordSnapshot {
+            cluster: self.cluster().into_owned(),
+            event_type: self.event_type(),
+            node_name: self.node_name().map(|s| s.into_owned()),
+            period_end: self.period_end(),
+            period_start: self.period_start(),
+            reason: self.reason().map(|s| s.into_owned()),
+            state: self.state(),
+        }
+    }
+}
+
+/// A plain-Rust copy of the data in an `EventRecord`.
+///
+/// See the accessors of `EventRecord` for the meanings of the fields.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct EventRecordSnapshot {
+    pub cluster: String,
+    pub event_type: EventType,
+    pub node_name: Option<String>,
+    pub period_end: Option<DateTime<Utc>>,
+    pub period_start: DateTime<Utc>,
+    pub reason: Option<String>,
+    pub state: u32,
+}
+
+#[cfg(feature = "slurmdb")]
+impl_snapshot_traits!(EventRecord, EventRecordSnapshot);
+
+#[cfg(feature = "slurmdb")]
+make_slurm_wrap_struct!(
+    ReservationFilters,
+    slurm_sys::slurmdb_reservation_cond_t,
+    "\
+A set of filters for identifying reservations of interest when querying the
+Slurm accounting database.
+
+The following items in the Slurm API are *not* exposed in these Rust bindings:
+
+```ignore
+pub struct slurmdb_reservation_cond_t {
+    pub flags: u16,
+    pub format_list: List,
+    pub id_list: List,
+    pub nodes: *mut c_char,
 }
 ```
 
 "
 );
 
-impl JobInfo {
-    /// Get this job's ID.
-    pub fn job_id(&self) -> JobId {
-        self.sys_data().job_id
+#[cfg(feature = "slurmdb")]
+impl_wrapper_debug!(ReservationFilters);
+
+#[cfg(feature = "slurmdb")]
+impl ReservationFilters {
+    /// Access the list of cluster names that will match this set of filters.
+    pub fn cluster_list(&self) -> &SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().cluster_list) }
     }
 
-    /// Get the cluster partition on which this job resides.
-    pub fn partition(&self) -> Cow<str> {
-        unsafe { CStr::from_ptr(self.sys_data().partition) }.to_string_lossy()
+    /// Mutably access the list of cluster names that will match this set of
+    /// filters.
+    pub fn cluster_list_mut(&mut self) -> &mut SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr_mut(&mut self.sys_data_mut().cluster_list) }
     }
-}
 
-/// Get information about a single job.
-///
-/// The job must still be running. If it existed but is no longer running,
-/// the result is an error (errno 2017, "invalid job id").
-///
-/// While the (successful) return value of this function is not a `JobInfo`
-/// struct, it is a type that derefs to `JobInfo`, and so can be used like
-/// one.
-pub fn get_job_info(jid: JobId) -> Result<SingleJobInfoMessageOwned, Error> {
-    let mut msg: *mut slurm_sys::job_info_msg_t = 0 as _;
+    /// Access the list of reservation names that will match this set of
+    /// filters.
+    pub fn name_list(&self) -> &SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().name_list) }
+    }
 
-    ustry!(slurm_sys::slurm_load_job(&mut msg, jid, 0));
+    /// Mutably access the list of reservation names that will match this set
+    /// of filters.
+    pub fn name_list_mut(&mut self) -> &mut SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr_mut(&mut self.sys_data_mut().name_list) }
+    }
 
-    let rc = unsafe { (*msg).record_count };
-    if rc != 1 {
-        return Err(format_err!(
-            "expected exactly one info record for job {}; got {} items",
-            jid,
-            rc
-        ));
+    /// Only match reservations that were active at or before the specified
+    /// time.
+    pub fn time_end(&mut self, time: DateTime<Utc>) -> &mut Self {
+        self.sys_data_mut().time_end = time.timestamp() as _;
+        self
     }
 
-    Ok(unsafe { SingleJobInfoMessageOwned::assume_ownership(msg as _) })
+    /// Only match reservations that were active at or after the specified
+    /// time.
+    pub fn time_start(&mut self, time: DateTime<Utc>) -> &mut Self {
+        self.sys_data_mut().time_start = time.timestamp() as _;
+        self
+    }
+
+    /// Specify whether the query should compute the usage of each
+    /// reservation.
+    ///
+    /// This is needed for `ReservationRecord::unused_wall` to be meaningful.
+    pub fn with_usage(&mut self, value: bool) -> &mut Self {
+        self.sys_data_mut().with_usage = value as u16;
+        self
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+make_owned_version!(@customdrop ReservationFilters, ReservationFiltersOwned, "An owned version of `ReservationFilters`.");
+
+#[cfg(feature = "slurmdb")]
+impl ReservationFiltersOwned {
+    /// Create a new, empty set of filters.
+    pub fn new() -> Result<Self, Error> {
+        Ok(unsafe { Self::alloc_zeroed() }?)
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl Drop for ReservationFiltersOwned {
+    fn drop(&mut self) {
+        unsafe { slurm_sys::slurmdb_destroy_reservation_cond((self.0).0 as _) };
+    }
 }
 
+#[cfg(feature = "slurmdb")]
 make_slurm_wrap_struct!(
-    SingleJobInfoMessage,
-    slurm_sys::job_info_msg_t,
-    "Information about a single job.
+    ReservationRecord,
+    slurm_sys::slurmdb_reservation_rec_t,
+    "\
+Accounting information about a reservation.
 
-This type implements `Deref` to `JobInfo` and so can be essentially be
-treated as a `JobInfo`. Due to how the Slurm library manages memory, this
-separate type is necessary in some cases."
+The following items in the Slurm API are *not* exposed in these Rust bindings:
+
+```ignore
+pub struct slurmdb_reservation_rec_t {
+    pub flags: u16,
+    pub node_inx: *mut c_char,
+    pub time_start_prev: time_t,
+    pub tres_list: List,
+}
+```
+
+"
 );
 
-impl Deref for SingleJobInfoMessage {
-    type Target = JobInfo;
+#[cfg(feature = "slurmdb")]
+impl_wrapper_debug!(ReservationRecord; id, name, cluster, time_start, time_end);
 
-    fn deref(&self) -> &JobInfo {
-        unsafe { JobInfo::transmute_ptr(&self.sys_data().job_array) }
+#[cfg(feature = "slurmdb")]
+impl ReservationRecord {
+    /// Get the IDs of the associations allowed to use this reservation.
+    ///
+    /// This is a comma-separated list of textual representations of numeric
+    /// association IDs.
+    pub fn assocs(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().assocs) }
     }
-}
 
-impl DerefMut for SingleJobInfoMessage {
-    fn deref_mut(&mut self) -> &mut JobInfo {
-        unsafe { JobInfo::transmute_ptr_mut(&mut self.sys_data_mut().job_array) }
+    /// Get the name of the cluster of this reservation.
+    pub fn cluster(&self) -> Cow<str> {
+        unsafe { CStr::from_ptr(self.sys_data().cluster) }.to_string_lossy()
     }
-}
 
-make_owned_version!(@customdrop SingleJobInfoMessage, SingleJobInfoMessageOwned,
-                    "An owned version of `SingleJobInfoMessage`.");
+    /// Get the unique ID number of this reservation.
+    pub fn id(&self) -> u32 {
+        self.sys_data().id
+    }
 
-impl Drop for SingleJobInfoMessageOwned {
-    fn drop(&mut self) {
-        unsafe { slurm_sys::slurm_free_job_info_msg((self.0).0) };
+    /// Get the reservation’s name.
+    pub fn name(&self) -> Cow<str> {
+        unsafe { CStr::from_ptr(self.sys_data().name) }.to_string_lossy()
     }
-}
 
-make_slurm_wrap_struct!(
-    DatabaseConnection,
-    c_void,
-    "A connection to the Slurm accounting database."
-);
+    /// Get the hostlist expression of the nodes included in the reservation.
+    pub fn nodes(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().nodes) }
+    }
 
-impl DatabaseConnection {
-    /// Query for information about jobs.
-    pub fn get_jobs(&self, filters: &JobFilters) -> Result<SlurmListOwned<JobRecord>, SlurmError> {
-        let ptr = pstry!(slurm_sys::slurmdb_jobs_get(self.0, filters.0));
-        Ok(unsafe { SlurmListOwned::assume_ownership(ptr as _) })
+    /// Get the time at which the reservation ended or will end.
+    pub fn time_end(&self) -> DateTime<Utc> {
+        unix_time(self.sys_data().time_end as i64)
+    }
+
+    /// Get the time at which the reservation started or will start.
+    pub fn time_start(&self) -> DateTime<Utc> {
+        unix_time(self.sys_data().time_start as i64)
     }
-}
 
-make_owned_version!(@customdrop DatabaseConnection, DatabaseConnectionOwned,
-                    "An owned version of `DatabaseConnection`.");
+    /// Get the TRES (trackable resources) included in the reservation.
+    ///
+    /// This is returned as the textual representation used by Slurm, in
+    /// which the resources are identified by their numeric TRES IDs.
+    pub fn tres(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().tres_str) }
+    }
 
-impl DatabaseConnectionOwned {
-    /// Connect to the Slurm database.
-    pub fn new() -> Result<Self, SlurmError> {
-        let ptr = pstry!(slurm_sys::slurmdb_connection_get());
-        Ok(unsafe { DatabaseConnectionOwned::assume_ownership(ptr) })
+    /// Get the amount of wallclock time, in seconds, during which the
+    /// reserved resources were not used by any job.
+    ///
+    /// This is only computed if the query asked for usage using
+    /// `ReservationFilters::with_usage`.
+    pub fn unused_wall(&self) -> f64 {
+        self.sys_data().unused_wall
     }
 }
 
-impl Drop for DatabaseConnectionOwned {
+#[cfg(feature = "slurmdb")]
+make_owned_version!(@customdrop ReservationRecord, ReservationRecordOwned, "An owned version of `ReservationRecord`.");
+
+#[cfg(feature = "slurmdb")]
+impl Drop for ReservationRecordOwned {
     fn drop(&mut self) {
-        // This function can return error codes, but we're not in a position
-        // to do anything about it in the Drop call.
-        let _ignored = unsafe { slurm_sys::slurmdb_connection_close(&mut (self.0).0) };
+        unsafe { slurm_sys::slurmdb_destroy_reservation_rec((self.0).0 as _) };
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl_owned_list_item!(ReservationRecord, ReservationRecordOwned);
+
+#[cfg(feature = "slurmdb")]
+impl ReservationRecord {
+    /// Copy the data in this record into a plain-Rust structure.
+    ///
+    /// The result does not refer to any Slurm memory, so it may outlive the
+    /// query result that contained this record.
+    pub fn to_snapshot(&self) -> ReservationRecordSnapshot {
+        ReservationRecordSnapshot {
+            assocs: self.assocs().map(|s| s.into_owned()),
+            cluster: self.cluster().into_owned(),
+            id: self.id(),
+            name: self.name().into_owned(),
+            nodes: self.nodes().map(|s| s.into_owned()),
+            time_end: self.time_end(),
+            time_start: self.time_start(),
+            tres: self.tres().map(|s| s.into_owned()),
+            unused_wall: self.unused_wall(),
+        }
     }
 }
 
+/// A plain-Rust copy of the data in a `ReservationRecord`.
+///
+/// See the accessors of `ReservationRecord` for the meanings of the fields.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ReservationRecordSnapshot {
+    pub assocs: Option<String>,
+    pub cluster: String,
+    pub id: u32,
+    pub name: String,
+    pub nodes: Option<String>,
+    pub time_end: DateTime<Utc>,
+    pub time_start: DateTime<Utc>,
+    pub tres: Option<String>,
+    pub unused_wall: f64,
+}
+
+#[cfg(feature = "slurmdb")]
+impl_snapshot_traits!(ReservationRecord, ReservationRecordSnapshot);
+
+#[cfg(feature = "slurmdb")]
 make_slurm_wrap_struct!(
-    JobFilters,
-    slurm_sys::slurmdb_job_cond_t,
+    WckeyFilters,
+    slurm_sys::slurmdb_wckey_cond_t,
     "\
-A set of filters for identifying jobs of interest when querying the Slurm
-accounting database.
+A set of filters for identifying WCKeys (workload characterization keys) of
+interest when querying the Slurm accounting database.
 
 The following items in the Slurm API are *not* exposed in these Rust bindings:
 
 ```ignore
-pub struct slurmdb_job_cond_t {
-    pub acct_list: List,
-    pub associd_list: List,
-    pub cluster_list: List,
-    pub cpus_max: u32,
-    pub cpus_min: u32,
-    pub duplicates: u16,
-    pub exitcode: i32,
+pub struct slurmdb_wckey_cond_t {
     pub format_list: List,
-    pub groupid_list: List,
-    pub jobname_list: List,
-    pub nodes_max: u32,
-    pub nodes_min: u32,
-    pub partition_list: List,
-    pub qos_list: List,
-    pub resv_list: List,
-    pub resvid_list: List,
-    pub state_list: List,
-    pub timelimit_max: u32,
-    pub timelimit_min: u32,
+    pub id_list: List,
     pub usage_end: time_t,
-    pub used_nodes: *mut c_char,
-    pub wckey_list: List,
-    pub without_steps: u16,
-    pub without_usage_truncation: u16,
+    pub usage_start: time_t,
+    pub with_usage: u16,
 }
 ```
 
 "
 );
 
-impl JobFilters {
-    pub fn step_list(&self) -> &SlurmList<JobStepFilter> {
-        unsafe { SlurmList::transmute_ptr(&self.sys_data().step_list) }
+#[cfg(feature = "slurmdb")]
+impl_wrapper_debug!(WckeyFilters);
+
+#[cfg(feature = "slurmdb")]
+impl WckeyFilters {
+    /// Access the list of cluster names that will match this set of filters.
+    pub fn cluster_list(&self) -> &SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().cluster_list) }
     }
 
-    pub fn step_list_mut(&mut self) -> &mut SlurmList<JobStepFilter> {
-        unsafe { SlurmList::transmute_ptr_mut(&mut self.sys_data_mut().step_list) }
+    /// Mutably access the list of cluster names that will match this set of
+    /// filters.
+    pub fn cluster_list_mut(&mut self) -> &mut SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr_mut(&mut self.sys_data_mut().cluster_list) }
     }
 
-    /// Add a filter on the earliest job "usage time".
-    ///
-    /// TODO: what is "usage time" really?
-    pub fn usage_start(&mut self, time: DateTime<Utc>) -> &Self {
-        self.sys_data_mut().without_usage_truncation = 0;
-        self.sys_data_mut().usage_start = time.timestamp() as _;
+    /// Access the list of WCKey names that will match this set of filters.
+    pub fn name_list(&self) -> &SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().name_list) }
+    }
+
+    /// Mutably access the list of WCKey names that will match this set of
+    /// filters.
+    pub fn name_list_mut(&mut self) -> &mut SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr_mut(&mut self.sys_data_mut().name_list) }
+    }
+
+    /// Specify whether to only match the default WCKeys of each user.
+    pub fn only_defs(&mut self, value: bool) -> &mut Self {
+        self.sys_data_mut().only_defs = value as u16;
         self
     }
 
-    /// Access the list of user ID numbers that will match this set of filters.
-    ///
-    /// Note that this list should consist of *textual* representations of
-    /// *numeric* user IDs. Yes, it's silly.
-    pub fn userid_list(&self) -> &SlurmList<*mut c_char> {
-        unsafe { SlurmList::transmute_ptr(&self.sys_data().userid_list) }
+    /// Access the list of user names that will match this set of filters.
+    pub fn user_list(&self) -> &SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().user_list) }
     }
 
-    /// Mutably access the list of user ID numbers that will match this set of
+    /// Mutably access the list of user names that will match this set of
     /// filters.
-    pub fn userid_list_mut(&mut self) -> &mut SlurmList<*mut c_char> {
-        unsafe { SlurmList::transmute_ptr_mut(&mut self.sys_data_mut().userid_list) }
+    pub fn user_list_mut(&mut self) -> &mut SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr_mut(&mut self.sys_data_mut().user_list) }
+    }
+
+    /// Specify whether the query should include deleted WCKeys.
+    pub fn with_deleted(&mut self, value: bool) -> &mut Self {
+        self.sys_data_mut().with_deleted = value as u16;
+        self
     }
 }
 
-make_owned_version!(
-    JobFilters,
-    JobFiltersOwned,
-    "An owned version of `JobFilters`"
-);
+#[cfg(feature = "slurmdb")]
+make_owned_version!(@customdrop WckeyFilters, WckeyFiltersOwned, "An owned version of `WckeyFilters`.");
 
-impl Default for JobFiltersOwned {
-    fn default() -> Self {
-        let mut inst = unsafe { Self::alloc_zeroed() };
-        {
-            let sdm = inst.sys_data_mut();
-            sdm.without_usage_truncation = 1;
-        }
-        inst
+#[cfg(feature = "slurmdb")]
+impl WckeyFiltersOwned {
+    /// Create a new, empty set of filters.
+    pub fn new() -> Result<Self, Error> {
+        Ok(unsafe { Self::alloc_zeroed() }?)
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+impl Drop for WckeyFiltersOwned {
+    fn drop(&mut self) {
+        unsafe { slurm_sys::slurmdb_destroy_wckey_cond((self.0).0 as _) };
     }
 }
 
+#[cfg(feature = "slurmdb")]
 make_slurm_wrap_struct!(
-    JobStepFilter,
-    slurm_sys::slurmdb_selected_step_t,
-    "A filter for selecting jobs and job steps."
+    WckeyRecord,
+    slurm_sys::slurmdb_wckey_rec_t,
+    "\
+Accounting information about a WCKey (workload characterization key) of a
+user.
+
+The following items in the Slurm API are *not* exposed in these Rust bindings:
+
+```ignore
+pub struct slurmdb_wckey_rec_t {
+    pub accounting_list: List,
+}
+```
+
+"
 );
 
-make_owned_version!(@customdrop JobStepFilter, JobStepFilterOwned, "An owned version of `JobStepFilter`.");
+#[cfg(feature = "slurmdb")]
+impl_wrapper_debug!(WckeyRecord; id, name, user, cluster);
 
-impl Drop for JobStepFilterOwned {
+#[cfg(feature = "slurmdb")]
+impl WckeyRecord {
+    /// Get the name of the cluster on which this WCKey is valid.
+    pub fn cluster(&self) -> Cow<str> {
+        unsafe { CStr::from_ptr(self.sys_data().cluster) }.to_string_lossy()
+    }
+
+    /// Get the unique ID number of this WCKey.
+    pub fn id(&self) -> u32 {
+        self.sys_data().id
+    }
+
+    /// Test whether this is the default WCKey of its user.
+    pub fn is_default(&self) -> bool {
+        self.sys_data().is_def == 1
+    }
+
+    /// Get the WCKey’s name.
+    pub fn name(&self) -> Cow<str> {
+        unsafe { CStr::from_ptr(self.sys_data().name) }.to_string_lossy()
+    }
+
+    /// Get the numeric UID of the user that may use this WCKey.
+    pub fn uid(&self) -> u32 {
+        self.sys_data().uid
+    }
+
+    /// Get the name of the user that may use this WCKey.
+    pub fn user(&self) -> Cow<str> {
+        unsafe { CStr::from_ptr(self.sys_data().user) }.to_string_lossy()
+    }
+}
+
+#[cfg(feature = "slurmdb")]
+make_owned_version!(@customdrop WckeyRecord, WckeyRecordOwned, "An owned version of `WckeyRecord`.");
+
+#[cfg(feature = "slurmdb")]
+impl Drop for WckeyRecordOwned {
     fn drop(&mut self) {
-        unsafe { slurm_sys::slurmdb_destroy_selected_step((self.0).0 as _) };
+        unsafe { slurm_sys::slurmdb_destroy_wckey_rec((self.0).0 as _) };
     }
 }
 
-impl JobStepFilterOwned {
-    /// Create a new job step filter.
-    pub fn new(jid: JobId) -> Self {
-        let mut inst = unsafe { Self::alloc_zeroed() };
-        {
-            let sdm = inst.sys_data_mut();
-            sdm.array_task_id = slurm_sys::SLURMRS_NO_VAL;
-            sdm.jobid = jid;
-            #[cfg(slurm_api_selected_step_t_pack_job_offset)]
-            {
-                sdm.pack_job_offset = slurm_sys::SLURMRS_NO_VAL;
-            }
-            sdm.stepid = slurm_sys::SLURMRS_NO_VAL;
+#[cfg(feature = "slurmdb")]
+impl_owned_list_item!(WckeyRecord, WckeyRecordOwned);
+
+#[cfg(feature = "slurmdb")]
+impl WckeyRecord {
+    /// Copy the data in this record into a plain-Rust structure.
+    ///
+    /// The result does not refer to any Slurm memory, so it may outlive the
+    /// query result that contained this record.
+    pub fn to_snapshot(&self) -> WckeyRecordSnapshot {
+        WckeyRecordSnapshot {
+            cluster: self.cluster().into_owned(),
+            id: self.id(),
+            is_default: self.is_default(),
+            name: self.name().into_owned(),
+            uid: self.uid(),
+            user: self.user().into_owned(),
         }
-        inst
     }
 }
 
-impl SlurmList<JobStepFilter> {
-    pub fn append(&mut self, item: JobStepFilterOwned) {
-        let item = unsafe { item.give_up_ownership() };
+/// A plain-Rust copy of the data in a `WckeyRecord`.
+///
+/// See the accessors of `WckeyRecord` for the meanings of the fields.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct WckeyRecordSnapshot {
+    pub cluster: String,
+    pub id: u32,
+    pub is_default: bool,
+    pub name: String,
+    pub uid: u32,
+    pub user: String,
+}
 
-        if self.0.is_null() {
-            // XXX if malloc fails, I think this function will abort under us.
-            self.0 = unsafe {
-                slurm_sys::slurm_list_create(Some(slurm_sys::slurmdb_destroy_selected_step))
-            };
-        }
+#[cfg(feature = "slurmdb")]
+impl_snapshot_traits!(WckeyRecord, WckeyRecordSnapshot);
+
+#[cfg(feature = "slurmdb")]
+make_slurm_wrap_struct!(
+    TransactionFilters,
+    slurm_sys::slurmdb_txn_cond_t,
+    "\
+A set of filters for identifying transactions of interest when querying the
+Slurm accounting database.
+
+The following items in the Slurm API are *not* exposed in these Rust bindings:
+
+```ignore
+pub struct slurmdb_txn_cond_t {
+    pub action_list: List,
+    pub format_list: List,
+    pub id_list: List,
+    pub info_list: List,
+    pub name_list: List,
+    pub with_assoc_info: u16,
+}
+```
+
+"
+);
+
+#[cfg(feature = "slurmdb")]
+impl_wrapper_debug!(TransactionFilters);
+
+#[cfg(feature = "slurmdb")]
+impl TransactionFilters {
+    /// Access the list of account names that will match this set of filters.
+    pub fn acct_list(&self) -> &SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().acct_list) }
+    }
+
+    /// Mutably access the list of account names that will match this set of
+    /// filters.
+    pub fn acct_list_mut(&mut self) -> &mut SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr_mut(&mut self.sys_data_mut().acct_list) }
+    }
+
+    /// Access the list of actor names that will match this set of filters.
+    pub fn actor_list(&self) -> &SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().actor_list) }
+    }
+
+    /// Mutably access the list of names of the users who performed the
+    /// transactions that will match this set of filters.
+    pub fn actor_list_mut(&mut self) -> &mut SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr_mut(&mut self.sys_data_mut().actor_list) }
+    }
+
+    /// Access the list of cluster names that will match this set of filters.
+    pub fn cluster_list(&self) -> &SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().cluster_list) }
+    }
+
+    /// Mutably access the list of cluster names that will match this set of
+    /// filters.
+    pub fn cluster_list_mut(&mut self) -> &mut SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr_mut(&mut self.sys_data_mut().cluster_list) }
+    }
+
+    /// Only match transactions that occurred at or before the specified time.
+    pub fn time_end(&mut self, time: DateTime<Utc>) -> &mut Self {
+        self.sys_data_mut().time_end = time.timestamp() as _;
+        self
+    }
+
+    /// Only match transactions that occurred at or after the specified time.
+    pub fn time_start(&mut self, time: DateTime<Utc>) -> &mut Self {
+        self.sys_data_mut().time_start = time.timestamp() as _;
+        self
+    }
+
+    /// Access the list of user names that will match this set of filters.
+    pub fn user_list(&self) -> &SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().user_list) }
+    }
 
-        unsafe {
-            slurm_sys::slurm_list_append(self.0, item.0 as _);
-        }
+    /// Mutably access the list of names of the users affected by the
+    /// transactions that will match this set of filters.
+    pub fn user_list_mut(&mut self) -> &mut SlurmList<*mut c_char> {
+        unsafe { SlurmList::transmute_ptr_mut(&mut self.sys_data_mut().user_list) }
     }
 }
 
-make_slurm_wrap_struct!(
-    JobRecord,
-    slurm_sys::slurmdb_job_rec_t,
-    "\
-Accounting information about a job.
+#[cfg(feature = "slurmdb")]
+make_owned_version!(@customdrop TransactionFilters, TransactionFiltersOwned, "An owned version of `TransactionFilters`.");
 
-The following items in the Slurm API are *not* exposed in these Rust bindings:
+#[cfg(feature = "slurmdb")]
+impl TransactionFiltersOwned {
+    /// Create a new, empty set of filters.
+    pub fn new() -> Result<Self, Error> {
+        Ok(unsafe { Self::alloc_zeroed() }?)
+    }
+}
 
-```ignore
-pub struct slurmdb_job_rec_t {
-    pub account: *mut c_char,
-    pub admin_comment: *mut c_char,
-    pub alloc_gres: *mut c_char,
-    pub alloc_nodes: u32,
-    pub array_max_tasks: u32,
-    pub array_task_id: u32,
-    pub array_task_str: *mut c_char,
-    pub associd: u32,
-    pub blockid: *mut c_char,
-    pub cluster: *mut c_char,
-    pub derived_ec: u32,
-    pub derived_es: *mut c_char,
-    pub first_step_ptr: *mut c_void,
-    pub gid: u32,
-    pub lft: u32,
-    pub mcs_label: *mut c_char,
-    pub partition: *mut c_char,
-    pub pack_job_id: u32,
-    pub pack_job_offset: u32,
-    pub priority: u32,
-    pub qosid: u32,
-    pub req_cpus: u32,
-    pub req_gres: *mut c_char,
-    pub req_mem: u64,
-    pub resvid: u32,
-    pub resv_name: *mut c_char,
-    pub show_full: u32,
-    pub steps: List,
-    pub track_steps: u16,
-    pub tres_req_str: *mut c_char,
-    pub uid: u32,
-    pub used_gres: *mut c_char,
-    pub user: *mut c_char,
-    pub wckey: *mut c_char,
-    pub wckeyid: u32,
-    pub work_dir: *mut c_char,
+#[cfg(feature = "slurmdb")]
+impl Drop for TransactionFiltersOwned {
+    fn drop(&mut self) {
+        unsafe { slurm_sys::slurmdb_destroy_txn_cond((self.0).0 as _) };
+    }
 }
-```
 
-(The above listing omits fields that would be handled by the
-`JobStepRecordSharedFields` trait.)
+#[cfg(feature = "slurmdb")]
+make_slurm_wrap_struct!(
+    TransactionRecord,
+    slurm_sys::slurmdb_txn_rec_t,
+    "\
+Information about a transaction that modified the Slurm accounting database.
 "
 );
 
-/// A trait for accessing fields common to SlurmDB job records and step
-/// records.
-pub trait JobStepRecordSharedFields {
-    /// Get the job/step's end time, or None if it has not yet ended.
-    fn end_time(&self) -> Option<DateTime<Utc>>;
+#[cfg(feature = "slurmdb")]
+impl_wrapper_debug!(TransactionRecord; id, action, actor_name, timestamp);
 
-    /// Get the job/step's exit code, or None if it has not yet ended.
-    fn exit_code(&self) -> Option<i32>;
+#[cfg(feature = "slurmdb")]
+impl TransactionRecord {
+    /// Get the names of the accounts affected by this transaction, if any.
+    pub fn accounts(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().accts) }
+    }
 
-    /// Get the maximum "virtual memory size" of the job/step, in kibibytes.
+    /// Get the raw code of the action performed in this transaction.
     ///
-    /// This quantity is not available (i.e., the function returns `None`)
-    /// until the job has finished running.
-    fn max_vm_size(&self) -> Option<u64>;
+    /// This is one of the `slurmdbd` message type codes (such as
+    /// `DBD_ADD_ACCOUNTS`), which are not exposed in Slurm’s public headers.
+    pub fn action(&self) -> u16 {
+        self.sys_data().action
+    }
 
-    /// Get the job/step's start time, or None if it has not yet started.
-    fn start_time(&self) -> Option<DateTime<Utc>>;
+    /// Get the name of the user who performed this transaction.
+    pub fn actor_name(&self) -> Cow<str> {
+        unsafe { CStr::from_ptr(self.sys_data().actor_name) }.to_string_lossy()
+    }
 
-    /// Get the job/step's state.
-    fn state(&self) -> JobState;
+    /// Get the names of the clusters affected by this transaction, if any.
+    pub fn clusters(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().clusters) }
+    }
 
-    /// Get the wallclock time taken by the job/step: end time minus start time.
-    ///
-    /// Returns None if the job/step has not yet completed (or even started).
-    fn wallclock_duration(&self) -> Option<Duration>;
-}
+    /// Get the unique ID number of this transaction.
+    pub fn id(&self) -> u32 {
+        self.sys_data().id
+    }
 
-/// We implement the JobStepRecordSharedFields trait with a macro; that seems
-/// like the easiest thing to do.
-///
-/// The complete list of overlapping fields is:
-///
-/// ```ignore
-/// {
-///    elapsed: u32,
-///    end: i64,
-///    exitcode: i32,
-///    nodes: *mut c_char,
-///    requid: u32,
-///    start: i64,
-///    state: u32,
-///    stats: &slurm_sys::slurmdb_stats_t,
-///    suspended: u32,
-///    sys_cpu_sec: u32,
-///    sys_cpu_usec: u32,
-///    tot_cpu_sec: u32,
-///    tot_cpu_usec: u32,
-///    tres_alloc_str: *mut c_char,
-///    user_cpu_sec: u32,
-///    user_cpu_usec: u32,
-/// }
-/// ```
-macro_rules! impl_job_step_record_shared_fields {
-    ($type:path) => {
-        impl JobStepRecordSharedFields for $type {
-            fn end_time(&self) -> Option<DateTime<Utc>> {
-                match self.sys_data().end as i64 {
-                    0 => None,
-                    t => Some(Utc.timestamp(t, 0)),
-                }
-            }
+    /// Get a textual description of the values set by this transaction.
+    pub fn set_info(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().set_info) }
+    }
 
-            fn exit_code(&self) -> Option<i32> {
-                match self.sys_data().end as i64 {
-                    0 => None,
-                    _ => Some(self.sys_data().exitcode as i32),
-                }
-            }
+    /// Get the time at which this transaction occurred.
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        unix_time(self.sys_data().timestamp as i64)
+    }
 
-            fn max_vm_size(&self) -> Option<u64> {
-                match self.sys_data().stats.vsize_max {
-                    slurm_sys::SLURMRS_NO_VAL64 => None,
-                    other => Some(other),
-                }
-            }
+    /// Get the names of the users affected by this transaction, if any.
+    pub fn users(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().users) }
+    }
 
-            fn start_time(&self) -> Option<DateTime<Utc>> {
-                match self.sys_data().start as i64 {
-                    0 => None,
-                    t => Some(Utc.timestamp(t, 0)),
-                }
-            }
+    /// Get a textual description of the objects selected by this
+    /// transaction.
+    pub fn where_query(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().where_query) }
+    }
+}
 
-            fn state(&self) -> JobState {
-                JobState::from_slurm(self.sys_data().state).expect("unhandled job_state code")
-            }
+#[cfg(feature = "slurmdb")]
+make_owned_version!(@customdrop TransactionRecord, TransactionRecordOwned, "An owned version of `TransactionRecord`.");
 
-            fn wallclock_duration(&self) -> Option<Duration> {
-                match (self.start_time(), self.end_time()) {
-                    (Some(start), Some(end)) => Some(end.signed_duration_since(start)),
-                    _ => None,
-                }
-            }
-        }
-    };
+#[cfg(feature = "slurmdb")]
+impl Drop for TransactionRecordOwned {
+    fn drop(&mut self) {
+        unsafe { slurm_sys::slurmdb_destroy_txn_rec((self.0).0 as _) };
+    }
 }
 
-impl_job_step_record_shared_fields!(JobRecord);
+#[cfg(feature = "slurmdb")]
+impl_owned_list_item!(TransactionRecord, TransactionRecordOwned);
 
-impl JobRecord {
-    /// Get the unique identifier of the array group this job belonged to.
+#[cfg(feature = "slurmdb")]
+impl TransactionRecord {
+    /// Copy the data in this record into a plain-Rust structure.
     ///
-    /// Returns None if this job was not part of an array.
-    pub fn array_job_id(&self) -> Option<JobId> {
-        match self.sys_data().array_job_id {
-            0 => None,
-            other => Some(other),
+    /// The result does not refer to any Slurm memory, so it may outlive the
+    /// query result that contained this record.
+    pub fn to_snapshot(&self) -> TransactionRecordSnapshot {
+        TransactionRecordSnapshot {
+            accounts: self.accounts().map(|s| s.into_owned()),
+            action: self.action(),
+            actor_name: self.actor_name().into_owned(),
+            clusters: self.clusters().map(|s| s.into_owned()),
+            id: self.id(),
+            set_info: self.set_info().map(|s| s.into_owned()),
+            timestamp: self.timestamp(),
+            users: self.users().map(|s| s.into_owned()),
+            where_query: self.where_query().map(|s| s.into_owned()),
         }
     }
+}
 
-    /// Get the job's "eligible" time, or None if the job is not yet eligible to run.
-    pub fn eligible_time(&self) -> Option<DateTime<Utc>> {
-        match self.sys_data().eligible as i64 {
-            0 => None,
-            t => Some(Utc.timestamp(t, 0)),
-        }
-    }
+/// A plain-Rust copy of the data in a `TransactionRecord`.
+///
+/// See the accessors of `TransactionRecord` for the meanings of the fields.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct TransactionRecordSnapshot {
+    pub accounts: Option<String>,
+    pub action: u16,
+    pub actor_name: String,
+    pub clusters: Option<String>,
+    pub id: u32,
+    pub set_info: Option<String>,
+    pub timestamp: DateTime<Utc>,
+    pub users: Option<String>,
+    pub where_query: Option<String>,
+}
 
-    /// Get the job's ID number.
-    pub fn job_id(&self) -> JobId {
-        self.sys_data().jobid
-    }
+#[cfg(feature = "slurmdb")]
+impl_snapshot_traits!(TransactionRecord, TransactionRecordSnapshot);
 
-    /// Get the job's name.
-    pub fn job_name(&self) -> Cow<str> {
-        unsafe { CStr::from_ptr(self.sys_data().jobname) }.to_string_lossy()
-    }
+/// Build the list of job-size bin edges passed to the reporting functions.
+#[cfg(feature = "slurmdb")]
+fn job_size_grouping_list(sizes: &[u32]) -> Result<SlurmListOwned<*mut c_char>, SlurmError> {
+    let mut list = unsafe { SlurmListOwned::assume_ownership(0 as _) };
 
-    /// Get the job's submission time.
-    pub fn submit_time(&self) -> DateTime<Utc> {
-        Utc.timestamp(self.sys_data().submit as i64, 0)
+    for size in sizes {
+        list.append(format!("{}", size))?;
     }
 
-    /// Get the wallclock time spent waiting for the job to become eligible,
-    /// or None if the job has not yet become eligible to run.
-    pub fn eligible_wait_duration(&self) -> Option<Duration> {
-        self.eligible_time()
-            .map(|t| t.signed_duration_since(self.submit_time()))
+    Ok(list)
+}
+
+#[cfg(feature = "slurmdb")]
+make_slurm_wrap_struct!(
+    ReportClusterGrouping,
+    slurm_sys::slurmdb_report_cluster_grouping_t,
+    "\
+The job-size report for one cluster.
+"
+);
+
+#[cfg(feature = "slurmdb")]
+impl_wrapper_debug!(ReportClusterGrouping; cluster, count);
+
+#[cfg(feature = "slurmdb")]
+impl ReportClusterGrouping {
+    /// Get the per-account groupings of the report.
+    pub fn acct_list(&self) -> &SlurmList<ReportAccountGrouping> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().acct_list) }
     }
 
-    /// Get the job's time limit in minutes.
-    pub fn time_limit(&self) -> u32 {
-        self.sys_data().timelimit
+    /// Get the name of the cluster.
+    pub fn cluster(&self) -> Cow<str> {
+        unsafe { CStr::from_ptr(self.sys_data().cluster) }.to_string_lossy()
     }
 
-    /// Get the wallclock time spent waiting for the job to start, or None
-    /// if the job has not yet started.
-    ///
-    /// This includes time that the job spent waiting to become eligible to run.
-    pub fn wait_duration(&self) -> Option<Duration> {
-        self.start_time()
-            .map(|t| t.signed_duration_since(self.submit_time()))
+    /// Get the number of jobs included in the report for this cluster.
+    pub fn count(&self) -> u64 {
+        self.sys_data().count as u64
     }
 
-    /// Steps.
-    pub fn steps(&self) -> &SlurmList<StepRecord> {
-        unsafe { SlurmList::transmute_ptr(&self.sys_data().steps) }
+    /// Get the total TRES usage of the jobs included in the report for this
+    /// cluster.
+    pub fn tres_list(&self) -> &SlurmList<TresRecord> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().tres_list) }
     }
 }
 
+#[cfg(feature = "slurmdb")]
 make_slurm_wrap_struct!(
-    StepRecord,
-    slurm_sys::slurmdb_step_rec_t,
+    ReportAccountGrouping,
+    slurm_sys::slurmdb_report_acct_grouping_t,
     "\
-Accounting information about a step within a job.
+The job-size report for one account (or WCKey) within a cluster.
+"
+);
 
-The following items in the Slurm API are *not* exposed in these Rust bindings:
+#[cfg(feature = "slurmdb")]
+impl_wrapper_debug!(ReportAccountGrouping; account, count);
 
-```ignore
-pub struct slurmdb_step_rec_t {
-    pub job_ptr: *mut slurmdb_job_rec_t,
-    pub nnodes: u32,
-    pub ntasks: u32,
-    pub pid_str: *mut c_char,
-    pub req_cpufreq_min: u32,
-    pub req_cpufreq_max: u32,
-    pub req_cpufreq_gov: u32,
-    pub task_dist: u32,
+#[cfg(feature = "slurmdb")]
+impl ReportAccountGrouping {
+    /// Get the name of the account (or WCKey).
+    pub fn account(&self) -> Cow<str> {
+        unsafe { CStr::from_ptr(self.sys_data().acct) }.to_string_lossy()
+    }
+
+    /// Get the number of jobs included in the report for this account.
+    pub fn count(&self) -> u64 {
+        self.sys_data().count as u64
+    }
+
+    /// Get the job-size bins for this account.
+    pub fn groups(&self) -> &SlurmList<ReportJobGrouping> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().groups) }
+    }
+
+    /// Get the total TRES usage of the jobs included in the report for this
+    /// account.
+    pub fn tres_list(&self) -> &SlurmList<TresRecord> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().tres_list) }
+    }
 }
-```
 
-(The above listing omits fields that would be handled by the
-`JobStepRecordSharedFields` trait.)
+#[cfg(feature = "slurmdb")]
+make_slurm_wrap_struct!(
+    ReportJobGrouping,
+    slurm_sys::slurmdb_report_job_grouping_t,
+    "\
+One job-size bin of a job-size report.
 "
 );
 
-impl_job_step_record_shared_fields!(StepRecord);
+#[cfg(feature = "slurmdb")]
+impl_wrapper_debug!(ReportJobGrouping; min_size, max_size, count);
 
-impl StepRecord {
-    /// Get the step's ID.
-    pub fn step_id(&self) -> StepId {
-        self.sys_data().stepid
+#[cfg(feature = "slurmdb")]
+impl ReportJobGrouping {
+    /// Get the number of jobs in this bin.
+    pub fn count(&self) -> u64 {
+        self.sys_data().count as u64
     }
 
-    /// Get the step's name.
-    pub fn step_name(&self) -> Cow<str> {
-        unsafe { CStr::from_ptr(self.sys_data().stepname) }.to_string_lossy()
+    /// Get the largest job size included in this bin.
+    pub fn max_size(&self) -> u32 {
+        self.sys_data().max_size
+    }
+
+    /// Get the smallest job size included in this bin.
+    pub fn min_size(&self) -> u32 {
+        self.sys_data().min_size
+    }
+
+    /// Get the total TRES usage of the jobs in this bin.
+    ///
+    /// Use `TresRecord::alloc_secs` to obtain the usage of each TRES.
+    pub fn tres_list(&self) -> &SlurmList<TresRecord> {
+        unsafe { SlurmList::transmute_ptr(&self.sys_data().tres_list) }
     }
 }
 
@@ -1345,13 +10639,10 @@ The following items in the Slurm API are *not* exposed in these Rust bindings:
 
 ```ignore
 pub struct job_descriptor {
-    pub account: *mut c_char,
     pub acctg_freq: *mut c_char,
-    pub admin_comment: *mut c_char,
     pub alloc_node: *mut c_char,
     pub alloc_resp_port: u16,
     pub alloc_sid: u32,
-    pub array_inx: *mut c_char,
     pub array_bitmap: *mut c_void,
     pub begin_time: time_t,
     pub bitflags: u32,
@@ -1360,7 +10651,6 @@ pub struct job_descriptor {
     pub ckpt_dir: *mut c_char,
     pub clusters: *mut c_char,
     pub cluster_features: *mut c_char,
-    pub comment: *mut c_char,
     pub contiguous: u16,
     pub core_spec: u16,
     pub cpu_bind: *mut c_char,
@@ -1381,7 +10671,6 @@ pub struct job_descriptor {
     pub fed_siblings_viable: u64,
     pub gres: *mut c_char,
     pub immediate: u16,
-    pub job_id: u32,
     pub job_id_str: *mut c_char,
     pub kill_on_node_fail: u16,
     pub licenses: *mut c_char,
@@ -1400,9 +10689,7 @@ pub struct job_descriptor {
     pub pack_job_offset: u32,
     pub plane_size: u16,
     pub power_flags: u8,
-    pub priority: u32,
     pub profile: u32,
-    pub qos: *mut c_char,
     pub reboot: u16,
     pub resp_host: *mut c_char,
     pub restart_cnt: u16,
@@ -1418,11 +10705,7 @@ pub struct job_descriptor {
     pub warn_flags: u16,
     pub warn_signal: u16,
     pub warn_time: u16,
-    pub cpus_per_task: u16,
-    pub min_cpus: u32,
     pub max_cpus: u32,
-    pub min_nodes: u32,
-    pub max_nodes: u32,
     pub boards_per_node: u16,
     pub sockets_per_board: u16,
     pub sockets_per_node: u16,
@@ -1433,7 +10716,6 @@ pub struct job_descriptor {
     pub ntasks_per_core: u16,
     pub ntasks_per_board: u16,
     pub pn_min_cpus: u16,
-    pub pn_min_memory: u64,
     pub pn_min_tmp_disk: u32,
     pub geometry: [u16; 5],
     pub conn_type: [u16; 5],
@@ -1456,21 +10738,82 @@ pub struct job_descriptor {
 "
 );
 
+impl_wrapper_debug!(JobDescriptor; uid, gid, num_tasks, time_limit);
+
 impl JobDescriptor {
+    /// Get the comment attached to this job by an administrator, if any.
+    pub fn admin_comment(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().admin_comment) }
+    }
+
+    /// Get the comment attached to this job, if any.
+    pub fn comment(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().comment) }
+    }
+
     /// Get the group ID associated with this job.
     pub fn gid(&self) -> u32 {
         self.sys_data().group_id
     }
 
-    /// Set the group ID associated with this job.
-    pub fn set_gid(&mut self, value: u32) -> &mut Self {
-        self.sys_data_mut().group_id = value;
+    /// Set the group ID associated with this job.
+    pub fn set_gid(&mut self, value: u32) -> &mut Self {
+        self.sys_data_mut().group_id = value;
+        self
+    }
+
+    /// Set the group ID associated with this job to that of the current process.
+    pub fn set_gid_current(&mut self) -> &mut Self {
+        self.set_gid(unsafe { libc::getgid() })
+    }
+
+    /// Get the ID of the existing job that this descriptor modifies, if it
+    /// was created with `JobDescriptorOwned::for_update`.
+    pub fn job_id(&self) -> Option<JobId> {
+        match self.sys_data().job_id {
+            0 | slurm_sys::SLURMRS_NO_VAL => None,
+            other => Some(other),
+        }
+    }
+
+    /// Get the minimum amount of memory required by this job, if specified.
+    pub fn min_memory(&self) -> Option<MemSize> {
+        MemSize::from_slurm(self.sys_data().pn_min_memory)
+    }
+
+    /// Set the minimum amount of memory required by this job.
+    ///
+    /// This is equivalent to the `--mem` or `--mem-per-cpu` options of
+    /// `sbatch`, depending on the variant of `value`.
+    pub fn set_min_memory(&mut self, value: MemSize) -> &mut Self {
+        self.sys_data_mut().pn_min_memory = value.to_slurm();
+        self
+    }
+
+    /// Get the minimum number of CPUs required by this job.
+    pub fn min_cpus(&self) -> u32 {
+        self.sys_data().min_cpus
+    }
+
+    /// Set the minimum number of CPUs required by this job.
+    pub fn set_min_cpus(&mut self, value: u32) -> &mut Self {
+        self.sys_data_mut().min_cpus = value;
         self
     }
 
-    /// Set the group ID associated with this job to that of the current process.
-    pub fn set_gid_current(&mut self) -> &mut Self {
-        self.set_gid(unsafe { libc::getgid() })
+    /// Set the number of CPUs required by each task of this job, as with
+    /// `sbatch --cpus-per-task`.
+    pub fn set_cpus_per_task(&mut self, value: u16) -> &mut Self {
+        self.sys_data_mut().cpus_per_task = value;
+        self
+    }
+
+    /// Set the number of nodes that this job requires, as with `sbatch
+    /// --nodes`.
+    pub fn set_num_nodes(&mut self, value: u32) -> &mut Self {
+        self.sys_data_mut().min_nodes = value;
+        self.sys_data_mut().max_nodes = value;
+        self
     }
 
     /// Get this job's name.
@@ -1514,16 +10857,26 @@ impl JobDescriptor {
         unsafe { CStr::from_ptr(self.sys_data().std_out) }.to_string_lossy()
     }
 
-    /// Get the time limit associated with this job, measured in minutes.
-    pub fn time_limit(&self) -> u32 {
-        self.sys_data().time_limit
+    /// Get the time limit associated with this job, or None if it has not
+    /// been specified.
+    pub fn time_limit(&self) -> Option<TimeLimit> {
+        TimeLimit::from_slurm(self.sys_data().time_limit)
     }
 
     /// Set the time limit associated with this job.
     ///
-    /// The value is measured in minutes.
-    pub fn set_time_limit(&mut self, minutes: u32) -> &mut Self {
-        self.sys_data_mut().time_limit = minutes;
+    /// Plain integers are interpreted as a number of minutes.
+    pub fn set_time_limit<T: Into<TimeLimit>>(&mut self, limit: T) -> &mut Self {
+        self.sys_data_mut().time_limit = limit.into().to_slurm();
+        self
+    }
+
+    /// Set the scheduling priority of this job.
+    ///
+    /// In an update, zero holds the job and `INFINITE` releases it, letting
+    /// the scheduler compute its priority again.
+    pub fn set_priority(&mut self, priority: u32) -> &mut Self {
+        self.sys_data_mut().priority = priority;
         self
     }
 
@@ -1556,18 +10909,56 @@ impl JobDescriptor {
         ustry!(slurm_sys::slurm_submit_batch_job(self.0, &mut msg as _));
         Ok(unsafe { SubmitResponseMessageOwned::assume_ownership(msg as _) })
     }
+
+    /// Apply the settings in this descriptor to an existing job, as with
+    /// `scontrol update job`.
+    ///
+    /// The descriptor should have been created with
+    /// `JobDescriptorOwned::for_update`. Only the settings that have been
+    /// changed from their defaults are applied.
+    pub fn update_job(&self) -> Result<(), SlurmError> {
+        if self.job_id().is_none() {
+            return Err(SlurmError::from_slurm(
+                slurm_sys::ESLURM_INVALID_JOB_ID as c_int,
+            ));
+        }
+
+        ustry!(slurm_sys::slurm_update_job(self.0));
+        Ok(())
+    }
 }
 
 make_owned_version!(@customdrop JobDescriptor, JobDescriptorOwned, "An owned version of `JobDescriptor`.");
 
 impl JobDescriptorOwned {
     /// Create a new, defaulted job descriptor.
-    pub fn new() -> Self {
-        let inst = unsafe { Self::alloc_zeroed() };
+    pub fn new() -> Result<Self, Error> {
+        let inst = unsafe { Self::alloc_zeroed() }?;
         unsafe {
             slurm_sys::slurm_init_job_desc_msg((inst.0).0);
         }
-        inst
+        Ok(inst)
+    }
+
+    /// Create a descriptor for modifying an existing job.
+    ///
+    /// Change the desired settings with the setters of this type, then apply
+    /// them with `update_job`. Settings that are left alone are not
+    /// modified.
+    ///
+    /// ```no_run
+    /// # fn main() -> Result<(), slurm::Error> {
+    /// let mut desc = slurm::JobDescriptorOwned::for_update(12345)?;
+    /// desc.set_comment("checkpointed at step 2000")?;
+    /// desc.update_job()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn for_update<J: Into<JobIdentifier>>(jid: J) -> Result<Self, Error> {
+        let jid = jid.into().resolve()?;
+        let mut inst = Self::new()?;
+        inst.sys_data_mut().job_id = jid;
+        Ok(inst)
     }
 
     fn maybe_clear_argv(&mut self) {
@@ -1577,15 +10968,18 @@ impl JobDescriptorOwned {
     }
 
     /// Specify the command-line arguments of the job.
-    pub fn set_argv<I: IntoIterator<Item = S>, S: AsRef<str>>(&mut self, argv: I) -> &mut Self {
+    pub fn set_argv<I: IntoIterator<Item = S>, S: AsRef<str>>(
+        &mut self,
+        argv: I,
+    ) -> Result<&mut Self, Error> {
+        let (ptr, size) = slurm_alloc_utf8_string_array(argv)?;
         self.maybe_clear_argv();
-        let (ptr, size) = slurm_alloc_utf8_string_array(argv);
         {
             let d = self.sys_data_mut();
             d.argv = ptr;
             d.argc = size as u32;
         }
-        self
+        Ok(self)
     }
 
     fn maybe_clear_environment(&mut self) {
@@ -1598,15 +10992,15 @@ impl JobDescriptorOwned {
     pub fn set_environment<I: IntoIterator<Item = S>, S: AsRef<str>>(
         &mut self,
         env: I,
-    ) -> &mut Self {
+    ) -> Result<&mut Self, Error> {
+        let (ptr, size) = slurm_alloc_utf8_string_array(env)?;
         self.maybe_clear_environment();
-        let (ptr, size) = slurm_alloc_utf8_string_array(env);
         {
             let d = self.sys_data_mut();
             d.environment = ptr;
             d.env_size = size as u32;
         }
-        self
+        Ok(self)
     }
 
     /// Set the UNIX environment of the job to match that of the current process.
@@ -1614,84 +11008,109 @@ impl JobDescriptorOwned {
     /// This will panic if any environment variables are not decodable as
     /// Unicode. This limitation could be worked around with some developer
     /// effort.
-    pub fn inherit_environment(&mut self) -> &mut Self {
+    pub fn inherit_environment(&mut self) -> Result<&mut Self, Error> {
         self.set_environment(std::env::vars().map(|(key, val)| format!("{}={}", key, val)))
     }
 
+    /// Set the maximum number of tasks of a job array that may run at once.
+    ///
+    /// This only has an effect when updating an existing job array with a
+    /// descriptor created by `for_update`. A value of zero removes the
+    /// limit. See also `set_array_throttle`.
+    pub fn set_array_throttle(&mut self, max_concurrent: u32) -> Result<&mut Self, Error> {
+        // For updates, Slurm interprets the array index field as the
+        // throttle, as `scontrol update ArrayTaskThrottle=` does.
+        slurm_replace_utf8_string(
+            &mut self.sys_data_mut().array_inx,
+            max_concurrent.to_string(),
+        )?;
+        Ok(self)
+    }
+
+    /// Set the indices of the tasks of a job array.
+    ///
+    /// The specification uses the syntax of `sbatch --array`, such as
+    /// `1,4,7-9` or `0-99%10`.
+    pub fn set_array_indices<S: AsRef<str>>(&mut self, spec: S) -> Result<&mut Self, Error> {
+        slurm_replace_utf8_string(&mut self.sys_data_mut().array_inx, spec)?;
+        Ok(self)
+    }
+
+    /// Set the account to charge this job to.
+    pub fn set_account<S: AsRef<str>>(&mut self, account: S) -> Result<&mut Self, Error> {
+        slurm_replace_utf8_string(&mut self.sys_data_mut().account, account)?;
+        Ok(self)
+    }
+
+    /// Set the comment attached to this job by an administrator.
+    ///
+    /// Only administrators may set this comment.
+    pub fn set_admin_comment<S: AsRef<str>>(&mut self, comment: S) -> Result<&mut Self, Error> {
+        slurm_replace_utf8_string(&mut self.sys_data_mut().admin_comment, comment)?;
+        Ok(self)
+    }
+
+    /// Set the comment attached to this job.
+    ///
+    /// Slurm does not interpret the comment, so it can be used to store
+    /// arbitrary metadata about the job.
+    pub fn set_comment<S: AsRef<str>>(&mut self, comment: S) -> Result<&mut Self, Error> {
+        slurm_replace_utf8_string(&mut self.sys_data_mut().comment, comment)?;
+        Ok(self)
+    }
+
     /// Set this job's name.
-    pub fn set_name<S: AsRef<str>>(&mut self, name: S) -> &mut Self {
-        {
-            let d = self.sys_data_mut();
-            slurm_free(&mut d.name);
-            d.name = slurm_alloc_utf8_string(name);
-        }
-        self
+    pub fn set_name<S: AsRef<str>>(&mut self, name: S) -> Result<&mut Self, Error> {
+        slurm_replace_utf8_string(&mut self.sys_data_mut().name, name)?;
+        Ok(self)
     }
 
     /// Set this job's partition.
-    pub fn set_partition<S: AsRef<str>>(&mut self, partition: S) -> &mut Self {
-        {
-            let d = self.sys_data_mut();
-            slurm_free(&mut d.partition);
-            d.partition = slurm_alloc_utf8_string(partition);
-        }
-        self
+    pub fn set_partition<S: AsRef<str>>(&mut self, partition: S) -> Result<&mut Self, Error> {
+        slurm_replace_utf8_string(&mut self.sys_data_mut().partition, partition)?;
+        Ok(self)
+    }
+
+    /// Set this job's quality of service (QOS), by name.
+    pub fn set_qos<S: AsRef<str>>(&mut self, qos: S) -> Result<&mut Self, Error> {
+        slurm_replace_utf8_string(&mut self.sys_data_mut().qos, qos)?;
+        Ok(self)
     }
 
     /// Set the contents of this job's wrapper shell script.
     ///
     /// This is the textual content of a shell script that will be executed as
     /// the batch job wrapper. It should start with a shebang (`#!`) line.
-    pub fn set_script<S: AsRef<str>>(&mut self, script: S) -> &mut Self {
-        {
-            let d = self.sys_data_mut();
-            slurm_free(&mut d.script);
-            d.script = slurm_alloc_utf8_string(script);
-        }
-        self
+    pub fn set_script<S: AsRef<str>>(&mut self, script: S) -> Result<&mut Self, Error> {
+        slurm_replace_utf8_string(&mut self.sys_data_mut().script, script)?;
+        Ok(self)
     }
 
     /// Set the path that will be used as this job's standard error stream.
-    pub fn set_stderr_path<S: AsRef<str>>(&mut self, path: S) -> &mut Self {
-        {
-            let d = self.sys_data_mut();
-            slurm_free(&mut d.std_err);
-            d.std_err = slurm_alloc_utf8_string(path);
-        }
-        self
+    pub fn set_stderr_path<S: AsRef<str>>(&mut self, path: S) -> Result<&mut Self, Error> {
+        slurm_replace_utf8_string(&mut self.sys_data_mut().std_err, path)?;
+        Ok(self)
     }
 
     /// Set the path that will be used as this job's standard input stream.
-    pub fn set_stdin_path<S: AsRef<str>>(&mut self, path: S) -> &mut Self {
-        {
-            let d = self.sys_data_mut();
-            slurm_free(&mut d.std_in);
-            d.std_in = slurm_alloc_utf8_string(path);
-        }
-        self
+    pub fn set_stdin_path<S: AsRef<str>>(&mut self, path: S) -> Result<&mut Self, Error> {
+        slurm_replace_utf8_string(&mut self.sys_data_mut().std_in, path)?;
+        Ok(self)
     }
 
     /// Set the path that will be used as this job's standard output stream.
-    pub fn set_stdout_path<S: AsRef<str>>(&mut self, path: S) -> &mut Self {
-        {
-            let d = self.sys_data_mut();
-            slurm_free(&mut d.std_out);
-            d.std_out = slurm_alloc_utf8_string(path);
-        }
-        self
+    pub fn set_stdout_path<S: AsRef<str>>(&mut self, path: S) -> Result<&mut Self, Error> {
+        slurm_replace_utf8_string(&mut self.sys_data_mut().std_out, path)?;
+        Ok(self)
     }
 
     /// Set this job's working directory.
     ///
     /// The working directory should be one that exists on all worker nodes of
     /// the cluster.
-    pub fn set_work_dir<S: AsRef<str>>(&mut self, work_dir: S) -> &mut Self {
-        {
-            let d = self.sys_data_mut();
-            slurm_free(&mut d.work_dir);
-            d.work_dir = slurm_alloc_utf8_string(work_dir);
-        }
-        self
+    pub fn set_work_dir<S: AsRef<str>>(&mut self, work_dir: S) -> Result<&mut Self, Error> {
+        slurm_replace_utf8_string(&mut self.sys_data_mut().work_dir, work_dir)?;
+        Ok(self)
     }
 
     /// Set this job's working directory to the current process's current
@@ -1700,11 +11119,11 @@ impl JobDescriptorOwned {
     /// See `std::env::current_dir` for an explanation of the cases in which
     /// this operation can fail.
     pub fn set_work_dir_cwd(&mut self) -> Result<&mut Self, Error> {
-        Ok(self.set_work_dir(
+        self.set_work_dir(
             std::env::current_dir()?
                 .to_str()
                 .ok_or(format_err!("could not express CWD as UTF8"))?,
-        ))
+        )
     }
 }
 
@@ -1715,8 +11134,13 @@ impl Drop for JobDescriptorOwned {
 
         {
             let d = self.sys_data_mut();
+            slurm_free(&mut d.account);
+            slurm_free(&mut d.admin_comment);
+            slurm_free(&mut d.array_inx);
+            slurm_free(&mut d.comment);
             slurm_free(&mut d.name);
             slurm_free(&mut d.partition);
+            slurm_free(&mut d.qos);
             slurm_free(&mut d.script);
             slurm_free(&mut d.std_err);
             slurm_free(&mut d.std_in);
@@ -1728,6 +11152,180 @@ impl Drop for JobDescriptorOwned {
     }
 }
 
+/// A plain-Rust description of a batch job to be submitted.
+///
+/// This is the counterpart of `JobDescriptor` for backends that do not go
+/// through the Slurm C library, such as the `slurmrestd` client in the
+/// `rest` module. Only the most commonly used settings are supported.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BatchJobSpec {
+    script: String,
+    name: Option<String>,
+    comment: Option<String>,
+    partition: Option<String>,
+    work_dir: Option<String>,
+    environment: Vec<String>,
+    num_tasks: Option<u32>,
+    time_limit: Option<TimeLimit>,
+    min_memory: Option<MemSize>,
+    stdin_path: Option<String>,
+    stdout_path: Option<String>,
+    stderr_path: Option<String>,
+}
+
+impl BatchJobSpec {
+    /// Create a new job that will run the specified wrapper script.
+    ///
+    /// The script should start with a shebang (`#!`) line.
+    #[must_use]
+    pub fn new<S: Into<String>>(script: S) -> BatchJobSpec {
+        BatchJobSpec {
+            script: script.into(),
+            name: None,
+            comment: None,
+            partition: None,
+            work_dir: None,
+            environment: Vec::new(),
+            num_tasks: None,
+            time_limit: None,
+            min_memory: None,
+            stdin_path: None,
+            stdout_path: None,
+            stderr_path: None,
+        }
+    }
+
+    /// Set this job's name.
+    pub fn name<S: Into<String>>(&mut self, name: S) -> &mut Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Set the comment attached to this job.
+    pub fn comment<S: Into<String>>(&mut self, comment: S) -> &mut Self {
+        self.comment = Some(comment.into());
+        self
+    }
+
+    /// Set this job's partition.
+    pub fn partition<S: Into<String>>(&mut self, partition: S) -> &mut Self {
+        self.partition = Some(partition.into());
+        self
+    }
+
+    /// Set this job's working directory.
+    pub fn work_dir<S: Into<String>>(&mut self, work_dir: S) -> &mut Self {
+        self.work_dir = Some(work_dir.into());
+        self
+    }
+
+    /// Specify the UNIX environment of the job, as a list of `NAME=value`
+    /// strings.
+    pub fn environment<I: IntoIterator<Item = S>, S: Into<String>>(&mut self, env: I) -> &mut Self {
+        self.environment = env.into_iter().map(|s| s.into()).collect();
+        self
+    }
+
+    /// Set the number of tasks within this job.
+    pub fn num_tasks(&mut self, value: u32) -> &mut Self {
+        self.num_tasks = Some(value);
+        self
+    }
+
+    /// Set the time limit associated with this job.
+    ///
+    /// Plain integers are interpreted as a number of minutes.
+    pub fn time_limit<T: Into<TimeLimit>>(&mut self, limit: T) -> &mut Self {
+        self.time_limit = Some(limit.into());
+        self
+    }
+
+    /// Set the minimum amount of memory required by this job.
+    pub fn min_memory(&mut self, value: MemSize) -> &mut Self {
+        self.min_memory = Some(value);
+        self
+    }
+
+    /// Set the path that will be used as this job's standard input stream.
+    pub fn stdin_path<S: Into<String>>(&mut self, path: S) -> &mut Self {
+        self.stdin_path = Some(path.into());
+        self
+    }
+
+    /// Set the path that will be used as this job's standard output stream.
+    pub fn stdout_path<S: Into<String>>(&mut self, path: S) -> &mut Self {
+        self.stdout_path = Some(path.into());
+        self
+    }
+
+    /// Set the path that will be used as this job's standard error stream.
+    pub fn stderr_path<S: Into<String>>(&mut self, path: S) -> &mut Self {
+        self.stderr_path = Some(path.into());
+        self
+    }
+
+    /// Convert this specification into a job descriptor for use with the
+    /// Slurm C library.
+    ///
+    /// The job will run as the current user and group. If no environment
+    /// has been specified, the job inherits that of the current process, and
+    /// if no working directory has been specified, it uses the current one.
+    pub fn to_descriptor(&self) -> Result<JobDescriptorOwned, Error> {
+        let mut desc = JobDescriptorOwned::new()?;
+        desc.set_script(&self.script)?;
+
+        if let Some(ref name) = self.name {
+            desc.set_name(name)?;
+        }
+
+        if let Some(ref comment) = self.comment {
+            desc.set_comment(comment)?;
+        }
+
+        if let Some(ref partition) = self.partition {
+            desc.set_partition(partition)?;
+        }
+
+        match self.work_dir {
+            Some(ref dir) => desc.set_work_dir(dir)?,
+            None => desc.set_work_dir_cwd()?,
+        };
+
+        if self.environment.is_empty() {
+            desc.inherit_environment()?;
+        } else {
+            desc.set_environment(&self.environment)?;
+        }
+
+        if let Some(ref path) = self.stdin_path {
+            desc.set_stdin_path(path)?;
+        }
+
+        if let Some(ref path) = self.stdout_path {
+            desc.set_stdout_path(path)?;
+        }
+
+        if let Some(ref path) = self.stderr_path {
+            desc.set_stderr_path(path)?;
+        }
+
+        if let Some(n) = self.num_tasks {
+            desc.set_num_tasks(n);
+        }
+
+        if let Some(limit) = self.time_limit {
+            desc.set_time_limit(limit);
+        }
+
+        if let Some(mem) = self.min_memory {
+            desc.set_min_memory(mem);
+        }
+
+        desc.set_uid_current().set_gid_current();
+        Ok(desc)
+    }
+}
+
 make_slurm_wrap_struct!(
     SubmitResponseMessage,
     slurm_sys::submit_response_msg,
@@ -1736,6 +11334,8 @@ Information returned by Slurm upon job submission.
 "
 );
 
+impl_wrapper_debug!(SubmitResponseMessage; job_id, step_id, error_code);
+
 impl SubmitResponseMessage {
     /// Get the job ID of the new job.
     ///
@@ -1750,7 +11350,7 @@ impl SubmitResponseMessage {
     /// XXX: It looks like it is possible to have a non-zero `error_code` with
     /// a non-zero job ID; I'm not sure in what cases that occurs.
     pub fn step_id(&self) -> StepId {
-        self.sys_data().step_id
+        StepId::from(self.sys_data().step_id)
     }
 
     /// Get the error code returned by the server.
@@ -1776,3 +11376,515 @@ impl Drop for SubmitResponseMessageOwned {
         unsafe { slurm_sys::slurm_free_submit_response_response_msg((self.0).0 as _) };
     }
 }
+
+make_slurm_wrap_struct!(
+    Allocation,
+    slurm_sys::resource_allocation_response_msg_t,
+    "\
+The resources allocated to a job, as returned by the controller.
+"
+);
+
+impl_wrapper_debug!(Allocation; job_id, node_list, node_count);
+
+impl Allocation {
+    /// Get the ID of the job that owns this allocation.
+    pub fn job_id(&self) -> JobId {
+        self.sys_data().job_id
+    }
+
+    /// Get the nodes in this allocation, in Slurm's compressed hostlist
+    /// format.
+    pub fn node_list(&self) -> Cow<str> {
+        unsafe { optional_cstr(self.sys_data().node_list) }.unwrap_or(Cow::Borrowed(""))
+    }
+
+    /// Get the number of nodes in this allocation.
+    pub fn node_count(&self) -> u32 {
+        self.sys_data().node_cnt
+    }
+
+    /// Get the number of CPUs allocated on each node, in the same order as
+    /// the nodes of `node_list`.
+    pub fn cpus_per_node(&self) -> Vec<u16> {
+        let data = self.sys_data();
+
+        if data.cpus_per_node.is_null() || data.cpu_count_reps.is_null() {
+            return Vec::new();
+        }
+
+        // Slurm run-length encodes the counts.
+        let n = data.num_cpu_groups as usize;
+        let cpus = unsafe { std::slice::from_raw_parts(data.cpus_per_node, n) };
+        let reps = unsafe { std::slice::from_raw_parts(data.cpu_count_reps, n) };
+        let mut result = Vec::new();
+
+        for (&c, &r) in cpus.iter().zip(reps) {
+            result.extend(std::iter::repeat(c).take(r as usize));
+        }
+
+        result
+    }
+
+    /// Get the partition in which the resources were allocated.
+    pub fn partition(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().partition) }
+    }
+
+    /// Get the account charged for the allocation.
+    pub fn account(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().account) }
+    }
+
+    /// Get the QOS under which the resources were allocated.
+    pub fn qos(&self) -> Option<Cow<str>> {
+        unsafe { optional_cstr(self.sys_data().qos) }
+    }
+}
+
+make_owned_version!(@customdrop Allocation, AllocationOwned, "An owned version of `Allocation`.");
+
+impl Drop for AllocationOwned {
+    fn drop(&mut self) {
+        unsafe { slurm_sys::slurm_free_resource_allocation_response_msg((self.0).0) };
+    }
+}
+
+impl AllocationOwned {
+    /// Look up the resources allocated to an existing job.
+    ///
+    /// This lets a process rediscover an allocation that was created by
+    /// another one, such as a batch script launching steps with a Rust tool.
+    /// The job must be running.
+    pub fn lookup<J: Into<JobIdentifier>>(jid: J) -> Result<Self, Error> {
+        let jid = jid.into().resolve()?;
+        let mut msg = std::ptr::null_mut();
+        ustry!(slurm_sys::slurm_allocation_lookup(jid, &mut msg));
+        Ok(unsafe { AllocationOwned::assume_ownership(msg as _) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tres_amounts_parse() {
+        let a = TresAmounts::parse("1=4,2=16000,1001=2").unwrap();
+        assert_eq!(a.cpus(), Some(4));
+        assert_eq!(a.mem_mib(), Some(16000));
+        assert_eq!(a.get(1001), Some(2));
+        assert_eq!(a.nodes(), None);
+        assert_eq!(
+            a.iter().collect::<Vec<_>>(),
+            vec![(1, 4), (2, 16000), (1001, 2)]
+        );
+
+        assert!(TresAmounts::parse("").unwrap().is_empty());
+        assert_eq!(TresAmounts::parse("4=1,,").unwrap().nodes(), Some(1));
+
+        // Slurm's NO_VAL64 sentinel is a valid amount as far as the string
+        // format is concerned.
+        assert_eq!(
+            TresAmounts::parse("2=18446744073709551614")
+                .unwrap()
+                .mem_mib(),
+            Some(slurm_sys::SLURMRS_NO_VAL64)
+        );
+    }
+
+    #[test]
+    fn tres_amounts_parse_malformed() {
+        for text in &[
+            "1",
+            "=4",
+            "cpu=4",
+            "1=x",
+            "1=-1",
+            "1=4;2=5",
+            "1=18446744073709551616",
+        ] {
+            assert!(TresAmounts::parse(text).is_err(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn tres_spec_parse() {
+        let spec: TresSpec = "cpu=4,mem=16G,gres/gpu=2".parse().unwrap();
+        assert_eq!(spec.cpus(), Some(4));
+        assert_eq!(spec.mem_mib(), Some(16384));
+        assert_eq!(spec.gpus(), Some(2));
+        assert_eq!(spec.nodes(), None);
+        assert_eq!(spec.to_string(), "cpu=4,gres/gpu=2,mem=16384M");
+        assert_eq!(spec.to_string().parse::<TresSpec>().unwrap(), spec);
+
+        // Memory is normalized to mebibytes, and unsuffixed amounts are
+        // mebibytes already.
+        let a: TresSpec = "mem=16384M".parse().unwrap();
+        let b: TresSpec = "mem=16384".parse().unwrap();
+        assert_eq!(a.mem_mib(), Some(16384));
+        assert_eq!(a, b);
+        assert_eq!("mem=1536K".parse::<TresSpec>().unwrap().mem_mib(), Some(2));
+        assert_eq!(
+            "vmem=1t".parse::<TresSpec>().unwrap().get("vmem"),
+            Some(1 << 20)
+        );
+
+        // Other amounts use plain powers of 1024.
+        assert_eq!(
+            "billing=2K".parse::<TresSpec>().unwrap().billing(),
+            Some(2048)
+        );
+        assert_eq!("node=1".parse::<TresSpec>().unwrap().nodes(), Some(1));
+
+        assert!("".parse::<TresSpec>().unwrap().is_empty());
+        assert_eq!(" cpu=1 , ,".parse::<TresSpec>().unwrap().cpus(), Some(1));
+    }
+
+    #[test]
+    fn tres_spec_parse_malformed() {
+        for text in &[
+            "cpu",
+            "=4",
+            "cpu=",
+            "cpu=x",
+            "cpu=4X",
+            "cpu=-1",
+            "cpu=+1",
+            "cpu=1.",
+            "cpu=.5",
+            "cpu=1.2.3",
+            "mem=1.x5G",
+            "cpu=20000P",
+            "mem=20000000000P",
+        ] {
+            assert!(text.parse::<TresSpec>().is_err(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn tres_spec_decimal_round_trip() {
+        let spec: TresSpec = "cpu=2,mem=3.50G".parse().unwrap();
+        assert_eq!(spec.mem_mib(), Some(3584));
+        assert_eq!(spec.to_string(), "cpu=2,mem=3584M");
+        assert_eq!(spec.to_string().parse::<TresSpec>().unwrap(), spec);
+
+        // Fractional results are rounded up to whole units.
+        assert_eq!(
+            "mem=1.0005G".parse::<TresSpec>().unwrap().mem_mib(),
+            Some(1025)
+        );
+        assert_eq!("mem=0.5K".parse::<TresSpec>().unwrap().mem_mib(), Some(1));
+        assert_eq!(
+            "billing=1.5K".parse::<TresSpec>().unwrap().billing(),
+            Some(1536)
+        );
+        assert_eq!(
+            "mem=2.000000000000000000000000000000000000000T"
+                .parse::<TresSpec>()
+                .unwrap()
+                .mem_mib(),
+            Some(2 << 20)
+        );
+    }
+
+    #[test]
+    fn mem_size_parse() {
+        assert_eq!("16G".parse::<MemSize>().unwrap(), MemSize::PerNode(16384));
+        assert_eq!("4000Mc".parse::<MemSize>().unwrap(), MemSize::PerCpu(4000));
+        assert_eq!("512".parse::<MemSize>().unwrap(), MemSize::PerNode(512));
+        assert_eq!("2gn".parse::<MemSize>().unwrap(), MemSize::PerNode(2048));
+        assert_eq!("1T".parse::<MemSize>().unwrap(), MemSize::PerNode(1 << 20));
+        assert_eq!(" 1536K ".parse::<MemSize>().unwrap(), MemSize::PerNode(2));
+        assert_eq!("0c".parse::<MemSize>().unwrap(), MemSize::PerCpu(0));
+
+        for text in &["", "c", "G", "abc", "12X", "-1", "1.5G", "9000000000000T"] {
+            assert!(text.parse::<MemSize>().is_err(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn mem_size_slurm_encoding() {
+        assert_eq!(MemSize::from_slurm(slurm_sys::SLURMRS_NO_VAL64), None);
+        assert_eq!(MemSize::from_slurm(4000), Some(MemSize::PerNode(4000)));
+        assert_eq!(
+            MemSize::from_slurm(MEM_PER_CPU | 100),
+            Some(MemSize::PerCpu(100))
+        );
+
+        for m in &[MemSize::PerNode(16384), MemSize::PerCpu(100)] {
+            assert_eq!(MemSize::from_slurm(m.to_slurm()), Some(*m));
+        }
+    }
+
+    #[test]
+    fn mem_size_display() {
+        assert_eq!(MemSize::PerNode(16384).to_string(), "16Gn");
+        assert_eq!(MemSize::PerNode(1 << 20).to_string(), "1Tn");
+        assert_eq!(MemSize::PerCpu(4000).to_string(), "4000Mc");
+        assert_eq!(MemSize::PerNode(0).to_string(), "0Mn");
+        assert_eq!(
+            "4000Mc"
+                .parse::<MemSize>()
+                .unwrap()
+                .to_string()
+                .parse::<MemSize>()
+                .unwrap(),
+            MemSize::PerCpu(4000)
+        );
+    }
+
+    #[test]
+    fn time_limit_parse() {
+        let p = |text: &str| text.parse::<TimeLimit>().unwrap();
+
+        assert_eq!(p("90"), TimeLimit::Minutes(90));
+        assert_eq!(p("1:30"), TimeLimit::Minutes(2)); // minutes:seconds, rounded up
+        assert_eq!(p("2:00:00"), TimeLimit::Minutes(120));
+        assert_eq!(p("0:0:1"), TimeLimit::Minutes(1));
+        assert_eq!(p("1-0"), TimeLimit::Minutes(1440));
+        assert_eq!(p("1-2:30"), TimeLimit::Minutes(1590));
+        assert_eq!(p("1-2:30:01"), TimeLimit::Minutes(1591));
+        assert_eq!(p(" 0 "), TimeLimit::Minutes(0));
+        assert_eq!(p("UNLIMITED"), TimeLimit::Unlimited);
+        assert_eq!(p("infinite"), TimeLimit::Unlimited);
+        assert_eq!(p("-1"), TimeLimit::Unlimited);
+
+        // The largest representable limit is one less than NO_VAL.
+        assert_eq!(p("4294967293"), TimeLimit::Minutes(4294967293));
+
+        for text in &[
+            "",
+            "abc",
+            "1:2:3:4",
+            "1-",
+            "-5",
+            "1-2:3:4:5",
+            "1.5",
+            "4294967294",
+            "8000000-0",
+        ] {
+            assert!(text.parse::<TimeLimit>().is_err(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn time_limit_slurm_encoding() {
+        assert_eq!(TimeLimit::from_slurm(slurm_sys::SLURMRS_NO_VAL), None);
+        assert_eq!(
+            TimeLimit::from_slurm(slurm_sys::SLURMRS_INFINITE),
+            Some(TimeLimit::Unlimited)
+        );
+        assert_eq!(TimeLimit::from_slurm(60), Some(TimeLimit::Minutes(60)));
+        assert_eq!(TimeLimit::Unlimited.to_slurm(), slurm_sys::SLURMRS_INFINITE);
+        assert_eq!(TimeLimit::Minutes(60).to_slurm(), 60);
+    }
+
+    #[test]
+    fn time_limit_display() {
+        assert_eq!(TimeLimit::Minutes(90).to_string(), "01:30:00");
+        assert_eq!(TimeLimit::Minutes(1590).to_string(), "1-02:30:00");
+        assert_eq!(TimeLimit::Unlimited.to_string(), "UNLIMITED");
+
+        for t in &[TimeLimit::Minutes(1590), TimeLimit::Unlimited] {
+            assert_eq!(t.to_string().parse::<TimeLimit>().unwrap(), *t);
+        }
+    }
+
+    #[test]
+    fn job_identifier_parse() {
+        let p = |text: &str| text.parse::<JobIdentifier>().unwrap();
+
+        assert_eq!(p("1234"), JobIdentifier::Plain(1234));
+        assert_eq!(p(" 42 "), JobIdentifier::Plain(42));
+        assert_eq!(p("1234_7"), JobIdentifier::ArrayTask(1234, 7));
+        assert_eq!(p("1234+1"), JobIdentifier::PackComponent(1234, 1));
+        assert_eq!(p("1234_7").base_id(), 1234);
+
+        for text in &[
+            "",
+            "abc",
+            "12_",
+            "_3",
+            "12_x",
+            "1234_[1-3]",
+            "1234_*",
+            "12+",
+            "-1",
+            "4294967296",
+        ] {
+            assert!(text.parse::<JobIdentifier>().is_err(), "{:?}", text);
+        }
+
+        for jid in &[
+            JobIdentifier::Plain(5),
+            JobIdentifier::ArrayTask(5, 0),
+            JobIdentifier::PackComponent(5, 2),
+        ] {
+            assert_eq!(p(&jid.to_string()), *jid);
+        }
+    }
+
+    #[test]
+    fn step_id_parse() {
+        let p = |text: &str| text.parse::<StepId>().unwrap();
+
+        assert_eq!(p("batch"), StepId::Batch);
+        assert_eq!(p("extern"), StepId::Extern);
+        assert_eq!(p("pending"), StepId::Pending);
+        assert_eq!(p("3"), StepId::Numbered(3));
+        assert_eq!(p(" 5 "), StepId::Numbered(5));
+
+        for text in &["", "-1", "bat", "Batch", "1.0", "4294967296"] {
+            assert!(text.parse::<StepId>().is_err(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn step_id_slurm_encoding() {
+        assert_eq!(StepId::from(slurm_sys::SLURM_BATCH_SCRIPT), StepId::Batch);
+        assert_eq!(StepId::from(slurm_sys::SLURM_EXTERN_CONT), StepId::Extern);
+        assert_eq!(StepId::from(slurm_sys::SLURM_PENDING_STEP), StepId::Pending);
+        assert_eq!(StepId::from(0), StepId::Numbered(0));
+
+        for s in &[
+            StepId::Numbered(7),
+            StepId::Batch,
+            StepId::Extern,
+            StepId::Pending,
+        ] {
+            assert_eq!(StepId::from(s.to_slurm()), *s);
+            assert_eq!(s.to_string().parse::<StepId>().unwrap(), *s);
+        }
+    }
+
+    #[test]
+    fn gres_allocation_parse() {
+        let p = |text: &str| text.parse::<GresAllocation>().unwrap();
+
+        let a = p("gpu:tesla:2(IDX:0-1)");
+        assert_eq!(a.name, "gpu");
+        assert_eq!(a.kind.as_deref(), Some("tesla"));
+        assert_eq!(a.count, Some(2));
+        assert_eq!(a.indices, vec![0, 1]);
+
+        let a = p("gpu(IDX:N/A)");
+        assert_eq!(a.name, "gpu");
+        assert_eq!(a.kind, None);
+        assert_eq!(a.count, None);
+        assert!(a.indices.is_empty());
+
+        let a = p("gpu:4(IDX:0,2-3,7)");
+        assert_eq!(a.kind, None);
+        assert_eq!(a.count, Some(4));
+        assert_eq!(a.indices, vec![0, 2, 3, 7]);
+
+        let a = p("mps:100(0/100,0/100)");
+        assert_eq!(a.name, "mps");
+        assert_eq!(a.count, Some(100));
+        assert!(a.indices.is_empty());
+
+        let a = p(" gpu:a100:mig ");
+        assert_eq!(a.kind.as_deref(), Some("a100:mig"));
+        assert_eq!(a.count, None);
+
+        assert_eq!(p("craynetwork").name, "craynetwork");
+
+        for text in &["", ":2", "gpu:2(IDX:0-1", "gpu:2(IDX:x)", "gpu(IDX:1-)"] {
+            assert!(text.parse::<GresAllocation>().is_err(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn gres_allocation_parse_list() {
+        let list =
+            GresAllocation::parse_list("gpu:2(IDX:0-1),mps:100(0/100,0/100),,bandwidth:4").unwrap();
+        assert_eq!(list.len(), 3);
+        assert_eq!(list[0].indices, vec![0, 1]);
+        assert_eq!(list[1].name, "mps");
+        assert_eq!(list[1].count, Some(100));
+        assert_eq!(list[2].name, "bandwidth");
+        assert_eq!(list[2].count, Some(4));
+
+        assert!(GresAllocation::parse_list("").unwrap().is_empty());
+        assert!(GresAllocation::parse_list("gpu:2(IDX:0-1),:1").is_err());
+    }
+
+    #[test]
+    fn glob_matching() {
+        assert!(glob_match("sweep-*", "sweep-12"));
+        assert!(glob_match("sweep-*", "sweep-"));
+        assert!(!glob_match("sweep-*", "sweep"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("", ""));
+        assert!(!glob_match("", "x"));
+        assert!(glob_match("job?", "job1"));
+        assert!(!glob_match("job?", "job"));
+        assert!(!glob_match("job?", "job12"));
+        assert!(glob_match("*-run-*.sh", "a-run-b-run-c.sh"));
+        assert!(!glob_match("*-run-*.sh", "a-run-b.py"));
+        assert!(glob_match("a*b*c", "aXbYbZc"));
+        assert!(!glob_match("a*b*c", "aXbYcZ"));
+        assert!(glob_match("**x", "abx"));
+        assert!(glob_match("é?", "éa"));
+        assert!(!glob_match("Sweep*", "sweep"));
+    }
+
+    #[test]
+    fn job_dependencies_parse() {
+        let p = |text: &str| text.parse::<JobDependencies>().unwrap();
+
+        let d = p("afterok:123:124,singleton");
+        assert!(!d.require_any);
+        assert_eq!(d.items.len(), 3);
+        assert_eq!(d.items[0].kind, DependencyKind::AfterOk);
+        assert_eq!(d.items[0].job, Some(JobIdentifier::Plain(123)));
+        assert_eq!(d.items[1].job, Some(JobIdentifier::Plain(124)));
+        assert_eq!(d.items[2].kind, DependencyKind::Singleton);
+        assert_eq!(d.items[2].job, None);
+
+        let d = p("afterok:123?afternotok:124");
+        assert!(d.require_any);
+        assert_eq!(d.items.len(), 2);
+        assert_eq!(d.items[0].kind, DependencyKind::AfterOk);
+        assert_eq!(d.items[1].kind, DependencyKind::AfterNotOk);
+        assert_eq!(d.items[1].job, Some(JobIdentifier::Plain(124)));
+
+        let d = p("afterany:123_*,aftercorr:124_7");
+        assert_eq!(d.items[0].job, Some(JobIdentifier::Plain(123)));
+        assert_eq!(d.items[1].kind, DependencyKind::AfterCorr);
+        assert_eq!(d.items[1].job, Some(JobIdentifier::ArrayTask(124, 7)));
+
+        let d = p("after:123+30:124");
+        assert_eq!(d.items[0].job, Some(JobIdentifier::Plain(123)));
+        assert_eq!(d.items[0].delay_minutes, Some(30));
+        assert_eq!(d.items[1].delay_minutes, None);
+
+        let d = p("afterok:123_*(unfulfilled),afterany:124(failed),singleton(unfulfilled)");
+        assert_eq!(d.items[0].job, Some(JobIdentifier::Plain(123)));
+        assert_eq!(d.items[0].status.as_deref(), Some("unfulfilled"));
+        assert_eq!(d.items[1].status.as_deref(), Some("failed"));
+        assert_eq!(d.items[2].kind, DependencyKind::Singleton);
+        assert_eq!(d.items[2].status.as_deref(), Some("unfulfilled"));
+
+        assert_eq!(p(" ").items.len(), 0);
+        assert_eq!(p("afterok:123").items[0].status, None);
+
+        assert!("afterwards:123".parse::<JobDependencies>().is_err());
+        assert!("after:123+soon".parse::<JobDependencies>().is_err());
+        assert!("afterok:abc_*".parse::<JobDependencies>().is_err());
+    }
+
+    #[test]
+    fn empty_collected_list() {
+        let list: SlurmListOwned<*mut c_char> = std::iter::empty::<&str>().collect();
+        assert!(list.is_empty());
+        assert_eq!(list.len(), 0);
+        assert_eq!(list.iter().len(), 0);
+        assert_eq!(list.iter().count(), 0);
+        assert!(list.find(|_| true).is_none());
+
+        let list: SlurmListOwned<*mut c_char> = Vec::<String>::new().into_iter().collect();
+        assert_eq!((&list).into_iter().count(), 0);
+    }
+}
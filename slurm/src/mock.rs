@@ -0,0 +1,452 @@
+// Copyright 2018 Peter Williams <peter@newton.cx> and collaborators
+// Licensed under the MIT License
+
+/*! An in-memory stand-in for a Slurm cluster.
+
+The `MockCluster` type implements the `Scheduler` and `Accounting` traits of
+the `backend` module using a simple table of jobs, so that applications
+written against those traits can be tested without a real cluster. Submitted
+jobs are assigned sequential IDs and start out pending; tests then move them
+through their lifecycle with `set_state` and `set_exit_status`.
+
+```no_run
+use slurm::backend::Scheduler;
+use slurm::mock::MockCluster;
+use slurm::{ExitStatus, JobState};
+
+fn main() {
+    let cluster = MockCluster::new();
+    let jid = cluster
+        .submit_batch(&slurm::BatchJobSpec::new("#! /bin/sh\ntrue\n"))
+        .unwrap();
+
+    cluster.set_state(jid, JobState::Running).unwrap();
+    cluster.set_exit_status(jid, ExitStatus::Exited(0)).unwrap();
+    cluster.set_state(jid, JobState::Complete).unwrap();
+}
+```
+
+*/
+
+use chrono::{DateTime, Duration, Utc};
+use std::collections::BTreeMap;
+use std::os::raw::c_int;
+use std::sync::{Mutex, MutexGuard};
+
+use crate::backend::{Accounting, Scheduler};
+use crate::{
+    BatchJobSpec, Error, ExitStatus, JobFlags, JobId, JobInfoSnapshot, JobQuery, JobRecordSnapshot,
+    JobState, JobStateFlags, PendingReason, SlurmError, TimeLimit, TresAmounts,
+};
+
+/// The ID assigned to the first job submitted to a `MockCluster` by default.
+pub const DEFAULT_FIRST_JOB_ID: JobId = 1000;
+
+/// A job in the table of a `MockCluster`.
+#[derive(Clone, Debug)]
+struct MockJob {
+    spec: BatchJobSpec,
+    state: JobState,
+    submit_time: DateTime<Utc>,
+    start_time: Option<DateTime<Utc>>,
+    end_time: Option<DateTime<Utc>>,
+    exit_status: Option<ExitStatus>,
+}
+
+#[derive(Debug)]
+struct MockState {
+    next_job_id: JobId,
+    jobs: BTreeMap<JobId, MockJob>,
+}
+
+/// An in-memory stand-in for a Slurm cluster.
+///
+/// All jobs are attributed to a single user and account, which are
+/// `mockuser` and `mockaccount` unless changed with `set_user` and
+/// `set_account`.
+#[derive(Debug)]
+pub struct MockCluster {
+    cluster: String,
+    user: String,
+    account: String,
+    state: Mutex<MockState>,
+}
+
+impl Default for MockCluster {
+    fn default() -> Self {
+        MockCluster::new()
+    }
+}
+
+impl MockCluster {
+    /// Create a new cluster with no jobs.
+    #[must_use]
+    pub fn new() -> MockCluster {
+        MockCluster::with_first_job_id(DEFAULT_FIRST_JOB_ID)
+    }
+
+    /// Create a new cluster whose first submitted job will have the
+    /// specified ID.
+    #[must_use]
+    pub fn with_first_job_id(jid: JobId) -> MockCluster {
+        MockCluster {
+            cluster: "mock".to_owned(),
+            user: "mockuser".to_owned(),
+            account: "mockaccount".to_owned(),
+            state: Mutex::new(MockState {
+                next_job_id: jid,
+                jobs: BTreeMap::new(),
+            }),
+        }
+    }
+
+    /// Set the name of the user that owns all of the jobs.
+    pub fn set_user<S: Into<String>>(&mut self, name: S) -> &mut Self {
+        self.user = name.into();
+        self
+    }
+
+    /// Set the name of the account that all of the jobs are charged to.
+    pub fn set_account<S: Into<String>>(&mut self, name: S) -> &mut Self {
+        self.account = name.into();
+        self
+    }
+
+    fn lock(&self) -> MutexGuard<MockState> {
+        // A panic while the lock is held can only come from a failing test,
+        // so a poisoned lock is not an issue.
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Get the IDs of all of the jobs that have been submitted, in order.
+    pub fn job_ids(&self) -> Vec<JobId> {
+        self.lock().jobs.keys().cloned().collect()
+    }
+
+    /// Get the specification with which a job was submitted.
+    pub fn submitted_spec(&self, jid: JobId) -> Result<BatchJobSpec, Error> {
+        self.with_job(jid, |job| job.spec.clone())
+    }
+
+    /// Get the current state of a job.
+    pub fn state(&self, jid: JobId) -> Result<JobState, Error> {
+        self.with_job(jid, |job| job.state)
+    }
+
+    /// Move a job into a new state.
+    ///
+    /// Moving a job into the running state sets its start time, and moving
+    /// it into a terminal state sets its end time (and its start time, if it
+    /// never ran).
+    pub fn set_state(&self, jid: JobId, state: JobState) -> Result<(), Error> {
+        self.with_job(jid, |job| {
+            let now = Utc::now();
+
+            if state == JobState::Running && job.start_time.is_none() {
+                job.start_time = Some(now);
+            }
+
+            if state.is_terminal() {
+                job.start_time.get_or_insert(now);
+                job.end_time.get_or_insert(now);
+            }
+
+            job.state = state;
+        })
+    }
+
+    /// Set the exit status that will be reported for a job once it ends.
+    pub fn set_exit_status(&self, jid: JobId, status: ExitStatus) -> Result<(), Error> {
+        self.with_job(jid, |job| job.exit_status = Some(status))
+    }
+
+    fn with_job<T, F: FnOnce(&mut MockJob) -> T>(&self, jid: JobId, func: F) -> Result<T, Error> {
+        match self.lock().jobs.get_mut(&jid) {
+            Some(job) => Ok(func(job)),
+            None => Err(SlurmError::from_slurm(slurm_sys::ESLURM_INVALID_JOB_ID as c_int).into()),
+        }
+    }
+
+    fn matches(&self, jid: JobId, job: &MockJob, query: &JobQuery) -> bool {
+        if !query.users.is_empty() && !query.users.contains(&self.user) {
+            return false;
+        }
+
+        if !query.accounts.is_empty() && !query.accounts.contains(&self.account) {
+            return false;
+        }
+
+        if !query.jobs.is_empty() && !query.jobs.contains(&jid) {
+            return false;
+        }
+
+        // As with Slurm, the usage window matches jobs that were eligible
+        // or running at some point within it.
+        if let Some(start) = query.start {
+            if job.end_time.map(|t| t < start).unwrap_or(false) {
+                return false;
+            }
+        }
+
+        if let Some(end) = query.end {
+            if job.submit_time >= end {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    fn to_record(&self, jid: JobId, job: &MockJob) -> JobRecordSnapshot {
+        let elapsed = match job.start_time {
+            Some(start) => job.end_time.unwrap_or_else(Utc::now) - start,
+            None => Duration::zero(),
+        };
+
+        let exit_code = job.end_time.map(|_| match job.exit_status {
+            Some(ExitStatus::Exited(code)) => (code as i32) << 8,
+            Some(ExitStatus::Signaled(signal)) => signal as i32,
+            None => 0,
+        });
+
+        JobRecordSnapshot {
+            account: Some(self.account.clone()),
+            alloc_nodes: if job.start_time.is_some() { 1 } else { 0 },
+            array_job_id: None,
+            cluster: Some(self.cluster.clone()),
+            derived_exit_code: 0,
+            elapsed,
+            eligible_time: Some(job.submit_time),
+            end_time: job.end_time,
+            exit_code,
+            job_id: jid,
+            job_name: job.spec.name.clone().unwrap_or_default(),
+            max_rss: None,
+            max_vm_size: None,
+            node_list: job.start_time.map(|_| "mock0".to_owned()),
+            partition: job.spec.partition.clone(),
+            priority: 1,
+            qos_id: 1,
+            req_cpus: job.spec.num_tasks.unwrap_or(1),
+            req_mem: job.spec.min_memory,
+            start_time: job.start_time,
+            state: job.state,
+            state_flags: JobStateFlags::default(),
+            steps: Vec::new(),
+            submit_time: job.submit_time,
+            sys_cpu: Duration::zero(),
+            time_limit: job.spec.time_limit.or(Some(TimeLimit::Unlimited)),
+            tot_cpu: Duration::zero(),
+            tres_alloc: TresAmounts::default(),
+            tres_req: TresAmounts::default(),
+            uid: 1000,
+            user: Some(self.user.clone()),
+            user_cpu: Duration::zero(),
+            wckey: None,
+            work_dir: job.spec.work_dir.clone(),
+        }
+    }
+}
+
+impl Scheduler for MockCluster {
+    fn get_job_info(&self, jid: JobId) -> Result<JobInfoSnapshot, Error> {
+        self.with_job(jid, |job| JobInfoSnapshot {
+            account: Some(self.account.clone()),
+            admin_comment: None,
+            array_job_id: None,
+            array_task_id: None,
+            batch_host: job.start_time.map(|_| "mock0".to_owned()),
+            bitflags: JobFlags::default(),
+            comment: job.spec.comment.clone(),
+            dependency: None,
+            exit_status: job.end_time.and(job.exit_status),
+            fed_origin: None,
+            fed_siblings_active: Vec::new(),
+            fed_siblings_viable: Vec::new(),
+            gres_detail: Vec::new(),
+            is_batch: true,
+            job_id: jid,
+            licenses: None,
+            mcs_label: None,
+            min_memory: job.spec.min_memory,
+            name: job.spec.name.clone().unwrap_or_default(),
+            nodes: job.start_time.map(|_| "mock0".to_owned()),
+            num_cpus: job.spec.num_tasks.unwrap_or(1),
+            partition: job.spec.partition.clone().unwrap_or_default(),
+            priority: 1,
+            qos: None,
+            requeue: false,
+            restart_cnt: 0,
+            start_time: job.start_time,
+            state: job.state,
+            state_flags: JobStateFlags::default(),
+            state_reason: PendingReason::from_slurm(0),
+            stderr_path: job.spec.stderr_path.clone(),
+            stdin_path: job.spec.stdin_path.clone(),
+            stdout_path: job.spec.stdout_path.clone(),
+            time_limit: job.spec.time_limit.or(Some(TimeLimit::Unlimited)),
+            tres_alloc: TresAmounts::default(),
+            user_id: 1000,
+            user_name: Some(self.user.clone()),
+            work_dir: job.spec.work_dir.clone(),
+        })
+    }
+
+    fn submit_batch(&self, spec: &BatchJobSpec) -> Result<JobId, Error> {
+        let mut state = self.lock();
+        let jid = state.next_job_id;
+        state.next_job_id += 1;
+
+        state.jobs.insert(
+            jid,
+            MockJob {
+                spec: spec.clone(),
+                state: JobState::Pending,
+                submit_time: Utc::now(),
+                start_time: None,
+                end_time: None,
+                exit_status: None,
+            },
+        );
+
+        Ok(jid)
+    }
+}
+
+impl Accounting for MockCluster {
+    fn get_jobs(&self, query: &JobQuery) -> Result<Vec<JobRecordSnapshot>, Error> {
+        Ok(self
+            .lock()
+            .jobs
+            .iter()
+            .filter(|&(jid, job)| self.matches(*jid, job, query))
+            .map(|(jid, job)| {
+                let mut rec = self.to_record(*jid, job);
+
+                if !query.without_usage_truncation {
+                    truncate_usage(&mut rec, query.start, query.end);
+                }
+
+                rec
+            })
+            .collect())
+    }
+}
+
+/// Clip the times of a job that ran to a usage window, as Slurm does for
+/// queries made without `without_usage_truncation`.
+fn truncate_usage(
+    rec: &mut JobRecordSnapshot,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+) {
+    let job_start = match rec.start_time {
+        Some(t) => t,
+        None => return,
+    };
+
+    let job_start = match start {
+        Some(s) if s > job_start => s,
+        _ => job_start,
+    };
+
+    let job_end = match (rec.end_time, end) {
+        (Some(e), Some(w)) if e > w => Some(w),
+        (None, Some(w)) => Some(w),
+        (e, _) => e,
+    };
+
+    rec.start_time = Some(job_start);
+    rec.end_time = job_end;
+    rec.elapsed = job_end.unwrap_or_else(Utc::now) - job_start;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec() -> BatchJobSpec {
+        let mut spec = BatchJobSpec::new("#! /bin/sh\ntrue\n");
+        spec.name("mockjob").partition("debug").num_tasks(4);
+        spec
+    }
+
+    #[test]
+    fn job_lifecycle() {
+        let cluster = MockCluster::new();
+        let jid = cluster.submit_batch(&spec()).unwrap();
+        assert_eq!(jid, DEFAULT_FIRST_JOB_ID);
+
+        let info = cluster.get_job_info(jid).unwrap();
+        assert_eq!(info.job_id, jid);
+        assert_eq!(info.name, "mockjob");
+        assert_eq!(info.partition, "debug");
+        assert_eq!(info.num_cpus, 4);
+        assert_eq!(info.state, JobState::Pending);
+        assert_eq!(info.start_time, None);
+        assert_eq!(info.nodes, None);
+
+        cluster.set_state(jid, JobState::Running).unwrap();
+        let info = cluster.get_job_info(jid).unwrap();
+        assert_eq!(info.state, JobState::Running);
+        assert!(info.start_time.is_some());
+        assert_eq!(info.nodes.as_deref(), Some("mock0"));
+        assert_eq!(info.exit_status, None);
+
+        cluster.set_exit_status(jid, ExitStatus::Exited(3)).unwrap();
+        assert_eq!(cluster.get_job_info(jid).unwrap().exit_status, None);
+
+        cluster.set_state(jid, JobState::Failed).unwrap();
+        let info = cluster.get_job_info(jid).unwrap();
+        assert_eq!(info.state, JobState::Failed);
+        assert_eq!(info.exit_status, Some(ExitStatus::Exited(3)));
+
+        let records = cluster.get_jobs(&JobQuery::new()).unwrap();
+        assert_eq!(records.len(), 1);
+        let rec = &records[0];
+        assert_eq!(rec.job_id, jid);
+        assert_eq!(rec.job_name, "mockjob");
+        assert_eq!(rec.state, JobState::Failed);
+        assert_eq!(rec.exit_code, Some(3 << 8));
+        assert_eq!(rec.req_cpus, 4);
+        assert!(rec.end_time.is_some());
+        assert!(rec.start_time <= rec.end_time);
+    }
+
+    #[test]
+    fn job_ids_and_unknown_jobs() {
+        let cluster = MockCluster::with_first_job_id(42);
+        let a = cluster.submit_batch(&spec()).unwrap();
+        let b = cluster.submit_batch(&spec()).unwrap();
+        assert_eq!((a, b), (42, 43));
+        assert_eq!(cluster.job_ids(), vec![42, 43]);
+        assert_eq!(cluster.submitted_spec(a).unwrap(), spec());
+
+        assert!(cluster.get_job_info(99).is_err());
+        assert!(cluster.set_state(99, JobState::Running).is_err());
+        assert!(cluster.state(99).is_err());
+    }
+
+    #[test]
+    fn get_jobs_filters() {
+        let mut cluster = MockCluster::new();
+        cluster.set_user("alice").set_account("astro");
+        let a = cluster.submit_batch(&spec()).unwrap();
+        cluster.submit_batch(&spec()).unwrap();
+
+        let count = |query: &JobQuery| cluster.get_jobs(query).unwrap().len();
+
+        assert_eq!(count(JobQuery::new().user("alice")), 2);
+        assert_eq!(count(JobQuery::new().user("bob")), 0);
+        assert_eq!(count(JobQuery::new().account("astro").job(a)), 1);
+        assert_eq!(count(JobQuery::new().account("bio")), 0);
+
+        // Jobs that ended before the usage window are excluded, and jobs
+        // submitted after it are too.
+        cluster.set_state(a, JobState::Complete).unwrap();
+        let later = Utc::now() + Duration::hours(1);
+        assert_eq!(count(JobQuery::new().usage_start(later)), 1);
+        let earlier = Utc::now() - Duration::hours(1);
+        assert_eq!(count(JobQuery::new().usage_end(earlier)), 0);
+    }
+}
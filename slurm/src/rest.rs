@@ -0,0 +1,304 @@
+// Copyright 2018 Peter Williams <peter@newton.cx> and collaborators
+// Licensed under the MIT License
+
+/*! A client for the `slurmrestd` REST API.
+
+This module is only available if the `rest` feature is enabled. The
+`RestClient` type talks to Slurm's REST daemon over HTTP, authenticating with
+a JWT such as one generated by `scontrol token`. None of its methods call
+into the Slurm C library, so it can be used even when the local Slurm
+installation does not match the version running on the cluster.
+
+This module is a thin layer over the `slurm-rest` crate, which implements the
+HTTP client and decodes the JSON responses. This crate as a whole still links
+with libslurm; applications that must run on machines without any Slurm
+installation should use `slurm-rest` directly.
+
+The REST API returns plain data rather than structures allocated by Slurm,
+so the methods of `RestClient` return the plain-Rust snapshot types of this
+crate, such as `JobRecordSnapshot`. Some fields are not reported by the REST
+API; these are noted on the individual methods. `RestClient` implements the
+`Scheduler` and `Accounting` traits of the `backend` module, so it can be
+swapped in for the native backend.
+
+```no_run
+fn print_my_jobs() -> Result<(), slurm::Error> {
+    let client = slurm::rest::RestClient::from_env()?;
+    let mut query = slurm::JobQuery::new();
+    query.user("alice");
+
+    for job in client.get_jobs(&query)? {
+        println!("{} {} {:?}", job.job_id, job.job_name, job.state);
+    }
+
+    Ok(())
+}
+```
+
+*/
+
+use crate::backend::{Accounting, Scheduler};
+use crate::{
+    unix_time, BatchJobSpec, Error, ExitStatus, JobFlags, JobId, JobInfoSnapshot, JobQuery,
+    JobRecordSnapshot, JobState, JobStateFlags, MemSize, PendingReason, StepId, StepRecordSnapshot,
+    TimeLimit, TresAmounts,
+};
+
+pub use slurm_rest::DEFAULT_API_VERSION;
+
+impl From<slurm_rest::Error> for Error {
+    fn from(e: slurm_rest::Error) -> Self {
+        format_err!("{}", e)
+    }
+}
+
+/// A connection to a `slurmrestd` server.
+#[derive(Clone)]
+pub struct RestClient {
+    inner: slurm_rest::RestClient,
+}
+
+impl RestClient {
+    /// Create a new client for the server at `base_url`, such as
+    /// `http://slurm-head:6820`, authenticating as `user_name` with the JWT
+    /// `token`.
+    #[must_use]
+    pub fn new<U, N, T>(base_url: U, user_name: N, token: T) -> RestClient
+    where
+        U: Into<String>,
+        N: Into<String>,
+        T: Into<String>,
+    {
+        RestClient {
+            inner: slurm_rest::RestClient::new(base_url, user_name, token),
+        }
+    }
+
+    /// Create a new client configured from environment variables.
+    ///
+    /// The server URL is taken from `SLURMRESTD_URL`, the token from
+    /// `SLURM_JWT` (as set by `scontrol token`), and the user name from
+    /// `USER`.
+    pub fn from_env() -> Result<RestClient, Error> {
+        Ok(RestClient {
+            inner: slurm_rest::RestClient::from_env()?,
+        })
+    }
+
+    /// Select the version of the REST API to use, such as `v0.0.39`.
+    pub fn api_version<S: Into<String>>(&mut self, version: S) -> &mut Self {
+        self.inner.api_version(version);
+        self
+    }
+
+    /// Get information about a single job from the controller.
+    ///
+    /// The REST API does not report the job's flag bits, state flags, or
+    /// allocated TRES, so those fields are left empty.
+    pub fn get_job_info(&self, jid: JobId) -> Result<JobInfoSnapshot, Error> {
+        let job = self.inner.get_job_info(jid)?;
+        let state: JobState = job.state.parse()?;
+
+        Ok(JobInfoSnapshot {
+            account: job.account,
+            admin_comment: job.admin_comment,
+            array_job_id: job.array_job_id,
+            array_task_id: job.array_task_id,
+            batch_host: job.batch_host,
+            bitflags: JobFlags::default(),
+            comment: job.comment,
+            dependency: job.dependency,
+            exit_status: job
+                .exit_code
+                .filter(|_| state.is_terminal())
+                .map(ExitStatus::from_slurm),
+            fed_origin: job.fed_origin,
+            fed_siblings_active: job.fed_siblings_active,
+            fed_siblings_viable: job.fed_siblings_viable,
+            gres_detail: job.gres_detail,
+            is_batch: job.is_batch,
+            job_id: job.job_id,
+            licenses: job.licenses,
+            mcs_label: job.mcs_label,
+            min_memory: memory(job.memory_per_cpu, job.memory_per_node),
+            name: job.name,
+            nodes: job.nodes,
+            num_cpus: job.num_cpus,
+            partition: job.partition,
+            priority: job.priority,
+            qos: job.qos,
+            requeue: job.requeue,
+            restart_cnt: job.restart_cnt,
+            start_time: job.start_time,
+            state,
+            state_flags: JobStateFlags::default(),
+            state_reason: job
+                .state_reason
+                .and_then(|r| r.parse().ok())
+                .unwrap_or_else(|| PendingReason::from_slurm(0)),
+            stderr_path: job.stderr_path,
+            stdin_path: job.stdin_path,
+            stdout_path: job.stdout_path,
+            time_limit: job.time_limit.map(time_limit),
+            tres_alloc: TresAmounts::default(),
+            user_id: job.user_id.unwrap_or(u32::MAX),
+            user_name: job.user_name,
+            work_dir: job.work_dir,
+        })
+    }
+
+    /// Query the accounting database for jobs.
+    ///
+    /// The REST API does not report numeric user and QOS IDs, so the `uid`
+    /// and `qos_id` fields of the results are set to `u32::MAX`. Memory
+    /// statistics and state flags are not reported either.
+    pub fn get_jobs(&self, query: &JobQuery) -> Result<Vec<JobRecordSnapshot>, Error> {
+        let query = slurm_rest::JobsQuery {
+            users: query.users.clone(),
+            accounts: query.accounts.clone(),
+            jobs: query.jobs.clone(),
+            start: query.start,
+            end: query.end,
+        };
+
+        self.inner
+            .get_jobs(&query)?
+            .into_iter()
+            .map(job_record)
+            .collect()
+    }
+
+    /// Submit a batch job, returning its ID.
+    pub fn submit_batch(&self, job: &BatchJobSpec) -> Result<JobId, Error> {
+        let (memory_per_cpu, memory_per_node) = match job.min_memory {
+            Some(MemSize::PerCpu(mib)) => (Some(mib), None),
+            Some(MemSize::PerNode(mib)) => (None, Some(mib)),
+            None => (None, None),
+        };
+
+        self.inner
+            .submit_batch(&slurm_rest::JobSubmission {
+                script: job.script.clone(),
+                environment: job.environment.clone(),
+                name: job.name.clone(),
+                comment: job.comment.clone(),
+                partition: job.partition.clone(),
+                work_dir: job.work_dir.clone(),
+                num_tasks: job.num_tasks,
+                time_limit: job.time_limit.map(|t| match t {
+                    TimeLimit::Minutes(m) => slurm_rest::TimeLimit::Minutes(m),
+                    TimeLimit::Unlimited => slurm_rest::TimeLimit::Unlimited,
+                }),
+                memory_per_cpu,
+                memory_per_node,
+                stdin_path: job.stdin_path.clone(),
+                stdout_path: job.stdout_path.clone(),
+                stderr_path: job.stderr_path.clone(),
+            })
+            .map_err(Error::from)
+    }
+}
+
+impl Scheduler for RestClient {
+    fn get_job_info(&self, jid: JobId) -> Result<JobInfoSnapshot, Error> {
+        RestClient::get_job_info(self, jid)
+    }
+
+    fn submit_batch(&self, job: &BatchJobSpec) -> Result<JobId, Error> {
+        RestClient::submit_batch(self, job)
+    }
+}
+
+impl Accounting for RestClient {
+    fn get_jobs(&self, query: &JobQuery) -> Result<Vec<JobRecordSnapshot>, Error> {
+        RestClient::get_jobs(self, query)
+    }
+}
+
+fn time_limit(limit: slurm_rest::TimeLimit) -> TimeLimit {
+    match limit {
+        slurm_rest::TimeLimit::Minutes(m) => TimeLimit::Minutes(m),
+        slurm_rest::TimeLimit::Unlimited => TimeLimit::Unlimited,
+    }
+}
+
+fn memory(per_cpu: Option<u64>, per_node: Option<u64>) -> Option<MemSize> {
+    per_cpu
+        .map(MemSize::PerCpu)
+        .or_else(|| per_node.map(MemSize::PerNode))
+}
+
+/// Decode a step ID, which the REST API gives either by name or as a raw
+/// step number.
+fn step_id(text: &str) -> Result<StepId, Error> {
+    match text.parse::<u32>() {
+        Ok(raw) => Ok(StepId::from(raw)),
+        Err(_) => text.parse(),
+    }
+}
+
+fn step_record(step: slurm_rest::StepRecord) -> Result<StepRecordSnapshot, Error> {
+    Ok(StepRecordSnapshot {
+        elapsed: step.elapsed,
+        end_time: step.end_time,
+        exit_code: step.exit_code.map(|c| c as i32),
+        max_rss: None,
+        max_vm_size: None,
+        nnodes: step.nnodes,
+        node_list: step.node_list,
+        ntasks: step.ntasks,
+        start_time: step.start_time,
+        state: step.state.parse()?,
+        state_flags: JobStateFlags::default(),
+        step_id: step_id(&step.step_id)?,
+        step_name: step.step_name,
+        sys_cpu: step.sys_cpu,
+        task_distribution: None,
+        tot_cpu: step.tot_cpu,
+        tres_alloc: TresAmounts(step.tres_alloc),
+        user_cpu: step.user_cpu,
+    })
+}
+
+fn job_record(job: slurm_rest::JobRecord) -> Result<JobRecordSnapshot, Error> {
+    Ok(JobRecordSnapshot {
+        account: job.account,
+        alloc_nodes: job.alloc_nodes,
+        array_job_id: job.array_job_id,
+        cluster: job.cluster,
+        derived_exit_code: job.derived_exit_code,
+        elapsed: job.elapsed,
+        eligible_time: job.eligible_time,
+        end_time: job.end_time,
+        exit_code: job.exit_code.map(|c| c as i32),
+        job_id: job.job_id,
+        job_name: job.job_name,
+        max_rss: None,
+        max_vm_size: None,
+        node_list: job.node_list,
+        partition: job.partition,
+        priority: job.priority,
+        qos_id: u32::MAX,
+        req_cpus: job.req_cpus,
+        req_mem: memory(job.req_memory_per_cpu, job.req_memory_per_node),
+        start_time: job.start_time,
+        state: job.state.parse()?,
+        state_flags: JobStateFlags::default(),
+        steps: job
+            .steps
+            .into_iter()
+            .map(step_record)
+            .collect::<Result<_, _>>()?,
+        submit_time: job.submit_time.unwrap_or_else(|| unix_time(0)),
+        sys_cpu: job.sys_cpu,
+        time_limit: job.time_limit.map(time_limit),
+        tot_cpu: job.tot_cpu,
+        tres_alloc: TresAmounts(job.tres_alloc),
+        tres_req: TresAmounts(job.tres_req),
+        uid: u32::MAX,
+        user: job.user,
+        user_cpu: job.user_cpu,
+        wckey: job.wckey,
+        work_dir: job.work_dir,
+    })
+}
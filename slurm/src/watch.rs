@@ -0,0 +1,277 @@
+// Copyright 2018 Peter Williams <peter@newton.cx> and collaborators
+// Licensed under the MIT License
+
+/*! Watching jobs for changes.
+
+The `JobWatcher` type polls the controller for information about a set of
+jobs and reports how they change: when they change state, when they are
+assigned nodes, when they are requeued, and when they leave the controller's
+queue. The controller is asked only whether anything has changed since the
+previous poll, so that watching jobs puts little load on it.
+
+A `JobWatcher` is an iterator over the events that it observes. The iterator
+blocks between polls and ends once all of the watched jobs have left the
+queue.
+
+```no_run
+use std::time::Duration;
+
+fn main() {
+    let mut watcher = slurm::watch::JobWatcher::new(Duration::from_secs(10));
+    watcher.watch(1234);
+
+    for event in watcher {
+        println!("{}", event.unwrap());
+    }
+}
+```
+
+*/
+
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::{Display, Error as FmtError, Formatter};
+use std::time::{Duration, Instant};
+
+use crate::{get_all_jobs, get_all_jobs_since, Error, JobId, JobInfoMessage, JobState, LoadResult};
+
+/// A change to a job observed by a `JobWatcher`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JobEvent {
+    /// The ID of the job that changed.
+    pub job_id: JobId,
+
+    /// The time at which the controller generated the information in which
+    /// the change was observed.
+    pub time: DateTime<Utc>,
+
+    /// What happened to the job.
+    pub kind: JobEventKind,
+}
+
+/// The kinds of change that a `JobWatcher` can observe.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum JobEventKind {
+    /// The job changed state. `old` is None if this is the first time that
+    /// the job has been seen.
+    StateChanged {
+        old: Option<JobState>,
+        new: JobState,
+    },
+
+    /// The job was allocated nodes, given in Slurm's compressed hostlist
+    /// format.
+    NodesAssigned { nodes: String },
+
+    /// The job was requeued. `restart_cnt` is the number of times that it
+    /// has now been restarted.
+    Requeued { restart_cnt: u16 },
+
+    /// The job is no longer known to the controller, either because it
+    /// finished long enough ago to have been purged, or because it never
+    /// existed. The job is no longer watched after this event.
+    Gone,
+}
+
+impl Display for JobEvent {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+        write!(f, "{} job {}: ", self.time.to_rfc3339(), self.job_id)?;
+
+        match self.kind {
+            JobEventKind::StateChanged { old: None, new } => write!(f, "{}", new.slurm_name()),
+            JobEventKind::StateChanged {
+                old: Some(old),
+                new,
+            } => write!(f, "{} -> {}", old.slurm_name(), new.slurm_name()),
+            JobEventKind::NodesAssigned { ref nodes } => write!(f, "assigned nodes {}", nodes),
+            JobEventKind::Requeued { restart_cnt } => {
+                write!(f, "requeued (restart count {})", restart_cnt)
+            }
+            JobEventKind::Gone => write!(f, "gone"),
+        }
+    }
+}
+
+/// What a `JobWatcher` last observed about a job.
+#[derive(Clone, Debug)]
+struct Observed {
+    state: JobState,
+    nodes: Option<String>,
+    restart_cnt: u16,
+}
+
+/// A poller that reports changes to a set of jobs.
+#[derive(Debug)]
+pub struct JobWatcher {
+    poll_interval: Duration,
+    last_update: Option<DateTime<Utc>>,
+    last_poll: Option<Instant>,
+    jobs: BTreeMap<JobId, Option<Observed>>,
+    pending: VecDeque<JobEvent>,
+}
+
+impl JobWatcher {
+    /// Create a new watcher that is not watching any jobs.
+    ///
+    /// When used as an iterator, the watcher polls the controller no more
+    /// often than once every `poll_interval`.
+    #[must_use]
+    pub fn new(poll_interval: Duration) -> JobWatcher {
+        JobWatcher {
+            poll_interval,
+            last_update: None,
+            last_poll: None,
+            jobs: BTreeMap::new(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Start watching a job.
+    ///
+    /// The first poll after a job is added reports its current state with a
+    /// `StateChanged` event whose `old` field is None.
+    pub fn watch(&mut self, jid: JobId) -> &mut Self {
+        self.jobs.entry(jid).or_insert(None);
+        self
+    }
+
+    /// Stop watching a job.
+    pub fn unwatch(&mut self, jid: JobId) -> &mut Self {
+        self.jobs.remove(&jid);
+        self
+    }
+
+    /// Get the IDs of the jobs being watched.
+    pub fn job_ids(&self) -> Vec<JobId> {
+        self.jobs.keys().cloned().collect()
+    }
+
+    /// Poll the controller once, returning the events observed.
+    ///
+    /// This does not wait for the poll interval to elapse. Events returned
+    /// by this function are not also returned by the iterator interface.
+    pub fn poll(&mut self) -> Result<Vec<JobEvent>, Error> {
+        let has_new_jobs = self.jobs.values().any(|o| o.is_none());
+        self.last_poll = Some(Instant::now());
+
+        // If a job was added since the last poll, we need its current state
+        // even if nothing has changed on the controller.
+        let msg = match self.last_update {
+            Some(t) if !has_new_jobs => match get_all_jobs_since(t)? {
+                LoadResult::Updated(msg) => msg,
+                LoadResult::NotChanged => return Ok(Vec::new()),
+            },
+            _ => get_all_jobs()?,
+        };
+
+        self.last_update = Some(msg.last_update());
+        Ok(self.process(&msg))
+    }
+
+    fn process(&mut self, msg: &JobInfoMessage) -> Vec<JobEvent> {
+        let time = msg.last_update();
+        let mut events = Vec::new();
+        let mut seen = Vec::new();
+
+        for job in msg.jobs() {
+            let job_id = job.job_id();
+
+            let slot = match self.jobs.get_mut(&job_id) {
+                Some(slot) => slot,
+                None => continue,
+            };
+
+            seen.push(job_id);
+
+            let cur = Observed {
+                state: job.state(),
+                nodes: job.nodes().map(|n| n.into_owned()),
+                restart_cnt: job.restart_cnt(),
+            };
+
+            let mut push = |kind| events.push(JobEvent { job_id, time, kind });
+
+            match *slot {
+                None => push(JobEventKind::StateChanged {
+                    old: None,
+                    new: cur.state,
+                }),
+
+                Some(ref prev) => {
+                    if cur.restart_cnt > prev.restart_cnt {
+                        push(JobEventKind::Requeued {
+                            restart_cnt: cur.restart_cnt,
+                        });
+                    }
+
+                    if cur.state != prev.state {
+                        push(JobEventKind::StateChanged {
+                            old: Some(prev.state),
+                            new: cur.state,
+                        });
+                    }
+                }
+            }
+
+            let prev_nodes = slot.as_ref().and_then(|o| o.nodes.as_ref());
+
+            if let Some(ref nodes) = cur.nodes {
+                if prev_nodes != Some(nodes) {
+                    push(JobEventKind::NodesAssigned {
+                        nodes: nodes.clone(),
+                    });
+                }
+            }
+
+            *slot = Some(cur);
+        }
+
+        let gone: Vec<JobId> = self
+            .jobs
+            .keys()
+            .filter(|jid| !seen.contains(*jid))
+            .cloned()
+            .collect();
+
+        for job_id in gone {
+            self.jobs.remove(&job_id);
+            events.push(JobEvent {
+                job_id,
+                time,
+                kind: JobEventKind::Gone,
+            });
+        }
+
+        events
+    }
+}
+
+impl Iterator for JobWatcher {
+    type Item = Result<JobEvent, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(Ok(event));
+            }
+
+            if self.jobs.is_empty() {
+                return None;
+            }
+
+            if let Some(t) = self.last_poll {
+                let elapsed = t.elapsed();
+
+                if elapsed < self.poll_interval {
+                    std::thread::sleep(self.poll_interval - elapsed);
+                }
+            }
+
+            match self.poll() {
+                Ok(events) => self.pending.extend(events),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
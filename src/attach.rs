@@ -0,0 +1,283 @@
+// Copyright 2018 Peter Williams <peter@newton.cx> and collaborators
+// Licensed under the MIT License
+
+/*! Follow the output of a batch job.
+
+This command works out where a batch job's standard output and error streams
+are going, prints what they contain so far, and then follows them like `tail
+-f` until the job finishes. It relies on the output files being visible from
+the machine that runs it, as they are on the usual shared filesystems.
+
+*/
+
+use crate::colorio::ColorIo;
+use crate::util;
+use crate::wait;
+use anyhow::{format_err, Error};
+use slurm::{ExitStatus, JobIdentifier, JobInfo, JobState};
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct AttachCommand {
+    #[structopt(short = "i", long = "interval", default_value = "2")]
+    /// How often to check for new output, in seconds.
+    interval_secs: u64,
+
+    #[structopt(help = "The ID of the job to follow.")]
+    jobid: slurm::JobIdentifier,
+}
+
+impl AttachCommand {
+    pub fn cli(self, cio: &mut ColorIo) -> Result<i32, Error> {
+        let interval = Duration::from_secs(self.interval_secs);
+        let mut followers: Option<Vec<Follower>> = None;
+        let mut announced_wait = false;
+        let mut last_state = None;
+
+        let (state, status) = loop {
+            let info = match slurm::get_job_info(self.jobid) {
+                Ok(i) => Some(i),
+                Err(slurm::Error::Slurm(slurm::SlurmError::InvalidJobId)) => None,
+                Err(e) => return Err(e.into()),
+            };
+
+            if let Some(ref i) = info {
+                last_state = Some(i.state());
+            }
+
+            if followers.is_none() {
+                match info {
+                    Some(ref i) if i.state() != JobState::Pending => {
+                        followers = Some(Follower::for_job(cio, i)?);
+                    }
+
+                    Some(_) => {
+                        if !announced_wait {
+                            cprintln!(cio, pl, "waiting for job {} to start ...", self.jobid);
+                            announced_wait = true;
+                        }
+                    }
+
+                    None => {
+                        return Err(format_err!(
+                            "job {} is no longer known to the controller, so its output \
+                             files cannot be located",
+                            self.jobid
+                        ));
+                    }
+                }
+            }
+
+            if let Some(ref mut fs) = followers {
+                for f in fs.iter_mut() {
+                    f.drain()?;
+                }
+            }
+
+            match info {
+                Some(ref i) if i.state().is_terminal() => break (i.state(), i.exit_status()),
+                Some(_) => {}
+                // The job finished and left the queue between polls; the
+                // database has its final state.
+                None => break final_state(cio, self.jobid, interval, last_state)?,
+            }
+
+            std::thread::sleep(interval);
+        };
+
+        cprint!(cio, hl, "{}", self.jobid);
+        cprint!(cio, pl, " ");
+        util::colorize_state(cio, state);
+
+        match status {
+            Some(s) => {
+                cprintln!(cio, pl, " {}", s);
+            }
+            None => {
+                cprintln!(cio, pl, "");
+            }
+        }
+
+        Ok(wait::exit_code(state, status))
+    }
+}
+
+/// How long to wait for the accounting database to record the final state
+/// of a job that has left the controller's queue.
+const DATABASE_GRACE: Duration = Duration::from_secs(30);
+
+/// Get the final state of a job that has left the controller's queue.
+///
+/// The accounting database can lag behind the controller, so we poll it for
+/// a little while. If it still can't tell us how the job ended, we fall back
+/// on the last state that the controller reported, without an exit status.
+fn final_state(
+    cio: &mut ColorIo,
+    jid: JobIdentifier,
+    interval: Duration,
+    last_state: Option<JobState>,
+) -> Result<(JobState, Option<ExitStatus>), Error> {
+    let deadline = Instant::now() + DATABASE_GRACE;
+
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+
+        let e = match slurm::wait_for_job(jid, interval, remaining) {
+            Ok(result) => return Ok(result),
+            Err(slurm::Error::Slurm(slurm::SlurmError::InvalidJobId))
+                if remaining > Duration::ZERO =>
+            {
+                std::thread::sleep(interval.min(remaining));
+                continue;
+            }
+            Err(e) => e,
+        };
+
+        let state = match last_state {
+            Some(s) => s,
+            None => return Err(e.into()),
+        };
+
+        ecprint!(cio, yellow, "warning:");
+        ecprintln!(
+            cio,
+            pl,
+            " could not get the final state of job {} ({}); showing the last state \
+             reported by the controller",
+            jid,
+            e
+        );
+        return Ok((state, None));
+    }
+}
+
+/// Copies new data from one of a job's output files to one of our streams.
+#[derive(Debug)]
+struct Follower {
+    path: PathBuf,
+    pos: u64,
+    to_stderr: bool,
+}
+
+impl Follower {
+    /// Set up followers for the output files of a job, reporting where they
+    /// are.
+    fn for_job(cio: &mut ColorIo, job: &JobInfo) -> Result<Vec<Follower>, Error> {
+        if !job.is_batch() {
+            return Err(format_err!(
+                "job {} is not a batch job, so its output does not go to files",
+                job.job_id()
+            ));
+        }
+
+        let work_dir = job
+            .work_dir()
+            .map(|d| PathBuf::from(d.as_ref()))
+            .ok_or_else(|| {
+                format_err!(
+                    "the controller did not report the working directory of job {}",
+                    job.job_id()
+                )
+            })?;
+
+        let array = match (job.array_job_id(), job.array_task_id()) {
+            (Some(a), Some(t)) => Some((a, t)),
+            _ => None,
+        };
+
+        let name = job.name();
+        let user = job.user_name().map(|u| u.into_owned()).or_else(|| {
+            users::get_user_by_uid(job.user_id()).map(|u| u.name().to_string_lossy().into_owned())
+        });
+        let node = job.batch_host();
+
+        let vars = util::LogPathVars {
+            job_id: job.job_id(),
+            array,
+            name: &name,
+            user: user.as_deref(),
+            node: node.as_deref(),
+        };
+
+        let default_stdout = if array.is_some() {
+            "slurm-%A_%a.out"
+        } else {
+            "slurm-%j.out"
+        };
+
+        let stdout_pattern = job
+            .stdout_path()
+            .map(|p| p.into_owned())
+            .unwrap_or_else(|| default_stdout.to_owned());
+        let stdout = util::log_path(&work_dir, &stdout_pattern, &vars);
+        let stderr = job
+            .stderr_path()
+            .map(|p| util::log_path(&work_dir, &p, &vars))
+            .unwrap_or_else(|| stdout.clone());
+
+        let mut followers = vec![Follower::new(&stdout, false)];
+
+        if stderr == stdout {
+            cprintln!(cio, pl, "following {}", stdout.display());
+        } else {
+            cprintln!(
+                cio,
+                pl,
+                "following {} and {}",
+                stdout.display(),
+                stderr.display()
+            );
+            followers.push(Follower::new(&stderr, true));
+        }
+
+        Ok(followers)
+    }
+
+    fn new(path: &Path, to_stderr: bool) -> Self {
+        Follower {
+            path: path.to_owned(),
+            pos: 0,
+            to_stderr,
+        }
+    }
+
+    /// Copy whatever has been appended to the file since the last call.
+    fn drain(&mut self) -> Result<(), Error> {
+        let mut f = match File::open(&self.path) {
+            Ok(f) => f,
+            // The file is not created until the job starts writing to it.
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+
+        // A file that has shrunk has been truncated or replaced, as happens
+        // when a job is requeued; start over.
+        if f.metadata()?.len() < self.pos {
+            self.pos = 0;
+        }
+
+        f.seek(SeekFrom::Start(self.pos))?;
+        let mut buf = Vec::new();
+        self.pos += f.read_to_end(&mut buf)? as u64;
+
+        if buf.is_empty() {
+            return Ok(());
+        }
+
+        if self.to_stderr {
+            let mut stream = io::stderr();
+            stream.write_all(&buf)?;
+            stream.flush()?;
+        } else {
+            let mut stream = io::stdout();
+            stream.write_all(&buf)?;
+            stream.flush()?;
+        }
+
+        Ok(())
+    }
+}
@@ -7,7 +7,7 @@ There are a few common colorized output styles that we use.
 
 */
 
-use failure::Error;
+use anyhow::Error;
 use std::fmt;
 use std::io::Write;
 use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
@@ -153,7 +153,7 @@ impl ColorIo {
     pub fn print_error(&mut self, err: Error) {
         let mut first = true;
 
-        for cause in err.iter_chain() {
+        for cause in err.chain() {
             if first {
                 ecprint!(self, red, "error:");
                 ecprintln!(self, pl, " {}", cause);
@@ -0,0 +1,205 @@
+// Copyright 2018 Peter Williams <peter@newton.cx> and collaborators
+// Licensed under the MIT License
+
+/*! Show the tree of jobs that a job depends on.
+
+The dependencies of each job are looked up recursively and printed as a tree,
+along with the current state of each job. Dependencies that can no longer be
+satisfied, such as an `afterok` dependency on a job that failed, are flagged.
+
+*/
+
+use crate::colorio::ColorIo;
+use crate::util;
+use anyhow::Error;
+use slurm::{
+    Dependency, DependencyKind, JobDependencies, JobIdentifier, JobState,
+    JobStepRecordSharedFields, PendingReason,
+};
+use std::collections::HashSet;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct DepsCommand {
+    #[structopt(help = "The ID of the job whose dependencies to show.")]
+    jobid: JobIdentifier,
+}
+
+impl DepsCommand {
+    pub fn cli(self, cio: &mut ColorIo) -> Result<i32, Error> {
+        let root = lookup(self.jobid)?;
+
+        cprint!(cio, hl, "{}", self.jobid);
+        root.print(cio);
+        cprintln!(cio, pl, "");
+
+        let mut seen = HashSet::new();
+        seen.insert(self.jobid);
+        let blocked = print_children(cio, &root, "", &mut seen)?;
+
+        if root.never_satisfied || blocked {
+            cprint!(cio, red, "warning:");
+            cprintln!(
+                cio,
+                pl,
+                " job {} can never start; cancel it or change its dependencies",
+                self.jobid
+            );
+            return Ok(1);
+        }
+
+        Ok(0)
+    }
+}
+
+/// What we know about a job in the tree.
+#[derive(Debug)]
+struct Node {
+    name: String,
+    state: Option<JobState>,
+    success: Option<bool>,
+    never_satisfied: bool,
+    deps: JobDependencies,
+}
+
+impl Node {
+    fn print(&self, cio: &mut ColorIo) {
+        cprint!(cio, pl, " {} ", self.name);
+
+        match self.state {
+            Some(s) => util::colorize_state(cio, s),
+            None => cprint!(cio, yellow, "??"),
+        }
+    }
+}
+
+/// Look up a job, first with the controller and then, if it has left the
+/// queue, in the accounting database.
+fn lookup(jid: JobIdentifier) -> Result<Node, Error> {
+    match slurm::get_job_info(jid) {
+        Ok(info) => {
+            let deps = match info.dependency() {
+                Some(d) => d.parse()?,
+                None => JobDependencies::default(),
+            };
+
+            Ok(Node {
+                name: info.name().into_owned(),
+                state: Some(info.state()),
+                success: info.exit_status().map(|s| s.success()),
+                never_satisfied: info.state_reason() == PendingReason::DependencyNeverSatisfied,
+                deps,
+            })
+        }
+
+        Err(slurm::Error::Slurm(slurm::SlurmError::InvalidJobId)) => {
+            let filter = slurm::JobFiltersOwned::for_job(jid)?;
+            let db = slurm::DatabaseConnectionOwned::new()?;
+            let jobs = db.get_jobs(&filter)?;
+
+            // A requeued job has one record per attempt; the last one is the
+            // current one.
+            Ok(match jobs.iter().last() {
+                Some(job) => Node {
+                    name: job.job_name().into_owned(),
+                    state: Some(job.state()),
+                    success: job.exit_status().map(|s| s.success()),
+                    never_satisfied: false,
+                    deps: JobDependencies::default(),
+                },
+                None => Node {
+                    name: "(unknown job)".to_owned(),
+                    state: None,
+                    success: None,
+                    never_satisfied: false,
+                    deps: JobDependencies::default(),
+                },
+            })
+        }
+
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Print the dependencies of a job, recursively, returning true if they
+/// can never all be satisfied.
+fn print_children(
+    cio: &mut ColorIo,
+    node: &Node,
+    prefix: &str,
+    seen: &mut HashSet<JobIdentifier>,
+) -> Result<bool, Error> {
+    let n = node.deps.items.len();
+    let mut n_bad = 0;
+
+    for (i, dep) in node.deps.items.iter().enumerate() {
+        let last = i + 1 == n;
+        cprint!(cio, pl, "{}{}", prefix, if last { "└── " } else { "├── " });
+        cprint!(cio, pl, "{} ", dep.kind.slurm_name());
+
+        let jid = match dep.job {
+            Some(j) => j,
+            None => {
+                cprintln!(cio, pl, "(jobs with the same name)");
+                continue;
+            }
+        };
+
+        cprint!(cio, hl, "{}", jid);
+
+        if let Some(m) = dep.delay_minutes {
+            cprint!(cio, pl, "+{}min", m);
+        }
+
+        if !seen.insert(jid) {
+            cprintln!(cio, pl, " (see above)");
+            continue;
+        }
+
+        let child = lookup(jid)?;
+        child.print(cio);
+
+        let bad = never_satisfiable(dep, &child);
+
+        if bad {
+            cprint!(cio, red, "  can never be satisfied");
+        }
+
+        cprintln!(cio, pl, "");
+
+        let child_prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+        let child_blocked = print_children(cio, &child, &child_prefix, seen)?;
+
+        // A job that can never start can never satisfy a dependency on it.
+        if bad || child_blocked {
+            n_bad += 1;
+        }
+    }
+
+    Ok(if node.deps.require_any {
+        n > 0 && n_bad == n
+    } else {
+        n_bad > 0
+    })
+}
+
+/// Decide whether a dependency can no longer be satisfied, given the state
+/// of the job depended upon.
+fn never_satisfiable(dep: &Dependency, target: &Node) -> bool {
+    if dep.status.as_deref() == Some("failed") {
+        return true;
+    }
+
+    let state = match target.state {
+        Some(s) if s.is_terminal() => s,
+        _ => return false,
+    };
+
+    let succeeded = state == JobState::Complete && target.success.unwrap_or(true);
+
+    match dep.kind {
+        DependencyKind::AfterOk | DependencyKind::AfterCorr => !succeeded,
+        DependencyKind::AfterNotOk => succeeded,
+        _ => false,
+    }
+}
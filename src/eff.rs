@@ -0,0 +1,173 @@
+// Copyright 2018 Peter Williams <peter@newton.cx> and collaborators
+// Licensed under the MIT License
+
+/*! Report how efficiently a job used its resources.
+
+This command is most similar to the `seff` script that is distributed with
+Slurm. It compares the CPU time and memory that a job actually used, as
+recorded in the accounting database, with what was allocated to it.
+
+*/
+
+use crate::colorio::ColorIo;
+use crate::util;
+use anyhow::{format_err, Error};
+use chrono::Duration;
+use slurm::{JobState, JobStepRecordSharedFields, MemSize};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct EffCommand {
+    #[structopt(help = "The ID of the job to analyze.")]
+    jobid: slurm::JobIdentifier,
+}
+
+impl EffCommand {
+    pub fn cli(self, cio: &mut ColorIo) -> Result<i32, Error> {
+        let filter = slurm::JobFiltersOwned::for_job(self.jobid)?;
+        let db = slurm::DatabaseConnectionOwned::new()?;
+        let jobs = db.get_jobs(&filter)?;
+        let mut n_jobs = 0;
+
+        for job in jobs.iter() {
+            n_jobs += 1;
+            let state = job.state();
+
+            cprint!(cio, hl, "{}", job.job_id());
+            cprint!(cio, pl, " {} ", job.job_name());
+            util::colorize_state(cio, state);
+
+            match job.exit_status() {
+                Some(s) => {
+                    cprintln!(cio, pl, " (exit {})", s);
+                }
+                None => {
+                    cprintln!(cio, pl, "");
+                }
+            }
+
+            if job.start_time().is_none() {
+                cprintln!(cio, pl, "  job has not started");
+                continue;
+            }
+
+            if !state.is_terminal() {
+                cprint!(cio, yellow, "  warning:");
+                cprintln!(
+                    cio,
+                    pl,
+                    " the job is still running, so its statistics are incomplete"
+                );
+            }
+
+            let tres = job.tres_alloc();
+            let cpus = tres.cpus().unwrap_or_else(|| job.req_cpus() as u64);
+            let nodes = tres
+                .nodes()
+                .unwrap_or_else(|| std::cmp::max(job.alloc_nodes(), 1) as u64);
+            cprintln!(cio, pl, "  cores: {} on {} node(s)", cpus, nodes);
+
+            let elapsed = job.elapsed();
+            let core_walltime = Duration::seconds(elapsed.num_seconds() * cpus as i64);
+            cprintln!(
+                cio,
+                pl,
+                "  CPU time used: {}",
+                util::dur_to_hms(&job.tot_cpu())
+            );
+
+            match job.cpu_efficiency() {
+                Some(eff) => {
+                    cprint!(cio, pl, "  CPU efficiency: ");
+                    colorize_efficiency(cio, eff);
+                    cprintln!(
+                        cio,
+                        pl,
+                        " of {} core-walltime",
+                        util::dur_to_hms(&core_walltime)
+                    );
+                }
+                None => {
+                    cprintln!(cio, pl, "  CPU efficiency: not available");
+                }
+            }
+
+            // The job record's own statistics are usually empty; the usage is
+            // recorded in its steps. Like `seff`, we take the largest
+            // per-task peak of any step.
+            let max_rss_kib = job
+                .steps()
+                .iter()
+                .filter_map(|step| step.stats().rss_max())
+                .chain(job.stats().rss_max())
+                .max();
+
+            let req_mib = tres.mem_mib().or_else(|| {
+                job.req_mem().map(|m| match m {
+                    MemSize::PerNode(mib) => mib * nodes,
+                    MemSize::PerCpu(mib) => mib * cpus,
+                })
+            });
+
+            match max_rss_kib {
+                Some(kib) => {
+                    cprintln!(cio, pl, "  memory used: {}", format_mib(kib / 1024));
+                }
+                None => {
+                    cprintln!(cio, pl, "  memory used: not available");
+                }
+            }
+
+            match (max_rss_kib, req_mib) {
+                (Some(kib), Some(mib)) if mib > 0 => {
+                    cprint!(cio, pl, "  memory efficiency: ");
+                    colorize_efficiency(cio, kib as f64 / 1024. / mib as f64);
+                    cprintln!(cio, pl, " of {}", format_mib(mib));
+                }
+                _ => {
+                    cprintln!(cio, pl, "  memory efficiency: not available");
+                }
+            }
+
+            if state == JobState::OutOfMemory {
+                cprint!(cio, red, "  note:");
+                cprintln!(
+                    cio,
+                    pl,
+                    " the job ran out of memory; request more than it was allocated"
+                );
+            }
+        }
+
+        if n_jobs == 0 {
+            return Err(format_err!(
+                "job {} not found in the accounting database",
+                self.jobid
+            ));
+        }
+
+        Ok(0)
+    }
+}
+
+/// Print an efficiency as a percentage, colored by how good it is.
+fn colorize_efficiency(cio: &mut ColorIo, eff: f64) {
+    let pct = 100. * eff;
+
+    if eff < 0.5 {
+        cprint!(cio, red, "{:.1}%", pct);
+    } else if eff < 0.8 {
+        cprint!(cio, yellow, "{:.1}%", pct);
+    } else {
+        cprint!(cio, green, "{:.1}%", pct);
+    }
+}
+
+/// Express an amount of memory in a convenient unit.
+fn format_mib(mib: u64) -> String {
+    if mib >= 1024 {
+        format!("{:.2} GiB", mib as f64 / 1024.)
+    } else {
+        format!("{} MiB", mib)
+    }
+}
@@ -0,0 +1,262 @@
+// Copyright 2018 Peter Williams <peter@newton.cx> and collaborators
+// Licensed under the MIT License
+
+/*! Show the fairshare standing of the current user's accounts.
+
+This command is a friendlier take on `sshare`. It asks the controller for
+the fairshare information of all associations, and draws the tree of each
+of the user's accounts, from the root association down through the users
+of the account, with their shares, usage, and fairshare factors.
+
+*/
+
+use crate::colorio::ColorIo;
+use anyhow::{format_err, Error};
+use itertools::Itertools;
+use slurm::SharesRecordSnapshot;
+use std::collections::{HashMap, HashSet};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct FairshareCommand {
+    #[structopt(short = "A", long = "account")]
+    /// Show the tree of this account rather than those of your own
+    /// associations.
+    account: Option<String>,
+}
+
+impl FairshareCommand {
+    pub fn cli(self, cio: &mut ColorIo) -> Result<i32, Error> {
+        let user = users::get_current_username()
+            .map(|u| u.to_string_lossy().into_owned())
+            .ok_or_else(|| format_err!("cannot determine the name of the current user"))?;
+
+        let msg = slurm::get_all_shares()?;
+
+        if msg.is_empty() {
+            return Err(format_err!(
+                "the controller reported no fairshare information"
+            ));
+        }
+
+        let records: Vec<SharesRecordSnapshot> =
+            msg.shares().iter().map(|r| r.to_snapshot()).collect();
+
+        let accounts: Vec<&str> = match self.account {
+            Some(ref a) => vec![a.as_str()],
+            None => records
+                .iter()
+                .filter(|r| r.is_user && r.name == user)
+                .filter_map(|r| r.parent.as_deref())
+                .unique()
+                .collect(),
+        };
+
+        if accounts.is_empty() {
+            return Err(format_err!(
+                "the controller reported no fairshare information for user {}",
+                user
+            ));
+        }
+
+        let mut tree = Tree::new(&records);
+
+        for account in accounts {
+            tree.show_account(account)?;
+        }
+
+        let rows = tree.rows();
+        let width = rows
+            .iter()
+            .map(|r| r.0.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(12);
+
+        cprintln!(
+            cio,
+            hl,
+            "{:<width$}  {:>10}  {:>11}  {:>11}  {:>13}  {:>9}",
+            "ACCOUNT/USER",
+            "RAW SHARES",
+            "NORM SHARES",
+            "NORM USAGE",
+            "EFFECTV USAGE",
+            "FAIRSHARE"
+        );
+
+        for (label, rec) in rows {
+            let raw_shares = if rec.shares_raw == slurm::FS_USE_PARENT {
+                "parent".to_owned()
+            } else {
+                rec.shares_raw.to_string()
+            };
+
+            // The fairshare factor only means something for users; the
+            // priority of a job comes from that of its user association.
+            let fairshare = if rec.is_user {
+                format!("{:.6}", rec.fairshare_factor)
+            } else {
+                String::new()
+            };
+
+            let line = format!(
+                "{:<width$}  {:>10}  {:>11.6}  {:>11.6}  {:>13.6}  {:>9}",
+                label, raw_shares, rec.shares_norm, rec.usage_norm, rec.effective_usage, fairshare
+            );
+
+            if rec.is_user && rec.name == user {
+                cprintln!(cio, hl, "{}", line);
+            } else {
+                cprintln!(cio, pl, "{}", line);
+            }
+        }
+
+        Ok(0)
+    }
+}
+
+/// The part of the association tree that is to be shown.
+struct Tree<'a> {
+    records: &'a [SharesRecordSnapshot],
+
+    /// The index of each account association, by account name.
+    accounts: HashMap<&'a str, usize>,
+
+    /// The indices of the associations under each account, in the order
+    /// that the controller reported them.
+    children: HashMap<&'a str, Vec<usize>>,
+
+    /// The indices of the associations to show.
+    shown: HashSet<usize>,
+}
+
+impl<'a> Tree<'a> {
+    fn new(records: &'a [SharesRecordSnapshot]) -> Self {
+        let mut accounts = HashMap::new();
+        let mut children: HashMap<&str, Vec<usize>> = HashMap::new();
+
+        for (i, rec) in records.iter().enumerate() {
+            if !rec.is_user {
+                accounts.insert(rec.name.as_str(), i);
+            }
+
+            if let Some(ref p) = rec.parent {
+                children.entry(p.as_str()).or_default().push(i);
+            }
+        }
+
+        Tree {
+            records,
+            accounts,
+            children,
+            shown: HashSet::new(),
+        }
+    }
+
+    /// Mark an account to be shown, along with everything under it and the
+    /// path to it from the root of the tree.
+    fn show_account(&mut self, account: &str) -> Result<(), Error> {
+        let idx = *self.accounts.get(account).ok_or_else(|| {
+            format_err!(
+                "the controller reported no fairshare information for account {}",
+                account
+            )
+        })?;
+
+        let mut stack = vec![idx];
+
+        while let Some(i) = stack.pop() {
+            if self.shown.insert(i) && !self.records[i].is_user {
+                if let Some(kids) = self.children.get(self.records[i].name.as_str()) {
+                    stack.extend(kids);
+                }
+            }
+        }
+
+        // Stop at an ancestor that is already shown: its own ancestors are
+        // too. This also guards against loops in malformed data.
+        let mut parent = self.parent_of(idx);
+
+        while let Some(i) = parent {
+            if !self.shown.insert(i) {
+                break;
+            }
+
+            parent = self.parent_of(i);
+        }
+
+        Ok(())
+    }
+
+    fn parent_of(&self, idx: usize) -> Option<usize> {
+        self.records[idx]
+            .parent
+            .as_deref()
+            .and_then(|p| self.accounts.get(p))
+            .copied()
+    }
+
+    /// Get the labeled rows of the tree, in display order.
+    fn rows(&self) -> Vec<(String, &'a SharesRecordSnapshot)> {
+        let mut rows = Vec::new();
+        let mut seen = HashSet::new();
+
+        for i in (0..self.records.len()).filter(|i| self.shown.contains(i)) {
+            if self.parent_of(i).map(|p| self.shown.contains(&p)) != Some(true) {
+                self.add_rows(i, "", "", &mut seen, &mut rows);
+            }
+        }
+
+        rows
+    }
+
+    fn add_rows(
+        &self,
+        idx: usize,
+        label_prefix: &str,
+        child_prefix: &str,
+        seen: &mut HashSet<usize>,
+        rows: &mut Vec<(String, &'a SharesRecordSnapshot)>,
+    ) {
+        if !seen.insert(idx) {
+            return;
+        }
+
+        let rec = &self.records[idx];
+        let mut label = format!("{}{}", label_prefix, rec.name);
+
+        if let Some(ref p) = rec.partition {
+            label.push_str(&format!(" ({})", p));
+        }
+
+        rows.push((label, rec));
+
+        if rec.is_user {
+            return;
+        }
+
+        let kids: Vec<usize> = self
+            .children
+            .get(rec.name.as_str())
+            .map(|v| {
+                v.iter()
+                    .copied()
+                    .filter(|i| self.shown.contains(i))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let n = kids.len();
+
+        for (k, kid) in kids.into_iter().enumerate() {
+            let last = k + 1 == n;
+            self.add_rows(
+                kid,
+                &format!("{}{}", child_prefix, if last { "└── " } else { "├── " }),
+                &format!("{}{}", child_prefix, if last { "    " } else { "│   " }),
+                seen,
+                rows,
+            );
+        }
+    }
+}
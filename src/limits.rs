@@ -0,0 +1,274 @@
+// Copyright 2018 Peter Williams <peter@newton.cx> and collaborators
+// Licensed under the MIT License
+
+/*! Show the limits that apply to the current user.
+
+The limits set on the user's associations and on the QOS that they may use
+are looked up in the accounting database, and shown alongside how much of
+each limit the user's current jobs are using, so that it is easy to see
+which limit is holding jobs back.
+
+*/
+
+use crate::colorio::ColorIo;
+use anyhow::{format_err, Error};
+use slurm::TimeLimit;
+use std::collections::{BTreeMap, BTreeSet};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct LimitsCommand {}
+
+impl LimitsCommand {
+    pub fn cli(self, cio: &mut ColorIo) -> Result<i32, Error> {
+        let uid = users::get_current_uid();
+        let user = users::get_current_username()
+            .map(|u| u.to_string_lossy().into_owned())
+            .ok_or_else(|| format_err!("cannot determine the name of the current user"))?;
+
+        let jobs: Vec<JobSummary> = slurm::get_all_jobs()?
+            .jobs()
+            .filter(|j| j.user_id() == uid && !j.state().is_terminal())
+            .map(|j| JobSummary {
+                account: j.account().map(|a| a.into_owned()),
+                qos: j.qos().map(|q| q.into_owned()),
+                partitions: j.partition().split(',').map(|p| p.to_owned()).collect(),
+                running: j.state() == slurm::JobState::Running,
+                tres: j.tres_alloc().iter().collect(),
+            })
+            .collect();
+
+        let db = slurm::DatabaseConnectionOwned::new()?;
+        let tres_names: BTreeMap<u32, String> = db
+            .get_tres(&slurm::TresFiltersOwned::new()?)?
+            .iter()
+            .map(|rec| (rec.id(), rec.full_name()))
+            .collect();
+
+        let mut filter = slurm::AssociationFiltersOwned::new()?;
+        filter.user_list_mut().append(&user)?;
+        let mut qos_ids = BTreeSet::new();
+
+        for assoc in db.get_associations(&filter)?.iter() {
+            let account = assoc.account().map(|a| a.into_owned()).unwrap_or_default();
+            let partition = assoc.partition().map(|p| p.into_owned());
+
+            for id in assoc.qos_list().iter() {
+                if let Ok(id) = id.parse::<u32>() {
+                    qos_ids.insert(id);
+                }
+            }
+
+            cprint!(cio, pl, "association ");
+            cprint!(cio, hl, "{}/{}", user, account);
+
+            match partition {
+                Some(ref p) => {
+                    cprintln!(cio, pl, " (partition {})", p);
+                }
+                None => {
+                    cprintln!(cio, pl, "");
+                }
+            }
+
+            let usage = Usage::of(jobs.iter().filter(|j| {
+                j.account.as_deref() == Some(account.as_str())
+                    && partition
+                        .as_ref()
+                        .map(|p| j.partitions.contains(p))
+                        .unwrap_or(true)
+            }));
+
+            let mut report = Report::new(&tres_names);
+            report.count("MaxJobs", assoc.max_jobs(), Some(usage.running));
+            report.count(
+                "MaxSubmitJobs",
+                assoc.max_submit_jobs(),
+                Some(usage.submitted),
+            );
+            report.tres("GrpTRES", assoc.grp_tres(), Some(&usage.tres));
+            report.tres("MaxTRESPerJob", assoc.max_tres_per_job(), None);
+            report.tres("MaxTRESPerNode", assoc.max_tres_per_node(), None);
+            report.minutes("MaxWall", assoc.max_wall_per_job());
+            report.print(cio);
+        }
+
+        // Association records often leave their QOS list to be inherited
+        // from their parents, so also include any QOS that jobs are using.
+        let job_qos: BTreeSet<&str> = jobs.iter().filter_map(|j| j.qos.as_deref()).collect();
+
+        for qos in db.get_qos(&slurm::QosFiltersOwned::new()?)?.iter() {
+            let name = qos.name();
+
+            if !qos_ids.contains(&qos.id()) && !job_qos.contains(name.as_ref()) {
+                continue;
+            }
+
+            cprint!(cio, pl, "QOS ");
+            cprintln!(cio, hl, "{}", name);
+
+            let usage = Usage::of(jobs.iter().filter(|j| j.qos.as_deref() == Some(&*name)));
+
+            let mut report = Report::new(&tres_names);
+            report.count(
+                "MaxJobsPerUser",
+                qos.max_jobs_per_user(),
+                Some(usage.running),
+            );
+            report.count(
+                "MaxSubmitJobsPerUser",
+                qos.max_submit_jobs_per_user(),
+                Some(usage.submitted),
+            );
+            report.tres("MaxTRESPerUser", qos.max_tres_per_user(), Some(&usage.tres));
+            report.tres("MaxTRESPerJob", qos.max_tres_per_job(), None);
+            report.tres("MaxTRESPerNode", qos.max_tres_per_node(), None);
+            report.minutes("MaxWall", qos.max_wall_per_job());
+            // These apply to the jobs of all users together, so our own
+            // usage does not tell the whole story.
+            report.count("GrpJobs", qos.grp_jobs(), None);
+            report.count("GrpSubmitJobs", qos.grp_submit_jobs(), None);
+            report.tres("GrpTRES", qos.grp_tres(), None);
+            report.print(cio);
+        }
+
+        Ok(0)
+    }
+}
+
+/// What we need to know about one of the user's current jobs.
+#[derive(Debug)]
+struct JobSummary {
+    account: Option<String>,
+    qos: Option<String>,
+    partitions: Vec<String>,
+    running: bool,
+    tres: BTreeMap<u32, u64>,
+}
+
+/// The usage of a set of jobs that counts against limits.
+#[derive(Debug, Default)]
+struct Usage {
+    running: u32,
+    submitted: u32,
+    tres: BTreeMap<u32, u64>,
+}
+
+impl Usage {
+    fn of<'a, I: Iterator<Item = &'a JobSummary>>(jobs: I) -> Self {
+        let mut u = Usage::default();
+
+        for job in jobs {
+            u.submitted += 1;
+
+            if job.running {
+                u.running += 1;
+
+                for (id, amount) in &job.tres {
+                    *u.tres.entry(*id).or_insert(0) += amount;
+                }
+            }
+        }
+
+        u
+    }
+}
+
+/// A list of limits in the `Name=value` style of `sacctmgr`, each with the
+/// current usage if it is known.
+struct Report<'a> {
+    tres_names: &'a BTreeMap<u32, String>,
+    rows: Vec<(String, Option<(String, f64)>)>,
+}
+
+impl<'a> Report<'a> {
+    fn new(tres_names: &'a BTreeMap<u32, String>) -> Self {
+        Report {
+            tres_names,
+            rows: Vec::new(),
+        }
+    }
+
+    fn count(&mut self, name: &str, limit: Option<u32>, used: Option<u32>) {
+        if let Some(limit) = limit {
+            let usage = used.map(|u| (u.to_string(), u as f64 / limit.max(1) as f64));
+            self.rows.push((format!("{}={}", name, limit), usage));
+        }
+    }
+
+    fn minutes(&mut self, name: &str, limit: Option<u32>) {
+        if let Some(limit) = limit {
+            self.rows
+                .push((format!("{}={}", name, TimeLimit::Minutes(limit)), None));
+        }
+    }
+
+    fn tres<S: AsRef<str>>(
+        &mut self,
+        name: &str,
+        limit: Option<S>,
+        used: Option<&BTreeMap<u32, u64>>,
+    ) {
+        let limits = match limit.and_then(|t| slurm::TresAmounts::parse(t.as_ref()).ok()) {
+            Some(a) if !a.is_empty() => a,
+            _ => return,
+        };
+
+        let tres_name = |id: u32| {
+            self.tres_names
+                .get(&id)
+                .cloned()
+                .unwrap_or_else(|| id.to_string())
+        };
+
+        let text = limits
+            .iter()
+            .map(|(id, amount)| format!("{}={}", tres_name(id), amount))
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let usage = used.map(|used| {
+            let mut worst: f64 = 0.;
+            let items: Vec<String> = limits
+                .iter()
+                .map(|(id, limit)| {
+                    let u = used.get(&id).cloned().unwrap_or(0);
+                    worst = worst.max(u as f64 / limit.max(1) as f64);
+                    format!("{}={}", tres_name(id), u)
+                })
+                .collect();
+            (items.join(","), worst)
+        });
+
+        self.rows.push((format!("{}={}", name, text), usage));
+    }
+
+    fn print(&self, cio: &mut ColorIo) {
+        if self.rows.is_empty() {
+            cprintln!(cio, pl, "    (none set)");
+            return;
+        }
+
+        let width = self.rows.iter().map(|r| r.0.len()).max().unwrap_or(0);
+
+        for (limit, usage) in &self.rows {
+            match usage {
+                None => {
+                    cprintln!(cio, pl, "    {}", limit);
+                }
+
+                Some((used, frac)) => {
+                    cprint!(cio, pl, "    {:<width$}  using ", limit);
+
+                    if *frac >= 1. {
+                        cprintln!(cio, red, "{}", used);
+                    } else if *frac >= 0.8 {
+                        cprintln!(cio, yellow, "{}", used);
+                    } else {
+                        cprintln!(cio, pl, "{}", used);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -3,26 +3,30 @@
 
 //! The main CLI driver logic.
 
-extern crate chrono;
-extern crate failure;
-extern crate itertools;
-extern crate slurm;
-#[macro_use]
-extern crate structopt;
-extern crate termcolor;
-extern crate users;
-
-use failure::Error;
+use anyhow::Error;
 use std::process;
 use structopt::StructOpt;
 
 #[macro_use]
 mod colorio; // keep first to get macros
+mod attach;
+mod deps;
+mod eff;
+mod fairshare;
+mod limits;
 mod recent;
+mod retry_failed;
+mod script;
+mod shell;
 mod status;
+mod submit;
+mod top;
+mod usage;
 mod util;
+mod wait;
+mod why;
 
-use colorio::ColorIo;
+use crate::colorio::ColorIo;
 
 #[derive(Debug, StructOpt)]
 #[structopt(
@@ -30,20 +34,85 @@ use colorio::ColorIo;
     about = "Better commands for interacting with Slurm."
 )]
 enum SlurmPlusCli {
+    #[structopt(name = "attach")]
+    /// Follow the output of a batch job until it finishes
+    Attach(attach::AttachCommand),
+
+    #[structopt(name = "deps")]
+    /// Show the tree of jobs that a job depends on
+    Deps(deps::DepsCommand),
+
+    #[structopt(name = "eff")]
+    /// Report how efficiently a job used its resources
+    Eff(eff::EffCommand),
+
+    #[structopt(name = "fairshare")]
+    /// Show the fairshare tree of your accounts
+    Fairshare(fairshare::FairshareCommand),
+
+    #[structopt(name = "limits")]
+    /// Show your association and QOS limits and how much of them you use
+    Limits(limits::LimitsCommand),
+
     #[structopt(name = "recent")]
     /// Summarize recently-run jobs
     Recent(recent::RecentCommand),
 
+    #[structopt(name = "retry-failed")]
+    /// Resubmit the failed tasks of a job array
+    RetryFailed(retry_failed::RetryFailedCommand),
+
+    #[structopt(name = "script")]
+    /// Show the batch script of a job
+    Script(script::ScriptCommand),
+
+    #[structopt(name = "shell")]
+    /// Open a shell on a node of a running job
+    Shell(shell::ShellCommand),
+
     #[structopt(name = "status")]
     /// Get the status of a job
     Status(status::StatusCommand),
+
+    #[structopt(name = "submit")]
+    /// Submit a batch script
+    Submit(submit::SubmitCommand),
+
+    #[structopt(name = "top")]
+    /// Interactively monitor your jobs and the cluster
+    Top(top::TopCommand),
+
+    #[structopt(name = "usage")]
+    /// Report CPU-hour and GPU-hour usage
+    Usage(usage::UsageCommand),
+
+    #[structopt(name = "wait")]
+    /// Wait for jobs to finish
+    Wait(wait::WaitCommand),
+
+    #[structopt(name = "why")]
+    /// Explain why a job has not started
+    Why(why::WhyCommand),
 }
 
 impl SlurmPlusCli {
     fn cli(self, cio: &mut ColorIo) -> Result<i32, Error> {
         match self {
+            SlurmPlusCli::Attach(cmd) => cmd.cli(cio),
+            SlurmPlusCli::Deps(cmd) => cmd.cli(cio),
+            SlurmPlusCli::Eff(cmd) => cmd.cli(cio),
+            SlurmPlusCli::Fairshare(cmd) => cmd.cli(cio),
+            SlurmPlusCli::Limits(cmd) => cmd.cli(cio),
             SlurmPlusCli::Recent(cmd) => cmd.cli(cio),
+            SlurmPlusCli::RetryFailed(cmd) => cmd.cli(cio),
+            SlurmPlusCli::Script(cmd) => cmd.cli(cio),
+            SlurmPlusCli::Shell(cmd) => cmd.cli(cio),
             SlurmPlusCli::Status(cmd) => cmd.cli(cio),
+            SlurmPlusCli::Submit(cmd) => cmd.cli(cio),
+            SlurmPlusCli::Top(cmd) => cmd.cli(cio),
+            SlurmPlusCli::Usage(cmd) => cmd.cli(cio),
+            SlurmPlusCli::Wait(cmd) => cmd.cli(cio),
+            SlurmPlusCli::Why(cmd) => cmd.cli(cio),
         }
     }
 }
@@ -4,15 +4,15 @@
 /*! Make a list of recent jobs belonging to this user.
  */
 
+use crate::colorio::ColorIo;
+use crate::util;
+use anyhow::Error;
 use chrono::{DateTime, Duration, Utc};
-use colorio::ColorIo;
-use failure::Error;
 use itertools::Itertools;
-use slurm::{self, JobState, JobStepRecordSharedFields};
+use slurm::{JobState, JobStepRecordSharedFields};
 use std::cmp;
 use std::collections::HashMap;
-use users;
-use util;
+use structopt::StructOpt;
 
 #[derive(Debug, StructOpt)]
 pub struct RecentCommand {
@@ -30,12 +30,8 @@ impl RecentCommand {
         let now = Utc::now();
         let min_start = now - Duration::days(self.span_days as i64);
 
-        // Note that the userid we have to filter on must be a string
-        // representation of the numeric UID.
         let uid = users::get_current_uid();
-        let mut filter = slurm::JobFiltersOwned::default();
-        filter.userid_list_mut().append(format!("{}", uid));
-        filter.usage_start(min_start);
+        let filter = slurm::JobFiltersOwned::for_user_since(uid, min_start)?;
 
         let mut grouped = HashMap::new();
         let db = slurm::DatabaseConnectionOwned::new()?;
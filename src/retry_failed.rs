@@ -0,0 +1,303 @@
+// Copyright 2018 Peter Williams <peter@newton.cx> and collaborators
+// Licensed under the MIT License
+
+/*! Resubmit the tasks of a job array that failed.
+
+The accounting database is searched for the tasks of the array that failed,
+timed out, or ran out of memory, and exactly those indices are submitted as
+a new array. The new array runs the original batch script with the original
+job's name, account, partition, QOS, working directory, and resource
+requests. Settings that the database does not record, such as the number of
+CPUs per task, are taken from the script's `#SBATCH` directives. Arrays that
+requested generic resources, licenses, or burst buffers are refused, since
+those requests cannot be reproduced.
+
+*/
+
+use crate::colorio::ColorIo;
+use crate::submit::Directives;
+use anyhow::{format_err, Error};
+use slurm::{JobId, JobState, MemSize, TimeLimit};
+use std::collections::BTreeMap;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct RetryFailedCommand {
+    #[structopt(short = "n", long = "dry-run")]
+    /// Only list the tasks that would be resubmitted.
+    dry_run: bool,
+
+    #[structopt(long = "ignore-unsupported")]
+    /// Resubmit even if the script has #SBATCH options that we don't
+    /// support, ignoring them.
+    ignore_unsupported: bool,
+
+    #[structopt(help = "The ID of the job array whose failed tasks to resubmit.")]
+    array_job_id: JobId,
+}
+
+impl RetryFailedCommand {
+    pub fn cli(self, cio: &mut ColorIo) -> Result<i32, Error> {
+        let mut filter = slurm::JobFiltersOwned::for_job(self.array_job_id)?;
+
+        if slurm::CAPABILITIES.job_scripts {
+            filter.include_script(true)?;
+        }
+
+        let db = slurm::DatabaseConnectionOwned::new()?;
+        let jobs = db.get_jobs(&filter)?;
+        let mut template = None;
+        let mut task_states = BTreeMap::new();
+
+        for job in jobs.iter() {
+            if job.array_job_id() != Some(self.array_job_id) {
+                continue;
+            }
+
+            if template.is_none() {
+                template = Some(Template::from_record(&job));
+            }
+
+            // The record of tasks that have not yet started has no index.
+            // Later records of a task supersede earlier ones.
+            if let Some(task) = job.array_task_id() {
+                task_states.insert(task, job.state());
+            }
+        }
+
+        let template = template.ok_or_else(|| {
+            format_err!(
+                "job {} not found in the accounting database, or not an array",
+                self.array_job_id
+            )
+        })?;
+
+        if template.special_tres {
+            return Err(format_err!(
+                "job {} requested generic resources, licenses, or burst buffers, which \
+                 cannot be resubmitted automatically; resubmit it with `sbatch --array` \
+                 yourself",
+                self.array_job_id
+            ));
+        }
+
+        let failed: Vec<u32> = task_states
+            .iter()
+            .filter(|(_, state)| {
+                matches!(
+                    state,
+                    JobState::Failed | JobState::Timeout | JobState::OutOfMemory
+                )
+            })
+            .map(|(task, _)| *task)
+            .collect();
+
+        if failed.is_empty() {
+            cprintln!(
+                cio,
+                pl,
+                "no tasks of array job {} failed, timed out, or ran out of memory",
+                self.array_job_id
+            );
+            return Ok(0);
+        }
+
+        let indices = index_ranges(&failed);
+        cprint!(cio, pl, "{} failed task(s): ", failed.len());
+        cprintln!(cio, hl, "{}", indices);
+
+        if self.dry_run {
+            return Ok(0);
+        }
+
+        let script = fetch_script(self.array_job_id, template.script.clone())?;
+        let settings = Directives::parse(&script, self.ignore_unsupported, cio)?;
+
+        let qos = match template.qos_id {
+            Some(id) => Some(qos_name(&db, id)?),
+            None => None,
+        };
+
+        let mut spec = slurm::BatchJobSpec::new(script.clone());
+        spec.name(template.name.clone());
+
+        if let Some(ref d) = template.work_dir {
+            spec.work_dir(d.clone());
+        }
+
+        if let Some(ref p) = template.partition {
+            spec.partition(p.clone());
+        }
+
+        if let Some(t) = template.time_limit {
+            spec.time_limit(t);
+        }
+
+        if let Some(m) = template.req_mem {
+            spec.min_memory(m);
+        }
+
+        // The remaining settings are not recorded in the database, so we
+        // take them from the script's directives.
+
+        if let Some(n) = settings.ntasks {
+            spec.num_tasks(n);
+        }
+
+        if let Some(ref c) = settings.comment {
+            spec.comment(c.clone());
+        }
+
+        if let Some(ref p) = settings.stdin {
+            spec.stdin_path(p.clone());
+        }
+
+        if let Some(ref p) = settings.stdout {
+            spec.stdout_path(p.clone());
+        }
+
+        if let Some(ref p) = settings.stderr {
+            spec.stderr_path(p.clone());
+        }
+
+        let mut desc = spec.to_descriptor()?;
+        desc.set_min_cpus(template.req_cpus);
+        desc.set_array_indices(&indices)?;
+
+        if let Some(ref a) = template.account {
+            desc.set_account(a)?;
+        }
+
+        if let Some(ref q) = qos {
+            desc.set_qos(q)?;
+        }
+
+        if let Some(n) = template.nodes.or(settings.nodes) {
+            desc.set_num_nodes(n);
+        }
+
+        if let Some(c) = settings.cpus_per_task {
+            desc.set_cpus_per_task(c);
+        }
+
+        let msg = desc.submit_batch()?;
+        cprint!(cio, pl, "submitted job array ");
+        cprintln!(cio, hl, "{}", msg.job_id());
+        Ok(0)
+    }
+}
+
+/// The settings of the original array, as recorded in the database.
+#[derive(Debug)]
+struct Template {
+    name: String,
+    account: Option<String>,
+    partition: Option<String>,
+    qos_id: Option<u32>,
+    work_dir: Option<String>,
+    time_limit: Option<TimeLimit>,
+    req_mem: Option<MemSize>,
+    req_cpus: u32,
+    nodes: Option<u32>,
+    special_tres: bool,
+    script: Option<String>,
+}
+
+impl Template {
+    fn from_record(job: &slurm::JobRecord) -> Self {
+        let tres = job.tres_req();
+
+        Template {
+            name: job.job_name().into_owned(),
+            account: job.account().map(|s| s.into_owned()),
+            partition: job.partition().map(|s| s.into_owned()),
+            qos_id: Some(job.qos_id()).filter(|&id| id != 0),
+            work_dir: job.work_dir().map(|s| s.into_owned()),
+            time_limit: job.time_limit(),
+            req_mem: job.req_mem(),
+            req_cpus: job.req_cpus(),
+            nodes: tres.get(slurm::TRES_NODE).map(|n| n as u32),
+            // Slurm numbers the TRES types that it defines itself below
+            // 1000; generic resources, licenses, and burst buffers come
+            // after.
+            special_tres: tres.iter().any(|(id, _)| id > 1000),
+            script: job.script().map(|s| s.into_owned()),
+        }
+    }
+}
+
+/// Look up the name of a QOS by its ID.
+fn qos_name(db: &slurm::DatabaseConnectionOwned, id: u32) -> Result<String, Error> {
+    let mut filter = slurm::QosFiltersOwned::new()?;
+    filter.id_list_mut().append(id.to_string())?;
+
+    db.get_qos(&filter)?
+        .iter()
+        .next()
+        .map(|rec| rec.name().into_owned())
+        .ok_or_else(|| format_err!("QOS {} not found in the accounting database", id))
+}
+
+/// Get the batch script of the array.
+///
+/// The controller has the script as long as any task of the array is in the
+/// queue. After that, it is only available if the database stores scripts.
+fn fetch_script(jid: JobId, from_db: Option<String>) -> Result<String, Error> {
+    match slurm::get_batch_script(jid) {
+        Ok(s) => return Ok(s),
+        Err(slurm::Error::Slurm(slurm::SlurmError::InvalidJobId)) => {}
+        Err(e) => return Err(e.into()),
+    }
+
+    from_db.ok_or_else(|| {
+        format_err!(
+            "the batch script of job {} is no longer available; resubmit it with \
+             `sbatch --array` yourself",
+            jid
+        )
+    })
+}
+
+/// Express a sorted list of array indices in the compact `1,4,7-9` syntax
+/// of `sbatch --array`.
+fn index_ranges(indices: &[u32]) -> String {
+    let mut ranges: Vec<(u32, u32)> = Vec::new();
+
+    for &i in indices {
+        if let Some(last) = ranges.last_mut() {
+            if last.1 + 1 == i {
+                last.1 = i;
+                continue;
+            }
+        }
+
+        ranges.push((i, i));
+    }
+
+    ranges
+        .iter()
+        .map(|&(start, end)| {
+            if start == end {
+                start.to_string()
+            } else {
+                format!("{}-{}", start, end)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_ranges_compact() {
+        assert_eq!(index_ranges(&[]), "");
+        assert_eq!(index_ranges(&[5]), "5");
+        assert_eq!(index_ranges(&[0, 1]), "0-1");
+        assert_eq!(index_ranges(&[1, 4, 7, 8, 9]), "1,4,7-9");
+        assert_eq!(index_ranges(&[0, 1, 2, 10, 12, 13]), "0-2,10,12-13");
+        assert_eq!(index_ranges(&[3, 5, 7]), "3,5,7");
+    }
+}
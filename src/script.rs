@@ -0,0 +1,141 @@
+// Copyright 2018 Peter Williams <peter@newton.cx> and collaborators
+// Licensed under the MIT License
+
+/*! Show the batch script of a job.
+
+The script is fetched from the controller while the job is in its queue.
+After that, it can only be fetched from the accounting database, and only if
+the database has been configured to store scripts, which Slurm supports as
+of version 21.08. When printing to a terminal, the script is highlighted.
+
+*/
+
+use crate::colorio::ColorIo;
+use anyhow::{format_err, Error};
+use slurm::JobIdentifier;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct ScriptCommand {
+    #[structopt(help = "The ID of the job whose script to show.")]
+    jobid: JobIdentifier,
+}
+
+impl ScriptCommand {
+    pub fn cli(self, cio: &mut ColorIo) -> Result<i32, Error> {
+        let script = match slurm::get_batch_script(self.jobid) {
+            Ok(s) => s,
+            Err(slurm::Error::Slurm(slurm::SlurmError::InvalidJobId)) => from_db(self.jobid)?,
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut hl = Highlighter::default();
+
+        for line in script.lines() {
+            hl.line(cio, line);
+            cprint!(cio, pl, "\n");
+        }
+
+        Ok(0)
+    }
+}
+
+/// Get the script of a job that has left the controller's queue.
+fn from_db(jid: JobIdentifier) -> Result<String, Error> {
+    let mut filter = slurm::JobFiltersOwned::for_job(jid)?;
+    filter.include_script(true)?;
+    let db = slurm::DatabaseConnectionOwned::new()?;
+    let jobs = db.get_jobs(&filter)?;
+
+    // A requeued job has one record per attempt; the last one is the
+    // current one.
+    match jobs.iter().last() {
+        Some(job) => job.script().map(|s| s.into_owned()).ok_or_else(|| {
+            format_err!(
+                "the accounting database does not have the script of job {}; it only \
+                 stores scripts if AccountingStoreFlags includes job_script",
+                jid
+            )
+        }),
+        None => Err(format_err!("job {} not found", jid)),
+    }
+}
+
+/// A rudimentary highlighter for shell scripts.
+///
+/// It distinguishes the shebang line, `#SBATCH` directives, comments, and
+/// quoted strings, which is enough to make the settings of a job stand out.
+/// Quoted strings may span lines, so the highlighter keeps its state from
+/// one line to the next.
+#[derive(Debug, Default)]
+struct Highlighter {
+    line_num: usize,
+    quote: Option<char>,
+}
+
+impl Highlighter {
+    fn line(&mut self, cio: &mut ColorIo, line: &str) {
+        self.line_num += 1;
+
+        if self.quote.is_none() {
+            if self.line_num == 1 && line.starts_with("#!") {
+                cprint!(cio, hl, "{}", line);
+                return;
+            }
+
+            if let Some(rest) = line.strip_prefix("#SBATCH") {
+                cprint!(cio, hl, "#SBATCH");
+                cprint!(cio, yellow, "{}", rest);
+                return;
+            }
+        }
+
+        // Print the line in runs of the same style.
+        let mut start = 0;
+        let mut escaped = false;
+        let mut prev_is_space = true;
+
+        for (i, c) in line.char_indices() {
+            if escaped {
+                escaped = false;
+                prev_is_space = false;
+                continue;
+            }
+
+            match self.quote {
+                Some(q) => {
+                    if c == '\\' && q == '"' {
+                        escaped = true;
+                    } else if c == q {
+                        let end = i + c.len_utf8();
+                        cprint!(cio, yellow, "{}", &line[start..end]);
+                        start = end;
+                        self.quote = None;
+                    }
+                }
+
+                None => {
+                    if c == '\\' {
+                        escaped = true;
+                    } else if c == '\'' || c == '"' {
+                        cprint!(cio, pl, "{}", &line[start..i]);
+                        start = i;
+                        self.quote = Some(c);
+                    } else if c == '#' && prev_is_space {
+                        cprint!(cio, pl, "{}", &line[start..i]);
+                        cprint!(cio, green, "{}", &line[i..]);
+                        return;
+                    }
+                }
+            }
+
+            prev_is_space = c.is_whitespace();
+        }
+
+        if self.quote.is_some() {
+            cprint!(cio, yellow, "{}", &line[start..]);
+        } else {
+            cprint!(cio, pl, "{}", &line[start..]);
+        }
+    }
+}
@@ -0,0 +1,109 @@
+// Copyright 2018 Peter Williams <peter@newton.cx> and collaborators
+// Licensed under the MIT License
+
+/*! Open a shell on a node of a running job.
+
+This command finds a node allocated to a running job, by default the one
+running its batch script, and replaces itself with an interactive shell
+there, started with `srun --jobid` so that it runs inside the job's
+allocation. If the cluster allows users to log in to nodes running their
+jobs, `ssh` can be used instead.
+
+*/
+
+use crate::colorio::ColorIo;
+use anyhow::{format_err, Error};
+use slurm::JobState;
+use std::os::unix::process::CommandExt;
+use std::process::Command;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct ShellCommand {
+    #[structopt(short = "w", long = "node")]
+    /// The node to connect to; by default, the one running the batch script.
+    node: Option<String>,
+
+    #[structopt(long = "ssh")]
+    /// Connect with ssh rather than srun.
+    ssh: bool,
+
+    #[structopt(help = "The ID of the running job to connect to.")]
+    jobid: slurm::JobIdentifier,
+}
+
+impl ShellCommand {
+    pub fn cli(self, cio: &mut ColorIo) -> Result<i32, Error> {
+        let info = slurm::get_job_info(self.jobid)?;
+        let state = info.state();
+
+        if state != JobState::Running {
+            return Err(format_err!(
+                "job {} is not running; its state is {}",
+                self.jobid,
+                state.slurm_name()
+            ));
+        }
+
+        let nodes = match info.nodes() {
+            Some(n) => slurm::expand_hostlist(n)?,
+            None => Vec::new(),
+        };
+
+        let node = match self.node {
+            Some(n) => {
+                if !nodes.contains(&n) {
+                    return Err(format_err!(
+                        "node {} is not allocated to job {}; its nodes are {}",
+                        n,
+                        self.jobid,
+                        nodes.join(",")
+                    ));
+                }
+
+                n
+            }
+
+            None => info
+                .batch_host()
+                .map(|h| h.into_owned())
+                .or_else(|| nodes.first().cloned())
+                .ok_or_else(|| format_err!("job {} has no nodes allocated", self.jobid))?,
+        };
+
+        let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_owned());
+
+        let mut cmd = if self.ssh {
+            let mut c = Command::new("ssh");
+            c.arg("-t").arg(&node);
+            c
+        } else {
+            let mut c = Command::new("srun");
+            c.arg(format!("--jobid={}", info.job_id()))
+                .arg(format!("--nodelist={}", node))
+                .arg("--nodes=1")
+                .arg("--ntasks=1")
+                .arg("--pty")
+                .arg(&shell)
+                .arg("-l");
+            // Since Slurm 20.11, a step must be allowed to overlap with the
+            // job's other steps, or it will wait for their resources. Older
+            // versions ignore the variable, while they would reject the
+            // equivalent `--overlap` option.
+            c.env("SLURM_OVERLAP", "1");
+            c
+        };
+
+        cprint!(cio, pl, "connecting to ");
+        cprint!(cio, hl, "{}", node);
+        cprintln!(cio, pl, " for job {}", self.jobid);
+
+        // This only returns if the command could not be started.
+        let err = cmd.exec();
+        Err(format_err!(
+            "could not run {:?}: {}",
+            cmd.get_program(),
+            err
+        ))
+    }
+}
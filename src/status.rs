@@ -8,25 +8,22 @@ running and completed jobs.
 
 */
 
-use chrono::{Duration, Utc};
-use colorio::ColorIo;
-use failure::Error;
-use slurm::{self, JobStepRecordSharedFields};
-use util;
+use crate::colorio::ColorIo;
+use crate::util;
+use anyhow::Error;
+use chrono::Utc;
+use slurm::JobStepRecordSharedFields;
+use structopt::StructOpt;
 
 #[derive(Debug, StructOpt)]
 pub struct StatusCommand {
     #[structopt(help = "The ID of the job to query.")]
-    jobid: slurm::JobId,
+    jobid: slurm::JobIdentifier,
 }
 
 impl StatusCommand {
     pub fn cli(self, cio: &mut ColorIo) -> Result<i32, Error> {
-        let mut filter = slurm::JobFiltersOwned::default();
-        filter
-            .step_list_mut()
-            .append(slurm::JobStepFilterOwned::new(self.jobid));
-
+        let filter = slurm::JobFiltersOwned::for_job(self.jobid)?;
         let db = slurm::DatabaseConnectionOwned::new()?;
         let jobs = db.get_jobs(&filter)?;
         let now = Utc::now();
@@ -73,8 +70,11 @@ impl StatusCommand {
                 continue;
             }
 
-            if let Some(t_st) = job.start_time() {
-                let t_limit = t_st + Duration::minutes(job.time_limit() as i64);
+            if let (Some(t_st), Some(limit)) = (
+                job.start_time(),
+                job.time_limit().and_then(|l| l.as_duration()),
+            ) {
+                let t_limit = t_st + limit;
                 let remaining = t_limit.signed_duration_since(now).num_minutes();
                 if remaining > 0 {
                     cprintln!(
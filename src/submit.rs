@@ -0,0 +1,394 @@
+// Copyright 2018 Peter Williams <peter@newton.cx> and collaborators
+// Licensed under the MIT License
+
+/*! Submit a batch job.
+
+This command is most similar to Slurm's `sbatch` command. It reads the most
+common settings from the `#SBATCH` directives at the top of the script, lets
+a few of them be overridden on the command line, and reports where the job's
+output will go.
+
+*/
+
+use crate::colorio::ColorIo;
+use crate::util;
+use anyhow::{format_err, Error};
+use slurm::{MemSize, TimeLimit};
+use std::fs;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct SubmitCommand {
+    #[structopt(short = "t", long = "time")]
+    /// Override the job's time limit.
+    time: Option<TimeLimit>,
+
+    #[structopt(long = "mem")]
+    /// Override the job's memory requirement, e.g. `16G` or `4000Mc`.
+    mem: Option<MemSize>,
+
+    #[structopt(short = "p", long = "partition")]
+    /// Override the partition to submit the job to.
+    partition: Option<String>,
+
+    #[structopt(long = "ignore-unsupported")]
+    /// Submit even if the script has #SBATCH options that we don't support,
+    /// ignoring them.
+    ignore_unsupported: bool,
+
+    #[structopt(help = "The batch script to submit.", parse(from_os_str))]
+    script: PathBuf,
+
+    #[structopt(help = "Arguments to pass to the script; put them after `--`.")]
+    args: Vec<String>,
+}
+
+impl SubmitCommand {
+    pub fn cli(self, cio: &mut ColorIo) -> Result<i32, Error> {
+        let script = fs::read_to_string(&self.script)
+            .map_err(|e| format_err!("cannot read {}: {}", self.script.display(), e))?;
+        let mut settings = Directives::parse(&script, self.ignore_unsupported, cio)?;
+
+        if self.time.is_some() {
+            settings.time = self.time;
+        }
+
+        if self.mem.is_some() {
+            settings.mem = self.mem;
+        }
+
+        if self.partition.is_some() {
+            settings.partition = self.partition;
+        }
+
+        let name = match settings.name {
+            Some(ref n) => n.clone(),
+            None => self
+                .script
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "sbatch".to_owned()),
+        };
+
+        let mut work_dir = std::env::current_dir()?;
+
+        if let Some(ref d) = settings.work_dir {
+            work_dir.push(d);
+        }
+
+        let mut spec = slurm::BatchJobSpec::new(script.clone());
+        spec.name(name.clone());
+        spec.work_dir(
+            work_dir
+                .to_str()
+                .ok_or_else(|| format_err!("could not express working directory as UTF8"))?,
+        );
+
+        if let Some(ref p) = settings.partition {
+            spec.partition(p.clone());
+        }
+
+        if let Some(t) = settings.time {
+            spec.time_limit(t);
+        }
+
+        if let Some(m) = settings.mem {
+            spec.min_memory(m);
+        }
+
+        if let Some(n) = settings.ntasks {
+            spec.num_tasks(n);
+        }
+
+        if let Some(ref c) = settings.comment {
+            spec.comment(c.clone());
+        }
+
+        if let Some(ref p) = settings.stdin {
+            spec.stdin_path(p.clone());
+        }
+
+        if let Some(ref p) = settings.stdout {
+            spec.stdout_path(p.clone());
+        }
+
+        if let Some(ref p) = settings.stderr {
+            spec.stderr_path(p.clone());
+        }
+
+        let mut desc = spec.to_descriptor()?;
+        let argv0 = self.script.to_string_lossy().into_owned();
+        desc.set_argv(std::iter::once(argv0).chain(self.args.iter().cloned()))?;
+
+        if let Some(ref a) = settings.account {
+            desc.set_account(a)?;
+        }
+
+        if let Some(ref q) = settings.qos {
+            desc.set_qos(q)?;
+        }
+
+        if let Some(n) = settings.nodes {
+            desc.set_num_nodes(n);
+        }
+
+        if let Some(c) = settings.cpus_per_task {
+            desc.set_cpus_per_task(c);
+        }
+
+        if let Some(ref a) = settings.array {
+            desc.set_array_indices(a)?;
+        }
+
+        let msg = desc.submit_batch()?;
+        let jid = msg.job_id();
+
+        cprint!(cio, pl, "submitted job ");
+        cprintln!(cio, hl, "{}", jid);
+
+        let default_stdout = if settings.array.is_some() {
+            "slurm-%A_%a.out"
+        } else {
+            "slurm-%j.out"
+        };
+        let stdout = settings
+            .stdout
+            .clone()
+            .unwrap_or_else(|| default_stdout.to_owned());
+        let stderr = settings.stderr.clone().unwrap_or_else(|| stdout.clone());
+        let user = users::get_current_username().map(|u| u.to_string_lossy().into_owned());
+        let vars = util::LogPathVars {
+            job_id: jid,
+            array: None,
+            name: &name,
+            user: user.as_deref(),
+            node: None,
+        };
+        let stdout = util::log_path(&work_dir, &stdout, &vars);
+        let stderr = util::log_path(&work_dir, &stderr, &vars);
+
+        if stdout == stderr {
+            cprintln!(cio, pl, "  output: {}", stdout.display());
+        } else {
+            cprintln!(cio, pl, "  stdout: {}", stdout.display());
+            cprintln!(cio, pl, "  stderr: {}", stderr.display());
+        }
+
+        Ok(0)
+    }
+}
+
+/// The settings that can be given in `#SBATCH` directives.
+#[derive(Debug, Default)]
+pub(crate) struct Directives {
+    pub name: Option<String>,
+    pub account: Option<String>,
+    pub partition: Option<String>,
+    pub qos: Option<String>,
+    pub time: Option<TimeLimit>,
+    pub mem: Option<MemSize>,
+    pub nodes: Option<u32>,
+    pub ntasks: Option<u32>,
+    pub cpus_per_task: Option<u16>,
+    pub array: Option<String>,
+    pub comment: Option<String>,
+    pub work_dir: Option<String>,
+    pub stdin: Option<String>,
+    pub stdout: Option<String>,
+    pub stderr: Option<String>,
+}
+
+impl Directives {
+    /// Gather the `#SBATCH` directives of a script.
+    ///
+    /// Like `sbatch`, we stop looking at the first line that is not a
+    /// comment or blank. Options that we do not understand are an error,
+    /// since the job would not get what it asked for, unless
+    /// `ignore_unsupported` is true, in which case they are reported and
+    /// skipped along with their values.
+    pub fn parse(script: &str, ignore_unsupported: bool, cio: &mut ColorIo) -> Result<Self, Error> {
+        let mut d = Directives::default();
+
+        for line in script.lines().skip(1) {
+            let line = line.trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            if !line.starts_with('#') {
+                break;
+            }
+
+            let rest = match line.strip_prefix("#SBATCH") {
+                Some(r) if r.starts_with(char::is_whitespace) => r,
+                _ => continue,
+            };
+
+            // Everything after a `#` in the directive is a comment.
+            let mut words = rest
+                .split_whitespace()
+                .take_while(|w| !w.starts_with('#'))
+                .peekable();
+
+            while let Some(word) = words.next() {
+                let (key, inline_value) = if let Some(long) = word.strip_prefix("--") {
+                    match long.find('=') {
+                        Some(i) => (long[..i].to_owned(), Some(long[i + 1..].to_owned())),
+                        None => (long.to_owned(), None),
+                    }
+                } else if let Some(short) = word.strip_prefix('-') {
+                    let mut chars = short.chars();
+                    let flag = match chars.next() {
+                        Some(c) => c,
+                        None => continue,
+                    };
+                    let value = chars.as_str();
+                    let value = value.strip_prefix('=').unwrap_or(value);
+
+                    let long = match flag {
+                        'A' => "account",
+                        'a' => "array",
+                        'c' => "cpus-per-task",
+                        'D' => "chdir",
+                        'e' => "error",
+                        'i' => "input",
+                        'J' => "job-name",
+                        'N' => "nodes",
+                        'n' => "ntasks",
+                        'o' => "output",
+                        'p' => "partition",
+                        'q' => "qos",
+                        't' => "time",
+                        _ => {
+                            if value.is_empty() {
+                                words.next_if(|w| !w.starts_with('-'));
+                            }
+
+                            unsupported(cio, word, ignore_unsupported)?;
+                            continue;
+                        }
+                    };
+
+                    (
+                        long.to_owned(),
+                        if value.is_empty() {
+                            None
+                        } else {
+                            Some(value.to_owned())
+                        },
+                    )
+                } else {
+                    unsupported(cio, word, ignore_unsupported)?;
+                    continue;
+                };
+
+                if !KNOWN_OPTIONS.contains(&key.as_ref()) {
+                    // Skip a separate value, as in `--gres gpu:1`, so that it
+                    // isn't taken for another option.
+                    if inline_value.is_none() {
+                        words.next_if(|w| !w.starts_with('-'));
+                    }
+
+                    unsupported(cio, word, ignore_unsupported)?;
+                    continue;
+                }
+
+                let value = match inline_value {
+                    Some(v) => v,
+                    None => words
+                        .next()
+                        .map(|w| w.to_owned())
+                        .ok_or_else(|| format_err!("#SBATCH option {} needs a value", word))?,
+                };
+
+                d.apply(&key, value)?;
+            }
+        }
+
+        Ok(d)
+    }
+
+    fn apply(&mut self, key: &str, value: String) -> Result<(), Error> {
+        match key {
+            "account" => self.account = Some(value),
+            "array" => self.array = Some(value),
+            "chdir" | "workdir" => self.work_dir = Some(value),
+            "comment" => self.comment = Some(value),
+            "cpus-per-task" => {
+                self.cpus_per_task = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format_err!("bad CPUs-per-task count {:?}", value))?,
+                )
+            }
+            "error" => self.stderr = Some(value),
+            "input" => self.stdin = Some(value),
+            "job-name" => self.name = Some(value),
+            "mem" => self.mem = Some(MemSize::PerNode(value.parse::<MemSize>()?.mib())),
+            "mem-per-cpu" => self.mem = Some(MemSize::PerCpu(value.parse::<MemSize>()?.mib())),
+            "nodes" => {
+                self.nodes = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format_err!("bad node count {:?}", value))?,
+                )
+            }
+            "ntasks" => {
+                self.ntasks = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format_err!("bad task count {:?}", value))?,
+                )
+            }
+            "output" => self.stdout = Some(value),
+            "partition" => self.partition = Some(value),
+            "qos" => self.qos = Some(value),
+            "time" => self.time = Some(value.parse()?),
+            _ => unreachable!(),
+        }
+
+        Ok(())
+    }
+}
+
+/// The long names of the `#SBATCH` options that we understand.
+const KNOWN_OPTIONS: &[&str] = &[
+    "account",
+    "array",
+    "chdir",
+    "comment",
+    "cpus-per-task",
+    "error",
+    "input",
+    "job-name",
+    "mem",
+    "mem-per-cpu",
+    "nodes",
+    "ntasks",
+    "output",
+    "partition",
+    "qos",
+    "time",
+    "workdir",
+];
+
+fn unsupported(cio: &mut ColorIo, option: &str, ignore: bool) -> Result<(), Error> {
+    if !ignore {
+        return Err(format_err!(
+            "unsupported #SBATCH option {}; use sbatch, or pass --ignore-unsupported to \
+             submit without it",
+            option
+        ));
+    }
+
+    ecprint!(cio, yellow, "warning:");
+    ecprintln!(
+        cio,
+        pl,
+        " ignoring unsupported #SBATCH option {}; use sbatch if you need it",
+        option
+    );
+    Ok(())
+}
@@ -0,0 +1,436 @@
+// Copyright 2018 Peter Williams <peter@newton.cx> and collaborators
+// Licensed under the MIT License
+
+/*! A full-screen, continuously updated view of this user's jobs.
+
+This command is in the spirit of the Unix `top` command. It lists the jobs
+belonging to the current user along with a summary of the state of the
+cluster, and lets the user cancel, hold, release, and inspect jobs with
+single keystrokes. The information is refreshed periodically, using the
+caching client so that idle dashboards put little load on the controller.
+
+The CPU and memory columns show what each job was allocated or requested,
+not what it is using. Slurm reports the live usage of job steps only in a
+structure that is opaque to users of its C API, so showing it would mean
+running `sstat` for every job on every refresh; use `sstat` directly to
+check on a particular job.
+
+*/
+
+use crate::colorio::ColorIo;
+use crate::util;
+use anyhow::Error;
+use chrono::Utc;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor};
+use crossterm::{cursor, execute, queue, terminal};
+use slurm::cache::CachedClient;
+use slurm::{JobId, JobState, NodeState};
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct TopCommand {
+    #[structopt(short = "i", long = "interval", default_value = "5")]
+    /// How often to refresh the display, in seconds.
+    interval_secs: u64,
+}
+
+impl TopCommand {
+    pub fn cli(self, _cio: &mut ColorIo) -> Result<i32, Error> {
+        let interval = Duration::from_secs(self.interval_secs);
+        let mut app = App {
+            client: CachedClient::new(interval),
+            uid: users::get_current_uid(),
+            summary: ClusterSummary::default(),
+            jobs: Vec::new(),
+            selected: 0,
+            detail: None,
+            confirm_cancel: false,
+            message: None,
+        };
+
+        let _guard = TerminalGuard::new()?;
+        let mut stdout = io::stdout();
+        let mut last_refresh: Option<Instant> = None;
+
+        loop {
+            if last_refresh
+                .map(|t| t.elapsed() >= interval)
+                .unwrap_or(true)
+            {
+                if let Err(e) = app.refresh() {
+                    app.message = Some(format!("refresh failed: {}", e));
+                }
+
+                last_refresh = Some(Instant::now());
+            }
+
+            app.draw(&mut stdout)?;
+
+            if !event::poll(Duration::from_millis(250))? {
+                continue;
+            }
+
+            let key = match event::read()? {
+                Event::Key(k) if k.kind != KeyEventKind::Release => k,
+                _ => continue,
+            };
+
+            if app.confirm_cancel {
+                app.confirm_cancel = false;
+
+                if let KeyCode::Char('y') = key.code {
+                    app.act("cancelled", slurm::cancel_job);
+                    last_refresh = None;
+                } else {
+                    app.message = Some("not cancelled".to_owned());
+                }
+
+                continue;
+            }
+
+            if app.detail.is_some() {
+                app.detail = None;
+                continue;
+            }
+
+            app.message = None;
+
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => break,
+                KeyCode::Up | KeyCode::Char('k') => app.selected = app.selected.saturating_sub(1),
+                KeyCode::Down | KeyCode::Char('j') => {
+                    if app.selected + 1 < app.jobs.len() {
+                        app.selected += 1;
+                    }
+                }
+                KeyCode::Char('c') => {
+                    if let Some(jid) = app.selected_job() {
+                        app.confirm_cancel = true;
+                        app.message = Some(format!("cancel job {}? (y/n)", jid));
+                    }
+                }
+                KeyCode::Char('h') => {
+                    app.act("held", slurm::hold_job);
+                    last_refresh = None;
+                }
+                KeyCode::Char('r') => {
+                    app.act("released", slurm::release_job);
+                    last_refresh = None;
+                }
+                KeyCode::Char('i') | KeyCode::Enter => app.inspect(),
+                KeyCode::Char('R') => {
+                    app.client.invalidate();
+                    last_refresh = None;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(0)
+    }
+}
+
+/// Puts the terminal into full-screen mode, and restores it when dropped,
+/// even if we bail out with an error.
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn new() -> Result<Self, Error> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(TerminalGuard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _r = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _r = terminal::disable_raw_mode();
+    }
+}
+
+/// Headline numbers about the cluster as a whole.
+#[derive(Debug, Default)]
+struct ClusterSummary {
+    nodes_idle: usize,
+    nodes_mixed: usize,
+    nodes_allocated: usize,
+    nodes_down: usize,
+    nodes_total: usize,
+    cpus_total: u64,
+    jobs_running: usize,
+    jobs_pending: usize,
+}
+
+/// What we show about one of the user's jobs.
+#[derive(Debug)]
+struct JobRow {
+    job_id: JobId,
+    name: String,
+    state: JobState,
+    held: bool,
+    partition: String,
+    cpus: u32,
+    memory: String,
+    time: String,
+    where_or_why: String,
+}
+
+struct App {
+    client: CachedClient,
+    uid: u32,
+    summary: ClusterSummary,
+    jobs: Vec<JobRow>,
+    selected: usize,
+    detail: Option<String>,
+    confirm_cancel: bool,
+    message: Option<String>,
+}
+
+impl App {
+    fn refresh(&mut self) -> Result<(), Error> {
+        let now = Utc::now();
+        let mut summary = ClusterSummary::default();
+
+        for node in self.client.nodes()?.nodes() {
+            summary.nodes_total += 1;
+            summary.cpus_total += node.cpus() as u64;
+
+            match node.state() {
+                NodeState::Idle => summary.nodes_idle += 1,
+                NodeState::Mixed => summary.nodes_mixed += 1,
+                NodeState::Allocated => summary.nodes_allocated += 1,
+                NodeState::Down | NodeState::Error => summary.nodes_down += 1,
+                _ => {}
+            }
+        }
+
+        let mut jobs = Vec::new();
+
+        for job in self.client.jobs()?.jobs() {
+            let state = job.state();
+
+            match state {
+                JobState::Running => summary.jobs_running += 1,
+                JobState::Pending => summary.jobs_pending += 1,
+                _ => {}
+            }
+
+            if job.user_id() != self.uid {
+                continue;
+            }
+
+            let limit = job
+                .time_limit()
+                .map(|t| t.to_string())
+                .unwrap_or_else(|| "-".to_owned());
+
+            let time = match (state, job.start_time()) {
+                (JobState::Running, Some(t)) => {
+                    format!("{}/{}", util::dur_to_hms(&(now - t)), limit)
+                }
+                _ => limit,
+            };
+
+            let where_or_why = if state == JobState::Pending {
+                format!("({})", job.state_reason())
+            } else {
+                job.nodes().map(|n| n.into_owned()).unwrap_or_default()
+            };
+
+            jobs.push(JobRow {
+                job_id: job.job_id(),
+                name: job.name().into_owned(),
+                state,
+                // Held jobs have a priority of zero; it is the only sign of
+                // a hold that does not depend on who placed it.
+                held: state == JobState::Pending && job.priority() == 0,
+                partition: job.partition().into_owned(),
+                cpus: job.num_cpus(),
+                memory: job
+                    .min_memory()
+                    .map(|m| m.to_string())
+                    .unwrap_or_else(|| "-".to_owned()),
+                time,
+                where_or_why,
+            });
+        }
+
+        jobs.sort_by_key(|j| j.job_id);
+        self.summary = summary;
+        self.jobs = jobs;
+
+        if self.selected >= self.jobs.len() {
+            self.selected = self.jobs.len().saturating_sub(1);
+        }
+
+        Ok(())
+    }
+
+    fn selected_job(&self) -> Option<JobId> {
+        self.jobs.get(self.selected).map(|j| j.job_id)
+    }
+
+    /// Perform an action on the selected job, reporting the outcome in the
+    /// status line.
+    fn act<F: FnOnce(JobId) -> Result<(), slurm::Error>>(&mut self, verb: &str, action: F) {
+        let jid = match self.selected_job() {
+            Some(j) => j,
+            None => return,
+        };
+
+        self.message = Some(match action(jid) {
+            Ok(()) => format!("{} job {}", verb, jid),
+            Err(e) => format!("job {}: {}", jid, e),
+        });
+        self.client.invalidate();
+    }
+
+    fn inspect(&mut self) {
+        let jid = match self.selected_job() {
+            Some(j) => j,
+            None => return,
+        };
+
+        let text = slurm::get_job_info(jid)
+            .map_err(Error::from)
+            .and_then(|info| info.sprint(false).map_err(Error::from));
+
+        match text {
+            Ok(t) => self.detail = Some(t),
+            Err(e) => self.message = Some(format!("job {}: {}", jid, e)),
+        }
+    }
+
+    fn draw<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+        let (width, height) = terminal::size()?;
+        let width = width as usize;
+        let height = height as usize;
+        let mut row = 0;
+
+        queue!(w, terminal::Clear(terminal::ClearType::All))?;
+
+        let line = |w: &mut W, row: &mut usize, text: &str| -> Result<(), Error> {
+            if *row < height {
+                let text: String = text.chars().take(width).collect();
+                queue!(w, cursor::MoveTo(0, *row as u16), Print(text))?;
+            }
+
+            *row += 1;
+            Ok(())
+        };
+
+        let s = &self.summary;
+        queue!(w, SetAttribute(Attribute::Bold))?;
+        line(
+            w,
+            &mut row,
+            &format!(
+                "nodes: {} total, {} idle, {} mixed, {} allocated, {} down; {} CPUs",
+                s.nodes_total,
+                s.nodes_idle,
+                s.nodes_mixed,
+                s.nodes_allocated,
+                s.nodes_down,
+                s.cpus_total
+            ),
+        )?;
+        queue!(w, SetAttribute(Attribute::Reset))?;
+        line(
+            w,
+            &mut row,
+            &format!(
+                "jobs: {} running, {} pending; {} mine",
+                s.jobs_running,
+                s.jobs_pending,
+                self.jobs.len()
+            ),
+        )?;
+        row += 1;
+
+        if let Some(ref detail) = self.detail {
+            for text in detail.lines() {
+                line(w, &mut row, text)?;
+            }
+
+            row = height.saturating_sub(1);
+            line(w, &mut row, "press any key to return")?;
+            w.flush()?;
+            return Ok(());
+        }
+
+        queue!(w, SetAttribute(Attribute::Reverse))?;
+        line(
+            w,
+            &mut row,
+            &format!(
+                "{:>10} {:<20} {:<4} {:<10} {:>5} {:>8} {:>22} {}",
+                "JOBID", "NAME", "ST", "PARTITION", "CPUS", "REQMEM", "TIME", "NODES/REASON"
+            ),
+        )?;
+        queue!(w, SetAttribute(Attribute::Reset))?;
+
+        for (i, job) in self.jobs.iter().enumerate() {
+            if i == self.selected {
+                queue!(w, SetAttribute(Attribute::Reverse))?;
+            }
+
+            queue!(w, SetForegroundColor(state_color(job.state)))?;
+
+            let state = if job.held {
+                "HELD"
+            } else {
+                job.state.shortcode()
+            };
+
+            line(
+                w,
+                &mut row,
+                &format!(
+                    "{:>10} {:<20.20} {:<4} {:<10.10} {:>5} {:>8} {:>22} {}",
+                    job.job_id,
+                    job.name,
+                    state,
+                    job.partition,
+                    job.cpus,
+                    job.memory,
+                    job.time,
+                    job.where_or_why
+                ),
+            )?;
+            queue!(w, ResetColor, SetAttribute(Attribute::Reset))?;
+        }
+
+        row = height.saturating_sub(1);
+        let status = match self.message {
+            Some(ref m) => m.as_str(),
+            None => "q: quit  j/k: move  c: cancel  h: hold  r: release  i: inspect  R: refresh",
+        };
+        line(w, &mut row, status)?;
+
+        w.flush()?;
+        Ok(())
+    }
+}
+
+/// The color for a job state, following `util::colorize_state`.
+fn state_color(state: JobState) -> Color {
+    match state {
+        JobState::Complete => Color::Green,
+
+        JobState::Cancelled
+        | JobState::Failed
+        | JobState::NodeFail
+        | JobState::BootFail
+        | JobState::Deadline
+        | JobState::OutOfMemory
+        | JobState::Revoked => Color::Red,
+
+        JobState::Suspended | JobState::Timeout | JobState::Preempted => Color::Yellow,
+
+        _ => Color::Reset,
+    }
+}
@@ -0,0 +1,243 @@
+// Copyright 2018 Peter Williams <peter@newton.cx> and collaborators
+// Licensed under the MIT License
+
+/*! Report CPU and GPU usage from the accounting database.
+
+This command is a friendlier take on `sreport`. It adds up the CPU-hours and
+GPU-hours allocated to jobs over a recent period, either for the current
+user alone or broken down by user or account. Only the part of each job's
+run that falls within the period is counted.
+
+*/
+
+use crate::colorio::ColorIo;
+use crate::util;
+use anyhow::{format_err, Error};
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone, Utc};
+use itertools::Itertools;
+use slurm::JobStepRecordSharedFields;
+use std::collections::HashMap;
+use std::str::FromStr;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct UsageCommand {
+    #[structopt(short = "s", long = "since", default_value = "30d")]
+    /// The start of the period to report: a span such as `30d`, `12h`, or
+    /// `2w`, or a date such as `2024-01-31`.
+    since: Since,
+
+    #[structopt(long = "by")]
+    /// Break down the usage of all visible jobs by `user` or `account`.
+    by: Option<GroupBy>,
+}
+
+impl UsageCommand {
+    pub fn cli(self, cio: &mut ColorIo) -> Result<i32, Error> {
+        let now = Utc::now();
+        let since = self.since.resolve(now);
+
+        let filter = match self.by {
+            None => slurm::JobFiltersOwned::for_user_since(users::get_current_uid(), since)?,
+            Some(_) => {
+                let mut f = slurm::JobFiltersOwned::new()?;
+                f.usage_start(since);
+                f
+            }
+        };
+
+        let db = slurm::DatabaseConnectionOwned::new()?;
+        let tres = db.get_tres(&slurm::TresFiltersOwned::new()?)?;
+        let jobs = db.get_jobs(&filter)?;
+        let mut totals: HashMap<String, Usage> = HashMap::new();
+
+        for job in jobs.iter() {
+            let start = match job.start_time() {
+                Some(t) => t.max(since),
+                None => continue,
+            };
+            let end = job.end_time().unwrap_or(now);
+
+            if end <= start {
+                continue;
+            }
+
+            let hours = (end - start).num_seconds() as f64 / 3600.;
+            let alloc = job.tres_alloc();
+
+            let key = match self.by {
+                None => String::new(),
+                Some(GroupBy::User) => job
+                    .user()
+                    .map(|u| u.into_owned())
+                    .unwrap_or_else(|| job.uid().to_string()),
+                Some(GroupBy::Account) => job
+                    .account()
+                    .map(|a| a.into_owned())
+                    .unwrap_or_else(|| "(none)".to_owned()),
+            };
+
+            let u = totals.entry(key).or_default();
+            u.jobs += 1;
+            u.cpu_hours += hours * alloc.cpus().unwrap_or(0) as f64;
+            u.gpu_hours += hours * alloc.gpus(&tres).unwrap_or(0) as f64;
+        }
+
+        cprint!(cio, pl, "usage since ");
+        cprint!(
+            cio,
+            hl,
+            "{}",
+            since.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+        );
+        cprintln!(cio, pl, " ({} ago)", util::dur_to_text(&(now - since)));
+
+        let by = match self.by {
+            Some(b) => b,
+            None => {
+                let u = totals.remove("").unwrap_or_default();
+                cprintln!(cio, pl, "  jobs: {}", u.jobs);
+                cprintln!(cio, pl, "  CPU-hours: {:.1}", u.cpu_hours);
+                cprintln!(cio, pl, "  GPU-hours: {:.1}", u.gpu_hours);
+                return Ok(0);
+            }
+        };
+
+        let width = totals.keys().map(|k| k.len()).max().unwrap_or(0).max(7);
+        cprintln!(
+            cio,
+            hl,
+            "{:<width$}  {:>7}  {:>12}  {:>12}",
+            by.heading(),
+            "JOBS",
+            "CPU-HOURS",
+            "GPU-HOURS"
+        );
+
+        let mut sum = Usage::default();
+
+        for (key, u) in totals
+            .iter()
+            .sorted_by(|a, b| b.1.cpu_hours.total_cmp(&a.1.cpu_hours))
+        {
+            cprintln!(
+                cio,
+                pl,
+                "{:<width$}  {:>7}  {:>12.1}  {:>12.1}",
+                key,
+                u.jobs,
+                u.cpu_hours,
+                u.gpu_hours
+            );
+            sum.jobs += u.jobs;
+            sum.cpu_hours += u.cpu_hours;
+            sum.gpu_hours += u.gpu_hours;
+        }
+
+        cprintln!(
+            cio,
+            hl,
+            "{:<width$}  {:>7}  {:>12.1}  {:>12.1}",
+            "(total)",
+            sum.jobs,
+            sum.cpu_hours,
+            sum.gpu_hours
+        );
+
+        Ok(0)
+    }
+}
+
+/// Accumulated usage of one user, account, or the whole query.
+#[derive(Debug, Default)]
+struct Usage {
+    jobs: usize,
+    cpu_hours: f64,
+    gpu_hours: f64,
+}
+
+/// How to break down the usage report.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum GroupBy {
+    User,
+    Account,
+}
+
+impl GroupBy {
+    fn heading(&self) -> &'static str {
+        match self {
+            GroupBy::User => "USER",
+            GroupBy::Account => "ACCOUNT",
+        }
+    }
+}
+
+impl FromStr for GroupBy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "user" => Ok(GroupBy::User),
+            "account" => Ok(GroupBy::Account),
+            _ => Err(format_err!(
+                "cannot break down usage by {:?}; use \"user\" or \"account\"",
+                s
+            )),
+        }
+    }
+}
+
+/// The start of a reporting period.
+#[derive(Clone, Copy, Debug)]
+enum Since {
+    /// A span of time before now.
+    Ago(Duration),
+
+    /// The start of a day, in local time.
+    Date(NaiveDate),
+}
+
+impl Since {
+    fn resolve(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        match *self {
+            Since::Ago(d) => now - d,
+            Since::Date(date) => {
+                let midnight = date.and_hms_opt(0, 0, 0).unwrap();
+
+                // A midnight that falls in a DST gap does not exist; fall
+                // back to interpreting it as UTC.
+                Local
+                    .from_local_datetime(&midnight)
+                    .earliest()
+                    .map(|t| t.with_timezone(&Utc))
+                    .unwrap_or_else(|| Utc.from_utc_datetime(&midnight))
+            }
+        }
+    }
+}
+
+impl FromStr for Since {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+            return Ok(Since::Date(date));
+        }
+
+        let bad = || format_err!("cannot parse {:?} as a span like \"30d\" or a date", s);
+
+        if !s.is_ascii() {
+            return Err(bad());
+        }
+
+        let (num, unit) = s.split_at(s.len().saturating_sub(1));
+        let n: i64 = num.parse().map_err(|_| bad())?;
+
+        Ok(Since::Ago(match unit {
+            "h" => Duration::hours(n),
+            "d" => Duration::days(n),
+            "w" => Duration::weeks(n),
+            _ => return Err(bad()),
+        }))
+    }
+}
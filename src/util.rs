@@ -5,9 +5,10 @@
 
 */
 
+use crate::colorio::ColorIo;
 use chrono::Duration;
-use colorio::ColorIo;
-use slurm::JobState;
+use slurm::{JobId, JobState};
+use std::path::{Path, PathBuf};
 
 /// Print out a shortcode for a job state with affective color.
 pub fn colorize_state(cio: &mut ColorIo, state: JobState) {
@@ -29,13 +30,18 @@ pub fn colorize_state(cio: &mut ColorIo, state: JobState) {
         | JobState::NodeFail
         | JobState::BootFail
         | JobState::Deadline
-        | JobState::OutOfMemory => {
+        | JobState::OutOfMemory
+        | JobState::Revoked => {
             cprint!(cio, red, "{}", state.shortcode());
         }
 
         JobState::Suspended | JobState::Timeout | JobState::Preempted => {
             cprint!(cio, yellow, "{}", state.shortcode());
         }
+
+        _ => {
+            cprint!(cio, pl, "{}", state.shortcode());
+        }
     }
 }
 
@@ -51,3 +57,83 @@ pub fn dur_to_text(dur: &Duration) -> String {
         format!("{} seconds", dur.num_seconds())
     }
 }
+
+/// Express a duration in the `[D-]HH:MM:SS` style used by Slurm's tools.
+///
+/// Negative durations are treated as zero.
+pub fn dur_to_hms(dur: &Duration) -> String {
+    let secs = dur.num_seconds().max(0);
+    let (days, secs) = (secs / 86400, secs % 86400);
+    let (hours, secs) = (secs / 3600, secs % 3600);
+    let (minutes, secs) = (secs / 60, secs % 60);
+
+    if days > 0 {
+        format!("{}-{:02}:{:02}:{:02}", days, hours, minutes, secs)
+    } else {
+        format!("{:02}:{:02}:{:02}", hours, minutes, secs)
+    }
+}
+
+/// The values that can be substituted into the filename pattern of a job's
+/// output file.
+#[derive(Debug)]
+pub struct LogPathVars<'a> {
+    pub job_id: JobId,
+    /// The array job ID and task index, if the job is an array task.
+    pub array: Option<(JobId, u32)>,
+    pub name: &'a str,
+    pub user: Option<&'a str>,
+    /// The node that runs the batch script, if known.
+    pub node: Option<&'a str>,
+}
+
+/// Expand the `sbatch` filename pattern of a job's output file into a path.
+///
+/// Only the replacement symbols that are meaningful for a batch script are
+/// expanded, including zero-padded forms such as `%4a`. Unknown symbols and
+/// those whose values are unknown are left as-is.
+pub fn log_path(work_dir: &Path, pattern: &str, vars: &LogPathVars) -> PathBuf {
+    let mut expanded = String::new();
+    let mut chars = pattern.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            expanded.push(c);
+            continue;
+        }
+
+        let mut width = String::new();
+
+        while let Some(d) = chars.peek().filter(|d| d.is_ascii_digit()) {
+            width.push(*d);
+            chars.next();
+        }
+
+        let pad = width.parse::<usize>().unwrap_or(0);
+        let number = |n: u32| format!("{:0width$}", n, width = pad);
+
+        let symbol = chars.next();
+
+        let value = match symbol {
+            Some('j') => Some(number(vars.job_id)),
+            Some('A') => Some(number(vars.array.map(|a| a.0).unwrap_or(vars.job_id))),
+            Some('a') => vars.array.map(|a| number(a.1)),
+            Some('x') => Some(vars.name.to_owned()),
+            Some('u') => vars.user.map(|u| u.to_owned()),
+            Some('N') => vars.node.map(|n| n.to_owned()),
+            Some('%') => Some("%".to_owned()),
+            _ => None,
+        };
+
+        match value {
+            Some(v) => expanded.push_str(&v),
+            None => {
+                expanded.push('%');
+                expanded.push_str(&width);
+                expanded.extend(symbol);
+            }
+        }
+    }
+
+    work_dir.join(expanded)
+}
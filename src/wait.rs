@@ -0,0 +1,95 @@
+// Copyright 2018 Peter Williams <peter@newton.cx> and collaborators
+// Licensed under the MIT License
+
+/*! Wait for jobs to finish.
+
+This command blocks until all of the specified jobs have reached a terminal
+state, then exits with a code reflecting how they ended, so that shell
+commands can be chained on the completion of Slurm jobs.
+
+*/
+
+use crate::colorio::ColorIo;
+use crate::util;
+use anyhow::Error;
+use slurm::{ExitStatus, JobState};
+use std::cmp;
+use std::time::{Duration, Instant};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct WaitCommand {
+    #[structopt(short = "i", long = "interval", default_value = "10")]
+    /// How often to poll the controller, in seconds.
+    interval_secs: u64,
+
+    #[structopt(short = "t", long = "timeout")]
+    /// Give up after this many seconds.
+    timeout_secs: Option<u64>,
+
+    #[structopt(short = "q", long = "quiet")]
+    /// Do not print how each job ended.
+    quiet: bool,
+
+    #[structopt(help = "The IDs of the jobs to wait for.", required = true)]
+    jobids: Vec<slurm::JobIdentifier>,
+}
+
+impl WaitCommand {
+    pub fn cli(self, cio: &mut ColorIo) -> Result<i32, Error> {
+        let interval = Duration::from_secs(self.interval_secs);
+        let deadline = self
+            .timeout_secs
+            .map(|s| Instant::now() + Duration::from_secs(s));
+        let mut worst = 0;
+
+        // Every job has to finish before we can exit, so there is no harm in
+        // waiting for them one at a time.
+        for jid in self.jobids {
+            let timeout = match deadline {
+                Some(t) => t.saturating_duration_since(Instant::now()),
+                None => Duration::MAX,
+            };
+
+            let (state, status) = slurm::wait_for_job(jid, interval, timeout)?;
+            let code = exit_code(state, status);
+            worst = cmp::max(worst, code);
+
+            if !self.quiet {
+                cprint!(cio, hl, "{}", jid);
+                cprint!(cio, pl, " ");
+                util::colorize_state(cio, state);
+
+                match status {
+                    Some(s) => {
+                        cprintln!(cio, pl, " {}", s);
+                    }
+                    None => {
+                        cprintln!(cio, pl, "");
+                    }
+                }
+            }
+        }
+
+        Ok(worst)
+    }
+}
+
+/// Map how a job ended to a process exit code, following the shell
+/// convention for processes that were killed by a signal.
+///
+/// A job that did not complete successfully never maps to zero, even if its
+/// script did not get the chance to report an error.
+pub(crate) fn exit_code(state: JobState, status: Option<ExitStatus>) -> i32 {
+    let code = match status {
+        Some(ExitStatus::Exited(c)) => c as i32,
+        Some(ExitStatus::Signaled(s)) => 128 + s as i32,
+        None => 0,
+    };
+
+    if code == 0 && state != JobState::Complete {
+        1
+    } else {
+        code
+    }
+}
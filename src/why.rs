@@ -0,0 +1,429 @@
+// Copyright 2018 Peter Williams <peter@newton.cx> and collaborators
+// Licensed under the MIT License
+
+/*! Explain why a job has not started.
+
+Slurm records a terse reason code for every pending job, such as `Priority`
+or `QOSMaxCpuPerUserLimit`. This command puts that code into context: where
+the job stands in its partition's queue, the factors that make up its
+priority, the state of the partition's nodes, and the limits that apply to
+the job's QOS and association.
+
+*/
+
+use crate::colorio::ColorIo;
+use crate::util;
+use anyhow::Error;
+use chrono::Utc;
+use slurm::{JobInfo, JobState, NodeState, PendingReason, TresAmounts};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct WhyCommand {
+    #[structopt(help = "The ID of the pending job to explain.")]
+    jobid: slurm::JobIdentifier,
+}
+
+impl WhyCommand {
+    pub fn cli(self, cio: &mut ColorIo) -> Result<i32, Error> {
+        let info = slurm::get_job_info(self.jobid)?;
+        let job: &JobInfo = &info;
+        let state = job.state();
+
+        cprint!(cio, hl, "{}", job.job_id());
+        cprint!(cio, pl, " {} ", job.name());
+        util::colorize_state(cio, state);
+        cprintln!(cio, pl, "");
+
+        if state != JobState::Pending {
+            cprintln!(
+                cio,
+                pl,
+                "  the job is not pending; its state is {}",
+                state.slurm_name()
+            );
+            return Ok(0);
+        }
+
+        let reason = job.state_reason();
+        cprint!(cio, pl, "  reason: ");
+        cprintln!(cio, yellow, "{}", reason);
+        cprintln!(cio, pl, "  {}", explain(reason));
+
+        if let Some(t) = job.start_time() {
+            let now = Utc::now();
+
+            if t > now {
+                cprintln!(
+                    cio,
+                    pl,
+                    "  the scheduler expects it to start in about {}",
+                    util::dur_to_text(&(t - now))
+                );
+            }
+        }
+
+        let partitions: Vec<String> = job
+            .partition()
+            .split(',')
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_owned())
+            .collect();
+
+        // Queue position. Jobs that can run in any of the same partitions
+        // compete with this one.
+        let all_jobs = slurm::get_all_jobs()?;
+        let ahead = all_jobs
+            .jobs()
+            .filter(|other| {
+                other.state() == JobState::Pending
+                    && other.priority() > job.priority()
+                    && other
+                        .partition()
+                        .split(',')
+                        .any(|p| partitions.iter().any(|q| q == p))
+            })
+            .count();
+
+        if job.priority() == 0 {
+            cprintln!(cio, pl, "  priority: 0 (the job is held)");
+        } else {
+            cprintln!(
+                cio,
+                pl,
+                "  priority: {}; {} pending job(s) in the same partition(s) rank higher",
+                job.priority(),
+                ahead
+            );
+
+            // The priority plugin may not compute factors, so failures here
+            // are not fatal.
+            if let Err(e) = print_priority_factors(cio, job.job_id()) {
+                cprint!(cio, yellow, "  warning:");
+                cprintln!(cio, pl, " could not look up priority factors: {}", e);
+            }
+        }
+
+        // Node availability.
+        let nodes = slurm::get_nodes()?;
+
+        for part in &partitions {
+            let mut counts = NodeCounts::default();
+
+            for node in nodes.nodes() {
+                if node.partitions().iter().any(|p| p == part) {
+                    counts.add(&node);
+                }
+            }
+
+            cprint!(cio, pl, "  partition ");
+            cprint!(cio, hl, "{}", part);
+            cprintln!(
+                cio,
+                pl,
+                ": {} nodes, {} idle, {} partly used, {} full, {} unavailable",
+                counts.total,
+                counts.idle,
+                counts.mixed,
+                counts.allocated,
+                counts.unavailable
+            );
+
+            if counts.total > 0 && counts.unavailable == counts.total {
+                cprint!(cio, red, "    warning:");
+                cprintln!(cio, pl, " none of this partition's nodes are available");
+            }
+        }
+
+        let mem = job
+            .min_memory()
+            .map(|m| format!(", memory {}", m))
+            .unwrap_or_default();
+        let time = job
+            .time_limit()
+            .map(|t| format!(", time limit {}", t))
+            .unwrap_or_default();
+        cprintln!(
+            cio,
+            pl,
+            "  the job asks for {} CPU(s){}{}",
+            job.num_cpus(),
+            mem,
+            time
+        );
+
+        // Limits from the accounting database. These are only relevant if
+        // the reason is a limit, and the database may not be reachable, so
+        // failures here are not fatal.
+        let reason_text = reason.to_string();
+
+        if reason_text.starts_with("QOS") {
+            if let Some(qos) = job.qos() {
+                if let Err(e) = print_qos_limits(cio, &qos) {
+                    warn_no_limits(cio, e);
+                }
+            }
+        }
+
+        if reason_text.starts_with("Assoc") {
+            let user = job.user_name().map(|u| u.into_owned()).or_else(|| {
+                users::get_user_by_uid(job.user_id())
+                    .map(|u| u.name().to_string_lossy().into_owned())
+            });
+
+            if let (Some(user), Some(account)) = (user, job.account()) {
+                if let Err(e) = print_assoc_limits(cio, &user, &account) {
+                    warn_no_limits(cio, e);
+                }
+            }
+        }
+
+        Ok(0)
+    }
+}
+
+/// Explain a pending reason in plain language.
+fn explain(reason: PendingReason) -> String {
+    let text = match reason {
+        PendingReason::NoReason => "The scheduler has not yet considered this job.",
+        PendingReason::Priority => {
+            "Other jobs with higher priority are waiting for the same resources; \
+             this job will start once they have."
+        }
+        PendingReason::Dependency => "The job is waiting for the jobs it depends on.",
+        PendingReason::Resources => {
+            "The job is next in line, but not enough resources are free yet."
+        }
+        PendingReason::PartitionNodeLimit => {
+            "The job asks for more nodes than its partition allows; it cannot start \
+             as submitted."
+        }
+        PendingReason::PartitionTimeLimit => {
+            "The job's time limit is longer than its partition allows; it cannot start \
+             as submitted."
+        }
+        PendingReason::PartitionDown | PendingReason::PartitionInactive => {
+            "The job's partition is not currently accepting jobs."
+        }
+        PendingReason::Held => "An administrator has held the job.",
+        PendingReason::HeldUser => {
+            "The job's owner has held it; release it with `scontrol release`."
+        }
+        PendingReason::BeginTime => "The job was submitted to start at a later time.",
+        PendingReason::Licenses => "The licenses that the job needs are in use.",
+        PendingReason::Reservation => "The job's reservation is not active yet.",
+        PendingReason::NodeNotAvailable => {
+            "Some nodes that the job needs are down, drained, or reserved."
+        }
+        PendingReason::DependencyNeverSatisfied => {
+            "The job's dependencies can never be satisfied, so it will never start. \
+             Cancel it, or change its dependencies."
+        }
+        _ => {
+            let name = reason.to_string();
+
+            return if name.starts_with("Assoc") {
+                format!(
+                    "A limit on the job's account or user association ({}) has been \
+                     reached. The job will start when the association's other jobs \
+                     finish, or if the limit is raised.",
+                    name
+                )
+            } else if name.starts_with("QOS") {
+                format!(
+                    "A limit of the job's QOS ({}) has been reached. The job will start \
+                     when other jobs in the QOS finish, or if the limit is raised.",
+                    name
+                )
+            } else if name.starts_with("ReqNodeNotAvail") {
+                "Some nodes that the job needs are down, drained, or reserved.".to_owned()
+            } else {
+                format!("Slurm gives the reason as {}.", name)
+            };
+        }
+    };
+
+    text.to_owned()
+}
+
+/// Counts of a partition's nodes by availability.
+#[derive(Debug, Default)]
+struct NodeCounts {
+    total: usize,
+    idle: usize,
+    mixed: usize,
+    allocated: usize,
+    unavailable: usize,
+}
+
+impl NodeCounts {
+    fn add(&mut self, node: &slurm::NodeInfo) {
+        self.total += 1;
+
+        // Drained, reserved, and similar nodes keep their base state, but
+        // won't take this job.
+        if node.state_flags().unavailable() {
+            self.unavailable += 1;
+            return;
+        }
+
+        match node.state() {
+            NodeState::Idle => self.idle += 1,
+            NodeState::Mixed => self.mixed += 1,
+            NodeState::Allocated => self.allocated += 1,
+            _ => self.unavailable += 1,
+        }
+    }
+}
+
+/// Print the weighted factors that make up a job's priority, in each of its
+/// partitions.
+fn print_priority_factors(cio: &mut ColorIo, jid: slurm::JobId) -> Result<(), Error> {
+    let msg = slurm::get_all_priority_factors()?;
+
+    for f in msg.factors().iter().filter(|f| f.job_id() == jid) {
+        let partition = f
+            .partition()
+            .map(|p| format!(" in partition {}", p))
+            .unwrap_or_default();
+        cprintln!(
+            cio,
+            pl,
+            "    factors{}: age={:.0} fairshare={:.0} jobsize={:.0} partition={:.0} \
+             qos={:.0} site={} nice={}",
+            partition,
+            f.age(),
+            f.fairshare(),
+            f.job_size(),
+            f.partition_factor(),
+            f.qos(),
+            f.site(),
+            f.nice()
+        );
+    }
+
+    Ok(())
+}
+
+/// Print the limits of a QOS.
+fn print_qos_limits(cio: &mut ColorIo, qos: &str) -> Result<(), Error> {
+    let db = slurm::DatabaseConnectionOwned::new()?;
+    let mut filter = slurm::QosFiltersOwned::new()?;
+    filter.name_list_mut().append(qos)?;
+
+    for rec in db.get_qos(&filter)?.iter() {
+        cprint!(cio, pl, "  limits of QOS ");
+        cprintln!(cio, hl, "{}", rec.name());
+        let mut limits = Limits::default();
+        limits.num("GrpJobs", rec.grp_jobs());
+        limits.num("GrpSubmitJobs", rec.grp_submit_jobs());
+        limits.tres("GrpTRES", rec.grp_tres());
+        limits.num("MaxJobsPerUser", rec.max_jobs_per_user());
+        limits.num("MaxSubmitJobsPerUser", rec.max_submit_jobs_per_user());
+        limits.tres("MaxTRESPerJob", rec.max_tres_per_job());
+        limits.tres("MaxTRESPerNode", rec.max_tres_per_node());
+        limits.tres("MaxTRESPerUser", rec.max_tres_per_user());
+        limits.minutes("MaxWall", rec.max_wall_per_job());
+        limits.print(cio);
+    }
+
+    Ok(())
+}
+
+/// Print the limits of a user's association with an account.
+fn print_assoc_limits(cio: &mut ColorIo, user: &str, account: &str) -> Result<(), Error> {
+    let db = slurm::DatabaseConnectionOwned::new()?;
+    let mut filter = slurm::AssociationFiltersOwned::new()?;
+    filter.user_list_mut().append(user)?;
+    filter.acct_list_mut().append(account)?;
+
+    for rec in db.get_associations(&filter)?.iter() {
+        cprint!(cio, pl, "  limits of association ");
+        cprint!(cio, hl, "{}/{}", user, account);
+
+        match rec.partition() {
+            Some(p) => {
+                cprintln!(cio, pl, " (partition {})", p);
+            }
+            None => {
+                cprintln!(cio, pl, "");
+            }
+        }
+
+        let mut limits = Limits::default();
+        limits.num("GrpJobs", rec.grp_jobs());
+        limits.num("GrpSubmitJobs", rec.grp_submit_jobs());
+        limits.tres("GrpTRES", rec.grp_tres());
+        limits.tres("GrpTRESRunMins", rec.grp_tres_run_mins());
+        limits.num("MaxJobs", rec.max_jobs());
+        limits.num("MaxSubmitJobs", rec.max_submit_jobs());
+        limits.tres("MaxTRESPerJob", rec.max_tres_per_job());
+        limits.tres("MaxTRESPerNode", rec.max_tres_per_node());
+        limits.minutes("MaxWall", rec.max_wall_per_job());
+        limits.print(cio);
+    }
+
+    Ok(())
+}
+
+fn warn_no_limits(cio: &mut ColorIo, e: Error) {
+    cprint!(cio, yellow, "  warning:");
+    cprintln!(cio, pl, " could not look up limits: {}", e);
+}
+
+/// A list of the limits that are set on a QOS or association, in the
+/// `Name=value` style of `sacctmgr`.
+#[derive(Debug, Default)]
+struct Limits(Vec<String>);
+
+impl Limits {
+    fn num(&mut self, name: &str, value: Option<u32>) {
+        if let Some(v) = value {
+            self.0.push(format!("{}={}", name, v));
+        }
+    }
+
+    fn minutes(&mut self, name: &str, value: Option<u32>) {
+        if let Some(v) = value {
+            self.0
+                .push(format!("{}={}", name, slurm::TimeLimit::Minutes(v)));
+        }
+    }
+
+    fn tres<S: AsRef<str>>(&mut self, name: &str, value: Option<S>) {
+        let text = match value {
+            Some(t) => t.as_ref().to_owned(),
+            None => return,
+        };
+
+        // Show the built-in TRES types by name; others keep their IDs.
+        let amounts = match TresAmounts::parse(&text) {
+            Ok(a) if !a.is_empty() => a,
+            _ => return,
+        };
+
+        let items: Vec<String> = amounts
+            .iter()
+            .map(|(id, amount)| match id {
+                slurm::TRES_CPU => format!("cpu={}", amount),
+                slurm::TRES_MEM => format!("mem={}M", amount),
+                slurm::TRES_ENERGY => format!("energy={}", amount),
+                slurm::TRES_NODE => format!("node={}", amount),
+                slurm::TRES_BILLING => format!("billing={}", amount),
+                other => format!("{}={}", other, amount),
+            })
+            .collect();
+
+        self.0.push(format!("{}={}", name, items.join(",")));
+    }
+
+    fn print(&self, cio: &mut ColorIo) {
+        if self.0.is_empty() {
+            cprintln!(cio, pl, "    (none set)");
+        } else {
+            for item in &self.0 {
+                cprintln!(cio, pl, "    {}", item);
+            }
+        }
+    }
+}